@@ -428,3 +428,95 @@ pub mod pull_requests_open {
         pub labels: Option<LabelConnection>,
     }
 }
+
+/// Mark a draft pull request as ready for review.
+pub mod mark_ready_for_review {
+    use super::schema;
+
+    #[derive(cynic::QueryVariables, Debug, Clone)]
+    pub struct MarkReadyForReviewVariables<'a> {
+        pub pull_request_id: &'a cynic::Id,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(graphql_type = "Mutation", variables = "MarkReadyForReviewVariables")]
+    pub struct MarkReadyForReview {
+        #[arguments(input: { pullRequestId: $pull_request_id })]
+        pub mark_pull_request_ready_for_review: Option<MarkPullRequestReadyForReviewPayload>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    pub struct MarkPullRequestReadyForReviewPayload {
+        pub pull_request: Option<PullRequest>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    pub struct PullRequest {
+        pub is_draft: bool,
+    }
+}
+
+/// Convert a pull request back to a draft.
+pub mod convert_to_draft {
+    use super::schema;
+
+    #[derive(cynic::QueryVariables, Debug, Clone)]
+    pub struct ConvertToDraftVariables<'a> {
+        pub pull_request_id: &'a cynic::Id,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(graphql_type = "Mutation", variables = "ConvertToDraftVariables")]
+    pub struct ConvertToDraft {
+        #[arguments(input: { pullRequestId: $pull_request_id })]
+        pub convert_pull_request_to_draft: Option<ConvertPullRequestToDraftPayload>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    pub struct ConvertPullRequestToDraftPayload {
+        pub pull_request: Option<PullRequest>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    pub struct PullRequest {
+        pub is_draft: bool,
+    }
+}
+
+/// Minimize (hide) a comment.
+pub mod minimize_comment {
+    use super::schema;
+
+    #[derive(cynic::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ReportedContentClassifiers {
+        Abuse,
+        Duplicate,
+        OffTopic,
+        Outdated,
+        Resolved,
+        Spam,
+    }
+
+    #[derive(cynic::QueryVariables, Debug, Clone)]
+    pub struct MinimizeCommentVariables<'a> {
+        pub subject_id: &'a cynic::Id,
+        pub classifier: ReportedContentClassifiers,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(graphql_type = "Mutation", variables = "MinimizeCommentVariables")]
+    pub struct MinimizeComment {
+        #[arguments(input: { subjectId: $subject_id, classifier: $classifier })]
+        pub minimize_comment: Option<MinimizeCommentPayload>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    pub struct MinimizeCommentPayload {
+        pub minimized_comment: Option<Minimizable>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    pub struct Minimizable {
+        pub is_minimized: bool,
+    }
+}
@@ -6,13 +6,22 @@ use regex::Regex;
 pub mod assign;
 pub mod close;
 pub mod glacier;
+pub mod help;
+pub mod link_zulip;
+pub mod milestone_closed;
 pub mod nominate;
 pub mod note;
 pub mod ping;
 pub mod prioritize;
+pub mod priority;
 pub mod relabel;
+pub mod retry_ci;
+pub mod review_pool;
+pub mod rollup;
 pub mod second;
 pub mod shortcut;
+pub mod subtree_update;
+pub mod time;
 pub mod transfer;
 
 #[derive(Debug, PartialEq)]
@@ -28,6 +37,90 @@ pub enum Command<'a> {
     Close(Result<close::CloseCommand, Error<'a>>),
     Note(Result<note::NoteCommand, Error<'a>>),
     Transfer(Result<transfer::TransferCommand, Error<'a>>),
+    RetryCi(Result<retry_ci::RetryCiCommand, Error<'a>>),
+    SubtreeUpdate(Result<subtree_update::SubtreeUpdateCommand, Error<'a>>),
+    Rollup(Result<rollup::RollupCommand, Error<'a>>),
+    Time(Result<time::TimeCommand, Error<'a>>),
+    LinkZulip(Result<link_zulip::LinkZulipCommand, Error<'a>>),
+    ReviewPool(Result<review_pool::ReviewPoolCommand, Error<'a>>),
+    MilestoneClosed(Result<milestone_closed::MilestoneClosedCommand, Error<'a>>),
+    Priority(Result<priority::PriorityCommand, Error<'a>>),
+    Help(Result<help::HelpCommand, Error<'a>>),
+}
+
+/// The kind of a [`Command`], used to enumerate all the commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKind {
+    Relabel,
+    Assign,
+    Ping,
+    Nominate,
+    Prioritize,
+    Second,
+    Glacier,
+    Shortcut,
+    Close,
+    Note,
+    Transfer,
+    RetryCi,
+    SubtreeUpdate,
+    Rollup,
+    Time,
+    LinkZulip,
+    ReviewPool,
+    MilestoneClosed,
+    Priority,
+    Help,
+}
+
+impl CommandKind {
+    pub const ALL: &'static [CommandKind] = &[
+        CommandKind::Relabel,
+        CommandKind::Assign,
+        CommandKind::Ping,
+        CommandKind::Nominate,
+        CommandKind::Prioritize,
+        CommandKind::Second,
+        CommandKind::Glacier,
+        CommandKind::Shortcut,
+        CommandKind::Close,
+        CommandKind::Note,
+        CommandKind::Transfer,
+        CommandKind::RetryCi,
+        CommandKind::SubtreeUpdate,
+        CommandKind::Rollup,
+        CommandKind::Time,
+        CommandKind::LinkZulip,
+        CommandKind::ReviewPool,
+        CommandKind::MilestoneClosed,
+        CommandKind::Priority,
+        CommandKind::Help,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CommandKind::Relabel => "relabel",
+            CommandKind::Assign => "assign",
+            CommandKind::Ping => "ping",
+            CommandKind::Nominate => "nominate",
+            CommandKind::Prioritize => "prioritize",
+            CommandKind::Second => "second",
+            CommandKind::Glacier => "glacier",
+            CommandKind::Shortcut => "shortcut",
+            CommandKind::Close => "close",
+            CommandKind::Note => "note",
+            CommandKind::Transfer => "transfer",
+            CommandKind::RetryCi => "retry-ci",
+            CommandKind::SubtreeUpdate => "subtree-update",
+            CommandKind::Rollup => "rollup",
+            CommandKind::Time => "time",
+            CommandKind::LinkZulip => "link-zulip",
+            CommandKind::ReviewPool => "review-pool",
+            CommandKind::MilestoneClosed => "milestone-closed",
+            CommandKind::Priority => "priority",
+            CommandKind::Help => "help",
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -139,6 +232,51 @@ impl<'a> Input<'a> {
             Command::Transfer,
             &original_tokenizer,
         ));
+        success.extend(parse_single_command(
+            retry_ci::RetryCiCommand::parse,
+            Command::RetryCi,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            subtree_update::SubtreeUpdateCommand::parse,
+            Command::SubtreeUpdate,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            rollup::RollupCommand::parse,
+            Command::Rollup,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            time::TimeCommand::parse,
+            Command::Time,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            link_zulip::LinkZulipCommand::parse,
+            Command::LinkZulip,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            review_pool::ReviewPoolCommand::parse,
+            Command::ReviewPool,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            milestone_closed::MilestoneClosedCommand::parse,
+            Command::MilestoneClosed,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            priority::PriorityCommand::parse,
+            Command::Priority,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            help::HelpCommand::parse,
+            Command::Help,
+            &original_tokenizer,
+        ));
 
         if success.len() > 1 {
             panic!(
@@ -215,12 +353,46 @@ impl<'a> Command<'a> {
             Command::Close(r) => r.is_ok(),
             Command::Note(r) => r.is_ok(),
             Command::Transfer(r) => r.is_ok(),
+            Command::RetryCi(r) => r.is_ok(),
+            Command::SubtreeUpdate(r) => r.is_ok(),
+            Command::Rollup(r) => r.is_ok(),
+            Command::Time(r) => r.is_ok(),
+            Command::LinkZulip(r) => r.is_ok(),
+            Command::ReviewPool(r) => r.is_ok(),
+            Command::MilestoneClosed(r) => r.is_ok(),
+            Command::Priority(r) => r.is_ok(),
+            Command::Help(r) => r.is_ok(),
         }
     }
 
     pub fn is_err(&self) -> bool {
         !self.is_ok()
     }
+
+    pub fn kind(&self) -> CommandKind {
+        match self {
+            Command::Relabel(_) => CommandKind::Relabel,
+            Command::Assign(_) => CommandKind::Assign,
+            Command::Ping(_) => CommandKind::Ping,
+            Command::Nominate(_) => CommandKind::Nominate,
+            Command::Prioritize(_) => CommandKind::Prioritize,
+            Command::Second(_) => CommandKind::Second,
+            Command::Glacier(_) => CommandKind::Glacier,
+            Command::Shortcut(_) => CommandKind::Shortcut,
+            Command::Close(_) => CommandKind::Close,
+            Command::Note(_) => CommandKind::Note,
+            Command::Transfer(_) => CommandKind::Transfer,
+            Command::RetryCi(_) => CommandKind::RetryCi,
+            Command::SubtreeUpdate(_) => CommandKind::SubtreeUpdate,
+            Command::Rollup(_) => CommandKind::Rollup,
+            Command::Time(_) => CommandKind::Time,
+            Command::LinkZulip(_) => CommandKind::LinkZulip,
+            Command::ReviewPool(_) => CommandKind::ReviewPool,
+            Command::MilestoneClosed(_) => CommandKind::MilestoneClosed,
+            Command::Priority(_) => CommandKind::Priority,
+            Command::Help(_) => CommandKind::Help,
+        }
+    }
 }
 
 #[test]
@@ -260,6 +432,38 @@ fn resumes_after_code() {
     assert_eq!(input.next(), None);
 }
 
+#[test]
+fn quoted_and_fenced_commands_are_ignored() {
+    // Quoting a previous comment, or showing a command in a code block,
+    // shouldn't run the command again.
+    for input in [
+        "> @bot ready",
+        "> > @bot ready",
+        "On Mon, octocat wrote:\n> r? @foo",
+        "1. > @bot ready",
+        "~~~\n@bot ready\n~~~",
+        "- item\n  ```\n  @bot ready\n  ```\n",
+        "* > ```\n  > @bot ready\n  > ```",
+    ] {
+        let mut input = Input::new(input, vec!["bot"]);
+        assert_eq!(input.next(), None);
+    }
+
+    let input = "> @bot label +A-bug
+
+```
+@bot claim
+```
+
+@bot ready";
+    let mut input = Input::new(input, vec!["bot"]);
+    assert_eq!(
+        input.next(),
+        Some(Command::Shortcut(Ok(shortcut::ShortcutCommand::Ready)))
+    );
+    assert_eq!(input.next(), None);
+}
+
 #[test]
 fn edit_1() {
     let input_old = "@bot modify labels: +bug.";
@@ -361,3 +565,69 @@ fn review_ignored() {
         assert_eq!(input.next(), None);
     }
 }
+
+#[test]
+fn multiple_commands() {
+    let input = "Thanks! @bot label +T-compiler -S-blocked
+@bot ready
+
+Also, @bot claim.
+r? @octocat";
+    let commands: Vec<_> = Input::new(input, vec!["bot"]).collect();
+    assert!(commands.iter().all(|c| c.is_ok()));
+    assert_eq!(
+        commands.iter().map(|c| c.kind()).collect::<Vec<_>>(),
+        [
+            CommandKind::Relabel,
+            CommandKind::Shortcut,
+            CommandKind::Assign,
+            CommandKind::Assign
+        ]
+    );
+}
+
+#[test]
+fn multiple_commands_on_one_line() {
+    let input = "@bot label +A-bug -S-blocked and @bot ready";
+    let commands: Vec<_> = Input::new(input, vec!["bot"]).collect();
+    assert_eq!(commands.len(), 2);
+    assert!(matches!(&commands[0], Command::Relabel(Ok(relabel)) if relabel.0.len() == 2));
+    assert_eq!(
+        commands[1],
+        Command::Shortcut(Ok(shortcut::ShortcutCommand::Ready))
+    );
+}
+
+#[test]
+fn malformed_command_among_others() {
+    // The malformed command is reported, and doesn't prevent parsing the
+    // following ones.
+    let input = "@bot ping
+@bot ready";
+    let commands: Vec<_> = Input::new(input, vec!["bot"]).collect();
+    assert_eq!(commands.len(), 2);
+    assert!(matches!(commands[0], Command::Ping(Err(_))));
+    assert_eq!(
+        commands[1],
+        Command::Shortcut(Ok(shortcut::ShortcutCommand::Ready))
+    );
+}
+
+#[test]
+fn unknown_command() {
+    let mut input = Input::new("@bot frobnicate", vec!["bot"]);
+    assert_eq!(input.next(), None);
+}
+
+#[test]
+fn command_kind_names() {
+    for kind in CommandKind::ALL {
+        assert_eq!(
+            CommandKind::ALL
+                .iter()
+                .filter(|k| k.name() == kind.name())
+                .count(),
+            1
+        );
+    }
+}
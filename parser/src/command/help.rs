@@ -0,0 +1,47 @@
+//! The help command parser.
+//!
+//! Lists the commands enabled in the repository.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot help`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct HelpCommand;
+
+impl HelpCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if let Some(Token::Word("help")) = input.peek_token()? {
+            input.next_token()?;
+            Ok(Some(Self))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<HelpCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(HelpCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn test_1() {
+    assert_eq!(parse("help"), Ok(Some(HelpCommand)));
+}
+
+#[test]
+fn test_2() {
+    assert_eq!(parse("help me"), Ok(Some(HelpCommand)));
+}
+
+#[test]
+fn test_3() {
+    assert_eq!(parse("helpful"), Ok(None));
+}
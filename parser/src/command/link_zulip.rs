@@ -0,0 +1,82 @@
+//! The link-zulip command parser.
+//!
+//! Confirms a link between the GitHub account of the commenter and the Zulip
+//! account that requested the code with the `link-github` Zulip command, so
+//! that the bot can notify them on Zulip.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot link-zulip <code>`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct LinkZulipCommand {
+    pub code: String,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    MissingCode,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingCode => write!(
+                f,
+                "missing the code given by the `link-github` Zulip command"
+            ),
+        }
+    }
+}
+
+impl LinkZulipCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if !matches!(input.peek_token()?, Some(Token::Word("link-zulip"))) {
+            return Ok(None);
+        }
+        input.next_token()?;
+        match input.next_token()? {
+            Some(Token::Word(code)) => Ok(Some(LinkZulipCommand {
+                code: code.to_string(),
+            })),
+            _ => Err(input.error(ParseError::MissingCode)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<LinkZulipCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(LinkZulipCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parse_code() {
+    assert_eq!(
+        parse("link-zulip 3f2a9c1e."),
+        Ok(Some(LinkZulipCommand {
+            code: "3f2a9c1e".to_string()
+        }))
+    );
+}
+
+#[test]
+fn parse_missing_code() {
+    use std::error::Error;
+    assert_eq!(
+        parse("link-zulip")
+            .unwrap_err()
+            .source()
+            .unwrap()
+            .downcast_ref(),
+        Some(&ParseError::MissingCode),
+    );
+}
@@ -0,0 +1,38 @@
+//! The milestone-closed command parser.
+//!
+//! Sets the milestone of a PR on the issues it closes.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot milestone-closed`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct MilestoneClosedCommand;
+
+impl MilestoneClosedCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if let Some(Token::Word("milestone-closed")) = input.peek_token()? {
+            input.next_token()?;
+            Ok(Some(Self))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<MilestoneClosedCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(MilestoneClosedCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parse_command() {
+    assert_eq!(parse("milestone-closed."), Ok(Some(MilestoneClosedCommand)));
+    assert_eq!(parse("milestone"), Ok(None));
+}
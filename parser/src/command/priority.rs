@@ -0,0 +1,145 @@
+//! The priority command parser.
+//!
+//! Sets the priority of an issue.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot priority <level>`.
+//!
+//! <level>:
+//!  - P-critical
+//!  - P-high
+//!  - P-medium
+//!  - P-low
+//! ```
+//!
+//! The `P-` prefix is optional and the level is case-insensitive, so
+//! `@bot priority high` also works.
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PriorityLevel {
+    Critical,
+    High,
+    Medium,
+    Low,
+}
+
+impl PriorityLevel {
+    pub const ALL: [PriorityLevel; 4] = [
+        PriorityLevel::Critical,
+        PriorityLevel::High,
+        PriorityLevel::Medium,
+        PriorityLevel::Low,
+    ];
+}
+
+impl fmt::Display for PriorityLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PriorityLevel::Critical => write!(f, "P-critical"),
+            PriorityLevel::High => write!(f, "P-high"),
+            PriorityLevel::Medium => write!(f, "P-medium"),
+            PriorityLevel::Low => write!(f, "P-low"),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct PriorityCommand(pub PriorityLevel);
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    ExpectedLevel,
+    UnknownLevel,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::ExpectedLevel => write!(
+                f,
+                "expected a priority: `P-critical`, `P-high`, `P-medium` or `P-low`"
+            ),
+            ParseError::UnknownLevel => write!(
+                f,
+                "unknown priority, expected `P-critical`, `P-high`, `P-medium` or `P-low`"
+            ),
+        }
+    }
+}
+
+impl PriorityCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if let Some(Token::Word("priority")) = input.peek_token()? {
+            input.next_token()?;
+        } else {
+            return Ok(None);
+        }
+        let Some(Token::Word(word)) = input.next_token()? else {
+            return Err(input.error(ParseError::ExpectedLevel));
+        };
+        let word = word.to_lowercase();
+        let level = match word.strip_prefix("p-").unwrap_or(&word) {
+            "critical" => PriorityLevel::Critical,
+            "high" => PriorityLevel::High,
+            "medium" => PriorityLevel::Medium,
+            "low" => PriorityLevel::Low,
+            _ => return Err(input.error(ParseError::UnknownLevel)),
+        };
+        Ok(Some(PriorityCommand(level)))
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<PriorityCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(PriorityCommand::parse(&mut toks)?)
+}
+
+#[cfg(test)]
+fn parse_error(input: &str) -> ParseError {
+    use std::error::Error;
+    let err = parse(input).unwrap_err();
+    match err.source().unwrap().downcast_ref() {
+        Some(ParseError::ExpectedLevel) => ParseError::ExpectedLevel,
+        Some(ParseError::UnknownLevel) => ParseError::UnknownLevel,
+        None => panic!("unexpected error {err}"),
+    }
+}
+
+#[test]
+fn parse_levels() {
+    for level in PriorityLevel::ALL {
+        assert_eq!(
+            parse(&format!("priority {level}.")),
+            Ok(Some(PriorityCommand(level)))
+        );
+    }
+    assert_eq!(
+        parse("priority high"),
+        Ok(Some(PriorityCommand(PriorityLevel::High)))
+    );
+    assert_eq!(
+        parse("priority p-Low"),
+        Ok(Some(PriorityCommand(PriorityLevel::Low)))
+    );
+}
+
+#[test]
+fn parse_invalid_level() {
+    assert_eq!(parse_error("priority P-urgent"), ParseError::UnknownLevel);
+    assert_eq!(parse_error("priority"), ParseError::ExpectedLevel);
+}
+
+#[test]
+fn parse_other_command() {
+    assert_eq!(parse("prioritize"), Ok(None));
+    assert_eq!(parse("priorities P-high"), Ok(None));
+}
@@ -26,6 +26,9 @@
 //!
 //! <label>: \S+
 //! ```
+//!
+//! The label list ends at the end of the line, at a `.` or `;`, or before a
+//! word starting with `@` (like a mention of the bot for another command).
 
 use crate::error::Error;
 use crate::token::{Token, Tokenizer};
@@ -153,12 +156,18 @@ impl RelabelCommand {
             toks.eat_token(Token::Comma)?;
             toks.eat_token(Token::Word("and"))?;
 
-            if let Some(Token::Semi) | Some(Token::Dot) | Some(Token::EndOfLine) =
-                toks.peek_token()?
-            {
-                toks.next_token()?;
-                *input = toks;
-                return Ok(Some(RelabelCommand(deltas)));
+            match toks.peek_token()? {
+                Some(Token::Semi) | Some(Token::Dot) | Some(Token::EndOfLine) => {
+                    toks.next_token()?;
+                    *input = toks;
+                    return Ok(Some(RelabelCommand(deltas)));
+                }
+                // Another command follows on the same line.
+                Some(Token::Word(word)) if word.starts_with('@') => {
+                    *input = toks;
+                    return Ok(Some(RelabelCommand(deltas)));
+                }
+                _ => {}
             }
         }
     }
@@ -182,6 +191,17 @@ fn parse_simple() {
     );
 }
 
+#[test]
+fn parse_followed_by_command() {
+    assert_eq!(
+        parse("label +A-bug -S-blocked and @bot ready"),
+        Ok(Some(vec![
+            LabelDelta::Add(Label("A-bug".into())),
+            LabelDelta::Remove(Label("S-blocked".into())),
+        ]))
+    );
+}
+
 #[test]
 fn parse_leading_to_label() {
     assert_eq!(
@@ -0,0 +1,47 @@
+//! The retry-ci command parser.
+//!
+//! Reruns the failed jobs of the latest CI run of a PR.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot retry-ci`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct RetryCiCommand;
+
+impl RetryCiCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if let Some(Token::Word("retry-ci")) = input.peek_token()? {
+            input.next_token()?;
+            Ok(Some(Self))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<RetryCiCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(RetryCiCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn test_1() {
+    assert_eq!(parse("retry-ci"), Ok(Some(RetryCiCommand)));
+}
+
+#[test]
+fn test_2() {
+    assert_eq!(parse("retry-ci."), Ok(Some(RetryCiCommand)));
+}
+
+#[test]
+fn test_3() {
+    assert_eq!(parse("retry"), Ok(None));
+}
@@ -0,0 +1,94 @@
+//! The review pool command parser.
+//!
+//! Adds the commenter to the review pool of a team, or removes them, so that
+//! the PRs for the team are only assigned to the members of its pool.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot assign-me-reviewer <team>` or `@bot unassign-me-reviewer <team>`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ReviewPoolCommand {
+    Join { team: String },
+    Leave { team: String },
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    MissingTeam,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingTeam => write!(f, "missing the team of the review pool"),
+        }
+    }
+}
+
+impl ReviewPoolCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        let join = match input.peek_token()? {
+            Some(Token::Word("assign-me-reviewer")) => true,
+            Some(Token::Word("unassign-me-reviewer")) => false,
+            _ => return Ok(None),
+        };
+        input.next_token()?;
+        let team = match input.next_token()? {
+            Some(Token::Word(team)) => team.to_string(),
+            _ => return Err(input.error(ParseError::MissingTeam)),
+        };
+        Ok(Some(if join {
+            ReviewPoolCommand::Join { team }
+        } else {
+            ReviewPoolCommand::Leave { team }
+        }))
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<ReviewPoolCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(ReviewPoolCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parse_join() {
+    assert_eq!(
+        parse("assign-me-reviewer compiler."),
+        Ok(Some(ReviewPoolCommand::Join {
+            team: "compiler".to_string()
+        }))
+    );
+}
+
+#[test]
+fn parse_leave() {
+    assert_eq!(
+        parse("unassign-me-reviewer compiler"),
+        Ok(Some(ReviewPoolCommand::Leave {
+            team: "compiler".to_string()
+        }))
+    );
+}
+
+#[test]
+fn parse_missing_team() {
+    use std::error::Error;
+    assert_eq!(
+        parse("assign-me-reviewer")
+            .unwrap_err()
+            .source()
+            .unwrap()
+            .downcast_ref(),
+        Some(&ParseError::MissingTeam),
+    );
+}
@@ -0,0 +1,137 @@
+//! The rollup command parser.
+//!
+//! Sets whether a PR can be included in a rollup.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot rollup`, `@bot rollup-` or `@bot rollup=<level>`.
+//!
+//! <level>:
+//!  - always
+//!  - maybe
+//!  - iffy
+//!  - never
+//! ```
+//!
+//! Like with bors, `rollup` means `rollup=always` and `rollup-` means
+//! `rollup=maybe`.
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RollupLevel {
+    Always,
+    Maybe,
+    Iffy,
+    Never,
+}
+
+impl RollupLevel {
+    pub const ALL: [RollupLevel; 4] = [
+        RollupLevel::Always,
+        RollupLevel::Maybe,
+        RollupLevel::Iffy,
+        RollupLevel::Never,
+    ];
+}
+
+impl fmt::Display for RollupLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RollupLevel::Always => write!(f, "always"),
+            RollupLevel::Maybe => write!(f, "maybe"),
+            RollupLevel::Iffy => write!(f, "iffy"),
+            RollupLevel::Never => write!(f, "never"),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct RollupCommand(pub RollupLevel);
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    UnknownLevel,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnknownLevel => write!(
+                f,
+                "unknown rollup level, expected `always`, `maybe`, `iffy` or `never`"
+            ),
+        }
+    }
+}
+
+impl RollupCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        let level = match input.peek_token()? {
+            Some(Token::Word("rollup")) => RollupLevel::Always,
+            Some(Token::Word("rollup-")) => RollupLevel::Maybe,
+            Some(Token::Word(word)) if word.starts_with("rollup=") => {
+                match &word["rollup=".len()..] {
+                    "always" => RollupLevel::Always,
+                    "maybe" => RollupLevel::Maybe,
+                    "iffy" => RollupLevel::Iffy,
+                    "never" => RollupLevel::Never,
+                    _ => {
+                        input.next_token()?;
+                        return Err(input.error(ParseError::UnknownLevel));
+                    }
+                }
+            }
+            _ => return Ok(None),
+        };
+        input.next_token()?;
+        Ok(Some(RollupCommand(level)))
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<RollupCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(RollupCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parse_levels() {
+    assert_eq!(
+        parse("rollup"),
+        Ok(Some(RollupCommand(RollupLevel::Always)))
+    );
+    assert_eq!(
+        parse("rollup-"),
+        Ok(Some(RollupCommand(RollupLevel::Maybe)))
+    );
+    for level in RollupLevel::ALL {
+        assert_eq!(
+            parse(&format!("rollup={level}.")),
+            Ok(Some(RollupCommand(level)))
+        );
+    }
+}
+
+#[test]
+fn parse_unknown_level() {
+    use std::error::Error;
+    assert_eq!(
+        parse("rollup=sometimes")
+            .unwrap_err()
+            .source()
+            .unwrap()
+            .downcast_ref(),
+        Some(&ParseError::UnknownLevel),
+    );
+}
+
+#[test]
+fn parse_other_command() {
+    assert_eq!(parse("rollups"), Ok(None));
+}
@@ -0,0 +1,96 @@
+//! The subtree-update command parser.
+//!
+//! Opens a PR updating a submodule to the latest commit of its default
+//! branch.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot subtree-update <submodule>`.
+//! ```
+//!
+//! The submodule is given either by its path, like `src/doc/book`, or by its
+//! last path component, like `book`.
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct SubtreeUpdateCommand {
+    pub submodule: String,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    MissingSubmodule,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingSubmodule => write!(f, "missing submodule name"),
+        }
+    }
+}
+
+impl SubtreeUpdateCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if !matches!(input.peek_token()?, Some(Token::Word("subtree-update"))) {
+            return Ok(None);
+        }
+        input.next_token()?;
+        match input.next_token()? {
+            Some(Token::Word(submodule)) => Ok(Some(SubtreeUpdateCommand {
+                submodule: submodule.to_owned(),
+            })),
+            _ => Err(input.error(ParseError::MissingSubmodule)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<SubtreeUpdateCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(SubtreeUpdateCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parse_path() {
+    assert_eq!(
+        parse("subtree-update src/doc/book"),
+        Ok(Some(SubtreeUpdateCommand {
+            submodule: "src/doc/book".to_string()
+        }))
+    );
+}
+
+#[test]
+fn parse_name() {
+    assert_eq!(
+        parse("subtree-update nomicon."),
+        Ok(Some(SubtreeUpdateCommand {
+            submodule: "nomicon".to_string()
+        }))
+    );
+}
+
+#[test]
+fn parse_missing_submodule() {
+    use std::error::Error;
+    assert_eq!(
+        parse("subtree-update")
+            .unwrap_err()
+            .source()
+            .unwrap()
+            .downcast_ref(),
+        Some(&ParseError::MissingSubmodule),
+    );
+}
+
+#[test]
+fn parse_other_command() {
+    assert_eq!(parse("subtree"), Ok(None));
+}
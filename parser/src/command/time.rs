@@ -0,0 +1,78 @@
+//! The time command parser.
+//!
+//! Replies with the next meeting of a team, in a few common timezones.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot time <team>`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct TimeCommand {
+    pub team: String,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    MissingTeam,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingTeam => write!(f, "missing team name"),
+        }
+    }
+}
+
+impl TimeCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if !matches!(input.peek_token()?, Some(Token::Word("time"))) {
+            return Ok(None);
+        }
+        input.next_token()?;
+        match input.next_token()? {
+            Some(Token::Word(team)) => Ok(Some(TimeCommand {
+                team: team.to_owned(),
+            })),
+            _ => Err(input.error(ParseError::MissingTeam)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<TimeCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(TimeCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parse_team() {
+    assert_eq!(
+        parse("time compiler."),
+        Ok(Some(TimeCommand {
+            team: "compiler".to_string()
+        }))
+    );
+}
+
+#[test]
+fn parse_missing_team() {
+    use std::error::Error;
+    assert_eq!(
+        parse("time").unwrap_err().source().unwrap().downcast_ref(),
+        Some(&ParseError::MissingTeam),
+    );
+}
+
+#[test]
+fn parse_other_command() {
+    assert_eq!(parse("timeout"), Ok(None));
+}
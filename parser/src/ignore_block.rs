@@ -1,6 +1,8 @@
 use pulldown_cmark::{Event, Parser, Tag, TagEnd};
 use std::ops::Range;
 
+/// The regions of a markdown document where commands are ignored: code
+/// blocks and spans, block quotes and HTML.
 #[derive(Debug)]
 pub struct IgnoreBlocks {
     ignore: Vec<Range<usize>>,
@@ -6,11 +6,25 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tera::{Context, Tera};
 
-use crate::github::{self, GithubClient, Repository};
+use crate::github::{self, GithubClient, IssuesPage, Page, Repository};
 
 #[async_trait]
 pub trait Action {
     async fn call(&self) -> anyhow::Result<String>;
+
+    /// Returns a page of the results of the query `name` on `repo` (like
+    /// `rust-lang/rust`), for the UIs loading the agenda incrementally.
+    ///
+    /// Returns `None` if there is no such query.
+    async fn query_page(
+        &self,
+        _gh: &GithubClient,
+        _name: &str,
+        _repo: &str,
+        _page: Page,
+    ) -> anyhow::Result<Option<IssuesPage>> {
+        Ok(None)
+    }
 }
 
 pub struct Step<'a> {
@@ -95,6 +109,36 @@ lazy_static! {
     };
 }
 
+/// Returns whether the results of the query `name` on `repo` include the FCP
+/// and the MCP details.
+fn includes_details(name: &str, repo: &Repository) -> (bool, bool) {
+    let fcps_groups = ["proposed_fcp", "in_pre_fcp", "in_fcp"];
+    let mcps_groups = [
+        "mcp_new_not_seconded",
+        "mcp_old_not_seconded",
+        "mcp_accepted",
+        "in_pre_fcp",
+        "in_fcp",
+    ];
+    (
+        fcps_groups.contains(&name),
+        mcps_groups.contains(&name) && repo.full_name.contains("rust-lang/compiler-team"),
+    )
+}
+
+/// Returns the repository `owner/name` for the queries, which only use its
+/// name.
+fn query_repository(owner: &str, name: &str) -> Repository {
+    Repository {
+        full_name: format!("{owner}/{name}"),
+        // These are unused for query.
+        default_branch: "master".to_string(),
+        fork: false,
+        archived: false,
+        parent: None,
+    }
+}
+
 pub fn to_human(d: DateTime<Utc>) -> String {
     let d1 = chrono::Utc::now() - d;
     let days = d1.num_days();
@@ -120,13 +164,7 @@ impl<'a> Action for Step<'a> {
 
         for Query { repos, queries } in &self.actions {
             for repo in repos {
-                let repository = Repository {
-                    full_name: format!("{}/{}", repo.0, repo.1),
-                    // These are unused for query.
-                    default_branch: "master".to_string(),
-                    fork: false,
-                    parent: None,
-                };
+                let repository = query_repository(repo.0, repo.1);
 
                 for QueryMap { name, kind, query } in queries {
                     let semaphore = semaphore.clone();
@@ -137,22 +175,10 @@ impl<'a> Action for Step<'a> {
                     let query = query.clone();
                     handles.push(tokio::task::spawn(async move {
                         let _permit = semaphore.acquire().await?;
-                        let fcps_groups = ["proposed_fcp", "in_pre_fcp", "in_fcp"];
-                        let mcps_groups = [
-                            "mcp_new_not_seconded",
-                            "mcp_old_not_seconded",
-                            "mcp_accepted",
-                            "in_pre_fcp",
-                            "in_fcp",
-                        ];
+                        let (include_fcp_details, include_mcp_details) =
+                            includes_details(&name, &repository);
                         let issues = query
-                            .query(
-                                &repository,
-                                fcps_groups.contains(&name.as_str()),
-                                mcps_groups.contains(&name.as_str())
-                                    && repository.full_name.contains("rust-lang/compiler-team"),
-                                &gh,
-                            )
+                            .query(&repository, include_fcp_details, include_mcp_details, &gh)
                             .await?;
                         Ok((name, kind, issues))
                     }));
@@ -190,4 +216,102 @@ impl<'a> Action for Step<'a> {
             .render(&format!("{}.tt", self.name), &context)
             .unwrap())
     }
+
+    async fn query_page(
+        &self,
+        gh: &GithubClient,
+        name: &str,
+        repo: &str,
+        page: Page,
+    ) -> anyhow::Result<Option<IssuesPage>> {
+        let query = self.actions.iter().find_map(|Query { repos, queries }| {
+            let (owner, repo_name) = repos
+                .iter()
+                .find(|(owner, repo_name)| format!("{owner}/{repo_name}") == repo)?;
+            let query = queries.iter().find(|query| query.name == name)?;
+            Some((query_repository(owner, repo_name), query))
+        });
+        let Some((repository, QueryMap { query, .. })) = query else {
+            return Ok(None);
+        };
+        let (include_fcp_details, include_mcp_details) = includes_details(name, &repository);
+        let page = query
+            .query_page(
+                &repository,
+                include_fcp_details,
+                include_mcp_details,
+                gh,
+                page,
+            )
+            .await?;
+        Ok(Some(page))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, test_pr_json, TestServer};
+
+    #[tokio::test]
+    async fn query_by_page() {
+        let issues: Vec<_> = (1..=3)
+            .map(|number| {
+                let mut issue = test_pr_json();
+                issue["number"] = serde_json::json!(number);
+                issue["title"] = serde_json::json!(format!("Issue {number}"));
+                issue
+            })
+            .collect();
+        let issues = serde_json::Value::from(issues).to_string();
+        let server = TestServer::start(vec![("200 OK", issues.clone()), ("200 OK", issues)]);
+        let gh = server.github_client();
+        let step = Step {
+            name: "lang_agenda",
+            actions: vec![Query {
+                repos: vec![("rust-lang", "rust")],
+                queries: vec![QueryMap {
+                    name: "nominated",
+                    kind: QueryKind::List,
+                    query: Arc::new(github::Query {
+                        filters: vec![("state", "open")],
+                        include_labels: vec!["I-lang-nominated"],
+                        exclude_labels: vec![],
+                    }),
+                }],
+            }],
+        };
+
+        let page = |cursor| Page { cursor, limit: 2 };
+        let first = step
+            .query_page(&gh, "nominated", "rust-lang/rust", page(0))
+            .await
+            .unwrap()
+            .unwrap();
+        let numbers = |page: &IssuesPage| page.issues.iter().map(|i| i.number).collect::<Vec<_>>();
+        assert_eq!(numbers(&first), [1, 2]);
+        assert_eq!(first.next_cursor, Some(2));
+        let last = step
+            .query_page(&gh, "nominated", "rust-lang/rust", page(2))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(numbers(&last), [3]);
+        assert_eq!(last.next_cursor, None);
+        // Unknown queries and repositories aren't fetched.
+        assert!(step
+            .query_page(&gh, "unknown", "rust-lang/rust", page(0))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(step
+            .query_page(&gh, "nominated", "rust-lang/cargo", page(0))
+            .await
+            .unwrap()
+            .is_none());
+
+        let url = "/repos/rust-lang/rust/issues?state=open&labels=I-lang-nominated&filter=all\
+                   &sort=created&direction=asc&per_page=100&page=1";
+        assert_requests(&server.finish(), &[expect("GET", url), expect("GET", url)]);
+    }
 }
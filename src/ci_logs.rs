@@ -0,0 +1,155 @@
+//! Extraction of the relevant parts of CI job logs.
+//!
+//! GitHub Actions logs are long and most of their content is noise. This
+//! looks for the markers rustc, cargo and libtest emit on failure and keeps
+//! only the lines around them.
+
+use chrono::DateTime;
+
+/// The maximum number of lines kept after a compiler error or a panic.
+const CONTEXT_LINES: usize = 10;
+
+/// Returns the most relevant lines of a job log, up to `max_lines`.
+///
+/// Lines following a recognized error marker are kept, up to the next empty
+/// line or marker. If the logs contain no marker, the last lines are returned
+/// instead.
+pub(crate) fn extract_snippet(logs: &str, max_lines: usize) -> String {
+    let lines: Vec<_> = logs.lines().map(strip_timestamp).collect();
+
+    let mut snippet = Vec::new();
+    let mut i = 0;
+    while i < lines.len() && snippet.len() < max_lines {
+        let Some(context) = error_marker_context(lines[i]) else {
+            i += 1;
+            continue;
+        };
+        snippet.push(lines[i]);
+        i += 1;
+        let end = (i + context).min(lines.len());
+        while i < end
+            && snippet.len() < max_lines
+            && !lines[i].trim().is_empty()
+            && error_marker_context(lines[i]).is_none()
+        {
+            snippet.push(lines[i]);
+            i += 1;
+        }
+    }
+
+    if snippet.is_empty() {
+        let non_empty: Vec<_> = lines
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        return non_empty[non_empty.len().saturating_sub(max_lines)..].join("\n");
+    }
+    snippet.join("\n")
+}
+
+/// If the line starts a failure message from rustc, cargo or libtest,
+/// returns how many of the following lines belong to the message.
+fn error_marker_context(line: &str) -> Option<usize> {
+    if line.starts_with("error[E") || (line.starts_with("thread '") && line.contains("' panicked"))
+    {
+        Some(CONTEXT_LINES)
+    } else if line.starts_with("test ") && line.ends_with(" ... FAILED") {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// Strips the `2024-04-20T10:31:31.1234567Z ` prefix GitHub adds to each line.
+fn strip_timestamp(line: &str) -> &str {
+    let (timestamp, rest) = line.split_once(' ').unwrap_or((line, ""));
+    if DateTime::parse_from_rfc3339(timestamp).is_ok() {
+        rest
+    } else {
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiler_error() {
+        let logs = "\
+2024-04-20T11:01:58.1234567Z    Compiling foo v0.1.0 (/checkout/foo)
+2024-04-20T11:01:59.1234567Z error[E0308]: mismatched types
+2024-04-20T11:01:59.1234567Z  --> src/lib.rs:2:5
+2024-04-20T11:01:59.1234567Z   |
+2024-04-20T11:01:59.1234567Z 1 | fn foo() -> u32 {
+2024-04-20T11:01:59.1234567Z   |             --- expected `u32` because of return type
+2024-04-20T11:01:59.1234567Z 2 |     \"foo\"
+2024-04-20T11:01:59.1234567Z   |     ^^^^^ expected `u32`, found `&str`
+2024-04-20T11:01:59.1234567Z
+2024-04-20T11:02:00.1234567Z For more information about this error, try `rustc --explain E0308`.
+2024-04-20T11:02:00.1234567Z error: could not compile `foo` (lib) due to 1 previous error
+2024-04-20T11:02:01.1234567Z ##[error]Process completed with exit code 101.
+";
+        assert_eq!(
+            extract_snippet(logs, 20),
+            "\
+error[E0308]: mismatched types
+ --> src/lib.rs:2:5
+  |
+1 | fn foo() -> u32 {
+  |             --- expected `u32` because of return type
+2 |     \"foo\"
+  |     ^^^^^ expected `u32`, found `&str`"
+        );
+    }
+
+    #[test]
+    fn panic() {
+        let logs = "\
+2024-04-20T11:01:58.1234567Z Building stage1 compiler artifacts
+2024-04-20T11:01:59.1234567Z thread 'main' panicked at src/bootstrap/src/core/builder.rs:1234:9:
+2024-04-20T11:01:59.1234567Z failed to run: cargo build
+2024-04-20T11:01:59.1234567Z note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+2024-04-20T11:02:01.1234567Z ##[error]Process completed with exit code 1.
+";
+        assert_eq!(
+            extract_snippet(logs, 2),
+            "\
+thread 'main' panicked at src/bootstrap/src/core/builder.rs:1234:9:
+failed to run: cargo build"
+        );
+    }
+
+    #[test]
+    fn failed_tests() {
+        let logs = "\
+2024-04-20T11:01:58.1234567Z running 4 tests
+2024-04-20T11:01:58.1234567Z test parser::ok ... ok
+2024-04-20T11:01:58.1234567Z test parser::empty ... FAILED
+2024-04-20T11:01:58.1234567Z test [ui] tests/ui/foo.rs ... FAILED
+2024-04-20T11:01:58.1234567Z test parser::long ... ok
+2024-04-20T11:01:58.1234567Z
+2024-04-20T11:01:59.1234567Z failures:
+";
+        assert_eq!(
+            extract_snippet(logs, 20),
+            "\
+test parser::empty ... FAILED
+test [ui] tests/ui/foo.rs ... FAILED"
+        );
+    }
+
+    #[test]
+    fn no_marker() {
+        let logs = "\
+2024-04-20T11:01:58.1234567Z Downloading artifacts
+2024-04-20T11:01:59.1234567Z
+2024-04-20T11:01:59.1234567Z ##[error]The operation was canceled.
+";
+        assert_eq!(
+            extract_snippet(logs, 20),
+            "Downloading artifacts\n##[error]The operation was canceled."
+        );
+        assert_eq!(extract_snippet("", 20), "");
+    }
+}
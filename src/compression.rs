@@ -0,0 +1,116 @@
+//! Gzip compression of the HTTP responses, for the clients accepting it.
+//!
+//! Some pages, like the agendas and the notifications, can be large, and
+//! compress well.
+
+use flate2::{write::GzEncoder, Compression};
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::{Body, Response};
+use std::io::Write as _;
+
+/// Smaller responses are sent as is, compressing them isn't worth it.
+const MIN_COMPRESSED_LEN: usize = 1024;
+
+/// Returns whether the `Accept-Encoding` header of a request allows gzip.
+pub fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|encoding| {
+            let mut params = encoding.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+            let disabled = params.any(|param| {
+                param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0)
+            });
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !disabled
+        })
+}
+
+/// Compresses the body of `response` if the client accepts gzip.
+pub async fn compress_response(
+    accepts_gzip: bool,
+    response: Response<Body>,
+) -> hyper::Result<Response<Body>> {
+    if !accepts_gzip || response.headers().contains_key(header::CONTENT_ENCODING) {
+        return Ok(response);
+    }
+    let (mut parts, body) = response.into_parts();
+    let body = hyper::body::to_bytes(body).await?;
+    if body.len() < MIN_COMPRESSED_LEN {
+        return Ok(Response::from_parts(parts, Body::from(body)));
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing to a `Vec` can't fail.
+    encoder.write_all(&body).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+    parts
+        .headers
+        .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    Ok(Response::from_parts(parts, Body::from(compressed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read as _;
+
+    fn accept_encoding(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn accept_encoding_header() {
+        assert!(accepts_gzip(&accept_encoding("gzip")));
+        assert!(accepts_gzip(&accept_encoding("deflate, gzip;q=1.0, br")));
+        assert!(accepts_gzip(&accept_encoding("*")));
+        assert!(!accepts_gzip(&accept_encoding("deflate, br")));
+        assert!(!accepts_gzip(&accept_encoding("gzip;q=0")));
+        assert!(!accepts_gzip(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn large_response_is_compressed_when_accepted() {
+        let page = "<li>rust-lang/rust#1234</li>\n".repeat(1000);
+        let response = || Response::new(Body::from(page.clone()));
+
+        let compressed = compress_response(true, response()).await.unwrap();
+        assert_eq!(compressed.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = hyper::body::to_bytes(compressed.into_body()).await.unwrap();
+        assert!(body.len() < page.len() / 10);
+        let mut decompressed = String::new();
+        GzDecoder::new(&body[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, page);
+
+        let uncompressed = compress_response(false, response()).await.unwrap();
+        assert!(!uncompressed
+            .headers()
+            .contains_key(header::CONTENT_ENCODING));
+        let body = hyper::body::to_bytes(uncompressed.into_body())
+            .await
+            .unwrap();
+        assert_eq!(body, page.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn small_response_is_not_compressed() {
+        let response = compress_response(true, Response::new(Body::from("processed request")))
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, "processed request");
+    }
+}
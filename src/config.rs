@@ -1,5 +1,8 @@
 use crate::changelogs::ChangelogFormat;
-use crate::github::{GithubClient, Repository};
+use crate::github::{AssigneeSuggestion, FileDiff, GithubClient, PushEvent, Repository};
+use anyhow::Context as _;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::{Arc, RwLock};
@@ -9,9 +12,16 @@ use tracing as log;
 pub(crate) static CONFIG_FILE_NAME: &str = "triagebot.toml";
 const REFRESH_EVERY: Duration = Duration::from_secs(2 * 60); // Every two minutes
 
+/// The repository and the head of its default branch a configuration was
+/// read at, `None` until a push to the branch is seen.
+type ConfigKey = (String, Option<String>);
+
 lazy_static::lazy_static! {
     static ref CONFIG_CACHE:
-        RwLock<HashMap<String, (Result<Arc<Config>, ConfigurationError>, Instant)>> =
+        RwLock<HashMap<ConfigKey, (Result<Arc<Config>, ConfigurationError>, Instant)>> =
+        RwLock::new(HashMap::new());
+    /// The head of the default branch of each repository, from its last push.
+    static ref DEFAULT_BRANCH_HEADS: RwLock<HashMap<String, String>> =
         RwLock::new(HashMap::new());
 }
 
@@ -46,8 +56,30 @@ pub(crate) struct Config {
     pub(crate) pr_tracking: Option<ReviewPrefsConfig>,
     pub(crate) transfer: Option<TransferConfig>,
     pub(crate) merge_conflicts: Option<MergeConflictConfig>,
+    pub(crate) active_hours: Option<ActiveHoursConfig>,
     pub(crate) bot_pull_requests: Option<BotPullRequests>,
     pub(crate) rendered_link: Option<RenderedLinkConfig>,
+    pub(crate) ci_failure_summary: Option<CiFailureSummaryConfig>,
+    pub(crate) retry_ci: Option<RetryCiConfig>,
+    pub(crate) subtree_update: Option<SubtreeUpdateConfig>,
+    pub(crate) stale_reviews: Option<StaleReviewsConfig>,
+    pub(crate) rollup: Option<RollupConfig>,
+    pub(crate) priority: Option<PriorityConfig>,
+    pub(crate) author_assignee: Option<AuthorAssigneeConfig>,
+    pub(crate) time: Option<TimeConfig>,
+    pub(crate) link_zulip: Option<LinkZulipConfig>,
+    pub(crate) review_pool: Option<ReviewPoolConfig>,
+    pub(crate) milestone_closed: Option<MilestoneClosedConfig>,
+    pub(crate) regression_labels: Option<RegressionLabelsConfig>,
+    pub(crate) issue_template: Option<IssueTemplateConfig>,
+    pub(crate) needs_mcve: Option<NeedsMcveConfig>,
+    pub(crate) markdown_links: Option<MarkdownLinksConfig>,
+    pub(crate) labels: Option<LabelsConfig>,
+    pub(crate) command_rate_limit: Option<CommandRateLimitConfig>,
+    pub(crate) reconcile: Option<ReconcileConfig>,
+    // The help is available even without the entry in the config file
+    #[serde(default = "HelpConfig::default")]
+    pub(crate) help: Option<HelpConfig>,
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
@@ -55,6 +87,12 @@ pub(crate) struct Config {
 pub(crate) struct NominateConfig {
     // team name -> label
     pub(crate) teams: HashMap<String, String>,
+    // team name -> Zulip stream where the nominations are announced
+    #[serde(default)]
+    pub(crate) zulip_streams: HashMap<String, u64>,
+    // label -> team name, nominates the issue when the label is added
+    #[serde(default)]
+    pub(crate) auto: HashMap<String, String>,
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
@@ -109,6 +147,13 @@ pub(crate) struct AssignConfig {
     pub(crate) owners: HashMap<String, Vec<String>>,
     #[serde(default)]
     pub(crate) users_on_vacation: HashSet<String>,
+    /// If enabled, a new PR touching files from several `owners` areas gets
+    /// one reviewer assigned per affected area instead of a single one.
+    #[serde(default)]
+    pub(crate) co_assign: bool,
+    /// A user or team to assign when no reviewer could be determined
+    /// otherwise, so that PRs don't go unassigned.
+    pub(crate) fallback_assignee: Option<String>,
 }
 
 impl AssignConfig {
@@ -118,6 +163,65 @@ impl AssignConfig {
             .iter()
             .any(|vacationer| name_lower == vacationer.to_lowercase())
     }
+
+    /// Returns the longest `owners` patterns that match the given path.
+    ///
+    /// This prefers deeply nested paths over those defined for top-level
+    /// paths, under the assumption that they are more specialized. Multiple
+    /// patterns are returned if several patterns of the same length match.
+    pub(crate) fn longest_owner_patterns(&self, path: &str) -> anyhow::Result<Vec<&str>> {
+        let mut longest = HashMap::new();
+        for owner_pattern in self.owners.keys() {
+            let ignore = ignore::gitignore::GitignoreBuilder::new("/")
+                .add_line(None, owner_pattern)
+                .with_context(|| format!("owner file pattern `{owner_pattern}` is not valid"))?
+                .build()?;
+            if ignore.matched_path_or_any_parents(path, false).is_ignore() {
+                let owner_len = owner_pattern.split('/').count();
+                longest.insert(owner_pattern.as_str(), owner_len);
+            }
+        }
+        let max_count = longest.values().copied().max().unwrap_or(0);
+        Ok(longest
+            .into_iter()
+            .filter(|(_, count)| *count == max_count)
+            .map(|(pattern, _)| pattern)
+            .collect())
+    }
+
+    /// Ranks the entries of `owners` by the number of files in `diff` they own.
+    ///
+    /// Each file is attributed to its longest matching patterns (see
+    /// [`AssignConfig::longest_owner_patterns`]), and each name listed for
+    /// those patterns scores one point per file. Names are returned as
+    /// written in the config (teams and ad-hoc groups are not expanded),
+    /// highest score first, with ties sorted by name.
+    pub(crate) fn assignee_suggestions(
+        &self,
+        diff: &[FileDiff],
+    ) -> anyhow::Result<Vec<AssigneeSuggestion>> {
+        let mut scores: HashMap<&str, u32> = HashMap::new();
+        for file_diff in diff {
+            let owners: HashSet<&str> = self
+                .longest_owner_patterns(&file_diff.path)?
+                .into_iter()
+                .flat_map(|pattern| &self.owners[pattern])
+                .map(|owner| owner.as_str())
+                .collect();
+            for owner in owners {
+                *scores.entry(owner).or_default() += 1;
+            }
+        }
+        let mut suggestions: Vec<_> = scores
+            .into_iter()
+            .map(|(name, score)| AssigneeSuggestion {
+                name: name.to_string(),
+                score,
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+        Ok(suggestions)
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
@@ -208,8 +312,10 @@ pub(crate) struct RelabelConfig {
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct ShortcutConfig {
+    /// If enabled, `ready` run by the PR author also marks a draft PR as
+    /// ready for review, and `author` converts the PR back to a draft.
     #[serde(default)]
-    _empty: (),
+    pub(crate) update_draft: bool,
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
@@ -227,6 +333,16 @@ impl ValidateConfig {
     }
 }
 
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct HelpConfig {}
+
+impl HelpConfig {
+    fn default() -> Option<Self> {
+        Some(HelpConfig {})
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
 pub(crate) struct AutolabelConfig {
     #[serde(flatten)]
@@ -357,20 +473,71 @@ pub(crate) async fn get(
     gh: &GithubClient,
     repo: &Repository,
 ) -> Result<Arc<Config>, ConfigurationError> {
-    if let Some(config) = get_cached_config(&repo.full_name) {
+    let key = config_key(&repo.full_name);
+    if let Some(config) = get_cached_config(&key) {
         log::trace!("returning config for {} from cache", repo.full_name);
         config
     } else {
         log::trace!("fetching fresh config for {}", repo.full_name);
-        let res = get_fresh_config(gh, repo).await;
+        let head = key.1.as_deref().unwrap_or(&repo.default_branch);
+        let res = get_fresh_config(gh, repo, head).await;
         CONFIG_CACHE
             .write()
             .unwrap()
-            .insert(repo.full_name.to_string(), (res.clone(), Instant::now()));
+            .insert(key, (res.clone(), Instant::now()));
         res
     }
 }
 
+/// The cache key of the current configuration of the repository.
+fn config_key(full_name: &str) -> ConfigKey {
+    let head = DEFAULT_BRANCH_HEADS.read().unwrap().get(full_name).cloned();
+    (full_name.to_string(), head)
+}
+
+/// Returns whether the repository has a valid configuration, so that the
+/// public endpoints don't run searches on any repository.
+pub(crate) async fn is_configured(gh: &GithubClient, full_name: &str) -> bool {
+    if let Some(config) = get_cached_config(&config_key(full_name)) {
+        return config.is_ok();
+    }
+    match gh.repository(full_name).await {
+        Ok(repo) => get(gh, &repo).await.is_ok(),
+        Err(e) => {
+            log::debug!("failed to get {full_name}: {e:?}");
+            false
+        }
+    }
+}
+
+/// Moves the cached configuration of the pushed repository to the new head
+/// of its default branch, or drops it if the push changed `triagebot.toml`.
+///
+/// Pushes that don't touch the configuration file keep the cached entry, so
+/// it is only refetched once it expires.
+pub(crate) fn invalidate_on_push(push: &PushEvent) {
+    if !push.is_default_branch() {
+        return;
+    }
+    let full_name = &push.repository.full_name;
+    let previous = DEFAULT_BRANCH_HEADS
+        .write()
+        .unwrap()
+        .insert(full_name.clone(), push.after.clone());
+    let mut cache = CONFIG_CACHE.write().unwrap();
+    let Some(cached) = cache.remove(&(full_name.clone(), previous)) else {
+        return;
+    };
+    if push.touches_path(CONFIG_FILE_NAME) {
+        log::debug!(
+            "invalidating cached config for {full_name} after push of {}",
+            push.after
+        );
+    } else {
+        cache.insert((full_name.clone(), Some(push.after.clone())), cached);
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
 #[serde(deny_unknown_fields)]
@@ -392,6 +559,305 @@ pub(crate) struct ReviewPrefsConfig {
 #[serde(deny_unknown_fields)]
 pub(crate) struct TransferConfig {}
 
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RetryCiConfig {}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SubtreeUpdateConfig {}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct StaleReviewsConfig {}
+
+/// The labels set by the `rollup` command for each level.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RollupConfig {
+    #[serde(default = "RollupConfig::default_always")]
+    pub(crate) always: String,
+    #[serde(default = "RollupConfig::default_maybe")]
+    pub(crate) maybe: String,
+    #[serde(default = "RollupConfig::default_iffy")]
+    pub(crate) iffy: String,
+    #[serde(default = "RollupConfig::default_never")]
+    pub(crate) never: String,
+}
+
+impl RollupConfig {
+    fn default_always() -> String {
+        "rollup".to_string()
+    }
+
+    fn default_maybe() -> String {
+        "rollup-".to_string()
+    }
+
+    fn default_iffy() -> String {
+        "rollup=iffy".to_string()
+    }
+
+    fn default_never() -> String {
+        "rollup=never".to_string()
+    }
+}
+
+/// The labels set by the `priority` command for each level.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PriorityConfig {
+    #[serde(default = "PriorityConfig::default_critical")]
+    pub(crate) critical: String,
+    #[serde(default = "PriorityConfig::default_high")]
+    pub(crate) high: String,
+    #[serde(default = "PriorityConfig::default_medium")]
+    pub(crate) medium: String,
+    #[serde(default = "PriorityConfig::default_low")]
+    pub(crate) low: String,
+}
+
+impl PriorityConfig {
+    fn default_critical() -> String {
+        "P-critical".to_string()
+    }
+
+    fn default_high() -> String {
+        "P-high".to_string()
+    }
+
+    fn default_medium() -> String {
+        "P-medium".to_string()
+    }
+
+    fn default_low() -> String {
+        "P-low".to_string()
+    }
+}
+
+/// The calendars used by the `time` command to find the next meeting of a
+/// team.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TimeConfig {
+    /// team name -> Google calendar id
+    pub(crate) calendars: HashMap<String, String>,
+}
+
+/// Allows users to link their GitHub account to their Zulip account.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LinkZulipConfig {}
+
+/// Allows team members to join the review pool of their team with
+/// `@rustbot assign-me-reviewer`.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ReviewPoolConfig {}
+
+/// Sets the milestone of a PR on the issues it closes.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MilestoneClosedConfig {
+    /// Whether to also do it when a PR is merged, not only with the command.
+    #[serde(default)]
+    pub(crate) on_merge: bool,
+}
+
+/// Labels the new issues reporting a regression.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RegressionLabelsConfig {
+    /// Regexes matching a regression report, with a `good` and a `bad`
+    /// capture group for the last working and the first broken versions.
+    #[serde(
+        default = "RegressionLabelsConfig::default_patterns",
+        deserialize_with = "regexes"
+    )]
+    pub(crate) patterns: Vec<regex::Regex>,
+}
+
+impl PartialEq for RegressionLabelsConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns.len() == other.patterns.len()
+            && self
+                .patterns
+                .iter()
+                .zip(&other.patterns)
+                .all(|(a, b)| a.as_str() == b.as_str())
+    }
+}
+
+impl Eq for RegressionLabelsConfig {}
+
+impl RegressionLabelsConfig {
+    fn default_patterns() -> Vec<regex::Regex> {
+        let pattern = concat!(
+            r"(?is)(?:used to (?:work|compile|build)|worked|works|compiled|compiles|builds)",
+            r"\s+(?:fine\s+)?(?:in|on|with)\s+(?:rust\s+)?(?P<good>[\w.-]*\w)",
+            r".{0,200}?",
+            r"(?:broken|breaks|fails?(?: to (?:compile|build))?|crash(?:es)?|errors?|ICEs?",
+            r"|(?:doesn't|does not|no longer) (?:work|compile|build))",
+            r"\s+(?:in|on|with|since)\s+(?:rust\s+)?(?P<bad>[\w.-]*\w)",
+        );
+        vec![regex::Regex::new(pattern).unwrap()]
+    }
+}
+
+fn regexes<'de, D>(deserializer: D) -> Result<Vec<regex::Regex>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let patterns: Vec<String> = serde::Deserialize::deserialize(deserializer)?;
+    patterns
+        .iter()
+        .map(|pattern| {
+            regex::Regex::new(pattern)
+                .map_err(|e| serde::de::Error::custom(format!("invalid regex `{pattern}`: {e}")))
+        })
+        .collect()
+}
+
+/// Asks for the sections of the issue template missing from new issues.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct IssueTemplateConfig {
+    /// The names of the sections a new issue must fill in, like
+    /// "Steps to reproduce".
+    pub(crate) required_sections: Vec<String>,
+    /// The label added until the sections are filled in.
+    #[serde(default = "IssueTemplateConfig::default_label")]
+    pub(crate) label: String,
+}
+
+impl IssueTemplateConfig {
+    fn default_label() -> String {
+        "needs-reproduction".to_string()
+    }
+}
+
+/// Notices the reproductions posted on the issues waiting for one.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NeedsMcveConfig {
+    /// The label of the issues waiting for a reproduction.
+    #[serde(default = "NeedsMcveConfig::default_label")]
+    pub(crate) label: String,
+    /// Who to ping to check the reproduction, like `@rust-lang/wg-triage`.
+    #[serde(default)]
+    pub(crate) ping: Option<String>,
+    /// Whether to remove the label when a reproduction is posted.
+    #[serde(default)]
+    pub(crate) remove_label: bool,
+    /// The minimum number of non-blank lines of the code block.
+    #[serde(default = "NeedsMcveConfig::default_min_lines")]
+    pub(crate) min_lines: usize,
+    /// The languages of the code block, `""` being a block without language.
+    #[serde(default = "NeedsMcveConfig::default_languages")]
+    pub(crate) languages: Vec<String>,
+}
+
+impl NeedsMcveConfig {
+    fn default_label() -> String {
+        "E-needs-mcve".to_string()
+    }
+
+    fn default_min_lines() -> usize {
+        3
+    }
+
+    fn default_languages() -> Vec<String> {
+        vec!["".to_string(), "rust".to_string(), "rs".to_string()]
+    }
+}
+
+/// Checks the relative links of the Markdown files changed by a PR.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MarkdownLinksConfig {
+    /// The directories whose Markdown files are checked, like `src/doc`.
+    /// All the Markdown files are checked if empty.
+    #[serde(default)]
+    pub(crate) paths: Vec<String>,
+}
+
+/// How the labels of the repository are managed.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LabelsConfig {
+    /// Whether the labels unknown to the repository are created when set
+    /// with `@rustbot label`, instead of being rejected.
+    #[serde(default)]
+    pub(crate) auto_create: bool,
+    /// The canonical labels of the repository, which the `label_sync` job
+    /// creates or updates.
+    #[serde(default)]
+    pub(crate) set: Vec<LabelDefinition>,
+    /// Whether the `label_sync` job applies the changes, rather than only
+    /// logging them.
+    #[serde(default)]
+    pub(crate) apply: bool,
+    /// The renamed labels, from their old name to their new one, so that
+    /// `@rustbot label` and `[autolabel]` keep working with the old names.
+    #[serde(default)]
+    pub(crate) aliases: HashMap<String, String>,
+}
+
+impl LabelsConfig {
+    /// Returns the current name of the label `name`, following the renames.
+    ///
+    /// A name whose renames form a cycle is returned unchanged.
+    pub(crate) fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        let mut current = name;
+        // A chain without cycle has at most one step per alias.
+        for _ in 0..=self.aliases.len() {
+            match self.aliases.get(current) {
+                Some(new) => current = new,
+                None => return current,
+            }
+        }
+        name
+    }
+}
+
+/// A label declared in `[[labels.set]]`.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LabelDefinition {
+    pub(crate) name: String,
+    /// The hex color, like `bfd4f2`.
+    pub(crate) color: String,
+    /// The description, left as is in the repository if missing.
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+}
+
+/// Warns when a PR waiting on review is assigned to its own author.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AuthorAssigneeConfig {
+    #[serde(default = "AuthorAssigneeConfig::default_waiting_on_review_label")]
+    pub(crate) waiting_on_review_label: String,
+}
+
+impl AuthorAssigneeConfig {
+    fn default_waiting_on_review_label() -> String {
+        "S-waiting-on-review".to_string()
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
 #[serde(deny_unknown_fields)]
@@ -404,11 +870,149 @@ pub(crate) struct MergeConflictConfig {
     pub unless: HashSet<String>,
 }
 
+/// The hours in which the bot posts its nudges, like the merge conflict
+/// warnings. The nudges due outside of these hours are deferred to the start
+/// of the next window.
+#[derive(Clone, PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ActiveHoursConfig {
+    /// The timezone of the hours, like `Europe/Berlin`.
+    #[serde(deserialize_with = "timezone")]
+    pub(crate) timezone: Tz,
+    /// The hour the window starts, from 0 to 23.
+    #[serde(deserialize_with = "hour")]
+    pub(crate) start: u32,
+    /// The hour the window ends, from 0 to 23. The window spans midnight if
+    /// it ends before it starts, and the whole day if they are equal.
+    #[serde(deserialize_with = "hour")]
+    pub(crate) end: u32,
+    /// Whether the windows starting on Saturdays and Sundays are skipped.
+    #[serde(default)]
+    pub(crate) weekdays_only: bool,
+}
+
+impl ActiveHoursConfig {
+    /// Returns the start of the next window if `now` is outside of the
+    /// active hours, `None` if a nudge can be posted now.
+    pub(crate) fn next_active(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = now.with_timezone(&self.timezone);
+        let hour = local.hour();
+        let in_window = if self.start < self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            self.start == self.end || hour >= self.start || hour < self.end
+        };
+        // The day the current window started on, for the windows spanning
+        // midnight.
+        let window_day = if self.start > self.end && hour < self.end {
+            local.date_naive().pred_opt()?
+        } else {
+            local.date_naive()
+        };
+        if in_window && !self.is_skipped(window_day) {
+            return None;
+        }
+
+        let start = NaiveTime::from_hms_opt(self.start, 0, 0)?;
+        (0..=7)
+            .filter_map(|days| local.date_naive().checked_add_days(chrono::Days::new(days)))
+            .filter(|day| !self.is_skipped(*day))
+            .filter_map(|day| {
+                self.timezone
+                    .from_local_datetime(&day.and_time(start))
+                    .earliest()
+            })
+            .find(|start| *start > local)
+            .map(|start| start.with_timezone(&Utc))
+    }
+
+    fn is_skipped(&self, day: NaiveDate) -> bool {
+        self.weekdays_only && matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+}
+
+fn timezone<'de, D>(deserializer: D) -> Result<Tz, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let name: String = serde::Deserialize::deserialize(deserializer)?;
+    name.parse()
+        .map_err(|_| serde::de::Error::custom(format!("unknown timezone `{name}`")))
+}
+
+fn hour<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let hour: u32 = serde::Deserialize::deserialize(deserializer)?;
+    if hour > 23 {
+        return Err(serde::de::Error::custom(format!(
+            "invalid hour {hour}, expected 0 to 23"
+        )));
+    }
+    Ok(hour)
+}
+
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
 #[serde(deny_unknown_fields)]
 pub(crate) struct BotPullRequests {}
 
+/// Limits how many commands of the same kind a user can run in a row.
+///
+/// Each user gets `burst` commands of each kind, and one more every
+/// `refill_seconds`.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CommandRateLimitConfig {
+    #[serde(default = "CommandRateLimitConfig::default_burst")]
+    pub(crate) burst: u32,
+    #[serde(default = "CommandRateLimitConfig::default_refill_seconds")]
+    pub(crate) refill_seconds: u64,
+}
+
+impl CommandRateLimitConfig {
+    fn default_burst() -> u32 {
+        5
+    }
+
+    fn default_refill_seconds() -> u64 {
+        60
+    }
+}
+
+/// What the reconcile job is allowed to fix in the repository.
+///
+/// The job leaves the repositories without this table alone.
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ReconcileConfig {
+    /// Restore the `new_pr` labels of the PRs without any state label.
+    #[serde(default)]
+    pub(crate) state_labels: bool,
+    /// Assign the PRs claimed with a reaction.
+    #[serde(default)]
+    pub(crate) reaction_claims: bool,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CiFailureSummaryConfig {
+    /// The minimum number of minutes between two summaries on the same PR.
+    #[serde(default = "CiFailureSummaryConfig::default_throttle_minutes")]
+    pub(crate) throttle_minutes: u32,
+}
+
+impl CiFailureSummaryConfig {
+    fn default_throttle_minutes() -> u32 {
+        60
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
 #[serde(deny_unknown_fields)]
@@ -416,9 +1020,9 @@ pub(crate) struct RenderedLinkConfig {
     pub(crate) trigger_files: Vec<String>,
 }
 
-fn get_cached_config(repo: &str) -> Option<Result<Arc<Config>, ConfigurationError>> {
+fn get_cached_config(key: &ConfigKey) -> Option<Result<Arc<Config>, ConfigurationError>> {
     let cache = CONFIG_CACHE.read().unwrap();
-    cache.get(repo).and_then(|(config, fetch_time)| {
+    cache.get(key).and_then(|(config, fetch_time)| {
         if fetch_time.elapsed() < REFRESH_EVERY {
             Some(config.clone())
         } else {
@@ -427,12 +1031,14 @@ fn get_cached_config(repo: &str) -> Option<Result<Arc<Config>, ConfigurationErro
     })
 }
 
+/// Reads the configuration of the repository at `head`, a branch or a commit.
 async fn get_fresh_config(
     gh: &GithubClient,
     repo: &Repository,
+    head: &str,
 ) -> Result<Arc<Config>, ConfigurationError> {
     let contents = gh
-        .raw_file(&repo.full_name, &repo.default_branch, CONFIG_FILE_NAME)
+        .raw_file(&repo.full_name, head, CONFIG_FILE_NAME)
         .await
         .map_err(|e| ConfigurationError::Http(Arc::new(e)))?
         .ok_or(ConfigurationError::Missing)?;
@@ -575,13 +1181,19 @@ mod tests {
                     adhoc_groups: HashMap::new(),
                     owners: HashMap::new(),
                     users_on_vacation: HashSet::from(["jyn514".into()]),
+                    co_assign: false,
+                    fallback_assignee: None,
                 }),
                 note: Some(NoteConfig { _empty: () }),
                 ping: Some(PingConfig { teams: ping_teams }),
                 nominate: Some(NominateConfig {
-                    teams: nominate_teams
+                    teams: nominate_teams,
+                    zulip_streams: HashMap::new(),
+                    auto: HashMap::new(),
+                }),
+                shortcut: Some(ShortcutConfig {
+                    update_draft: false
                 }),
-                shortcut: Some(ShortcutConfig { _empty: () }),
                 prioritize: None,
                 major_change: None,
                 glacier: None,
@@ -597,14 +1209,124 @@ mod tests {
                 pr_tracking: None,
                 transfer: None,
                 merge_conflicts: None,
+                active_hours: None,
                 bot_pull_requests: None,
                 rendered_link: Some(RenderedLinkConfig {
                     trigger_files: vec!["posts/".to_string()]
-                })
+                }),
+                ci_failure_summary: None,
+                retry_ci: None,
+                subtree_update: None,
+                stale_reviews: None,
+                rollup: None,
+                priority: None,
+                author_assignee: None,
+                time: None,
+                link_zulip: None,
+                review_pool: None,
+                milestone_closed: None,
+                regression_labels: None,
+                issue_template: None,
+                needs_mcve: None,
+                markdown_links: None,
+                labels: None,
+                command_rate_limit: None,
+                reconcile: None,
+                help: Some(HelpConfig {}),
             }
         );
     }
 
+    /// A push of `after`, whose commits modified the given files.
+    fn push_event(repo: &str, git_ref: &str, after: &str, commits: &[&[&str]]) -> PushEvent {
+        let commits: Vec<_> = commits
+            .iter()
+            .enumerate()
+            .map(|(i, modified)| {
+                serde_json::json!({
+                    "id": format!("{after}{i}"),
+                    "added": [],
+                    "removed": [],
+                    "modified": modified,
+                })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "after": after,
+            "ref": git_ref,
+            "commits": commits,
+            "repository": {
+                "full_name": repo,
+                "default_branch": "master",
+            },
+            "sender": {
+                "login": "octocat",
+                "id": 583231,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn cache_empty_config(repo: &str) {
+        let config = toml::from_str::<Config>("").unwrap();
+        CONFIG_CACHE
+            .write()
+            .unwrap()
+            .insert(config_key(repo), (Ok(Arc::new(config)), Instant::now()));
+    }
+
+    fn is_cached(repo: &str) -> bool {
+        get_cached_config(&config_key(repo)).is_some()
+    }
+
+    #[test]
+    fn push_invalidates_cached_config() {
+        let repo = "rust-lang/config-push-test";
+        cache_empty_config(repo);
+
+        invalidate_on_push(&push_event(
+            repo,
+            "refs/heads/master",
+            "a1",
+            &[&["src/lib.rs"]],
+        ));
+        assert!(is_cached(repo));
+        // The entry follows the head of the default branch.
+        assert_eq!(config_key(repo).1.as_deref(), Some("a1"));
+
+        invalidate_on_push(&push_event(
+            repo,
+            "refs/heads/beta",
+            "b1",
+            &[&[CONFIG_FILE_NAME]],
+        ));
+        assert!(is_cached(repo));
+        assert_eq!(config_key(repo).1.as_deref(), Some("a1"));
+
+        invalidate_on_push(&push_event(
+            repo,
+            "refs/heads/master",
+            "a2",
+            &[&[CONFIG_FILE_NAME]],
+        ));
+        assert!(!is_cached(repo));
+        assert_eq!(config_key(repo).1.as_deref(), Some("a2"));
+    }
+
+    #[test]
+    fn truncated_push_invalidates_cached_config() {
+        let repo = "rust-lang/config-truncated-push-test";
+        cache_empty_config(repo);
+        let commits = vec![&["src/lib.rs"][..]; crate::github::PUSH_MAX_COMMITS - 1];
+        invalidate_on_push(&push_event(repo, "refs/heads/master", "a1", &commits));
+        assert!(is_cached(repo));
+
+        // The commit changing the config may be one of those left out.
+        let commits = vec![&["src/lib.rs"][..]; crate::github::PUSH_MAX_COMMITS];
+        invalidate_on_push(&push_event(repo, "refs/heads/master", "a2", &commits));
+        assert!(!is_cached(repo));
+    }
+
     #[test]
     fn warn_non_default_branch() {
         let config = r#"
@@ -642,6 +1364,8 @@ mod tests {
                     adhoc_groups: HashMap::new(),
                     owners: HashMap::new(),
                     users_on_vacation: HashSet::new(),
+                    co_assign: false,
+                    fallback_assignee: None,
                 }),
                 note: None,
                 ping: None,
@@ -662,9 +1386,88 @@ mod tests {
                 pr_tracking: None,
                 transfer: None,
                 merge_conflicts: None,
+                active_hours: None,
                 bot_pull_requests: None,
                 rendered_link: None,
+                ci_failure_summary: None,
+                retry_ci: None,
+                subtree_update: None,
+                stale_reviews: None,
+                rollup: None,
+                priority: None,
+                author_assignee: None,
+                time: None,
+                link_zulip: None,
+                review_pool: None,
+                milestone_closed: None,
+                regression_labels: None,
+                issue_template: None,
+                needs_mcve: None,
+                markdown_links: None,
+                labels: None,
+                command_rate_limit: None,
+                reconcile: None,
+                help: Some(HelpConfig {}),
             }
         );
     }
+
+    #[test]
+    fn label_aliases() {
+        let config: LabelsConfig = toml::from_str(
+            r#"
+            [aliases]
+            "A-diagnostic" = "A-diagnostics"
+            "O-macos" = "O-apple"
+            "O-osx" = "O-macos"
+            "A-loop" = "A-cycle"
+            "A-cycle" = "A-loop"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.resolve("A-diagnostic"), "A-diagnostics");
+        assert_eq!(config.resolve("A-diagnostics"), "A-diagnostics");
+        assert_eq!(config.resolve("O-osx"), "O-apple");
+        assert_eq!(config.resolve("T-compiler"), "T-compiler");
+        // A cycle leaves the name alone rather than looping forever.
+        assert_eq!(config.resolve("A-loop"), "A-loop");
+        assert_eq!(config.resolve("A-cycle"), "A-cycle");
+    }
+
+    #[test]
+    fn active_hours() {
+        let config: ActiveHoursConfig = toml::from_str(
+            r#"
+            timezone = "Europe/Berlin"
+            start = 9
+            end = 18
+            weekdays-only = true
+            "#,
+        )
+        .unwrap();
+        let utc = |d, h, m| Utc.with_ymd_and_hms(2024, 5, d, h, m, 0).unwrap();
+
+        // Thursday 16:30 in Berlin.
+        assert_eq!(config.next_active(utc(16, 14, 30)), None);
+        // Thursday 21:00 in Berlin, deferred to Friday 9:00.
+        assert_eq!(config.next_active(utc(16, 19, 0)), Some(utc(17, 7, 0)));
+        // Friday 7:00 in Berlin.
+        assert_eq!(config.next_active(utc(17, 5, 0)), Some(utc(17, 7, 0)));
+        // Saturday 11:00 in Berlin, deferred to Monday 9:00.
+        assert_eq!(config.next_active(utc(18, 9, 0)), Some(utc(20, 7, 0)));
+
+        let config: ActiveHoursConfig =
+            toml::from_str("timezone = \"UTC\"\nstart = 22\nend = 6").unwrap();
+        assert_eq!(config.next_active(utc(16, 23, 0)), None);
+        assert_eq!(config.next_active(utc(17, 2, 0)), None);
+        assert_eq!(config.next_active(utc(17, 12, 0)), Some(utc(17, 22, 0)));
+
+        assert!(
+            toml::from_str::<ActiveHoursConfig>("timezone = \"Mars\"\nstart = 9\nend = 18")
+                .is_err()
+        );
+        assert!(
+            toml::from_str::<ActiveHoursConfig>("timezone = \"UTC\"\nstart = 9\nend = 24").is_err()
+        );
+    }
 }
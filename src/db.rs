@@ -1,4 +1,8 @@
-use crate::{db::jobs::*, handlers::Context, jobs::jobs};
+use crate::{
+    db::jobs::*,
+    handlers::Context,
+    jobs::{is_skipped, jobs, nudges_paused},
+};
 use anyhow::Context as _;
 use chrono::Utc;
 use native_tls::{Certificate, TlsConnector};
@@ -7,10 +11,16 @@ use std::sync::{Arc, Mutex};
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio_postgres::Client as DbClient;
 
+pub mod assign_notifications;
+pub mod assignment_history;
+pub mod deferred_nudges;
 pub mod issue_data;
 pub mod jobs;
+pub mod meeting_reminders;
 pub mod notifications;
+pub mod review_pools;
 pub mod rustc_commits;
+pub mod zulip_links;
 
 const CERT_URL: &str = "https://truststore.pki.rds.amazonaws.com/global/global-bundle.pem";
 
@@ -66,6 +76,16 @@ impl ClientPool {
         }
     }
 
+    /// Returns a pool handing out only `client`, like the one of
+    /// [`test_client`], so that handlers can be tested with a database.
+    #[cfg(test)]
+    pub(crate) fn with_client(client: DbClient) -> ClientPool {
+        ClientPool {
+            connections: Arc::new(Mutex::new(vec![client])),
+            permits: Arc::new(Semaphore::new(1)),
+        }
+    }
+
     pub async fn get(&self) -> PooledClient {
         let permit = self.permits.clone().acquire_owned().await.unwrap();
         {
@@ -151,6 +171,42 @@ fn cert() {
     make_certificates();
 }
 
+/// Connects to the database at `TEST_DATABASE_URL` with the migrations applied
+/// to a fresh schema, so that tests don't see each other's rows.
+///
+/// Returns `None` when the variable is unset, in which case database tests
+/// are skipped with a note on stderr. Panics if the database is set but
+/// unreachable, so that a misconfigured CI doesn't skip them silently.
+#[cfg(test)]
+pub(crate) async fn test_client() -> Option<DbClient> {
+    let Ok(db_url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!(
+            "skipping {}: TEST_DATABASE_URL is not set",
+            std::thread::current().name().unwrap_or("database test")
+        );
+        return None;
+    };
+    let (mut client, connection) = tokio_postgres::connect(&db_url, tokio_postgres::NoTls)
+        .await
+        .expect("failed to connect to the test DB");
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("database connection error: {}", e);
+        }
+    });
+    let schema = format!("test_{}", uuid::Uuid::new_v4().to_simple());
+    client
+        .batch_execute(&format!(
+            "CREATE SCHEMA {schema}; SET search_path TO {schema}, public;"
+        ))
+        .await
+        .expect("failed to create the test schema");
+    run_migrations(&mut client)
+        .await
+        .expect("failed to run the migrations");
+    Some(client)
+}
+
 pub async fn run_migrations(client: &mut DbClient) -> anyhow::Result<()> {
     client
         .execute(
@@ -206,9 +262,7 @@ pub async fn run_migrations(client: &mut DbClient) -> anyhow::Result<()> {
 
 pub async fn schedule_jobs(db: &DbClient, jobs: Vec<JobSchedule>) -> anyhow::Result<()> {
     for job in jobs {
-        let mut upcoming = job.schedule.upcoming(Utc).take(1);
-
-        if let Some(scheduled_at) = upcoming.next() {
+        if let Some(scheduled_at) = job.next_run(Utc::now()) {
             schedule_job(db, job.name, job.metadata, scheduled_at).await?;
         }
     }
@@ -265,6 +319,10 @@ async fn handle_job(
 ) -> anyhow::Result<()> {
     for job in jobs() {
         if &job.name() == &name {
+            if is_skipped(job.as_ref(), nudges_paused()) {
+                tracing::info!("skipping job {name}: the nudges are paused");
+                return Ok(());
+            }
             return job.run(ctx, metadata).await;
         }
     }
@@ -346,5 +404,62 @@ CREATE UNIQUE INDEX IF NOT EXISTS review_prefs_user_id ON review_prefs(user_id);
  ",
     "
 ALTER TABLE review_prefs ADD COLUMN IF NOT EXISTS max_assigned_prs INTEGER DEFAULT NULL;
+",
+    "
+CREATE TABLE assignment_history (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    repo TEXT NOT NULL,
+    pr_number INTEGER NOT NULL,
+    assignee TEXT,
+    candidates TEXT[] NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+",
+    "
+CREATE INDEX assignment_history_repo_pr_number ON assignment_history (repo, pr_number);
+",
+    "
+CREATE TABLE meeting_reminders (
+    meeting_id TEXT NOT NULL,
+    start_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    reminded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (meeting_id, start_time)
+);
+",
+    "
+CREATE TABLE assign_notifications (
+    user_id BIGINT PRIMARY KEY
+);
+",
+    "
+CREATE TABLE zulip_links (
+    github_id BIGINT PRIMARY KEY,
+    zulip_id BIGINT NOT NULL UNIQUE
+);
+",
+    "
+CREATE TABLE review_pools (
+    team TEXT NOT NULL,
+    user_id BIGINT NOT NULL,
+    PRIMARY KEY (team, user_id)
+);
+",
+    "
+CREATE TABLE deferred_nudges (
+    kind TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    issue_number BIGINT NOT NULL,
+    metadata JSONB NOT NULL,
+    run_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (kind, repo, issue_number)
+);
+",
+    "
+CREATE TABLE zulip_link_requests (
+    zulip_id BIGINT PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
 ",
 ];
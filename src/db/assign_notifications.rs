@@ -0,0 +1,54 @@
+//! The `assign_notifications` table records the users who opted in to be
+//! notified on Zulip when a PR is assigned to them.
+use anyhow::Context as _;
+use tokio_postgres::Client as DbClient;
+
+/// Opts the GitHub user in or out of the assignment notifications.
+pub async fn set_assign_notifications(
+    db: &DbClient,
+    user_id: u64,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    tracing::trace!("set_assign_notifications(user_id={user_id}, enabled={enabled})");
+    let q = if enabled {
+        "INSERT INTO assign_notifications (user_id) VALUES ($1) ON CONFLICT DO NOTHING"
+    } else {
+        "DELETE FROM assign_notifications WHERE user_id = $1"
+    };
+    db.execute(q, &[&(user_id as i64)])
+        .await
+        .context("updating assign notifications")?;
+    Ok(())
+}
+
+/// Whether the GitHub user opted in to the assignment notifications.
+pub async fn wants_assign_notifications(db: &DbClient, user_id: u64) -> anyhow::Result<bool> {
+    let row = db
+        .query_opt(
+            "SELECT 1 FROM assign_notifications WHERE user_id = $1",
+            &[&(user_id as i64)],
+        )
+        .await
+        .context("querying assign notifications")?;
+    Ok(row.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn opt_in_and_out() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        assert!(!wants_assign_notifications(&db, 583231).await.unwrap());
+        set_assign_notifications(&db, 583231, true).await.unwrap();
+        // Opting in twice is fine.
+        set_assign_notifications(&db, 583231, true).await.unwrap();
+        assert!(wants_assign_notifications(&db, 583231).await.unwrap());
+        assert!(!wants_assign_notifications(&db, 1).await.unwrap());
+        set_assign_notifications(&db, 583231, false).await.unwrap();
+        assert!(!wants_assign_notifications(&db, 583231).await.unwrap());
+    }
+}
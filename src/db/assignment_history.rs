@@ -0,0 +1,236 @@
+//! The `assignment_history` table records every reviewer assignment decision
+//! made by the `assign` handler, so that the selection algorithm can be
+//! audited and tuned.
+
+use crate::github::Issue;
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::str::FromStr;
+use tokio_postgres::Client as DbClient;
+
+/// Why a reviewer was (or was not) chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssignmentReason {
+    /// Requested with `r?` in the PR body or a comment.
+    ReviewCommand,
+    /// Picked from the `owners` matching the PR diff.
+    Diff,
+    /// Picked from the `fallback` ad-hoc group.
+    FallbackGroup,
+    /// Picked from the `fallback_assignee` config.
+    FallbackAssignee,
+    /// An additional reviewer picked in `co_assign` mode.
+    CoAssign,
+    /// No reviewer could be determined.
+    NoneFound,
+    /// Claimed by a reviewer with a reaction to the bot comment.
+    ReactionClaim,
+}
+
+impl AssignmentReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AssignmentReason::ReviewCommand => "review-command",
+            AssignmentReason::Diff => "diff",
+            AssignmentReason::FallbackGroup => "fallback-group",
+            AssignmentReason::FallbackAssignee => "fallback-assignee",
+            AssignmentReason::CoAssign => "co-assign",
+            AssignmentReason::NoneFound => "none-found",
+            AssignmentReason::ReactionClaim => "reaction-claim",
+        }
+    }
+}
+
+impl fmt::Display for AssignmentReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AssignmentReason {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<AssignmentReason> {
+        Ok(match s {
+            "review-command" => AssignmentReason::ReviewCommand,
+            "diff" => AssignmentReason::Diff,
+            "fallback-group" => AssignmentReason::FallbackGroup,
+            "fallback-assignee" => AssignmentReason::FallbackAssignee,
+            "co-assign" => AssignmentReason::CoAssign,
+            "none-found" => AssignmentReason::NoneFound,
+            "reaction-claim" => AssignmentReason::ReactionClaim,
+            _ => anyhow::bail!("unknown assignment reason `{s}`"),
+        })
+    }
+}
+
+/// A single assignment decision.
+#[derive(Debug, serde::Serialize)]
+pub struct AssignmentRecord {
+    pub repo: String,
+    pub pr_number: u64,
+    /// The chosen reviewer, if any.
+    pub assignee: Option<String>,
+    /// The names the reviewer was chosen from.
+    pub candidates: Vec<String>,
+    pub reason: AssignmentReason,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Records an assignment decision for the given PR.
+pub async fn record_assignment(
+    db: &DbClient,
+    issue: &Issue,
+    assignee: Option<&str>,
+    candidates: &[String],
+    reason: AssignmentReason,
+) -> anyhow::Result<()> {
+    tracing::trace!(
+        "record_assignment(pr={}, assignee={assignee:?}, reason={reason})",
+        issue.global_id()
+    );
+    db.execute(
+        "INSERT INTO assignment_history (repo, pr_number, assignee, candidates, reason) \
+         VALUES ($1, $2, $3, $4, $5)",
+        &[
+            &issue.repository().to_string(),
+            &(issue.number as i32),
+            &assignee,
+            &candidates,
+            &reason.as_str(),
+        ],
+    )
+    .await
+    .context("inserting assignment history")?;
+    Ok(())
+}
+
+/// Returns the most recent assignment decisions, newest first.
+///
+/// The results can be narrowed down to a repository (e.g. `rust-lang/rust`)
+/// and a PR number within it.
+pub async fn get_assignment_history(
+    db: &DbClient,
+    repo: Option<&str>,
+    pr_number: Option<u64>,
+    limit: u32,
+) -> anyhow::Result<Vec<AssignmentRecord>> {
+    let pr_number = pr_number.map(|n| n as i32);
+    let rows = db
+        .query(
+            "SELECT repo, pr_number, assignee, candidates, reason, created_at
+             FROM assignment_history
+             WHERE ($1::TEXT IS NULL OR repo = $1)
+             AND ($2::INTEGER IS NULL OR pr_number = $2)
+             ORDER BY created_at DESC
+             LIMIT $3",
+            &[&repo, &pr_number, &(limit as i64)],
+        )
+        .await
+        .context("querying assignment history")?;
+
+    rows.into_iter()
+        .map(|row| {
+            let pr_number: i32 = row.get(1);
+            let reason: &str = row.get(4);
+            Ok(AssignmentRecord {
+                repo: row.get(0),
+                pr_number: pr_number as u64,
+                assignee: row.get(2),
+                candidates: row.get(3),
+                reason: reason.parse()?,
+                created_at: row.get(5),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reason_roundtrip() {
+        for reason in [
+            AssignmentReason::ReviewCommand,
+            AssignmentReason::Diff,
+            AssignmentReason::FallbackGroup,
+            AssignmentReason::FallbackAssignee,
+            AssignmentReason::CoAssign,
+            AssignmentReason::NoneFound,
+            AssignmentReason::ReactionClaim,
+        ] {
+            assert_eq!(
+                reason.to_string().parse::<AssignmentReason>().unwrap(),
+                reason
+            );
+            assert_eq!(
+                serde_json::to_value(reason).unwrap(),
+                serde_json::json!(reason.as_str())
+            );
+        }
+        assert!("random".parse::<AssignmentReason>().is_err());
+    }
+
+    #[tokio::test]
+    async fn record_and_query() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let pr = crate::test_server::test_pr();
+        let candidates = vec!["alice".to_string(), "bob".to_string()];
+        record_assignment(&db, &pr, None, &[], AssignmentReason::NoneFound)
+            .await
+            .unwrap();
+        record_assignment(&db, &pr, Some("bob"), &candidates, AssignmentReason::Diff)
+            .await
+            .unwrap();
+
+        let history = get_assignment_history(&db, Some("rust-lang/rust"), Some(pr.number), 100)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].repo, "rust-lang/rust");
+        assert_eq!(history[0].pr_number, pr.number);
+        assert_eq!(history[0].assignee.as_deref(), Some("bob"));
+        assert_eq!(history[0].candidates, candidates);
+        assert_eq!(history[0].reason, AssignmentReason::Diff);
+        assert_eq!(history[1].assignee, None);
+        assert_eq!(history[1].reason, AssignmentReason::NoneFound);
+
+        let limited = get_assignment_history(&db, None, None, 1).await.unwrap();
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].reason, AssignmentReason::Diff);
+    }
+
+    #[tokio::test]
+    async fn query_filters() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let pr = crate::test_server::test_pr();
+        record_assignment(
+            &db,
+            &pr,
+            Some("alice"),
+            &[],
+            AssignmentReason::ReviewCommand,
+        )
+        .await
+        .unwrap();
+
+        let other_pr =
+            get_assignment_history(&db, Some("rust-lang/rust"), Some(pr.number + 1), 100)
+                .await
+                .unwrap();
+        assert!(other_pr.is_empty());
+        let other_repo = get_assignment_history(&db, Some("rust-lang/cargo"), None, 100)
+            .await
+            .unwrap();
+        assert!(other_repo.is_empty());
+        let all = get_assignment_history(&db, None, None, 100).await.unwrap();
+        assert_eq!(all.len(), 1);
+    }
+}
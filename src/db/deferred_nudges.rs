@@ -0,0 +1,143 @@
+//! The `deferred_nudges` table records the nudges that were due outside of
+//! the active hours of their repository, see
+//! [`crate::handlers::deferred_nudges`].
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use tokio_postgres::Client as DbClient;
+
+#[derive(Debug, PartialEq)]
+pub struct DeferredNudge {
+    /// The kind of nudge, like `merge-conflicts`.
+    pub kind: String,
+    /// The full name of the repository, like `rust-lang/rust`.
+    pub repo: String,
+    pub issue_number: u64,
+    /// Data specific to the kind of nudge.
+    pub metadata: serde_json::Value,
+    pub run_at: DateTime<Utc>,
+}
+
+/// Defers a nudge to `run_at`, replacing the previous deferral of the same
+/// kind on the same issue.
+pub async fn defer_nudge(db: &DbClient, nudge: &DeferredNudge) -> anyhow::Result<()> {
+    tracing::trace!("defer_nudge({nudge:?})");
+    db.execute(
+        "INSERT INTO deferred_nudges (kind, repo, issue_number, metadata, run_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (kind, repo, issue_number)
+        DO UPDATE SET metadata = EXCLUDED.metadata, run_at = EXCLUDED.run_at",
+        &[
+            &nudge.kind,
+            &nudge.repo,
+            &(nudge.issue_number as i64),
+            &nudge.metadata,
+            &nudge.run_at,
+        ],
+    )
+    .await
+    .context("deferring nudge")?;
+    Ok(())
+}
+
+/// Returns the nudges due at `now`, oldest first.
+///
+/// They stay in the table until removed with [`remove_nudge`] once posted,
+/// so that a failed nudge is retried.
+pub async fn due_nudges(db: &DbClient, now: DateTime<Utc>) -> anyhow::Result<Vec<DeferredNudge>> {
+    let rows = db
+        .query(
+            "SELECT kind, repo, issue_number, metadata, run_at FROM deferred_nudges
+            WHERE run_at <= $1 ORDER BY run_at",
+            &[&now],
+        )
+        .await
+        .context("querying due nudges")?;
+    Ok(rows
+        .iter()
+        .map(|row| DeferredNudge {
+            kind: row.get(0),
+            repo: row.get(1),
+            issue_number: row.get::<_, i64>(2) as u64,
+            metadata: row.get(3),
+            run_at: row.get(4),
+        })
+        .collect())
+}
+
+/// Removes a nudge returned by [`due_nudges`], unless it was deferred again
+/// in the meantime.
+pub async fn remove_nudge(db: &DbClient, nudge: &DeferredNudge) -> anyhow::Result<()> {
+    db.execute(
+        "DELETE FROM deferred_nudges
+        WHERE kind = $1 AND repo = $2 AND issue_number = $3 AND run_at = $4",
+        &[
+            &nudge.kind,
+            &nudge.repo,
+            &(nudge.issue_number as i64),
+            &nudge.run_at,
+        ],
+    )
+    .await
+    .context("removing deferred nudge")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn nudge(issue_number: u64, run_at: DateTime<Utc>) -> DeferredNudge {
+        DeferredNudge {
+            kind: "merge-conflicts".to_string(),
+            repo: "rust-lang/rust".to_string(),
+            issue_number,
+            metadata: serde_json::json!({ "possibly": null }),
+            run_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn due_nudges_stay_until_removed() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let now = Utc.with_ymd_and_hms(2024, 5, 16, 8, 0, 0).unwrap();
+        defer_nudge(&db, &nudge(1, now - Duration::hours(1)))
+            .await
+            .unwrap();
+        defer_nudge(&db, &nudge(2, now + Duration::hours(1)))
+            .await
+            .unwrap();
+
+        let due = due_nudges(&db, now).await.unwrap();
+        assert_eq!(due, [nudge(1, now - Duration::hours(1))]);
+        // Not posted yet, so still due.
+        assert_eq!(due_nudges(&db, now).await.unwrap(), due);
+
+        remove_nudge(&db, &due[0]).await.unwrap();
+        assert!(due_nudges(&db, now).await.unwrap().is_empty());
+        assert_eq!(
+            due_nudges(&db, now + Duration::hours(1)).await.unwrap(),
+            [nudge(2, now + Duration::hours(1))]
+        );
+    }
+
+    #[tokio::test]
+    async fn nudge_deferred_again_is_kept() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let now = Utc.with_ymd_and_hms(2024, 5, 16, 8, 0, 0).unwrap();
+        let first = nudge(1, now);
+        defer_nudge(&db, &first).await.unwrap();
+        defer_nudge(&db, &nudge(1, now + Duration::days(1)))
+            .await
+            .unwrap();
+        remove_nudge(&db, &first).await.unwrap();
+        assert_eq!(
+            due_nudges(&db, now + Duration::days(1)).await.unwrap(),
+            [nudge(1, now + Duration::days(1))]
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! The `jobs` table provides a way to have scheduled jobs
 use anyhow::{Context as _, Result};
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use cron::Schedule;
 use serde::{Deserialize, Serialize};
 use tokio_postgres::Client as DbClient;
@@ -9,9 +10,22 @@ use uuid::Uuid;
 pub struct JobSchedule {
     pub name: &'static str,
     pub schedule: Schedule,
+    /// The timezone in which the schedule is evaluated, so that a job can
+    /// keep running at the same local time across DST changes.
+    pub timezone: Tz,
     pub metadata: serde_json::Value,
 }
 
+impl JobSchedule {
+    /// Returns when the job should run next, strictly after `after`.
+    pub fn next_run(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.timezone))
+            .next()
+            .map(|t| t.with_timezone(&Utc))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Job {
     pub id: Uuid,
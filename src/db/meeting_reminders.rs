@@ -0,0 +1,58 @@
+//! The `meeting_reminders` table records the meetings that were reminded, so
+//! that each meeting is only reminded once.
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use tokio_postgres::Client as DbClient;
+
+/// Whether the meeting starting at `start` was already reminded.
+pub async fn was_reminded(
+    db: &DbClient,
+    meeting_id: &str,
+    start: DateTime<Utc>,
+) -> anyhow::Result<bool> {
+    let row = db
+        .query_opt(
+            "SELECT 1 FROM meeting_reminders WHERE meeting_id = $1 AND start_time = $2",
+            &[&meeting_id, &start],
+        )
+        .await
+        .context("querying meeting reminder")?;
+    Ok(row.is_some())
+}
+
+/// Records that the meeting starting at `start` was reminded.
+pub async fn record_reminder(
+    db: &DbClient,
+    meeting_id: &str,
+    start: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    tracing::trace!("record_reminder(meeting_id={meeting_id}, start={start})");
+    db.execute(
+        "INSERT INTO meeting_reminders (meeting_id, start_time) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        &[&meeting_id, &start],
+    )
+    .await
+    .context("recording meeting reminder")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn reminded_once_recorded() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let start = Utc.with_ymd_and_hms(2024, 5, 16, 14, 0, 0).unwrap();
+        assert!(!was_reminded(&db, "triage", start).await.unwrap());
+        record_reminder(&db, "triage", start).await.unwrap();
+        record_reminder(&db, "triage", start).await.unwrap();
+        assert!(was_reminded(&db, "triage", start).await.unwrap());
+        // The next occurrence of a recurring meeting has the same id.
+        let next_week = start + chrono::Duration::days(7);
+        assert!(!was_reminded(&db, "triage", next_week).await.unwrap());
+    }
+}
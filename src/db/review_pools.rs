@@ -0,0 +1,121 @@
+//! The `review_pools` table records the team members who opted in to the
+//! round-robin review assignment of their team.
+use anyhow::Context as _;
+use std::collections::{HashMap, HashSet};
+use tokio_postgres::Client as DbClient;
+
+/// Adds the GitHub user to the review pool of `team`, or removes them.
+pub async fn set_review_pool_member(
+    db: &DbClient,
+    team: &str,
+    user_id: u64,
+    member: bool,
+) -> anyhow::Result<()> {
+    tracing::trace!("set_review_pool_member(team={team}, user_id={user_id}, member={member})");
+    let q = if member {
+        "INSERT INTO review_pools (team, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+    } else {
+        "DELETE FROM review_pools WHERE team = $1 AND user_id = $2"
+    };
+    db.execute(q, &[&team, &(user_id as i64)])
+        .await
+        .context("updating review pool")?;
+    Ok(())
+}
+
+/// Returns the teams whose review pool the GitHub user is in.
+pub async fn user_review_pools(db: &DbClient, user_id: u64) -> anyhow::Result<Vec<String>> {
+    let rows = db
+        .query(
+            "SELECT team FROM review_pools WHERE user_id = $1 ORDER BY team",
+            &[&(user_id as i64)],
+        )
+        .await
+        .context("querying review pools of user")?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Returns the usernames of the review pool members of the given teams.
+///
+/// Teams without members are not included.
+pub async fn review_pools(
+    db: &DbClient,
+    teams: &[&str],
+) -> anyhow::Result<HashMap<String, HashSet<String>>> {
+    let rows = db
+        .query(
+            "SELECT team, username
+            FROM review_pools
+            JOIN users ON users.user_id = review_pools.user_id
+            WHERE team = ANY($1)",
+            &[&teams],
+        )
+        .await
+        .context("querying review pools")?;
+    let mut pools: HashMap<String, HashSet<String>> = HashMap::new();
+    for row in rows {
+        pools.entry(row.get(0)).or_default().insert(row.get(1));
+    }
+    Ok(pools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::notifications::record_username;
+
+    #[tokio::test]
+    async fn join_and_leave_review_pools() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        set_review_pool_member(&db, "compiler", 1, true)
+            .await
+            .unwrap();
+        set_review_pool_member(&db, "compiler", 1, true)
+            .await
+            .unwrap();
+        set_review_pool_member(&db, "libs", 1, true).await.unwrap();
+        assert_eq!(
+            user_review_pools(&db, 1).await.unwrap(),
+            ["compiler", "libs"]
+        );
+        set_review_pool_member(&db, "compiler", 1, false)
+            .await
+            .unwrap();
+        assert_eq!(user_review_pools(&db, 1).await.unwrap(), ["libs"]);
+        assert!(user_review_pools(&db, 2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn review_pools_by_team() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        record_username(&db, 1, "alice").await.unwrap();
+        record_username(&db, 2, "bob").await.unwrap();
+        record_username(&db, 3, "carol").await.unwrap();
+        set_review_pool_member(&db, "compiler", 1, true)
+            .await
+            .unwrap();
+        set_review_pool_member(&db, "compiler", 2, true)
+            .await
+            .unwrap();
+        set_review_pool_member(&db, "libs", 3, true).await.unwrap();
+        set_review_pool_member(&db, "types", 3, true).await.unwrap();
+
+        let pools = review_pools(&db, &["compiler", "libs", "lang"])
+            .await
+            .unwrap();
+        assert_eq!(
+            pools,
+            HashMap::from([
+                (
+                    "compiler".to_string(),
+                    HashSet::from(["alice".to_string(), "bob".to_string()])
+                ),
+                ("libs".to_string(), HashSet::from(["carol".to_string()])),
+            ])
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! The `zulip_links` table maps GitHub users to their Zulip account, for the
+//! users who linked it themselves.
+//!
+//! A link is made in two steps, so that it is confirmed by both accounts:
+//! the Zulip user asks for a code with the `link-github` Zulip command, which
+//! is stored in the `zulip_link_requests` table, and the GitHub user posts it
+//! with the `link-zulip` command.
+//!
+//! The `zulip-id` of the team repository takes precedence over the links,
+//! see [`crate::zulip::lookup_zulip_id`].
+use anyhow::Context as _;
+use chrono::{DateTime, Duration, Utc};
+use tokio_postgres::Client as DbClient;
+
+/// How long a code of the `link-github` Zulip command can be used.
+pub const LINK_REQUEST_VALIDITY: Duration = Duration::hours(1);
+
+/// Records a new link request of the Zulip user, replacing their previous
+/// one, and returns its code.
+pub async fn create_link_request(db: &DbClient, zulip_id: u64) -> anyhow::Result<String> {
+    let code = uuid::Uuid::new_v4().to_simple().to_string();
+    db.execute(
+        "INSERT INTO zulip_link_requests (zulip_id, code) VALUES ($1, $2)
+         ON CONFLICT (zulip_id) DO UPDATE SET code = EXCLUDED.code, created_at = NOW()",
+        &[&(zulip_id as i64), &code],
+    )
+    .await
+    .context("recording zulip link request")?;
+    Ok(code)
+}
+
+/// Consumes the link request with the `code`, returning the Zulip user who
+/// made it, unless it is unknown or older than `now - LINK_REQUEST_VALIDITY`.
+pub async fn take_link_request(
+    db: &DbClient,
+    code: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<u64>> {
+    let row = db
+        .query_opt(
+            "DELETE FROM zulip_link_requests WHERE code = $1 RETURNING zulip_id, created_at",
+            &[&code],
+        )
+        .await
+        .context("taking zulip link request")?;
+    Ok(row.and_then(|row| {
+        let created_at: DateTime<Utc> = row.get(1);
+        (now - created_at < LINK_REQUEST_VALIDITY).then(|| row.get::<_, i64>(0) as u64)
+    }))
+}
+
+/// Links the GitHub user to the Zulip user, replacing any previous link of
+/// either of them.
+pub async fn set_zulip_link(db: &DbClient, github_id: u64, zulip_id: u64) -> anyhow::Result<()> {
+    tracing::trace!("set_zulip_link(github_id={github_id}, zulip_id={zulip_id})");
+    db.execute(
+        "DELETE FROM zulip_links WHERE zulip_id = $1",
+        &[&(zulip_id as i64)],
+    )
+    .await
+    .context("removing previous zulip link")?;
+    db.execute(
+        "INSERT INTO zulip_links (github_id, zulip_id) VALUES ($1, $2)
+         ON CONFLICT (github_id) DO UPDATE SET zulip_id = EXCLUDED.zulip_id",
+        &[&(github_id as i64), &(zulip_id as i64)],
+    )
+    .await
+    .context("recording zulip link")?;
+    Ok(())
+}
+
+/// Returns the Zulip user linked to the GitHub user, if any.
+pub async fn get_zulip_link(db: &DbClient, github_id: u64) -> anyhow::Result<Option<u64>> {
+    let row = db
+        .query_opt(
+            "SELECT zulip_id FROM zulip_links WHERE github_id = $1",
+            &[&(github_id as i64)],
+        )
+        .await
+        .context("querying zulip link")?;
+    Ok(row.map(|row| row.get::<_, i64>(0) as u64))
+}
+
+/// Returns the GitHub user linked to the Zulip user, if any.
+pub async fn get_github_link(db: &DbClient, zulip_id: u64) -> anyhow::Result<Option<u64>> {
+    let row = db
+        .query_opt(
+            "SELECT github_id FROM zulip_links WHERE zulip_id = $1",
+            &[&(zulip_id as i64)],
+        )
+        .await
+        .context("querying zulip link")?;
+    Ok(row.map(|row| row.get::<_, i64>(0) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn link_request_is_used_once() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let old = create_link_request(&db, 123456).await.unwrap();
+        let code = create_link_request(&db, 123456).await.unwrap();
+        // A new request replaces the previous one.
+        assert_eq!(
+            take_link_request(&db, &old, Utc::now()).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            take_link_request(&db, &code, Utc::now()).await.unwrap(),
+            Some(123456)
+        );
+        assert_eq!(
+            take_link_request(&db, &code, Utc::now()).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_link_request() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let code = create_link_request(&db, 123456).await.unwrap();
+        let later = Utc::now() + LINK_REQUEST_VALIDITY;
+        assert_eq!(take_link_request(&db, &code, later).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn set_and_get_link() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        assert_eq!(get_zulip_link(&db, 583231).await.unwrap(), None);
+        set_zulip_link(&db, 583231, 123456).await.unwrap();
+        set_zulip_link(&db, 583231, 654321).await.unwrap();
+        assert_eq!(get_zulip_link(&db, 583231).await.unwrap(), Some(654321));
+        assert_eq!(get_github_link(&db, 654321).await.unwrap(), Some(583231));
+        assert_eq!(get_github_link(&db, 123456).await.unwrap(), None);
+        // Another GitHub user takes over the Zulip account.
+        set_zulip_link(&db, 47979223, 654321).await.unwrap();
+        assert_eq!(get_github_link(&db, 654321).await.unwrap(), Some(47979223));
+        assert_eq!(get_zulip_link(&db, 583231).await.unwrap(), None);
+    }
+}
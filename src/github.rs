@@ -6,11 +6,12 @@ use futures::{future::BoxFuture, FutureExt};
 use hyper::header::HeaderValue;
 use once_cell::sync::OnceCell;
 use regex::Regex;
-use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use reqwest::header::{HeaderMap, AUTHORIZATION, ETAG, IF_NONE_MATCH, LINK, USER_AGENT};
 use reqwest::{Client, Request, RequestBuilder, Response, StatusCode};
 use std::collections::{HashMap, HashSet};
 use std::{
     fmt,
+    sync::{Arc, Mutex},
     time::{Duration, SystemTime},
 };
 use tracing as log;
@@ -23,6 +24,15 @@ pub struct User {
 
 impl GithubClient {
     async fn send_req(&self, req: RequestBuilder) -> anyhow::Result<(Bytes, String)> {
+        let (body, _headers, req_dbg) = self.send_req_with_headers(req).await?;
+        Ok((body, req_dbg))
+    }
+
+    /// Like [`GithubClient::send_req`], also returning the response headers.
+    async fn send_req_with_headers(
+        &self,
+        req: RequestBuilder,
+    ) -> anyhow::Result<(Bytes, HeaderMap, String)> {
         const MAX_ATTEMPTS: u32 = 2;
         log::debug!("send_req with {:?}", req);
         let req_dbg = format!("{:?}", req);
@@ -36,7 +46,21 @@ impl GithubClient {
                 resp = self.retry(req, sleep, MAX_ATTEMPTS).await?;
             }
         }
+        if let Some(remaining) = resp
+            .headers()
+            .get("X-RateLimit-Remaining")
+            .and_then(|v| v.to_str().ok()?.parse().ok())
+        {
+            // The search and GraphQL APIs have their own limits.
+            let resource = resp
+                .headers()
+                .get("X-RateLimit-Resource")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("core");
+            crate::metrics::metrics().set_rate_limit_remaining(resource, remaining);
+        }
         let maybe_err = resp.error_for_status_ref().err();
+        let headers = resp.headers().clone();
         let body = resp
             .bytes()
             .await
@@ -46,7 +70,7 @@ impl GithubClient {
                 .with_context(|| format!("response: {}", String::from_utf8_lossy(&body)));
         }
 
-        Ok((body, req_dbg))
+        Ok((body, headers, req_dbg))
     }
 
     async fn needs_retry(resp: &Response) -> Option<Duration> {
@@ -80,29 +104,6 @@ impl GithubClient {
         sleep: Duration,
         remaining_attempts: u32,
     ) -> BoxFuture<Result<Response, reqwest::Error>> {
-        #[derive(Debug, serde::Deserialize)]
-        struct RateLimit {
-            #[allow(unused)]
-            pub limit: u64,
-            pub remaining: u64,
-            pub reset: u64,
-        }
-
-        #[derive(Debug, serde::Deserialize)]
-        struct RateLimitResponse {
-            pub resources: Resources,
-        }
-
-        #[derive(Debug, serde::Deserialize)]
-        struct Resources {
-            pub core: RateLimit,
-            pub search: RateLimit,
-            #[allow(unused)]
-            pub graphql: RateLimit,
-            #[allow(unused)]
-            pub source_import: RateLimit,
-        }
-
         log::warn!(
             "Retrying after {} seconds, remaining attepts {}",
             sleep.as_secs(),
@@ -141,7 +142,7 @@ impl GithubClient {
             // If we still don't have any more remaining attempts, try sleeping for the remaining
             // period of time
             if rate_limit.remaining == 0 {
-                let sleep = Self::calc_sleep(rate_limit.reset);
+                let sleep = Self::calc_sleep(rate_limit.reset.timestamp().max(0) as u64);
                 if sleep > 0 {
                     tokio::time::sleep(Duration::from_secs(sleep)).await;
                 }
@@ -208,6 +209,30 @@ impl GithubClient {
     }
 }
 
+/// Returns the hidden marker identifying bot comments of the given `kind`,
+/// like `<!-- triagebot:error -->`.
+pub fn bot_comment_marker(kind: &str) -> String {
+    format!("<!-- triagebot:{kind} -->")
+}
+
+/// Appends the hidden marker of `kind` to a bot comment, so that it can be
+/// found again with [`Issue::find_bot_comment`].
+pub fn with_bot_comment_marker(body: &str, kind: &str) -> String {
+    format!("{body}\n\n{}\n", bot_comment_marker(kind))
+}
+
+/// Returns the most recent comment of `bot` marked for `kind`.
+///
+/// The author is checked since anyone can paste the marker in a comment.
+fn find_marked_comment(comments: Vec<Comment>, bot: &str, kind: &str) -> Option<Comment> {
+    let marker = bot_comment_marker(kind);
+    comments
+        .into_iter()
+        .rev()
+        .filter(|comment| comment.user.login == bot)
+        .find(|comment| comment.body.contains(&marker))
+}
+
 #[derive(Debug, serde::Serialize)]
 pub(crate) enum PrState {
     #[serde(rename = "open")]
@@ -277,11 +302,36 @@ pub async fn get_team(
     Ok(map.swap_remove(team))
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Label {
     pub name: String,
+    /// The hex color, like `ededed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Label {
+    pub fn new(name: impl Into<String>) -> Label {
+        Label {
+            name: name.into(),
+            color: None,
+            description: None,
+        }
+    }
+}
+
+// The labels are identified by their name, the labels of the events have
+// their color and description but the ones built by the handlers don't.
+impl PartialEq for Label {
+    fn eq(&self, other: &Label) -> bool {
+        self.name == other.name
+    }
 }
 
+impl Eq for Label {}
+
 /// An indicator used to differentiate between an issue and a pull request.
 ///
 /// Some webhook events include a `pull_request` field in the Issue object,
@@ -307,6 +357,15 @@ pub struct FileDiff {
     pub diff: String,
 }
 
+/// A suggested reviewer for a PR, derived from the files it changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssigneeSuggestion {
+    /// A username, team name, or ad-hoc group from the `owners` config.
+    pub name: String,
+    /// The number of changed files owned by `name`.
+    pub score: u32,
+}
+
 impl PullRequestDetails {
     pub fn new() -> PullRequestDetails {
         PullRequestDetails {
@@ -324,6 +383,13 @@ impl PullRequestDetails {
 #[derive(Debug, serde::Deserialize)]
 pub struct Issue {
     pub number: u64,
+    /// The GraphQL node id.
+    ///
+    /// Note that GitHub gives the id of the *issue* node for PRs fetched
+    /// through the issues API or delivered with issue comment events, and
+    /// the id of the *pull request* node for pull request payloads.
+    #[serde(default)]
+    pub node_id: Option<String>,
     #[serde(deserialize_with = "opt_string")]
     pub body: String,
     pub created_at: chrono::DateTime<Utc>,
@@ -432,6 +498,31 @@ pub struct Comment {
     pub pr_review_state: Option<PullRequestReviewState>,
 }
 
+/// A reaction on an issue or a comment.
+#[derive(Debug, serde::Deserialize)]
+pub struct Reaction {
+    pub id: u64,
+    pub user: User,
+    pub content: ReactionContent,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+/// The emoji of a reaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReactionContent {
+    #[serde(rename = "+1")]
+    PlusOne,
+    #[serde(rename = "-1")]
+    MinusOne,
+    Laugh,
+    Hooray,
+    Confused,
+    Heart,
+    Rocket,
+    Eyes,
+}
+
 #[derive(Debug, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ReportedContentClassifiers {
@@ -443,6 +534,22 @@ pub enum ReportedContentClassifiers {
     Spam,
 }
 
+impl From<ReportedContentClassifiers>
+    for github_graphql::minimize_comment::ReportedContentClassifiers
+{
+    fn from(reason: ReportedContentClassifiers) -> Self {
+        use github_graphql::minimize_comment::ReportedContentClassifiers as Graphql;
+        match reason {
+            ReportedContentClassifiers::Abuse => Graphql::Abuse,
+            ReportedContentClassifiers::Duplicate => Graphql::Duplicate,
+            ReportedContentClassifiers::OffTopic => Graphql::OffTopic,
+            ReportedContentClassifiers::Outdated => Graphql::Outdated,
+            ReportedContentClassifiers::Resolved => Graphql::Resolved,
+            ReportedContentClassifiers::Spam => Graphql::Spam,
+        }
+    }
+}
+
 #[derive(Debug, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
 pub enum LockReason {
     #[serde(rename = "off-topic")]
@@ -465,6 +572,30 @@ pub enum PullRequestReviewState {
     Pending,
 }
 
+/// A review of a PR, as returned by [`Issue::reviews`].
+#[derive(Debug, serde::Deserialize)]
+pub struct PullRequestReview {
+    pub id: u64,
+    pub user: User,
+    /// Like `APPROVED` or `CHANGES_REQUESTED`.
+    pub state: String,
+    /// The head commit of the PR when the review was submitted.
+    pub commit_id: String,
+}
+
+/// How a commit relates to another, see [`IssueRepository::compare_status`].
+#[derive(Debug, Clone, Copy, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareStatus {
+    /// The head contains the base and new commits.
+    Ahead,
+    /// The head is an ancestor of the base.
+    Behind,
+    /// Both have commits the other doesn't have.
+    Diverged,
+    Identical,
+}
+
 fn opt_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: serde::de::Deserializer<'de>,
@@ -520,10 +651,96 @@ impl IssueRepository {
         )
     }
 
-    fn full_repo_name(&self) -> String {
+    pub fn full_repo_name(&self) -> String {
         format!("{}/{}", self.organization, self.repository)
     }
 
+    /// Returns how `head` relates to `base` in the commit history.
+    pub async fn compare_status(
+        &self,
+        client: &GithubClient,
+        base: &str,
+        head: &str,
+    ) -> anyhow::Result<CompareStatus> {
+        #[derive(serde::Deserialize)]
+        struct Comparison {
+            status: CompareStatus,
+        }
+        // Only the status is needed, avoid listing all the commits.
+        let url = format!("{}/compare/{base}...{head}?per_page=1", self.url(client));
+        let comparison: Comparison = client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("failed to compare {base}...{head}"))?;
+        Ok(comparison.status)
+    }
+
+    /// Creates `label`, in GitHub's default gray if it has no color.
+    pub async fn create_label(&self, client: &GithubClient, label: &Label) -> anyhow::Result<()> {
+        let label = Label {
+            color: Some(
+                label
+                    .color
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_LABEL_COLOR.to_string()),
+            ),
+            ..label.clone()
+        };
+        let url = format!("{}/labels", self.url(client));
+        client
+            .send_req(client.post(&url).json(&label))
+            .await
+            .with_context(|| format!("failed to create label {}", label.name))?;
+        Ok(())
+    }
+
+    /// Updates the color and description of `label`, leaving the ones that
+    /// are `None` as is.
+    pub async fn update_label(&self, client: &GithubClient, label: &Label) -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct UpdateLabel<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            color: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            description: Option<&'a str>,
+        }
+        // The label names may have spaces or slashes, so they are encoded.
+        let mut url = url::Url::parse(&format!("{}/labels", self.url(client)))
+            .with_context(|| format!("invalid repository URL {}", self.url(client)))?;
+        url.path_segments_mut()
+            .map_err(|()| anyhow::anyhow!("invalid repository URL {}", self.url(client)))?
+            .push(&label.name);
+        let update = UpdateLabel {
+            color: label.color.as_deref(),
+            description: label.description.as_deref(),
+        };
+        client
+            .send_req(client.patch(&url).json(&update))
+            .await
+            .with_context(|| format!("failed to update label {}", label.name))?;
+        Ok(())
+    }
+
+    /// Returns all the labels of the repository.
+    pub async fn labels(&self, client: &GithubClient) -> anyhow::Result<Vec<Label>> {
+        let mut labels = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!("{}/labels?page={page}&per_page=100", self.url(client));
+            let new: Vec<Label> = client
+                .json(client.get(&url))
+                .await
+                .with_context(|| format!("failed to list the labels of {self}"))?;
+            let is_last = new.len() < 100;
+            labels.extend(new);
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+        Ok(labels)
+    }
+
     async fn has_label(&self, client: &GithubClient, label: &str) -> anyhow::Result<bool> {
         #[allow(clippy::redundant_pattern_matching)]
         let url = format!("{}/labels/{}", self.url(client), label);
@@ -542,6 +759,29 @@ impl IssueRepository {
     }
 }
 
+/// Posts a reaction to the reactions endpoint `url`, returning its id.
+async fn post_reaction(
+    client: &GithubClient,
+    url: &str,
+    content: ReactionContent,
+) -> anyhow::Result<u64> {
+    #[derive(serde::Serialize)]
+    struct PostReaction {
+        content: ReactionContent,
+    }
+    #[derive(serde::Deserialize)]
+    struct CreatedReaction {
+        id: u64,
+    }
+    let reaction: CreatedReaction = client
+        .json(client.post(url).json(&PostReaction { content }))
+        .await?;
+    Ok(reaction.id)
+}
+
+/// The color of the labels created by the bot, GitHub's default gray.
+const DEFAULT_LABEL_COLOR: &str = "ededed";
+
 #[derive(Debug)]
 pub(crate) struct UnknownLabels {
     labels: Vec<String>,
@@ -615,6 +855,44 @@ impl Issue {
             .await?)
     }
 
+    /// Returns all the comments of the issue, oldest first.
+    pub async fn get_all_comments(&self, client: &GithubClient) -> anyhow::Result<Vec<Comment>> {
+        let mut comments = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!(
+                "{}/issues/{}/comments?page={page}&per_page=100",
+                self.repository().url(client),
+                self.number,
+            );
+            let new: Vec<Comment> = client
+                .json(client.get(&url))
+                .await
+                .with_context(|| format!("failed to list the comments of {}", self.global_id()))?;
+            let is_last = new.len() < 100;
+            comments.extend(new);
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+        Ok(comments)
+    }
+
+    /// Returns the most recent comment of the bot marked with
+    /// [`bot_comment_marker`] for the given `kind`.
+    ///
+    /// All the comments are searched, and the comments of other users are
+    /// ignored (see [`GithubClient::bot_login`]).
+    pub async fn find_bot_comment(
+        &self,
+        client: &GithubClient,
+        kind: &str,
+    ) -> anyhow::Result<Option<Comment>> {
+        let comments = self.get_all_comments(client).await?;
+        Ok(find_marked_comment(comments, client.bot_login(), kind))
+    }
+
     pub async fn edit_body(&self, client: &GithubClient, body: &str) -> anyhow::Result<()> {
         let edit_url = format!("{}/issues/{}", self.repository().url(client), self.number);
         #[derive(serde::Serialize)]
@@ -655,11 +933,11 @@ impl Issue {
         struct PostComment<'a> {
             body: &'a str,
         }
-        let comments_path = self
-            .comments_url
-            .strip_prefix("https://api.github.com")
-            .expect("expected api host");
-        let comments_url = format!("{}{comments_path}", client.api_url);
+        let comments_url = format!(
+            "{}/issues/{}/comments",
+            self.repository().url(client),
+            self.number
+        );
         let comment = client
             .json(client.post(&comments_url).json(&PostComment { body }))
             .await
@@ -667,26 +945,103 @@ impl Issue {
         Ok(comment)
     }
 
+    /// Reacts to the issue with `content`, returning the id of the reaction.
+    ///
+    /// If the bot already reacted with `content`, the existing reaction is
+    /// returned.
+    pub async fn add_reaction(
+        &self,
+        client: &GithubClient,
+        content: ReactionContent,
+    ) -> anyhow::Result<u64> {
+        let url = format!(
+            "{}/issues/{}/reactions",
+            self.repository().url(client),
+            self.number
+        );
+        post_reaction(client, &url, content)
+            .await
+            .with_context(|| format!("failed to react to {}", self.global_id()))
+    }
+
+    /// Reacts to the comment `id` with `content`, returning the id of the
+    /// reaction.
+    pub async fn add_comment_reaction(
+        &self,
+        client: &GithubClient,
+        id: u64,
+        content: ReactionContent,
+    ) -> anyhow::Result<u64> {
+        let url = format!(
+            "{}/issues/comments/{id}/reactions",
+            self.repository().url(client)
+        );
+        post_reaction(client, &url, content)
+            .await
+            .with_context(|| format!("failed to react to comment {id}"))
+    }
+
+    /// Returns the reactions to the issue, or to its comment `comment_id`,
+    /// oldest first.
+    pub async fn list_reactions(
+        &self,
+        client: &GithubClient,
+        comment_id: Option<u64>,
+    ) -> anyhow::Result<Vec<Reaction>> {
+        let reactions_url = match comment_id {
+            Some(id) => format!(
+                "{}/issues/comments/{id}/reactions",
+                self.repository().url(client)
+            ),
+            None => format!(
+                "{}/issues/{}/reactions",
+                self.repository().url(client),
+                self.number
+            ),
+        };
+        let mut reactions = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!("{reactions_url}?page={page}&per_page=100");
+            let new: Vec<Reaction> = client
+                .json(client.get(&url))
+                .await
+                .with_context(|| format!("failed to list the reactions from {url}"))?;
+            let is_last = new.len() < 100;
+            reactions.extend(new);
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+        Ok(reactions)
+    }
+
+    /// Deletes a comment.
+    ///
+    /// Deleting a comment that no longer exists is not an error.
+    pub async fn delete_comment(&self, client: &GithubClient, id: u64) -> anyhow::Result<()> {
+        let comment_url = format!("{}/issues/comments/{}", self.repository().url(client), id);
+        match client.send_req(client.delete(&comment_url)).await {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.downcast_ref::<reqwest::Error>()
+                    .map_or(false, |e| e.status() == Some(StatusCode::NOT_FOUND)) =>
+            {
+                log::debug!("comment {id} on {} was already deleted", self.global_id());
+                Ok(())
+            }
+            Err(e) => Err(e.context("failed to delete comment")),
+        }
+    }
+
     pub async fn hide_comment(
         &self,
         client: &GithubClient,
         node_id: &str,
         reason: ReportedContentClassifiers,
     ) -> anyhow::Result<()> {
-        client
-            .graphql_query(
-                "mutation($node_id: ID!, $reason: ReportedContentClassifiers!) {
-                    minimizeComment(input: {subjectId: $node_id, classifier: $reason}) {
-                        __typename
-                    }
-                }",
-                serde_json::json!({
-                    "node_id": node_id,
-                    "reason": reason,
-                }),
-            )
-            .await?;
-        Ok(())
+        client.minimize_comment(node_id, reason).await
     }
 
     pub async fn remove_label(&self, client: &GithubClient, label: &str) -> anyhow::Result<()> {
@@ -716,10 +1071,23 @@ impl Issue {
         Ok(())
     }
 
+    /// Adds `labels` to the issue, failing with [`UnknownLabels`] if some of
+    /// them don't exist in the repository.
     pub async fn add_labels(
         &self,
         client: &GithubClient,
         labels: Vec<Label>,
+    ) -> anyhow::Result<()> {
+        self.add_labels_with(client, labels, false).await
+    }
+
+    /// Adds `labels` to the issue, like [`Issue::add_labels`], but creates
+    /// the labels unknown to the repository if `create_unknown` is set.
+    pub async fn add_labels_with(
+        &self,
+        client: &GithubClient,
+        labels: Vec<Label>,
+        create_unknown: bool,
     ) -> anyhow::Result<()> {
         log::info!("add_labels: {} +{:?}", self.global_id(), labels);
         // POST /repos/:owner/:repo/issues/:number/labels
@@ -753,6 +1121,15 @@ impl Issue {
             }
         }
 
+        if create_unknown {
+            for label in unknown_labels.drain(..) {
+                self.repository()
+                    .create_label(client, &Label::new(label.as_str()))
+                    .await?;
+                known_labels.push(label);
+            }
+        }
+
         if !unknown_labels.is_empty() {
             return Err(UnknownLabels {
                 labels: unknown_labels,
@@ -924,6 +1301,27 @@ impl Issue {
         Ok(())
     }
 
+    /// Unlocks an issue.
+    ///
+    /// Unlocking an issue that isn't locked is not an error.
+    pub async fn unlock(&self, client: &GithubClient) -> anyhow::Result<()> {
+        let lock_url = format!(
+            "{}/issues/{}/lock",
+            self.repository().url(client),
+            self.number
+        );
+        match client.send_req(client.delete(&lock_url)).await {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.downcast_ref::<reqwest::Error>()
+                    .map_or(false, |e| e.status() == Some(StatusCode::NOT_FOUND)) =>
+            {
+                Ok(())
+            }
+            Err(e) => Err(e.context("failed to unlock issue")),
+        }
+    }
+
     pub async fn close(&self, client: &GithubClient) -> anyhow::Result<()> {
         let edit_url = format!("{}/issues/{}", self.repository().url(client), self.number);
         #[derive(serde::Serialize)]
@@ -974,6 +1372,21 @@ impl Issue {
         Ok(Some(diff))
     }
 
+    /// Returns reviewer suggestions for this PR based on the `owners` config,
+    /// ranked by the number of changed files each entry owns.
+    ///
+    /// Returns an empty list if this is not a PR.
+    pub(crate) async fn assignee_suggestions_from_diff(
+        &self,
+        client: &GithubClient,
+        config: &crate::config::AssignConfig,
+    ) -> anyhow::Result<Vec<AssigneeSuggestion>> {
+        let Some(diff) = self.diff(client).await? else {
+            return Ok(Vec::new());
+        };
+        config.assignee_suggestions(diff)
+    }
+
     /// Returns the commits from this pull request (no commits are returned if this `Issue` is not
     /// a pull request).
     pub async fn commits(&self, client: &GithubClient) -> anyhow::Result<Vec<GithubCommit>> {
@@ -1014,6 +1427,95 @@ impl Issue {
         Ok(client.json(req).await?)
     }
 
+    /// Returns the reviews of this PR, oldest first.
+    ///
+    /// Only the first 100 reviews are returned.
+    pub async fn reviews(&self, client: &GithubClient) -> anyhow::Result<Vec<PullRequestReview>> {
+        let url = format!(
+            "{}/pulls/{}/reviews?per_page=100",
+            self.repository().url(client),
+            self.number
+        );
+        client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("failed to get the reviews of {}", self.global_id()))
+    }
+
+    /// Dismisses a review of this PR, explaining why with `message`.
+    pub async fn dismiss_review(
+        &self,
+        client: &GithubClient,
+        review_id: u64,
+        message: &str,
+    ) -> anyhow::Result<PullRequestReview> {
+        let url = format!(
+            "{}/pulls/{}/reviews/{review_id}/dismissals",
+            self.repository().url(client),
+            self.number
+        );
+        client
+            .json(client.put(&url).json(&serde_json::json!({
+                "message": message,
+                "event": "DISMISS",
+            })))
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to dismiss review {review_id} of {}",
+                    self.global_id()
+                )
+            })
+    }
+
+    /// Returns everyone taking part in the issue: the authors of the comments
+    /// and reviews, and the assignees.
+    ///
+    /// Each user is listed once, in order of first participation. Only the
+    /// first 100 comments and reviews are considered.
+    pub async fn participants(&self, client: &GithubClient) -> anyhow::Result<Vec<User>> {
+        let mut users: Vec<&User> = Vec::new();
+        let comments = self.get_first100_comments(client).await?;
+        users.extend(comments.iter().map(|c| &c.user));
+        let reviews = if self.is_pr() {
+            self.reviews(client).await?
+        } else {
+            Vec::new()
+        };
+        users.extend(reviews.iter().map(|r| &r.user));
+        users.extend(&self.assignees);
+
+        let mut seen = HashSet::new();
+        Ok(users
+            .into_iter()
+            .filter(|u| seen.insert(u.id))
+            .map(|u| User {
+                login: u.login.clone(),
+                id: u.id,
+            })
+            .collect())
+    }
+
+    /// Requests a review of this PR from the given users.
+    pub async fn request_reviewers(
+        &self,
+        client: &GithubClient,
+        reviewers: &[&str],
+    ) -> anyhow::Result<()> {
+        let url = format!(
+            "{}/pulls/{}/requested_reviewers",
+            self.repository().url(client),
+            self.number
+        );
+        client
+            .send_req(client.post(&url).json(&serde_json::json!({
+                "reviewers": reviewers,
+            })))
+            .await
+            .with_context(|| format!("failed to request reviewers of {}", self.global_id()))?;
+        Ok(())
+    }
+
     /// Returns the GraphQL ID of this issue.
     async fn graphql_issue_id(&self, client: &GithubClient) -> anyhow::Result<String> {
         let repo = self.repository();
@@ -1042,15 +1544,142 @@ impl Issue {
         Ok(issue_id)
     }
 
-    /// Transfers this issue to the given repository.
-    pub async fn transfer(
-        &self,
-        client: &GithubClient,
-        owner: &str,
-        repo: &str,
-    ) -> anyhow::Result<()> {
-        let issue_id = self.graphql_issue_id(client).await?;
-        let repo_id = client.graphql_repo_id(owner, repo).await?;
+    /// Returns the GraphQL node id of this pull request, as needed by the
+    /// draft mutations below.
+    pub async fn pr_node_id(&self, client: &GithubClient) -> anyhow::Result<String> {
+        // Avoid the lookup if the payload already came with the pull request node.
+        if let Some(node_id) = self.node_id.as_ref().filter(|id| id.starts_with("PR_")) {
+            return Ok(node_id.clone());
+        }
+        let repo = self.repository();
+        let mut pr_id = client
+            .graphql_query(
+                "query($owner:String!, $repo:String!, $prNum:Int!) {
+                    repository(owner: $owner, name: $repo) {
+                        pullRequest(number: $prNum) {
+                            id
+                        }
+                    }
+                }
+                ",
+                serde_json::json!({
+                    "owner": repo.organization,
+                    "repo": repo.repository,
+                    "prNum": self.number,
+                }),
+            )
+            .await?;
+        let serde_json::Value::String(pr_id) =
+            pr_id["data"]["repository"]["pullRequest"]["id"].take()
+        else {
+            anyhow::bail!("expected pull request id, got {pr_id}");
+        };
+        Ok(pr_id)
+    }
+
+    /// Returns the numbers of the issues of the same repository that this
+    /// pull request closes when merged, like with `Fixes #1234`.
+    pub async fn closing_issues(&self, client: &GithubClient) -> anyhow::Result<Vec<u64>> {
+        let repo = self.repository();
+        let data = client
+            .graphql_query(
+                "query($owner:String!, $repo:String!, $prNum:Int!) {
+                    repository(owner: $owner, name: $repo) {
+                        pullRequest(number: $prNum) {
+                            closingIssuesReferences(first: 100) {
+                                nodes {
+                                    number
+                                    repository {
+                                        nameWithOwner
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                ",
+                serde_json::json!({
+                    "owner": repo.organization,
+                    "repo": repo.repository,
+                    "prNum": self.number,
+                }),
+            )
+            .await?;
+        let Some(nodes) = data["data"]["repository"]["pullRequest"]["closingIssuesReferences"]
+            ["nodes"]
+            .as_array()
+        else {
+            anyhow::bail!("expected closing issues, got {data}");
+        };
+        let full_repo_name = repo.full_repo_name();
+        Ok(nodes
+            .iter()
+            .filter(|node| node["repository"]["nameWithOwner"] == full_repo_name.as_str())
+            .filter_map(|node| node["number"].as_u64())
+            .collect())
+    }
+
+    /// Marks this draft pull request as ready for review.
+    ///
+    /// `node_id` is the GraphQL node id of the pull request, see
+    /// [`Issue::pr_node_id`].
+    pub async fn mark_ready(&self, client: &GithubClient, node_id: &str) -> anyhow::Result<()> {
+        use cynic::MutationBuilder;
+        use github_graphql::mark_ready_for_review::{
+            MarkReadyForReview, MarkReadyForReviewVariables,
+        };
+
+        log::info!("mark_ready {}", self.global_id());
+        let pr_id = cynic::Id::new(node_id);
+        let mutation = MarkReadyForReview::build(MarkReadyForReviewVariables {
+            pull_request_id: &pr_id,
+        });
+        let data: cynic::GraphQlResponse<MarkReadyForReview> = client
+            .json(client.post(&client.graphql_url).json(&mutation))
+            .await
+            .with_context(|| format!("failed to mark {} ready for review", self.global_id()))?;
+        if let Some(errors) = data.errors {
+            anyhow::bail!("There were graphql errors. {:?}", errors);
+        }
+        Ok(())
+    }
+
+    /// Converts this pull request back to a draft.
+    ///
+    /// `node_id` is the GraphQL node id of the pull request, see
+    /// [`Issue::pr_node_id`].
+    pub async fn convert_to_draft(
+        &self,
+        client: &GithubClient,
+        node_id: &str,
+    ) -> anyhow::Result<()> {
+        use cynic::MutationBuilder;
+        use github_graphql::convert_to_draft::{ConvertToDraft, ConvertToDraftVariables};
+
+        log::info!("convert_to_draft {}", self.global_id());
+        let pr_id = cynic::Id::new(node_id);
+        let mutation = ConvertToDraft::build(ConvertToDraftVariables {
+            pull_request_id: &pr_id,
+        });
+        let data: cynic::GraphQlResponse<ConvertToDraft> = client
+            .json(client.post(&client.graphql_url).json(&mutation))
+            .await
+            .with_context(|| format!("failed to convert {} to draft", self.global_id()))?;
+        if let Some(errors) = data.errors {
+            anyhow::bail!("There were graphql errors. {:?}", errors);
+        }
+        Ok(())
+    }
+
+    /// Transfers this issue to the given repository.
+    pub async fn transfer(
+        &self,
+        client: &GithubClient,
+        owner: &str,
+        repo: &str,
+    ) -> anyhow::Result<()> {
+        let issue_id = self.graphql_issue_id(client).await?;
+        let repo_id = client.graphql_repo_id(owner, repo).await?;
         client
             .graphql_query(
                 "mutation ($issueId: ID!, $repoId: ID!) {
@@ -1077,12 +1706,15 @@ pub struct PullRequestFile {
     pub sha: String,
     pub filename: String,
     pub blob_url: String,
+    /// How the file is changed, like `added`, `modified` or `removed`.
+    #[serde(default)]
+    pub status: String,
 }
 
 #[derive(Debug, serde::Deserialize)]
 pub struct Milestone {
     number: u64,
-    title: String,
+    pub title: String,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -1202,6 +1834,12 @@ pub struct IssuesEvent {
     pub repository: Repository,
     /// The GitHub user that triggered the event.
     pub sender: User,
+    /// The head commit of the PR before a `synchronize` event.
+    #[serde(default)]
+    pub before: Option<String>,
+    /// The head commit of the PR after a `synchronize` event.
+    #[serde(default)]
+    pub after: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -1253,9 +1891,16 @@ pub struct Repository {
     pub default_branch: String,
     #[serde(default)]
     pub fork: bool,
+    /// Whether the repository is archived (read-only).
+    #[serde(default)]
+    pub archived: bool,
     pub parent: Option<Box<Repository>>,
 }
 
+/// The maximum number of searches aliased in a single GraphQL query, to stay
+/// well below the limits of GitHub on the cost of a query.
+const MAX_ALIASED_SEARCHES: usize = 10;
+
 #[derive(Copy, Clone)]
 struct Ordering<'a> {
     pub sort: &'a str,
@@ -1277,6 +1922,11 @@ impl Repository {
         self.full_name.split_once('/').unwrap().1
     }
 
+    /// Whether this repository passes the filter of [`GithubClient::list_org_repos`].
+    fn is_listed(&self, include_archived: bool, include_forks: bool) -> bool {
+        (include_archived || !self.archived) && (include_forks || !self.fork)
+    }
+
     pub async fn get_issues<'a>(
         &self,
         client: &GithubClient,
@@ -1308,6 +1958,22 @@ impl Repository {
             })
             .collect();
 
+        let per_page: usize = ordering.per_page.parse().with_context(|| {
+            format!(
+                "invalid `per_page` `{}`, expected a number",
+                ordering.per_page
+            )
+        })?;
+
+        // `since` only lists the issues updated after the given timestamp,
+        // for incremental syncs
+        let since = filters.iter().find(|&&(key, _)| key == "since");
+        if let Some((_, since)) = since {
+            DateTime::parse_from_rfc3339(since).with_context(|| {
+                format!("invalid `since` timestamp `{since}`, expected e.g. `2024-05-16T14:00:00Z`")
+            })?;
+        }
+
         // `is: pull-request` indicates the query to retrieve PRs only
         let is_pr = filters
             .iter()
@@ -1317,12 +1983,14 @@ impl Repository {
         // 1. When using negating label filters (exclude_labels)
         // 2. When there's a key parameter key=no
         // 3. When the query is to retrieve PRs only and there are label filters
+        // 4. When the query is to retrieve PRs only and there is a `since`
+        //    filter, which the pulls endpoint doesn't support
         //
         // Check https://docs.github.com/en/rest/reference/search#search-issues-and-pull-requests
         // for more information
         let use_search_api = !exclude_labels.is_empty()
             || filters.iter().any(|&(key, _)| key == "no")
-            || is_pr && !include_labels.is_empty();
+            || is_pr && (!include_labels.is_empty() || since.is_some());
 
         // If there are more than `per_page` of issues, we need to paginate
         let mut issues = vec![];
@@ -1353,11 +2021,17 @@ impl Repository {
                     continue;
                 }
             } else {
-                // FIXME: paginate with non-search
-                issues = client
+                let result: Vec<Issue> = client
                     .json(result)
                     .await
-                    .with_context(|| format!("failed to list issues from {}", url))?
+                    .with_context(|| format!("failed to list issues from {}", url))?;
+                // A partial page is the last one
+                let is_last = result.len() < per_page;
+                issues.extend(result);
+                if !is_last {
+                    ordering.page += 1;
+                    continue;
+                }
             }
 
             break;
@@ -1365,6 +2039,70 @@ impl Repository {
         Ok(issues)
     }
 
+    /// Returns the open issues and PRs in the milestone titled `milestone`,
+    /// like `1.80.0`, for release tracking.
+    ///
+    /// The search only returns the first 1000 results of a query, so the
+    /// issues are listed in windows, each one starting at the creation of the
+    /// last issue of the previous one.
+    pub async fn get_milestone_issues(
+        &self,
+        client: &GithubClient,
+        milestone: &str,
+    ) -> anyhow::Result<Vec<crate::actions::IssueDecorator>> {
+        const PER_PAGE: u64 = 100;
+        const MAX_RESULTS: u64 = 1000;
+
+        let milestone: String =
+            url::form_urlencoded::byte_serialize(format!("\"{milestone}\"").as_bytes()).collect();
+        let mut issues: Vec<Issue> = vec![];
+        let mut seen = HashSet::new();
+        let mut since: Option<DateTime<Utc>> = None;
+        let mut page = 1;
+        loop {
+            let created = since.map_or(String::new(), |since| {
+                format!("+created:>={}", since.format("%Y-%m-%dT%H:%M:%SZ"))
+            });
+            let url = format!(
+                "{}/search/issues?q=milestone:{milestone}+state:open+repo:{}{created}\
+                &sort=created&order=asc&per_page={PER_PAGE}&page={page}",
+                client.api_url, self.full_name
+            );
+            let result = client
+                .json::<IssueSearchResult>(client.get(&url))
+                .await
+                .with_context(|| format!("failed to list the milestone issues from {url}"))?;
+            let done = result.items.is_empty() || page * PER_PAGE >= result.total_count;
+            // The issues created at the start of a window were already listed
+            // at the end of the previous one.
+            issues.extend(
+                result
+                    .items
+                    .into_iter()
+                    .filter(|issue| seen.insert(issue.number)),
+            );
+            if done {
+                break;
+            }
+            if page * PER_PAGE < MAX_RESULTS {
+                page += 1;
+                continue;
+            }
+            let last = issues.last().map(|issue| issue.created_at);
+            if last == since {
+                log::warn!(
+                    "more than {MAX_RESULTS} issues of {} were created at {last:?}, \
+                     the milestone issues are incomplete",
+                    self.full_name
+                );
+                break;
+            }
+            since = last;
+            page = 1;
+        }
+        decorate_issues(self, issues, false, false, client).await
+    }
+
     fn build_issues_url(
         &self,
         client: &GithubClient,
@@ -1406,6 +2144,7 @@ impl Repository {
                 format!("direction={}", ordering.direction,),
             ))
             .chain(std::iter::once(format!("per_page={}", ordering.per_page,)))
+            .chain(std::iter::once(format!("page={}", ordering.page)))
             .collect::<Vec<_>>()
             .join("&");
         format!(
@@ -1425,7 +2164,10 @@ impl Repository {
         let filters = filters
             .iter()
             .filter(|&&(key, val)| !(key == "state" && val == "all"))
-            .map(|(key, val)| format!("{}:{}", key, val))
+            .map(|&(key, val)| match key {
+                "since" => format!("updated:>={}", val),
+                _ => format!("{}:{}", key, val),
+            })
             .chain(
                 include_labels
                     .iter()
@@ -1534,6 +2276,14 @@ impl Repository {
             .with_context(|| format!("{} failed to get git reference {refname}", self.full_name))
     }
 
+    /// Returns the SHA of the head commit of the default branch.
+    pub async fn default_branch_sha(&self, client: &GithubClient) -> anyhow::Result<String> {
+        let reference = self
+            .get_reference(client, &format!("heads/{}", self.default_branch))
+            .await?;
+        Ok(reference.object.sha)
+    }
+
     /// Updates an existing git reference to a new SHA.
     pub async fn update_reference(
         &self,
@@ -1556,6 +2306,53 @@ impl Repository {
             })
     }
 
+    /// Creates a new git reference, like `tags/1.0.0`, pointing to `sha`.
+    pub async fn create_reference(
+        &self,
+        client: &GithubClient,
+        refname: &str,
+        sha: &str,
+    ) -> anyhow::Result<GitReference> {
+        let url = format!("{}/git/refs", self.url(client));
+        client
+            .json(client.post(&url).json(&serde_json::json!({
+                "ref": format!("refs/{refname}"),
+                "sha": sha,
+            })))
+            .await
+            .with_context(|| {
+                format!(
+                    "{} failed to create reference {refname} to {sha}",
+                    self.full_name
+                )
+            })
+    }
+
+    /// Creates an annotated tag object for the commit `target`.
+    ///
+    /// This only creates the tag object, the tag itself is made visible by
+    /// creating a `tags/{tag}` reference to it with [`Self::create_reference`].
+    pub async fn create_tag(
+        &self,
+        client: &GithubClient,
+        tag: &str,
+        message: &str,
+        target: &str,
+        tagger: &GitTagger,
+    ) -> anyhow::Result<GitTag> {
+        let url = format!("{}/git/tags", self.url(client));
+        client
+            .json(client.post(&url).json(&serde_json::json!({
+                "tag": tag,
+                "message": message,
+                "object": target,
+                "type": "commit",
+                "tagger": tagger,
+            })))
+            .await
+            .with_context(|| format!("{} failed to create tag {tag}", self.full_name))
+    }
+
     /// Returns a list of recent commits on the given branch.
     ///
     /// Returns results in the OID range `oldest` (exclusive) to `newest`
@@ -1724,7 +2521,11 @@ impl Repository {
         Ok(recent_commits)
     }
 
-    /// Creates a new git tree based on another tree.
+    /// Creates a tree applying the `tree` entries on top of `base_tree`.
+    ///
+    /// GitHub rejects overly large requests, so the entries are sent in
+    /// chunks of [`TREE_ENTRIES_PER_REQUEST`], each tree building on the
+    /// previous one. The last tree is returned.
     pub async fn update_tree(
         &self,
         client: &GithubClient,
@@ -1732,18 +2533,83 @@ impl Repository {
         tree: &[GitTreeEntry],
     ) -> anyhow::Result<GitTreeObject> {
         let url = format!("{}/git/trees", self.url(client));
-        client
+        let mut new_tree = GitTreeObject {
+            sha: base_tree.to_string(),
+        };
+        for chunk in tree.chunks(TREE_ENTRIES_PER_REQUEST) {
+            let base_tree = &new_tree.sha;
+            new_tree = client
+                .json(client.post(&url).json(&serde_json::json!({
+                    "base_tree": base_tree,
+                    "tree": chunk,
+                })))
+                .await
+                .with_context(|| {
+                    format!(
+                        "{} failed to update tree with base {base_tree}",
+                        self.full_name
+                    )
+                })?;
+        }
+        Ok(new_tree)
+    }
+
+    /// Creates a blob with the given content, and returns its SHA.
+    pub async fn create_blob(
+        &self,
+        client: &GithubClient,
+        content: &[u8],
+    ) -> anyhow::Result<String> {
+        let url = format!("{}/git/blobs", self.url(client));
+        let blob: GitBlobObject = client
             .json(client.post(&url).json(&serde_json::json!({
-                "base_tree": base_tree,
-                "tree": tree,
+                "content": openssl::base64::encode_block(content),
+                "encoding": "base64",
             })))
             .await
-            .with_context(|| {
-                format!(
-                    "{} failed to update tree with base {base_tree}",
-                    self.full_name
-                )
-            })
+            .with_context(|| format!("{} failed to create blob", self.full_name))?;
+        Ok(blob.sha)
+    }
+
+    /// Returns the content of the blob with the given SHA.
+    pub async fn get_blob(&self, client: &GithubClient, sha: &str) -> anyhow::Result<Vec<u8>> {
+        let url = format!("{}/git/blobs/{sha}", self.url(client));
+        let blob: GitBlob = client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to get blob {sha}", self.full_name))?;
+        if blob.encoding != "base64" {
+            anyhow::bail!("blob {sha} has unexpected encoding `{}`", blob.encoding);
+        }
+        // The content is wrapped on multiple lines.
+        let content: String = blob.content.split_whitespace().collect();
+        openssl::base64::decode_block(&content)
+            .with_context(|| format!("blob {sha} has invalid base64 content"))
+    }
+
+    /// Lists the files and directories of the directory at `path`.
+    ///
+    /// `refname` is the ref to list the directory at. If `None`, will use the
+    /// latest version on the default branch.
+    ///
+    /// GitHub only returns the first 1000 entries of a directory.
+    pub async fn get_contents(
+        &self,
+        client: &GithubClient,
+        path: &str,
+        refname: Option<&str>,
+    ) -> anyhow::Result<Vec<ContentEntry>> {
+        let mut url = format!("{}/contents/{}", self.url(client), path.trim_matches('/'));
+        if let Some(refname) = refname {
+            url.push_str("?ref=");
+            url.push_str(refname);
+        }
+        client.json(client.get(&url)).await.with_context(|| {
+            format!(
+                "{} failed to list directory path={path} refname={refname:?}",
+                self.full_name
+            )
+        })
     }
 
     /// Returns information about the git submodule at the given path.
@@ -1769,6 +2635,24 @@ impl Repository {
         })
     }
 
+    /// Returns the open PR whose head is `head`, like `rustbot:docs-update`,
+    /// if there is one.
+    pub async fn open_pr_for_head(
+        &self,
+        client: &GithubClient,
+        head: &str,
+    ) -> anyhow::Result<Option<Issue>> {
+        let url = format!("{}/pulls?head={head}&state=open", self.url(client));
+        let prs: Vec<Issue> = client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to list the PRs of {head}", self.full_name))?;
+        Ok(prs.into_iter().next().map(|mut pr| {
+            pr.pull_request = Some(PullRequestDetails::new());
+            pr
+        }))
+    }
+
     /// Creates a new PR.
     pub async fn new_pr(
         &self,
@@ -1878,6 +2762,16 @@ impl Repository {
             .await
     }
 
+    /// Returns the issue or PR with the given number.
+    ///
+    /// PRs are returned without their PR-specific fields, like `head`.
+    pub async fn get_issue(&self, full_repo_name: &str, issue_num: u64) -> anyhow::Result<Issue> {
+        let url = format!("{}/repos/{full_repo_name}/issues/{issue_num}", self.api_url);
+        self.json(self.get(&url))
+            .await
+            .with_context(|| format!("failed to get issue {full_repo_name}#{issue_num}"))
+    }
+
     /// Set the milestone of an issue or PR.
     pub async fn set_milestone(
         &self,
@@ -1972,6 +2866,82 @@ impl Repository {
             .await
             .with_context(|| format!("{} failed to get pulls for commit {sha}", self.full_name))
     }
+
+    /// Returns a job of a GitHub Actions workflow run.
+    pub async fn workflow_run_job(
+        &self,
+        client: &GithubClient,
+        job_id: u64,
+    ) -> anyhow::Result<WorkflowRunJob> {
+        let url = format!("{}/actions/jobs/{job_id}", self.url(client));
+        client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to get job {job_id}", self.full_name))
+    }
+
+    /// Returns the plain text logs of a GitHub Actions job.
+    pub async fn raw_job_logs(&self, client: &GithubClient, job_id: u64) -> anyhow::Result<String> {
+        let url = format!("{}/actions/jobs/{job_id}/logs", self.url(client));
+        let (body, _) = client
+            .send_req(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to get logs of job {job_id}", self.full_name))?;
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Returns the GitHub Actions workflow runs for the given head commit,
+    /// newest first.
+    pub async fn workflow_runs_for_sha(
+        &self,
+        client: &GithubClient,
+        sha: &str,
+    ) -> anyhow::Result<Vec<WorkflowRun>> {
+        let url = format!("{}/actions/runs?head_sha={sha}", self.url(client));
+        let runs: WorkflowRuns = client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to get workflow runs for {sha}", self.full_name))?;
+        Ok(runs.workflow_runs)
+    }
+
+    /// Returns the most recent GitHub Actions workflow run for the given head
+    /// commit, if there is any.
+    pub async fn latest_workflow_run_for_sha(
+        &self,
+        client: &GithubClient,
+        sha: &str,
+    ) -> anyhow::Result<Option<WorkflowRun>> {
+        let url = format!(
+            "{}/actions/runs?head_sha={sha}&per_page=1",
+            self.url(client)
+        );
+        let runs: WorkflowRuns = client.json(client.get(&url)).await.with_context(|| {
+            format!(
+                "{} failed to get latest workflow run for {sha}",
+                self.full_name
+            )
+        })?;
+        Ok(runs.workflow_runs.into_iter().next())
+    }
+
+    /// Reruns the failed jobs of a GitHub Actions workflow run, and the jobs
+    /// depending on them.
+    pub async fn rerun_failed_jobs(
+        &self,
+        client: &GithubClient,
+        run_id: u64,
+    ) -> anyhow::Result<()> {
+        let url = format!(
+            "{}/actions/runs/{run_id}/rerun-failed-jobs",
+            self.url(client)
+        );
+        client
+            .send_req(client.post(&url))
+            .await
+            .with_context(|| format!("{} failed to rerun workflow run {run_id}", self.full_name))?;
+        Ok(())
+    }
 }
 
 /// Information about a merge conflict on a PR.
@@ -2022,163 +2992,212 @@ impl<'q> IssuesQuery for Query<'q> {
             .get_issues(&client, self)
             .await
             .with_context(|| "Unable to get issues.")?;
+        decorate_issues(
+            repo,
+            issues,
+            include_fcp_details,
+            include_mcp_details,
+            client,
+        )
+        .await
+    }
 
-        let fcp_map = if include_fcp_details {
-            crate::rfcbot::get_all_fcps()
-                .await
-                .with_context(|| "Unable to get all fcps from rfcbot.")?
-        } else {
-            HashMap::new()
-        };
-
-        let zulip_map = if include_fcp_details {
-            Some(crate::team_data::zulip_map(client).await?)
-        } else {
-            None
-        };
-
-        let mut issues_decorator = Vec::new();
-        let re = regex::Regex::new("https://github.com/rust-lang/|/").unwrap();
-        let re_zulip_link = regex::Regex::new(r"\[stream\]:\s").unwrap();
-        for issue in issues {
-            let fcp_details = if include_fcp_details {
-                let repository_name = if let Some(repo) = issue.repository.get() {
-                    repo.repository.clone()
-                } else {
-                    let split = re.split(&issue.html_url).collect::<Vec<&str>>();
-                    split[1].to_string()
-                };
-                let key = format!(
-                    "rust-lang/{}:{}:{}",
-                    repository_name, issue.number, issue.title,
-                );
-
-                if let Some(fcp) = fcp_map.get(&key) {
-                    let bot_tracking_comment_html_url = format!(
-                        "{}#issuecomment-{}",
-                        issue.html_url, fcp.fcp.fk_bot_tracking_comment
-                    );
-                    let bot_tracking_comment_content = quote_reply(&fcp.status_comment.body);
-                    let fk_initiating_comment = fcp.fcp.fk_initiating_comment;
-                    let (initiating_comment_html_url, initiating_comment_content) =
-                        if u32::try_from(fk_initiating_comment).is_err() {
-                            // We blew out the GH comment incremental counter (a i32 on their end)
-                            // See: https://rust-lang.zulipchat.com/#narrow/stream/242791-t-infra/topic/rfcbot.20asleep
-                            log::debug!("Ignoring overflowed comment id from GitHub");
-                            ("".to_string(), "".to_string())
-                        } else {
-                            let comment = issue
-                                .get_comment(&client, fk_initiating_comment.try_into()?)
-                                .await
-                                .with_context(|| {
-                                    format!(
-                                        "failed to get first comment id={} for fcp={}",
-                                        fk_initiating_comment, fcp.fcp.id
-                                    )
-                                })?;
-                            (comment.html_url, quote_reply(&comment.body))
-                        };
-
-                    // TODO: agree with the team(s) a policy to emit actual mentions to remind FCP
-                    // voting member to cast their vote
-                    let should_mention = false;
-                    Some(crate::actions::FCPDetails {
-                        bot_tracking_comment_html_url,
-                        bot_tracking_comment_content,
-                        initiating_comment_html_url,
-                        initiating_comment_content,
-                        disposition: fcp
-                            .fcp
-                            .disposition
-                            .as_deref()
-                            .unwrap_or("<unknown>")
-                            .to_string(),
-                        should_mention,
-                        pending_reviewers: fcp
-                            .reviews
-                            .iter()
-                            .filter_map(|r| {
-                                (!r.approved).then(|| crate::actions::FCPReviewerDetails {
-                                    github_login: r.reviewer.login.clone(),
-                                    zulip_id: zulip_map
-                                        .as_ref()
-                                        .map(|map| {
-                                            map.users
-                                                .iter()
-                                                .find(|&(_, &github)| github == r.reviewer.id)
-                                                .map(|v| *v.0)
-                                        })
-                                        .flatten(),
-                                })
-                            })
-                            .collect(),
-                        concerns: fcp
-                            .concerns
-                            .iter()
-                            .map(|c| crate::actions::FCPConcernDetails {
-                                name: c.name.clone(),
-                                reviewer_login: c.reviewer.login.clone(),
-                                concern_url: format!(
-                                    "{}#issuecomment-{}",
-                                    issue.html_url, c.comment.id
-                                ),
-                            })
-                            .collect(),
-                    })
-                } else {
-                    None
-                }
+    async fn query_page<'a>(
+        &'a self,
+        repo: &'a Repository,
+        include_fcp_details: bool,
+        include_mcp_details: bool,
+        client: &'a GithubClient,
+        page: Page,
+    ) -> anyhow::Result<IssuesPage> {
+        let issues = repo
+            .get_issues(&client, self)
+            .await
+            .with_context(|| "Unable to get issues.")?;
+        // Only the issues of the page are decorated, as it requires a few
+        // requests per issue.
+        let (issues, next_cursor) = page.select(issues);
+        Ok(IssuesPage {
+            issues: decorate_issues(
+                repo,
+                issues,
+                include_fcp_details,
+                include_mcp_details,
+                client,
+            )
+            .await?,
+            next_cursor,
+        })
+    }
+}
+
+/// Adds the FCP and MCP details to the issues, if requested.
+async fn decorate_issues(
+    repo: &Repository,
+    issues: Vec<Issue>,
+    include_fcp_details: bool,
+    include_mcp_details: bool,
+    client: &GithubClient,
+) -> anyhow::Result<Vec<crate::actions::IssueDecorator>> {
+    let fcp_map = if include_fcp_details {
+        crate::rfcbot::get_all_fcps()
+            .await
+            .with_context(|| "Unable to get all fcps from rfcbot.")?
+    } else {
+        HashMap::new()
+    };
+
+    let zulip_map = if include_fcp_details {
+        Some(crate::team_data::zulip_map(client).await?)
+    } else {
+        None
+    };
+
+    let mut issues_decorator = Vec::new();
+    let re = regex::Regex::new(&format!(
+        "{}/rust-lang/|/",
+        regex::escape(client.html_url())
+    ))
+    .unwrap();
+    let re_zulip_link = regex::Regex::new(r"\[stream\]:\s").unwrap();
+    for issue in issues {
+        let fcp_details = if include_fcp_details {
+            let repository_name = if let Some(repo) = issue.repository.get() {
+                repo.repository.clone()
             } else {
-                None
+                let split = re.split(&issue.html_url).collect::<Vec<&str>>();
+                split[1].to_string()
             };
+            let key = format!(
+                "rust-lang/{}:{}:{}",
+                repository_name, issue.number, issue.title,
+            );
 
-            let mcp_details = if include_mcp_details {
-                let first100_comments = issue.get_first100_comments(&client).await?;
-                let (zulip_link, concerns) = if !first100_comments.is_empty() {
-                    let split = re_zulip_link
-                        .split(&first100_comments[0].body)
-                        .collect::<Vec<&str>>();
-                    let zulip_link = split.last().unwrap_or(&"#").to_string();
-                    let concerns = find_open_concerns(first100_comments);
-                    (zulip_link, concerns)
-                } else {
-                    ("".to_string(), None)
-                };
-
-                Some(crate::actions::MCPDetails {
-                    zulip_link,
-                    concerns,
+            if let Some(fcp) = fcp_map.get(&key) {
+                let bot_tracking_comment_html_url = format!(
+                    "{}#issuecomment-{}",
+                    issue.html_url, fcp.fcp.fk_bot_tracking_comment
+                );
+                let bot_tracking_comment_content = quote_reply(&fcp.status_comment.body);
+                let fk_initiating_comment = fcp.fcp.fk_initiating_comment;
+                let (initiating_comment_html_url, initiating_comment_content) =
+                    if u32::try_from(fk_initiating_comment).is_err() {
+                        // We blew out the GH comment incremental counter (a i32 on their end)
+                        // See: https://rust-lang.zulipchat.com/#narrow/stream/242791-t-infra/topic/rfcbot.20asleep
+                        log::debug!("Ignoring overflowed comment id from GitHub");
+                        ("".to_string(), "".to_string())
+                    } else {
+                        let comment = issue
+                            .get_comment(&client, fk_initiating_comment.try_into()?)
+                            .await
+                            .with_context(|| {
+                                format!(
+                                    "failed to get first comment id={} for fcp={}",
+                                    fk_initiating_comment, fcp.fcp.id
+                                )
+                            })?;
+                        (comment.html_url, quote_reply(&comment.body))
+                    };
+
+                // TODO: agree with the team(s) a policy to emit actual mentions to remind FCP
+                // voting member to cast their vote
+                let should_mention = false;
+                Some(crate::actions::FCPDetails {
+                    bot_tracking_comment_html_url,
+                    bot_tracking_comment_content,
+                    initiating_comment_html_url,
+                    initiating_comment_content,
+                    disposition: fcp
+                        .fcp
+                        .disposition
+                        .as_deref()
+                        .unwrap_or("<unknown>")
+                        .to_string(),
+                    should_mention,
+                    pending_reviewers: fcp
+                        .reviews
+                        .iter()
+                        .filter_map(|r| {
+                            (!r.approved).then(|| crate::actions::FCPReviewerDetails {
+                                github_login: r.reviewer.login.clone(),
+                                zulip_id: zulip_map
+                                    .as_ref()
+                                    .map(|map| {
+                                        map.users
+                                            .iter()
+                                            .find(|&(_, &github)| github == r.reviewer.id)
+                                            .map(|v| *v.0)
+                                    })
+                                    .flatten(),
+                            })
+                        })
+                        .collect(),
+                    concerns: fcp
+                        .concerns
+                        .iter()
+                        .map(|c| crate::actions::FCPConcernDetails {
+                            name: c.name.clone(),
+                            reviewer_login: c.reviewer.login.clone(),
+                            concern_url: format!(
+                                "{}#issuecomment-{}",
+                                issue.html_url, c.comment.id
+                            ),
+                        })
+                        .collect(),
                 })
             } else {
                 None
+            }
+        } else {
+            None
+        };
+
+        let mcp_details = if include_mcp_details {
+            let first100_comments = issue.get_first100_comments(&client).await?;
+            let (zulip_link, concerns) = if !first100_comments.is_empty() {
+                let split = re_zulip_link
+                    .split(&first100_comments[0].body)
+                    .collect::<Vec<&str>>();
+                let zulip_link = split.last().unwrap_or(&"#").to_string();
+                let concerns = find_open_concerns(first100_comments);
+                (zulip_link, concerns)
+            } else {
+                ("".to_string(), None)
             };
 
-            issues_decorator.push(crate::actions::IssueDecorator {
-                title: issue.title.clone(),
-                number: issue.number,
-                html_url: issue.html_url.clone(),
-                repo_name: repo.name().to_owned(),
-                labels: issue
-                    .labels
-                    .iter()
-                    .map(|l| l.name.as_ref())
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                assignees: issue
-                    .assignees
-                    .iter()
-                    .map(|u| u.login.as_ref())
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                author: issue.user.login,
-                updated_at_hts: crate::actions::to_human(issue.updated_at),
-                fcp_details,
-                mcp_details,
-            });
-        }
+            Some(crate::actions::MCPDetails {
+                zulip_link,
+                concerns,
+            })
+        } else {
+            None
+        };
 
-        Ok(issues_decorator)
+        issues_decorator.push(crate::actions::IssueDecorator {
+            title: issue.title.clone(),
+            number: issue.number,
+            html_url: issue.html_url.clone(),
+            repo_name: repo.name().to_owned(),
+            labels: issue
+                .labels
+                .iter()
+                .map(|l| l.name.as_ref())
+                .collect::<Vec<_>>()
+                .join(", "),
+            assignees: issue
+                .assignees
+                .iter()
+                .map(|u| u.login.as_ref())
+                .collect::<Vec<_>>()
+                .join(", "),
+            author: issue.user.login,
+            updated_at_hts: crate::actions::to_human(issue.updated_at),
+            fcp_details,
+            mcp_details,
+        });
     }
+
+    Ok(issues_decorator)
 }
 
 /// Return open concerns filed in an issue under MCP/RFC process
@@ -2254,6 +3273,133 @@ pub struct PushEvent {
     /// Example: `refs/heads/main` or `refs/tags/v3.14.1`.
     #[serde(rename = "ref")]
     pub git_ref: String,
+    /// The commits included in the push.
+    #[serde(default)]
+    pub commits: Vec<PushCommit>,
+    pub repository: Repository,
+    sender: User,
+}
+
+impl PushEvent {
+    /// Whether this push updated the default branch of the repository.
+    pub fn is_default_branch(&self) -> bool {
+        self.git_ref
+            .strip_prefix("refs/heads/")
+            .map_or(false, |branch| branch == self.repository.default_branch)
+    }
+
+    /// Whether any commit in this push added, removed, or modified the file at `path`.
+    ///
+    /// GitHub lists at most [`PUSH_MAX_COMMITS`] commits of a push, so a push
+    /// listing that many is assumed to touch every path.
+    pub fn touches_path(&self, path: &str) -> bool {
+        if self.commits.len() >= PUSH_MAX_COMMITS {
+            return true;
+        }
+        self.commits.iter().any(|commit| {
+            commit
+                .added
+                .iter()
+                .chain(&commit.removed)
+                .chain(&commit.modified)
+                .any(|p| p == path)
+        })
+    }
+}
+
+/// The maximum number of commits listed in a [`PushEvent`], the others are
+/// left out.
+pub const PUSH_MAX_COMMITS: usize = 20;
+
+/// A commit included in a [`PushEvent`].
+#[derive(Debug, serde::Deserialize)]
+pub struct PushCommit {
+    pub id: String,
+    /// Paths of the files added by this commit.
+    #[serde(default)]
+    pub added: Vec<String>,
+    /// Paths of the files removed by this commit.
+    #[serde(default)]
+    pub removed: Vec<String>,
+    /// Paths of the files modified by this commit.
+    #[serde(default)]
+    pub modified: Vec<String>,
+}
+
+/// A GitHub Actions workflow run.
+///
+/// <https://docs.github.com/en/rest/actions/workflow-runs>
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct WorkflowRun {
+    pub id: u64,
+    pub name: Option<String>,
+    pub head_sha: String,
+    /// The state of the run, like `queued`, `in_progress` or `completed`.
+    pub status: Option<String>,
+    /// The result of the run, like `success` or `failure`.
+    ///
+    /// This is `None` while the run has not completed yet.
+    pub conclusion: Option<String>,
+    pub html_url: String,
+}
+
+/// The response of `GET /repos/{owner}/{repo}/actions/runs`.
+#[derive(Debug, serde::Deserialize)]
+struct WorkflowRuns {
+    workflow_runs: Vec<WorkflowRun>,
+}
+
+/// A job of a GitHub Actions workflow run.
+///
+/// <https://docs.github.com/en/rest/actions/workflow-jobs>
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct WorkflowRunJob {
+    pub id: u64,
+    pub run_id: u64,
+    pub name: String,
+    pub head_sha: String,
+    /// The result of the job, like `success` or `failure`.
+    ///
+    /// This is `None` while the job has not completed yet.
+    pub conclusion: Option<String>,
+    pub html_url: String,
+    /// The steps of the job, in execution order.
+    #[serde(default)]
+    pub steps: Vec<WorkflowJobStep>,
+}
+
+impl WorkflowRunJob {
+    /// Returns the first step of the job that failed, if any.
+    pub fn failed_step(&self) -> Option<&WorkflowJobStep> {
+        self.steps
+            .iter()
+            .find(|step| step.conclusion.as_deref() == Some("failure"))
+    }
+}
+
+/// A step of a [`WorkflowRunJob`].
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct WorkflowJobStep {
+    pub name: String,
+    /// The result of the step, like `success`, `failure` or `skipped`.
+    pub conclusion: Option<String>,
+    /// The 1-based position of the step in the job.
+    pub number: u32,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowJobAction {
+    Queued,
+    InProgress,
+    Completed,
+    Waiting,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct WorkflowJobEvent {
+    pub action: WorkflowJobAction,
+    pub workflow_job: WorkflowRunJob,
     pub repository: Repository,
     sender: User,
 }
@@ -2277,6 +3423,8 @@ pub enum Event {
     Issue(IssuesEvent),
     /// One or more commits are pushed to a repository branch or tag.
     Push(PushEvent),
+    /// Activity on a GitHub Actions job.
+    WorkflowJob(WorkflowJobEvent),
 }
 
 impl Event {
@@ -2286,6 +3434,7 @@ impl Event {
             Event::IssueComment(event) => &event.repository,
             Event::Issue(event) => &event.repository,
             Event::Push(event) => &event.repository,
+            Event::WorkflowJob(event) => &event.repository,
         }
     }
 
@@ -2294,7 +3443,7 @@ impl Event {
             Event::Create(_) => None,
             Event::IssueComment(event) => Some(&event.issue),
             Event::Issue(event) => Some(&event.issue),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::WorkflowJob(_) => None,
         }
     }
 
@@ -2304,7 +3453,7 @@ impl Event {
             Event::Create(_) => None,
             Event::Issue(e) => Some(&e.issue.body),
             Event::IssueComment(e) => Some(&e.comment.body),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::WorkflowJob(_) => None,
         }
     }
 
@@ -2314,7 +3463,7 @@ impl Event {
             Event::Create(_) => None,
             Event::Issue(e) => Some(&e.changes.as_ref()?.body.as_ref()?.from),
             Event::IssueComment(e) => Some(&e.changes.as_ref()?.body.as_ref()?.from),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::WorkflowJob(_) => None,
         }
     }
 
@@ -2323,7 +3472,7 @@ impl Event {
             Event::Create(_) => None,
             Event::Issue(e) => Some(&e.issue.html_url),
             Event::IssueComment(e) => Some(&e.comment.html_url),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::WorkflowJob(_) => None,
         }
     }
 
@@ -2333,6 +3482,7 @@ impl Event {
             Event::Issue(e) => &e.issue.user,
             Event::IssueComment(e) => &e.comment.user,
             Event::Push(e) => &e.sender,
+            Event::WorkflowJob(e) => &e.sender,
         }
     }
 
@@ -2341,7 +3491,7 @@ impl Event {
             Event::Create(_) => None,
             Event::Issue(e) => Some(e.issue.created_at.into()),
             Event::IssueComment(e) => Some(e.comment.updated_at.into()),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::WorkflowJob(_) => None,
         }
     }
 }
@@ -2361,24 +3511,78 @@ impl RequestSend for RequestBuilder {
 
 /// Finds the token in the user's environment, panicking if no suitable token
 /// can be found.
+///
+/// See [`token_from_env`] for a variant returning an error instead.
 pub fn default_token_from_env() -> String {
-    match std::env::var("GITHUB_TOKEN") {
-        Ok(v) => return v,
-        Err(_) => (),
+    token_from_env().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Finds the token in the user's environment, in order:
+///
+/// - the `GITHUB_TOKEN` environment variable,
+/// - the `GITHUB_API_TOKEN` environment variable, which is deprecated,
+/// - the `github.oauth-token` setting of the git configuration.
+pub fn token_from_env() -> anyhow::Result<String> {
+    find_token(|name| std::env::var(name).ok(), get_token_from_git_config)
+}
+
+fn find_token(
+    env: impl Fn(&str) -> Option<String>,
+    git_config: impl FnOnce() -> anyhow::Result<String>,
+) -> anyhow::Result<String> {
+    if let Some(token) = env("GITHUB_TOKEN") {
+        return Ok(token);
     }
 
     // kept for retrocompatibility but usage is discouraged and will be deprecated
-    match std::env::var("GITHUB_API_TOKEN") {
-        Ok(v) => return v,
-        Err(_) => (),
+    if let Some(token) = env("GITHUB_API_TOKEN") {
+        return Ok(token);
+    }
+
+    match git_config() {
+        Ok(token) if !token.is_empty() => Ok(token),
+        Ok(_) => anyhow::bail!(
+            "could not find a GitHub token: `GITHUB_TOKEN` and `GITHUB_API_TOKEN` are not set, \
+            and `github.oauth-token` is empty in the git configuration"
+        ),
+        Err(e) => anyhow::bail!(
+            "could not find a GitHub token: `GITHUB_TOKEN` and `GITHUB_API_TOKEN` are not set, \
+            and `github.oauth-token` could not be read from the git configuration ({e})"
+        ),
     }
+}
 
-    match get_token_from_git_config() {
-        Ok(v) => return v,
-        Err(_) => (),
+/// Checks that `url`, configured with the `name` environment variable, is an
+/// absolute HTTP(S) URL the paths can be appended to.
+fn validate_url(name: &str, url: &str) -> anyhow::Result<()> {
+    let parsed =
+        url::Url::parse(url).with_context(|| format!("`{name}` is not a valid URL: `{url}`"))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host().is_none() {
+        anyhow::bail!("`{name}` must be an HTTP(S) URL with a host, got `{url}`");
     }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        anyhow::bail!("`{name}` must not have a query or a fragment, got `{url}`");
+    }
+    if url.ends_with('/') {
+        anyhow::bail!("`{name}` must not end with a `/`, got `{url}`");
+    }
+    Ok(())
+}
 
-    panic!("could not find token in GITHUB_TOKEN, GITHUB_API_TOKEN or .gitconfig/github.oath-token")
+/// Returns the URL of the next page from the `Link` header of a paginated
+/// response to `url`, if any.
+///
+/// The link is resolved against `url`, in case it is relative.
+fn next_page_url(url: &str, headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+    let next = link.split(',').find_map(|part| {
+        let (target, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#)
+            .then(|| target.trim().trim_start_matches('<').trim_end_matches('>'))
+    })?;
+    Some(url::Url::parse(url).ok()?.join(next).ok()?.to_string())
 }
 
 fn get_token_from_git_config() -> anyhow::Result<String> {
@@ -2403,9 +3607,114 @@ pub struct GithubClient {
     raw_url: String,
     /// If `true`, requests will sleep if it hits GitHub's rate limit.
     retry_rate_limit: bool,
+    /// The URL of the web interface, like `https://github.com`.
+    html_url: String,
+    /// The URL of the gists, like `https://gist.github.com`.
+    gist_url: String,
+    /// The URL of the raw content of the gists.
+    gist_raw_url: String,
+    /// The files fetched with [`GithubClient::raw_file`], shared by the
+    /// clones of this client.
+    raw_files: Arc<Mutex<RawFileCache>>,
+    /// The login of the user the bot posts as, like `rustbot`.
+    bot_login: String,
+}
+
+/// The rate limits of the GitHub APIs, returned by
+/// [`GithubClient::rate_limit`].
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct RateLimitStatus {
+    /// The REST API, apart from the search.
+    pub core: RateLimit,
+    pub search: RateLimit,
+    pub graphql: RateLimit,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct RateLimit {
+    /// The requests allowed per window.
+    pub limit: u64,
+    /// The requests left until `reset`.
+    pub remaining: u64,
+    /// When the window ends, and `remaining` goes back to `limit`.
+    #[serde(deserialize_with = "chrono::serde::ts_seconds::deserialize")]
+    pub reset: DateTime<Utc>,
+}
+
+#[derive(serde::Deserialize)]
+struct RateLimitResponse {
+    resources: RateLimitStatus,
+}
+
+/// The number of files kept by [`RawFileCache`].
+const RAW_FILE_CACHE_CAPACITY: usize = 256;
+
+/// The last fetched content of the raw files, with its ETag, so that the
+/// unchanged files are not downloaded again.
+///
+/// The least recently used file is evicted when the cache is full.
+#[derive(Default)]
+struct RawFileCache {
+    /// The files by repository, branch and path.
+    files: HashMap<(String, String, String), CachedRawFile>,
+    /// Incremented on each use of the cache, to order the files by last use.
+    clock: u64,
+}
+
+struct CachedRawFile {
+    etag: String,
+    body: Bytes,
+    last_used: u64,
+}
+
+impl RawFileCache {
+    /// Returns the ETag and the content of a file.
+    fn get(&mut self, key: &(String, String, String)) -> Option<(String, Bytes)> {
+        self.clock += 1;
+        let file = self.files.get_mut(key)?;
+        file.last_used = self.clock;
+        Some((file.etag.clone(), file.body.clone()))
+    }
+
+    fn insert(&mut self, key: (String, String, String), etag: String, body: Bytes) {
+        self.clock += 1;
+        if self.files.len() >= RAW_FILE_CACHE_CAPACITY && !self.files.contains_key(&key) {
+            let oldest = self
+                .files
+                .iter()
+                .min_by_key(|(_, file)| file.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.files.remove(&oldest);
+            }
+        }
+        let last_used = self.clock;
+        self.files.insert(
+            key,
+            CachedRawFile {
+                etag,
+                body,
+                last_used,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &(String, String, String)) {
+        self.files.remove(key);
+    }
 }
 
+const DEFAULT_API_URL: &str = "https://api.github.com";
+const DEFAULT_GRAPHQL_URL: &str = "https://api.github.com/graphql";
+const DEFAULT_RAW_URL: &str = "https://raw.githubusercontent.com";
+const DEFAULT_HTML_URL: &str = "https://github.com";
+const DEFAULT_GIST_URL: &str = "https://gist.github.com";
+const DEFAULT_GIST_RAW_URL: &str = "https://gist.githubusercontent.com";
+const DEFAULT_BOT_LOGIN: &str = "rustbot";
+
 impl GithubClient {
+    /// Creates a client with the given API URLs, and the web and gist URLs of
+    /// github.com.
     pub fn new(token: String, api_url: String, graphql_url: String, raw_url: String) -> Self {
         GithubClient {
             client: Client::new(),
@@ -2414,19 +3723,92 @@ impl GithubClient {
             graphql_url,
             raw_url,
             retry_rate_limit: false,
+            html_url: DEFAULT_HTML_URL.to_string(),
+            gist_url: DEFAULT_GIST_URL.to_string(),
+            gist_raw_url: DEFAULT_GIST_RAW_URL.to_string(),
+            raw_files: Arc::default(),
+            bot_login: DEFAULT_BOT_LOGIN.to_string(),
         }
     }
 
+    /// Creates a client from the environment, panicking if no token is found.
+    ///
+    /// See [`GithubClient::try_new_from_env`] for a variant returning an
+    /// error instead.
     pub fn new_from_env() -> Self {
-        Self::new(
-            default_token_from_env(),
-            std::env::var("GITHUB_API_URL")
-                .unwrap_or_else(|_| "https://api.github.com".to_string()),
-            std::env::var("GITHUB_GRAPHQL_API_URL")
-                .unwrap_or_else(|_| "https://api.github.com/graphql".to_string()),
-            std::env::var("GITHUB_RAW_URL")
-                .unwrap_or_else(|_| "https://raw.githubusercontent.com".to_string()),
-        )
+        Self::try_new_from_env().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates a client from the environment.
+    ///
+    /// The token is found with [`token_from_env`], and the URLs default to
+    /// the ones of github.com. For a GitHub Enterprise instance, they can be
+    /// set with `GITHUB_API_URL`, `GITHUB_GRAPHQL_API_URL`, `GITHUB_RAW_URL`,
+    /// `GITHUB_HTML_URL`, `GITHUB_GIST_URL` and `GITHUB_GIST_RAW_URL`.
+    ///
+    /// The login of the bot is read from `TRIAGEBOT_USERNAME`, defaulting to
+    /// `rustbot`.
+    pub fn try_new_from_env() -> anyhow::Result<Self> {
+        let var = |name, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+        let mut client = Self::new(
+            token_from_env()?,
+            var("GITHUB_API_URL", DEFAULT_API_URL),
+            var("GITHUB_GRAPHQL_API_URL", DEFAULT_GRAPHQL_URL),
+            var("GITHUB_RAW_URL", DEFAULT_RAW_URL),
+        );
+        client.html_url = var("GITHUB_HTML_URL", DEFAULT_HTML_URL);
+        client.gist_url = var("GITHUB_GIST_URL", DEFAULT_GIST_URL);
+        client.gist_raw_url = var("GITHUB_GIST_RAW_URL", DEFAULT_GIST_RAW_URL);
+        client.bot_login = var("TRIAGEBOT_USERNAME", DEFAULT_BOT_LOGIN);
+        Ok(client)
+    }
+
+    /// Checks that the URLs of this client are well-formed, to catch a
+    /// misconfiguration at startup rather than on the first request.
+    pub fn validate_urls(&self) -> anyhow::Result<()> {
+        validate_url("GITHUB_API_URL", &self.api_url)?;
+        validate_url("GITHUB_GRAPHQL_API_URL", &self.graphql_url)?;
+        validate_url("GITHUB_RAW_URL", &self.raw_url)?;
+        validate_url("GITHUB_HTML_URL", &self.html_url)?;
+        validate_url("GITHUB_GIST_URL", &self.gist_url)?;
+        validate_url("GITHUB_GIST_RAW_URL", &self.gist_raw_url)?;
+        Ok(())
+    }
+
+    /// Returns the URL of the REST API, like `https://api.github.com`.
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// Returns the URL of the web interface, like `https://github.com`.
+    pub fn html_url(&self) -> &str {
+        &self.html_url
+    }
+
+    /// Returns the login of the user the bot posts as, like `rustbot`.
+    pub fn bot_login(&self) -> &str {
+        &self.bot_login
+    }
+
+    /// Returns the current rate limits of the token.
+    ///
+    /// Checking the rate limits doesn't count against them.
+    pub async fn rate_limit(&self) -> anyhow::Result<RateLimitStatus> {
+        let url = format!("{}/rate_limit", self.api_url);
+        let response: RateLimitResponse = self
+            .json(self.get(&url))
+            .await
+            .context("failed to get the rate limits")?;
+        Ok(response.resources)
+    }
+
+    /// Checks that the REST API is reachable and accepts the token.
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        let url = format!("{}/rate_limit", self.api_url);
+        self.send_req(self.get(&url))
+            .await
+            .with_context(|| format!("failed to reach the GitHub API at {}", self.api_url))?;
+        Ok(())
     }
 
     /// Sets whether or not this client will retry when it hits GitHub's rate limit.
@@ -2441,6 +3823,10 @@ impl GithubClient {
         &self.client
     }
 
+    /// Returns the content of a file, or `None` if it doesn't exist.
+    ///
+    /// The files are revalidated with their ETag, so an unchanged file is
+    /// returned from the cache rather than downloaded again.
     pub async fn raw_file(
         &self,
         repo: &str,
@@ -2448,21 +3834,42 @@ impl GithubClient {
         path: &str,
     ) -> anyhow::Result<Option<Bytes>> {
         let url = format!("{}/{repo}/{branch}/{path}", self.raw_url);
-        let req = self.get(&url);
+        let key = (repo.to_string(), branch.to_string(), path.to_string());
+        let cached = self.raw_files.lock().unwrap().get(&key);
+        let mut req = self.get(&url);
+        if let Some((etag, _)) = &cached {
+            req = req.header(IF_NONE_MATCH, etag);
+        }
         let req_dbg = format!("{:?}", req);
         let req = req
             .build()
             .with_context(|| format!("failed to build request {:?}", req_dbg))?;
         let resp = self.client.execute(req).await.context(req_dbg.clone())?;
         let status = resp.status();
+        let etag = resp
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
         let body = resp
             .bytes()
             .await
             .with_context(|| format!("failed to read response body {req_dbg}"))?;
-        match status {
-            StatusCode::OK => Ok(Some(body)),
-            StatusCode::NOT_FOUND => Ok(None),
-            status => anyhow::bail!("failed to GET {}: {}", url, status),
+        match (status, cached) {
+            (StatusCode::OK, _) => {
+                let mut raw_files = self.raw_files.lock().unwrap();
+                match etag {
+                    Some(etag) => raw_files.insert(key, etag, body.clone()),
+                    None => raw_files.remove(&key),
+                }
+                Ok(Some(body))
+            }
+            (StatusCode::NOT_MODIFIED, Some((_, body))) => Ok(Some(body)),
+            (StatusCode::NOT_FOUND, _) => {
+                self.raw_files.lock().unwrap().remove(&key);
+                Ok(None)
+            }
+            (status, _) => anyhow::bail!("failed to GET {}: {}", url, status),
         }
     }
 
@@ -2476,11 +3883,42 @@ impl GithubClient {
         html_url: &str,
         filename: &str,
     ) -> anyhow::Result<String> {
-        let url = html_url.replace("github.com", "githubusercontent.com") + "/raw/" + filename;
+        let url = self.gist_raw_url(html_url, filename)?;
         let response = self.raw().get(&url).send().await?;
         response.text().await.context("raw gist from url")
     }
 
+    /// Returns the URL of the raw content of `filename` in a gist.
+    ///
+    /// Only the host of `html_url` (and the path prefix of the gists, on
+    /// GitHub Enterprise) is swapped, the rest of the path is kept as is.
+    fn gist_raw_url(&self, html_url: &str, filename: &str) -> anyhow::Result<String> {
+        let gist_url = url::Url::parse(&self.gist_url).context("invalid gist URL")?;
+        let mut raw_url = url::Url::parse(&self.gist_raw_url).context("invalid gist raw URL")?;
+        let url =
+            url::Url::parse(html_url).with_context(|| format!("invalid gist `{html_url}`"))?;
+        if url.host() != gist_url.host()
+            || url.port_or_known_default() != gist_url.port_or_known_default()
+        {
+            anyhow::bail!("`{html_url}` is not a gist of {}", self.gist_url);
+        }
+        let prefix = gist_url.path().trim_end_matches('/');
+        let path = url
+            .path()
+            .strip_prefix(prefix)
+            .filter(|path| path.starts_with('/'))
+            .map(|path| path.trim_matches('/'))
+            .filter(|path| !path.is_empty())
+            .with_context(|| format!("`{html_url}` is not a gist of {}", self.gist_url))?;
+        let raw_prefix = raw_url.path().trim_end_matches('/').to_string();
+        raw_url.set_path(&format!("{raw_prefix}/{path}"));
+        raw_url
+            .path_segments_mut()
+            .map_err(|()| anyhow::anyhow!("invalid gist raw URL"))?
+            .extend(["raw", filename]);
+        Ok(raw_url.to_string())
+    }
+
     fn get(&self, url: &str) -> RequestBuilder {
         log::trace!("get {:?}", url);
         self.client.get(url).configure(self)
@@ -2575,6 +4013,73 @@ impl GithubClient {
         Ok(result)
     }
 
+    /// Returns the number of issues and PRs matching a search query, like
+    /// `repo:rust-lang/rust is:pr is:merged`.
+    pub async fn issue_search_count(&self, query: &str) -> anyhow::Result<u64> {
+        Ok(self.issue_search_counts(&[query]).await?[0])
+    }
+
+    /// Returns the number of issues and PRs matching each search query.
+    ///
+    /// The searches are batched as aliased fields of a few GraphQL queries,
+    /// instead of making one request per search.
+    pub async fn issue_search_counts(&self, queries: &[&str]) -> anyhow::Result<Vec<u64>> {
+        let mut counts = Vec::with_capacity(queries.len());
+        for chunk in queries.chunks(MAX_ALIASED_SEARCHES) {
+            let params: Vec<_> = (0..chunk.len())
+                .map(|i| format!("$q{i}: String!"))
+                .collect();
+            let fields: String = (0..chunk.len())
+                .map(|i| {
+                    format!("q{i}: search(query: $q{i}, type: ISSUE, first: 1) {{ issueCount }}\n")
+                })
+                .collect();
+            let vars: serde_json::Map<_, _> = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, query)| (format!("q{i}"), serde_json::json!(query)))
+                .collect();
+            let data = self
+                .graphql_query(
+                    &format!("query({}) {{\n{fields}}}", params.join(", ")),
+                    serde_json::Value::Object(vars),
+                )
+                .await
+                .with_context(|| format!("failed to count the issues matching {chunk:?}"))?;
+            for (i, query) in chunk.iter().enumerate() {
+                let count = data["data"][format!("q{i}")]["issueCount"]
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("no issue count for `{query}`"))?;
+                counts.push(count);
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Minimizes (hides) a comment, given its GraphQL node id.
+    pub async fn minimize_comment(
+        &self,
+        node_id: &str,
+        reason: ReportedContentClassifiers,
+    ) -> anyhow::Result<()> {
+        use cynic::MutationBuilder;
+        use github_graphql::minimize_comment::{MinimizeComment, MinimizeCommentVariables};
+
+        let subject_id = cynic::Id::new(node_id);
+        let mutation = MinimizeComment::build(MinimizeCommentVariables {
+            subject_id: &subject_id,
+            classifier: reason.into(),
+        });
+        let data: cynic::GraphQlResponse<MinimizeComment> = self
+            .json(self.post(&self.graphql_url).json(&mutation))
+            .await
+            .with_context(|| format!("failed to minimize comment {node_id}"))?;
+        if let Some(errors) = data.errors {
+            anyhow::bail!("There were graphql errors. {:?}", errors);
+        }
+        Ok(())
+    }
+
     /// Returns the object ID of the given user.
     ///
     /// Returns `None` if the user doesn't exist.
@@ -2610,6 +4115,60 @@ impl GithubClient {
         anyhow::bail!("query for user {user} failed, no error message? {user_info:?}");
     }
 
+    /// Returns the GraphQL node ids of several users and repositories in a
+    /// single query.
+    ///
+    /// Repositories are given as `owner/name`. Users and repositories that
+    /// don't exist are mapped to `None`.
+    pub async fn object_ids(&self, users: &[&str], repos: &[&str]) -> anyhow::Result<ObjectIds> {
+        let mut params = Vec::new();
+        let mut fields = Vec::new();
+        let mut vars = serde_json::Map::new();
+        for (i, user) in users.iter().enumerate() {
+            params.push(format!("$u{i}:String!"));
+            fields.push(format!("u{i}: user(login: $u{i}) {{ id }}"));
+            vars.insert(format!("u{i}"), (*user).into());
+        }
+        for (i, repo) in repos.iter().enumerate() {
+            let (owner, name) = repo
+                .split_once('/')
+                .ok_or_else(|| anyhow::anyhow!("expected owner/name, got {repo}"))?;
+            params.push(format!("$ro{i}:String!, $rn{i}:String!"));
+            fields.push(format!(
+                "r{i}: repository(owner: $ro{i}, name: $rn{i}) {{ id }}"
+            ));
+            vars.insert(format!("ro{i}"), owner.into());
+            vars.insert(format!("rn{i}"), name.into());
+        }
+        let mut ids = ObjectIds::default();
+        if fields.is_empty() {
+            return Ok(ids);
+        }
+
+        let query = format!("query({}) {{ {} }}", params.join(", "), fields.join(" "));
+        let result = self
+            .graphql_query_with_errors(&query, serde_json::Value::Object(vars))
+            .await?;
+        if let Some(errors) = result["errors"].as_array() {
+            let messages: Vec<_> = errors
+                .iter()
+                .filter(|err| err["type"].as_str().unwrap_or_default() != "NOT_FOUND")
+                .map(|err| err["message"].as_str().unwrap_or_default())
+                .collect();
+            if !messages.is_empty() {
+                anyhow::bail!("failed to query object ids: {}", messages.join("\n"));
+            }
+        }
+        let id = |alias: String| result["data"][alias]["id"].as_str().map(str::to_string);
+        for (i, user) in users.iter().enumerate() {
+            ids.users.insert(user.to_string(), id(format!("u{i}")));
+        }
+        for (i, repo) in repos.iter().enumerate() {
+            ids.repos.insert(repo.to_string(), id(format!("r{i}")));
+        }
+        Ok(ids)
+    }
+
     /// Returns whether or not the given GitHub login has made any commits to
     /// the given repo.
     pub async fn is_new_contributor(&self, repo: &Repository, author: &str) -> bool {
@@ -2688,6 +4247,35 @@ impl GithubClient {
             .with_context(|| format!("{} failed to get repo", full_name))
     }
 
+    /// Returns the repositories of the given organization.
+    ///
+    /// Archived and forked repositories are skipped unless `include_archived`
+    /// or `include_forks` is set.
+    pub async fn list_org_repos(
+        &self,
+        org: &str,
+        include_archived: bool,
+        include_forks: bool,
+    ) -> anyhow::Result<Vec<Repository>> {
+        let mut repos = Vec::new();
+        let mut next = Some(format!("{}/orgs/{org}/repos?per_page=100", self.api_url));
+        while let Some(url) = next {
+            let (body, headers, _req_dbg) = self
+                .send_req_with_headers(self.get(&url))
+                .await
+                .with_context(|| format!("failed to list repos of {org} from {url}"))?;
+            let this_page: Vec<Repository> = serde_json::from_slice(&body)
+                .with_context(|| format!("failed to list repos of {org} from {url}"))?;
+            repos.extend(
+                this_page
+                    .into_iter()
+                    .filter(|repo| repo.is_listed(include_archived, include_forks)),
+            );
+            next = next_page_url(&url, &headers);
+        }
+        Ok(repos)
+    }
+
     /// Get or create a [`Milestone`].
     ///
     /// This will not change the state if it already exists.
@@ -2781,6 +4369,15 @@ impl GithubClient {
     }
 }
 
+/// The GraphQL node ids returned by [`GithubClient::object_ids`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ObjectIds {
+    /// Login -> node id
+    pub users: HashMap<String, Option<String>>,
+    /// `owner/name` -> node id
+    pub repos: HashMap<String, Option<String>>,
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct GithubCommit {
     pub sha: String,
@@ -2803,6 +4400,23 @@ pub struct GitCommit {
     pub tree: GitCommitTree,
 }
 
+/// An annotated tag object.
+#[derive(Debug, serde::Deserialize)]
+pub struct GitTag {
+    pub sha: String,
+    pub tag: String,
+    pub message: String,
+    pub tagger: GitTagger,
+    /// The tagged object.
+    pub object: GitObject,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct GitTagger {
+    pub name: String,
+    pub email: String,
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct GitCommitTree {
     pub sha: String,
@@ -2813,6 +4427,20 @@ pub struct GitTreeObject {
     pub sha: String,
 }
 
+/// The maximum number of entries sent in a single tree update.
+const TREE_ENTRIES_PER_REQUEST: usize = 100;
+
+#[derive(Debug, serde::Deserialize)]
+struct GitBlobObject {
+    sha: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct GitBlob {
+    content: String,
+    encoding: String,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct GitTreeEntry {
     pub path: String,
@@ -2822,13 +4450,36 @@ pub struct GitTreeEntry {
     pub sha: String,
 }
 
-pub struct RecentCommit {
-    pub title: String,
-    pub pr_num: Option<i32>,
-    pub oid: String,
-    pub committed_date: DateTime<Utc>,
-}
-
+/// An entry of a directory, see [`Repository::get_contents`].
+#[derive(Debug, serde::Deserialize)]
+pub struct ContentEntry {
+    /// The name of the entry, like `README.md`.
+    pub name: String,
+    /// The path of the entry from the root of the repository.
+    pub path: String,
+    pub sha: String,
+    /// The size in bytes, `0` for directories.
+    pub size: u64,
+    #[serde(rename = "type")]
+    pub kind: ContentKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    File,
+    Dir,
+    Symlink,
+    Submodule,
+}
+
+pub struct RecentCommit {
+    pub title: String,
+    pub pr_num: Option<i32>,
+    pub oid: String,
+    pub committed_date: DateTime<Utc>,
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct GitUser {
     pub date: DateTime<FixedOffset>,
@@ -2840,7 +4491,7 @@ pub struct Parent {
 }
 
 #[async_trait]
-pub trait IssuesQuery {
+pub trait IssuesQuery: Sync {
     async fn query<'a>(
         &'a self,
         repo: &'a Repository,
@@ -2848,9 +4499,69 @@ pub trait IssuesQuery {
         include_mcp_details: bool,
         client: &'a GithubClient,
     ) -> anyhow::Result<Vec<crate::actions::IssueDecorator>>;
+
+    /// Returns a page of the results of [`IssuesQuery::query`], for the UIs
+    /// loading them incrementally.
+    ///
+    /// By default, all the results are fetched and the page is selected
+    /// from them.
+    async fn query_page<'a>(
+        &'a self,
+        repo: &'a Repository,
+        include_fcp_details: bool,
+        include_mcp_details: bool,
+        client: &'a GithubClient,
+        page: Page,
+    ) -> anyhow::Result<IssuesPage> {
+        let issues = self
+            .query(repo, include_fcp_details, include_mcp_details, client)
+            .await?;
+        let (issues, next_cursor) = page.select(issues);
+        Ok(IssuesPage {
+            issues,
+            next_cursor,
+        })
+    }
+}
+
+/// A page of the results of an [`IssuesQuery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    /// The index of the first result, `0` for the first page, or the
+    /// `next_cursor` of the previous page.
+    pub cursor: usize,
+    /// The maximum number of results in the page.
+    pub limit: usize,
+}
+
+impl Page {
+    /// Keeps the items of this page, returning them with the cursor of the
+    /// next page, if there are more items.
+    fn select<T>(self, items: Vec<T>) -> (Vec<T>, Option<usize>) {
+        let end = self.cursor.saturating_add(self.limit);
+        let next_cursor = (end < items.len()).then_some(end);
+        let page = items
+            .into_iter()
+            .skip(self.cursor)
+            .take(self.limit)
+            .collect();
+        (page, next_cursor)
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct IssuesPage {
+    pub issues: Vec<crate::actions::IssueDecorator>,
+    /// The cursor of the next page, `None` for the last page.
+    pub next_cursor: Option<usize>,
 }
 
 pub struct LeastRecentlyReviewedPullRequests;
+
+/// The number of PRs returned by [`LeastRecentlyReviewedPullRequests`],
+/// unless they are queried by page.
+const LEAST_RECENTLY_REVIEWED_LIMIT: usize = 50;
+
 #[async_trait]
 impl IssuesQuery for LeastRecentlyReviewedPullRequests {
     async fn query<'a>(
@@ -2859,6 +4570,35 @@ impl IssuesQuery for LeastRecentlyReviewedPullRequests {
         _include_fcp_details: bool,
         _include_mcp_details: bool,
         client: &'a GithubClient,
+    ) -> anyhow::Result<Vec<crate::actions::IssueDecorator>> {
+        let mut prs = LeastRecentlyReviewedPullRequests::all(repo, client).await?;
+        prs.truncate(LEAST_RECENTLY_REVIEWED_LIMIT);
+        Ok(prs)
+    }
+
+    async fn query_page<'a>(
+        &'a self,
+        repo: &'a Repository,
+        _include_fcp_details: bool,
+        _include_mcp_details: bool,
+        client: &'a GithubClient,
+        page: Page,
+    ) -> anyhow::Result<IssuesPage> {
+        let prs = LeastRecentlyReviewedPullRequests::all(repo, client).await?;
+        let (issues, next_cursor) = page.select(prs);
+        Ok(IssuesPage {
+            issues,
+            next_cursor,
+        })
+    }
+}
+
+impl LeastRecentlyReviewedPullRequests {
+    /// Returns all the non-draft T-compiler PRs, least recently reviewed
+    /// first.
+    async fn all(
+        repo: &Repository,
+        client: &GithubClient,
     ) -> anyhow::Result<Vec<crate::actions::IssueDecorator>> {
         use cynic::QueryBuilder;
         use github_graphql::queries;
@@ -2972,7 +4712,6 @@ impl IssuesQuery for LeastRecentlyReviewedPullRequests {
 
         let prs: Vec<_> = prs
             .into_iter()
-            .take(50)
             .map(
                 |(updated_at, number, title, html_url, repo_name, labels, author, assignees)| {
                     let updated_at_hts = crate::actions::to_human(updated_at);
@@ -3214,28 +4953,613 @@ pub struct Submodule {
 impl Submodule {
     /// Returns the `Repository` this submodule points to.
     ///
-    /// This assumes that the submodule is on GitHub.
-    pub async fn repository(&self, client: &GithubClient) -> anyhow::Result<Repository> {
-        let fullname = self
-            .submodule_git_url
-            .strip_prefix("https://github.com/")
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "only github submodules supported, got {}",
-                    self.submodule_git_url
-                )
-            })?
-            .strip_suffix(".git")
-            .ok_or_else(|| {
-                anyhow::anyhow!("expected .git suffix, got {}", self.submodule_git_url)
-            })?;
-        client.repository(fullname).await
+    /// Only submodules on GitHub are supported.
+    pub async fn repository(&self, client: &GithubClient) -> Result<Repository, SubmoduleError> {
+        client
+            .repository(self.github_full_name(client.html_url())?)
+            .await
+            .map_err(SubmoduleError::Http)
+    }
+
+    /// Returns the full name of the repository, like `rust-lang/book`.
+    ///
+    /// `html_url` is the URL of the GitHub web interface.
+    fn github_full_name(&self, html_url: &str) -> Result<&str, SubmoduleError> {
+        let url = &self.submodule_git_url;
+        let fullname = url
+            .strip_prefix(html_url)
+            .and_then(|path| path.strip_prefix('/'))
+            .ok_or_else(|| SubmoduleError::Unsupported { url: url.clone() })?;
+        Ok(fullname.strip_suffix(".git").unwrap_or(fullname))
     }
 }
 
+#[derive(Debug)]
+pub enum SubmoduleError {
+    /// The submodule is not hosted on GitHub.
+    Unsupported {
+        url: String,
+    },
+    Http(anyhow::Error),
+}
+
+impl fmt::Display for SubmoduleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubmoduleError::Unsupported { url } => {
+                write!(f, "only github submodules are supported, got {url}")
+            }
+            SubmoduleError::Http(e) => write!(f, "cannot get submodule repository: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SubmoduleError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_server::{
+        assert_requests, expect, test_pr, test_pr_json, test_repo, TestServer,
+    };
+    use chrono::TimeZone;
+
+    #[test]
+    fn token_lookup() {
+        let env = |name: &str| (name == "GITHUB_API_TOKEN").then(|| "api-token".to_string());
+        let token = find_token(env, || anyhow::bail!("unreachable")).unwrap();
+        assert_eq!(token, "api-token");
+
+        let token = find_token(|_| None, || Ok("git-token".to_string())).unwrap();
+        assert_eq!(token, "git-token");
+    }
+
+    #[test]
+    fn url_validation() {
+        assert!(validate_url("GITHUB_API_URL", "https://api.github.com").is_ok());
+        assert!(validate_url("GITHUB_API_URL", "https://ghe.example.com/api/v3").is_ok());
+        assert!(validate_url("GITHUB_API_URL", "http://127.0.0.1:8080").is_ok());
+
+        let err = |url| validate_url("GITHUB_API_URL", url).unwrap_err().to_string();
+        assert_eq!(
+            err("api.github.com"),
+            "`GITHUB_API_URL` is not a valid URL: `api.github.com`"
+        );
+        assert_eq!(
+            err("ftp://ghe.example.com"),
+            "`GITHUB_API_URL` must be an HTTP(S) URL with a host, got `ftp://ghe.example.com`"
+        );
+        assert_eq!(
+            err("https://ghe.example.com/api/v3?x=1"),
+            "`GITHUB_API_URL` must not have a query or a fragment, \
+            got `https://ghe.example.com/api/v3?x=1`"
+        );
+        assert_eq!(
+            err("https://ghe.example.com/api/v3/"),
+            "`GITHUB_API_URL` must not end with a `/`, got `https://ghe.example.com/api/v3/`"
+        );
+    }
+
+    #[tokio::test]
+    async fn update_label_encodes_the_name() {
+        let server = TestServer::start(vec![(
+            "200 OK",
+            r#"{"name": "good first issue"}"#.to_string(),
+        )]);
+        let repo = IssueRepository {
+            organization: "rust-lang".to_string(),
+            repository: "rust".to_string(),
+        };
+        let mut label = Label::new("good first issue");
+        label.color = Some("7057ff".to_string());
+        repo.update_label(&server.github_client(), &label)
+            .await
+            .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("PATCH", "/repos/rust-lang/rust/labels/good%20first%20issue")
+                    .with_body(serde_json::json!({"color": "7057ff"})),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn ping_api() {
+        let server = TestServer::start(vec![
+            ("200 OK", r#"{"resources": {}}"#.to_string()),
+            (
+                "401 Unauthorized",
+                r#"{"message": "Bad credentials"}"#.to_string(),
+            ),
+        ]);
+        let client = server.github_client();
+        client.ping().await.unwrap();
+        let err = client.ping().await.unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("failed to reach the GitHub API at http://127.0.0.1:"));
+        let requests = server.finish();
+        assert_eq!(requests[0].path, "/rate_limit");
+    }
+
+    #[test]
+    fn missing_token() {
+        let err = find_token(|_| None, || anyhow::bail!("`git` exited with 1")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "could not find a GitHub token: `GITHUB_TOKEN` and `GITHUB_API_TOKEN` are not set, \
+            and `github.oauth-token` could not be read from the git configuration (`git` exited with 1)"
+        );
+        let err = find_token(|_| None, || Ok(String::new())).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("`github.oauth-token` is empty in the git configuration"));
+    }
+
+    #[tokio::test]
+    async fn batched_search_counts() {
+        let counts = |range: std::ops::Range<u64>| {
+            let data: serde_json::Map<_, _> = range
+                .enumerate()
+                .map(|(i, n)| (format!("q{i}"), serde_json::json!({ "issueCount": n })))
+                .collect();
+            ("200 OK", serde_json::json!({ "data": data }).to_string())
+        };
+        let server = TestServer::start(vec![counts(0..10), counts(10..12)]);
+        let queries: Vec<String> = (0..12)
+            .map(|i| format!("repo:rust-lang/rust label:A-{i}"))
+            .collect();
+        let queries: Vec<&str> = queries.iter().map(|q| q.as_str()).collect();
+        let result = server
+            .github_client()
+            .issue_search_counts(&queries)
+            .await
+            .unwrap();
+        assert_eq!(result, (0..12).collect::<Vec<u64>>());
+
+        let requests = server.finish();
+        assert_eq!(requests.len(), 2);
+        let body: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(
+            body["query"],
+            "query($q0: String!, $q1: String!) {\n\
+            q0: search(query: $q0, type: ISSUE, first: 1) { issueCount }\n\
+            q1: search(query: $q1, type: ISSUE, first: 1) { issueCount }\n\
+            }"
+        );
+        assert_eq!(
+            body["variables"],
+            serde_json::json!({
+                "q0": "repo:rust-lang/rust label:A-10",
+                "q1": "repo:rust-lang/rust label:A-11",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn since_filter() {
+        let server = TestServer::start(vec![]);
+        let client = server.github_client();
+        let repo = test_repo();
+        let ordering = Ordering {
+            sort: "updated",
+            direction: "asc",
+            per_page: "100",
+            page: 1,
+        };
+        let filters = vec![("state", "open"), ("since", "2024-05-16T14:00:00Z")];
+        assert_eq!(
+            repo.build_issues_url(&client, &filters, &vec!["T-compiler"], ordering),
+            format!(
+                "{}/repos/rust-lang/rust/issues?state=open&since=2024-05-16T14:00:00Z&\
+                labels=T-compiler&filter=all&sort=updated&direction=asc&per_page=100&page=1",
+                server.url()
+            )
+        );
+        assert_eq!(
+            repo.build_search_issues_url(&client, &filters, &vec![], &vec!["rollup"], ordering),
+            format!(
+                "{}/search/issues?q=state:open+updated:>=2024-05-16T14:00:00Z+-label:rollup+\
+                repo:rust-lang/rust&sort=updated&order=asc&per_page=100&page=1",
+                server.url()
+            )
+        );
+
+        let query = Query {
+            filters: vec![("since", "yesterday")],
+            include_labels: vec![],
+            exclude_labels: vec![],
+        };
+        let err = repo.get_issues(&client, &query).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid `since` timestamp `yesterday`, expected e.g. `2024-05-16T14:00:00Z`"
+        );
+        assert!(server.finish().is_empty());
+    }
+
+    #[tokio::test]
+    async fn paginated_issues() {
+        let page = |numbers: std::ops::RangeInclusive<u64>| {
+            serde_json::Value::from(
+                numbers
+                    .map(|number| {
+                        let mut issue = test_pr_json();
+                        issue["number"] = number.into();
+                        issue["labels"] = serde_json::json!([{"name": "A-diagnostics"}]);
+                        issue
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .to_string()
+        };
+        let server =
+            TestServer::start(vec![("200 OK", page(1..=100)), ("200 OK", page(101..=150))]);
+        let query = Query {
+            filters: vec![("state", "open")],
+            include_labels: vec!["A-diagnostics"],
+            exclude_labels: vec![],
+        };
+        let issues = test_repo()
+            .get_issues(&server.github_client(), &query)
+            .await
+            .unwrap();
+        assert_eq!(
+            issues.iter().map(|issue| issue.number).collect::<Vec<_>>(),
+            (1..=150).collect::<Vec<_>>()
+        );
+
+        let url = |page: u64| {
+            format!(
+                "/repos/rust-lang/rust/issues?state=open&labels=A-diagnostics&filter=all&\
+                sort=created&direction=asc&per_page=100&page={page}"
+            )
+        };
+        assert_requests(
+            &server.finish(),
+            &[expect("GET", url(1)), expect("GET", url(2))],
+        );
+    }
+
+    #[tokio::test]
+    async fn directory_contents() {
+        let entry = |name: &str, kind: &str, size: u64| {
+            serde_json::json!({
+                "type": kind,
+                "size": size,
+                "name": name,
+                "path": format!("src/doc/{name}"),
+                "sha": "fbe5c7d3b6ac5e1a3dd2e4ea4a5f3e6a8e70bb0c",
+                "url": format!("https://api.github.com/repos/rust-lang/rust/contents/src/doc/{name}?ref=master"),
+                "git_url": "https://api.github.com/repos/rust-lang/rust/git/blobs/fbe5c7d3b6ac5e1a3dd2e4ea4a5f3e6a8e70bb0c",
+                "html_url": format!("https://github.com/rust-lang/rust/blob/master/src/doc/{name}"),
+                "download_url": null,
+            })
+        };
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!([
+                entry("README.md", "file", 1234),
+                entry("rustc", "dir", 0),
+                entry("book", "submodule", 0),
+            ])
+            .to_string(),
+        )]);
+        let contents = test_repo()
+            .get_contents(&server.github_client(), "src/doc/", Some("master"))
+            .await
+            .unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[0].name, "README.md");
+        assert_eq!(contents[0].path, "src/doc/README.md");
+        assert_eq!(contents[0].size, 1234);
+        assert_eq!(contents[0].kind, ContentKind::File);
+        assert_eq!(contents[1].kind, ContentKind::Dir);
+        assert_eq!(contents[2].kind, ContentKind::Submodule);
+
+        let requests = server.finish();
+        assert_eq!(
+            requests[0].path,
+            "/repos/rust-lang/rust/contents/src/doc?ref=master"
+        );
+    }
+
+    #[tokio::test]
+    async fn milestone_issues() {
+        let mut pr = test_pr_json();
+        pr["pull_request"] = serde_json::json!({});
+        pr["labels"] = serde_json::json!([{ "name": "beta-nominated" }]);
+        let mut issue = test_pr_json();
+        issue["number"] = 1200.into();
+        issue["title"] = "ICE in borrowck".into();
+        issue["html_url"] = "https://github.com/rust-lang/rust/issues/1200".into();
+        issue["assignees"] = serde_json::json!([{ "login": "oli-obk", "id": 332036 }]);
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!({
+                "total_count": 2,
+                "incomplete_results": false,
+                "items": [issue, pr],
+            })
+            .to_string(),
+        )]);
+        let issues = test_repo()
+            .get_milestone_issues(&server.github_client(), "1.80.0")
+            .await
+            .unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].number, 1200);
+        assert_eq!(issues[0].title, "ICE in borrowck");
+        assert_eq!(issues[0].assignees, "oli-obk");
+        assert_eq!(issues[1].number, 1234);
+        assert_eq!(issues[1].labels, "beta-nominated");
+
+        let requests = server.finish();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].path,
+            "/search/issues?q=milestone:%221.80.0%22+state:open+repo:rust-lang/rust\
+            &sort=created&order=asc&per_page=100&page=1"
+        );
+    }
+
+    #[tokio::test]
+    async fn milestone_issues_past_the_search_limit() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let issue = |i: i64| {
+            let mut issue = test_pr_json();
+            issue["number"] = i.into();
+            issue["created_at"] = (start + chrono::Duration::hours(i)).to_rfc3339().into();
+            issue
+        };
+        let page = |total: u64, items: std::ops::Range<i64>| {
+            let items: Vec<_> = items.map(issue).collect();
+            let page = serde_json::json!({
+                "total_count": total,
+                "incomplete_results": false,
+                "items": items,
+            });
+            ("200 OK", page.to_string())
+        };
+        // 1500 issues: the first window stops at the 1000th, and the second
+        // one starts again from its creation.
+        let mut responses: Vec<_> = (0..10)
+            .map(|p| page(1500, p * 100..(p + 1) * 100))
+            .collect();
+        responses.push(page(501, 999..1099));
+        for p in 1..6 {
+            responses.push(page(501, 999 + p * 100..(999 + (p + 1) * 100).min(1500)));
+        }
+        let server = TestServer::start(responses);
+        let issues = test_repo()
+            .get_milestone_issues(&server.github_client(), "1.80.0")
+            .await
+            .unwrap();
+        let numbers: Vec<_> = issues.iter().map(|i| i.number).collect();
+        assert_eq!(numbers, (0..1500).collect::<Vec<u64>>());
+
+        let requests = server.finish();
+        assert_eq!(requests.len(), 16);
+        assert_eq!(
+            requests[10].path,
+            "/search/issues?q=milestone:%221.80.0%22+state:open+repo:rust-lang/rust\
+            +created:>=2024-02-11T15:00:00Z&sort=created&order=asc&per_page=100&page=1"
+        );
+        assert!(requests[15].path.ends_with("&page=6"));
+    }
+
+    #[test]
+    fn paged_results() {
+        let items: Vec<u32> = (0..5).collect();
+        let mut pages = vec![];
+        let mut cursor = Some(0);
+        while let Some(c) = cursor {
+            let (page, next) = Page {
+                cursor: c,
+                limit: 2,
+            }
+            .select(items.clone());
+            pages.push(page);
+            cursor = next;
+        }
+        assert_eq!(pages, [vec![0, 1], vec![2, 3], vec![4]]);
+
+        let page = Page {
+            cursor: 0,
+            limit: 5,
+        };
+        assert_eq!(page.select(items.clone()), (items.clone(), None));
+        let page = Page {
+            cursor: 7,
+            limit: 2,
+        };
+        assert_eq!(page.select(items), (vec![], None));
+    }
+
+    #[test]
+    fn submodule_urls() {
+        let submodule = |url: &str| Submodule {
+            name: "book".to_string(),
+            path: "src/doc/book".to_string(),
+            sha: "45c1a6d69edfd1fc91fb7504cb73958dbd09441e".to_string(),
+            submodule_git_url: url.to_string(),
+        };
+        assert_eq!(
+            submodule("https://github.com/rust-lang/book.git")
+                .github_full_name("https://github.com")
+                .unwrap(),
+            "rust-lang/book"
+        );
+        assert_eq!(
+            submodule("https://github.com/rust-lang/book")
+                .github_full_name("https://github.com")
+                .unwrap(),
+            "rust-lang/book"
+        );
+        match submodule("https://gitlab.com/rust-lang/book.git")
+            .github_full_name("https://github.com")
+        {
+            Err(SubmoduleError::Unsupported { url }) => {
+                assert_eq!(url, "https://gitlab.com/rust-lang/book.git")
+            }
+            res => panic!("expected an unsupported submodule, got {res:?}"),
+        }
+        assert_eq!(
+            submodule("https://ghe.example.com/rust-lang/book.git")
+                .github_full_name("https://ghe.example.com")
+                .unwrap(),
+            "rust-lang/book"
+        );
+    }
+
+    #[test]
+    fn gist_raw_urls() {
+        let mut client = GithubClient::new(
+            "token".to_string(),
+            DEFAULT_API_URL.to_string(),
+            DEFAULT_GRAPHQL_URL.to_string(),
+            DEFAULT_RAW_URL.to_string(),
+        );
+        assert_eq!(
+            client
+                .gist_raw_url(
+                    "https://gist.github.com/rust-play/7e80ca3b1ec7abe08f60c41aff91f060",
+                    "playground.rs"
+                )
+                .unwrap(),
+            "https://gist.githubusercontent.com/rust-play/7e80ca3b1ec7abe08f60c41aff91f060\
+            /raw/playground.rs"
+        );
+
+        client.gist_url = "https://ghe.example.com/gist".to_string();
+        client.gist_raw_url = "https://ghe.example.com/gist".to_string();
+        assert_eq!(
+            client
+                .gist_raw_url("https://ghe.example.com/gist/octocat/aa5a315d", "main.rs")
+                .unwrap(),
+            "https://ghe.example.com/gist/octocat/aa5a315d/raw/main.rs"
+        );
+        assert!(client
+            .gist_raw_url("https://gist.github.com/octocat/aa5a315d", "main.rs")
+            .is_err());
+        assert!(client
+            .gist_raw_url("https://ghe.example.com/gists/octocat/aa5a315d", "main.rs")
+            .is_err());
+    }
+
+    #[test]
+    fn gist_raw_url_edge_cases() {
+        let client = GithubClient::new(
+            "token".to_string(),
+            DEFAULT_API_URL.to_string(),
+            DEFAULT_GRAPHQL_URL.to_string(),
+            DEFAULT_RAW_URL.to_string(),
+        );
+        let raw_url = |html_url| client.gist_raw_url(html_url, "playground.rs");
+        // Only the host is swapped, not the `github.com` in the username.
+        assert_eq!(
+            raw_url("https://gist.github.com/github.com-fan/7e80ca3b").unwrap(),
+            "https://gist.githubusercontent.com/github.com-fan/7e80ca3b/raw/playground.rs"
+        );
+        // Trailing slashes, queries and fragments are dropped.
+        assert_eq!(
+            raw_url("https://gist.github.com/rust-play/7e80ca3b/?permalink=1#file-main-rs")
+                .unwrap(),
+            "https://gist.githubusercontent.com/rust-play/7e80ca3b/raw/playground.rs"
+        );
+        assert!(raw_url("https://gist.github.com.evil.example/rust-play/7e80ca3b").is_err());
+        assert!(raw_url("https://gist.github.com/").is_err());
+        assert!(raw_url("gist.github.com/rust-play/7e80ca3b").is_err());
+    }
+
+    #[tokio::test]
+    async fn raw_file_revalidation() {
+        let server = TestServer::start_with_headers(vec![
+            (
+                "200 OK",
+                vec![("ETag", "\"5d41402a\"")],
+                "[assign]\n".to_string(),
+            ),
+            (
+                "304 Not Modified",
+                vec![("ETag", "\"5d41402a\"")],
+                String::new(),
+            ),
+        ]);
+        let client = server.github_client();
+        for _ in 0..2 {
+            let body = client
+                .raw_file("rust-lang/rust", "master", "triagebot.toml")
+                .await
+                .unwrap();
+            assert_eq!(body.as_deref(), Some(&b"[assign]\n"[..]));
+        }
+
+        let requests = server.finish();
+        assert_requests(
+            &requests,
+            &[
+                expect("GET", "/rust-lang/rust/master/triagebot.toml"),
+                expect("GET", "/rust-lang/rust/master/triagebot.toml"),
+            ],
+        );
+        assert_eq!(requests[0].header("if-none-match"), None);
+        assert_eq!(requests[1].header("if-none-match"), Some("\"5d41402a\""));
+    }
+
+    #[test]
+    fn raw_file_cache_eviction() {
+        let key = |i: usize| {
+            (
+                "rust-lang/rust".to_string(),
+                "master".to_string(),
+                i.to_string(),
+            )
+        };
+        let mut cache = RawFileCache::default();
+        for i in 0..RAW_FILE_CACHE_CAPACITY {
+            cache.insert(key(i), i.to_string(), Bytes::new());
+        }
+        // The first file is used again, so the second one is evicted.
+        assert!(cache.get(&key(0)).is_some());
+        cache.insert(key(RAW_FILE_CACHE_CAPACITY), String::new(), Bytes::new());
+        assert_eq!(cache.files.len(), RAW_FILE_CACHE_CAPACITY);
+        assert!(cache.get(&key(0)).is_some());
+        assert!(cache.get(&key(1)).is_none());
+        assert!(cache.get(&key(RAW_FILE_CACHE_CAPACITY)).is_some());
+    }
+
+    #[test]
+    fn label_metadata() {
+        let label: Label = serde_json::from_value(serde_json::json!({
+            "id": 208045946,
+            "node_id": "MDU6TGFiZWwyMDgwNDU5NDY=",
+            "url": "https://api.github.com/repos/rust-lang/rust/labels/T-compiler",
+            "name": "T-compiler",
+            "color": "bfd4f2",
+            "description": "Relevant to the compiler team",
+            "default": false,
+        }))
+        .unwrap();
+        assert_eq!(label.color.as_deref(), Some("bfd4f2"));
+        assert_eq!(
+            label.description.as_deref(),
+            Some("Relevant to the compiler team")
+        );
+        assert_eq!(
+            serde_json::to_value(&label).unwrap(),
+            serde_json::json!({
+                "name": "T-compiler",
+                "color": "bfd4f2",
+                "description": "Relevant to the compiler team",
+            })
+        );
+        // The labels built by the handlers have no metadata.
+        assert_eq!(label, Label::new("T-compiler"));
+        let label: Label = serde_json::from_str(r#"{"name": "A-new"}"#).unwrap();
+        assert_eq!((label.color, label.description), (None, None));
+    }
 
     #[test]
     fn display_labels() {
@@ -3245,6 +5569,345 @@ mod tests {
         assert_eq!(x.to_string(), "Unknown labels: A-bootstrap, xxx");
     }
 
+    #[tokio::test]
+    async fn add_and_list_reactions() {
+        let reaction = |id: u64, login: &str, content: &str| {
+            serde_json::json!({
+                "id": id,
+                "node_id": "REA_lATOAAQmKM5",
+                "user": {"login": login, "id": id},
+                "content": content,
+                "created_at": "2022-06-26T21:40:12Z",
+            })
+        };
+        let server = TestServer::start(vec![
+            ("201 Created", reaction(11, "rustbot", "+1").to_string()),
+            ("201 Created", reaction(12, "rustbot", "eyes").to_string()),
+            (
+                "200 OK",
+                serde_json::json!([
+                    reaction(10, "octocat", "heart"),
+                    reaction(11, "rustbot", "+1"),
+                ])
+                .to_string(),
+            ),
+            (
+                "200 OK",
+                serde_json::json!([reaction(12, "rustbot", "eyes")]).to_string(),
+            ),
+        ]);
+        let client = server.github_client();
+        let pr = test_pr();
+        assert_eq!(
+            pr.add_reaction(&client, ReactionContent::PlusOne)
+                .await
+                .unwrap(),
+            11
+        );
+        assert_eq!(
+            pr.add_comment_reaction(&client, 42, ReactionContent::Eyes)
+                .await
+                .unwrap(),
+            12
+        );
+        let reactions: Vec<_> = pr
+            .list_reactions(&client, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|reaction| (reaction.user.login, reaction.content))
+            .collect();
+        assert_eq!(
+            reactions,
+            [
+                ("octocat".to_string(), ReactionContent::Heart),
+                ("rustbot".to_string(), ReactionContent::PlusOne),
+            ]
+        );
+        let reactions = pr.list_reactions(&client, Some(42)).await.unwrap();
+        assert_eq!(reactions.len(), 1);
+        assert_eq!(reactions[0].id, 12);
+        assert_eq!(reactions[0].content, ReactionContent::Eyes);
+
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("POST", "/repos/rust-lang/rust/issues/1234/reactions")
+                    .with_body(serde_json::json!({"content": "+1"})),
+                expect("POST", "/repos/rust-lang/rust/issues/comments/42/reactions")
+                    .with_body(serde_json::json!({"content": "eyes"})),
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/issues/1234/reactions?page=1&per_page=100",
+                ),
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/issues/comments/42/reactions?page=1&per_page=100",
+                ),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn lock_and_unlock() {
+        let server = TestServer::start(vec![
+            ("204 No Content", String::new()),
+            ("204 No Content", String::new()),
+            ("204 No Content", String::new()),
+            ("404 Not Found", r#"{"message": "Not Found"}"#.to_string()),
+        ]);
+        let client = server.github_client();
+        let pr = test_pr();
+        pr.lock(&client, Some(LockReason::TooHeated)).await.unwrap();
+        pr.lock(&client, None).await.unwrap();
+        pr.unlock(&client).await.unwrap();
+        // The issue is no longer locked.
+        pr.unlock(&client).await.unwrap();
+
+        let lock = "/repos/rust-lang/rust/issues/1234/lock";
+        let requests = server.finish();
+        assert_requests(
+            &requests,
+            &[
+                expect("PUT", lock).with_body(serde_json::json!({"lock_reason": "too heated"})),
+                expect("PUT", lock),
+                expect("DELETE", lock),
+                expect("DELETE", lock),
+            ],
+        );
+        assert!(requests[1].body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rate_limits() {
+        let limit = |limit: u64, remaining: u64, reset: i64| {
+            serde_json::json!({
+                "limit": limit,
+                "used": limit - remaining,
+                "remaining": remaining,
+                "reset": reset,
+            })
+        };
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!({
+                "resources": {
+                    "core": limit(5000, 4999, 1372700873),
+                    "search": limit(30, 18, 1372697452),
+                    "graphql": limit(5000, 4993, 1372700389),
+                    "integration_manifest": limit(5000, 5000, 1551806725),
+                    "code_scanning_upload": limit(500, 500, 1551806725),
+                },
+                "rate": limit(5000, 4999, 1372700873),
+            })
+            .to_string(),
+        )]);
+        let status = server.github_client().rate_limit().await.unwrap();
+        assert_eq!(status.core.limit, 5000);
+        assert_eq!(status.core.remaining, 4999);
+        assert_eq!(
+            status.core.reset,
+            Utc.with_ymd_and_hms(2013, 7, 1, 17, 47, 53).unwrap()
+        );
+        assert_eq!(status.search.remaining, 18);
+        assert_eq!(status.graphql.remaining, 4993);
+        assert_eq!(
+            serde_json::to_value(&status.search).unwrap(),
+            serde_json::json!({
+                "limit": 30,
+                "remaining": 18,
+                "reset": "2013-07-01T16:50:52Z",
+            })
+        );
+        assert_requests(&server.finish(), &[expect("GET", "/rate_limit")]);
+    }
+
+    #[tokio::test]
+    async fn unknown_labels() {
+        let server = TestServer::start(vec![
+            ("200 OK", r#"{"name": "T-compiler"}"#.to_string()),
+            ("404 Not Found", r#"{"message": "Not Found"}"#.to_string()),
+        ]);
+        let labels = vec![Label::new("T-compiler"), Label::new("A-new")];
+        let err = test_pr()
+            .add_labels(&server.github_client(), labels)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unknown labels: A-new");
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust/labels/T-compiler"),
+                expect("GET", "/repos/rust-lang/rust/labels/A-new"),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn auto_created_labels() {
+        let server = TestServer::start(vec![
+            ("200 OK", r#"{"name": "T-compiler"}"#.to_string()),
+            ("404 Not Found", r#"{"message": "Not Found"}"#.to_string()),
+            ("201 Created", r#"{"name": "A-new"}"#.to_string()),
+            ("200 OK", "[]".to_string()),
+        ]);
+        let labels = vec![Label::new("T-compiler"), Label::new("A-new")];
+        test_pr()
+            .add_labels_with(&server.github_client(), labels, true)
+            .await
+            .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust/labels/T-compiler"),
+                expect("GET", "/repos/rust-lang/rust/labels/A-new"),
+                expect("POST", "/repos/rust-lang/rust/labels")
+                    .with_body(serde_json::json!({"name": "A-new", "color": "ededed"})),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({"labels": ["T-compiler", "A-new"]})),
+            ],
+        );
+    }
+
+    #[test]
+    fn repository_archived() {
+        let repo: Repository = serde_json::from_value(serde_json::json!({
+            "full_name": "rust-lang/rust-www",
+            "default_branch": "master",
+            "fork": false,
+            "archived": true,
+        }))
+        .unwrap();
+        assert!(repo.archived);
+        let repo: Repository = serde_json::from_value(serde_json::json!({
+            "full_name": "rust-lang/rust",
+            "default_branch": "master",
+        }))
+        .unwrap();
+        assert!(!repo.archived);
+    }
+
+    #[test]
+    fn org_repos_filter() {
+        let pages: Vec<Vec<Repository>> = serde_json::from_value(serde_json::json!([
+            [
+                {"full_name": "rust-lang/rust", "default_branch": "master"},
+                {"full_name": "rust-lang/old", "default_branch": "master", "archived": true},
+            ],
+            [
+                {"full_name": "rust-lang/cargo", "default_branch": "master", "fork": false},
+                {"full_name": "rust-lang/llvm-project", "default_branch": "main", "fork": true},
+            ],
+        ]))
+        .unwrap();
+        let listed = |include_archived, include_forks| {
+            pages
+                .iter()
+                .flatten()
+                .filter(|repo| repo.is_listed(include_archived, include_forks))
+                .map(|repo| repo.full_name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(listed(false, false), ["rust-lang/rust", "rust-lang/cargo"]);
+        assert_eq!(
+            listed(true, false),
+            ["rust-lang/rust", "rust-lang/old", "rust-lang/cargo"]
+        );
+        assert_eq!(
+            listed(false, true),
+            [
+                "rust-lang/rust",
+                "rust-lang/cargo",
+                "rust-lang/llvm-project"
+            ]
+        );
+    }
+
+    #[test]
+    fn next_page_link() {
+        let url = "https://api.github.com/orgs/rust-lang/repos?per_page=100";
+        let headers = |link: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(LINK, HeaderValue::from_static(link));
+            headers
+        };
+        assert_eq!(
+            next_page_url(
+                url,
+                &headers(
+                    "<https://api.github.com/organizations/5430905/repos?per_page=100&page=2>; \
+                     rel=\"next\", \
+                     <https://api.github.com/organizations/5430905/repos?per_page=100&page=3>; \
+                     rel=\"last\""
+                )
+            )
+            .as_deref(),
+            Some("https://api.github.com/organizations/5430905/repos?per_page=100&page=2")
+        );
+        assert_eq!(
+            next_page_url(
+                url,
+                &headers(
+                    "<https://api.github.com/organizations/5430905/repos?per_page=100&page=2>; \
+                     rel=\"prev\""
+                )
+            ),
+            None
+        );
+        assert_eq!(next_page_url(url, &HeaderMap::new()), None);
+    }
+
+    #[tokio::test]
+    async fn org_repos_pagination() {
+        let server = TestServer::start_with_headers(vec![
+            (
+                "200 OK",
+                vec![(
+                    "Link",
+                    "</orgs/rust-lang/repos?per_page=100&page=2>; rel=\"next\", \
+                     </orgs/rust-lang/repos?per_page=100&page=2>; rel=\"last\"",
+                )],
+                serde_json::json!([
+                    {"full_name": "rust-lang/rust", "default_branch": "master"},
+                    {"full_name": "rust-lang/old", "default_branch": "master", "archived": true},
+                ])
+                .to_string(),
+            ),
+            (
+                "200 OK",
+                vec![(
+                    "Link",
+                    "</orgs/rust-lang/repos?per_page=100&page=1>; rel=\"prev\", \
+                     </orgs/rust-lang/repos?per_page=100&page=1>; rel=\"first\"",
+                )],
+                serde_json::json!([
+                    {"full_name": "rust-lang/cargo", "default_branch": "master"},
+                ])
+                .to_string(),
+            ),
+        ]);
+        let client = server.github_client();
+        let repos = client
+            .list_org_repos("rust-lang", false, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            repos
+                .iter()
+                .map(|repo| repo.full_name.as_str())
+                .collect::<Vec<_>>(),
+            ["rust-lang/rust", "rust-lang/cargo"]
+        );
+
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/orgs/rust-lang/repos?per_page=100"),
+                expect("GET", "/orgs/rust-lang/repos?per_page=100&page=2"),
+            ],
+        );
+    }
+
     #[test]
     fn extract_one_file() {
         let input = r##"\
@@ -3311,4 +5974,740 @@ index c58310947d2..3b0854d4a9b 100644
             ]
         )
     }
+
+    #[test]
+    fn draft_mutations() {
+        use cynic::MutationBuilder;
+        use github_graphql::convert_to_draft::{ConvertToDraft, ConvertToDraftVariables};
+        use github_graphql::mark_ready_for_review::{
+            MarkReadyForReview, MarkReadyForReviewVariables,
+        };
+
+        let id = cynic::Id::new("PR_kwDOAAABBB");
+        let variables = serde_json::json!({"pullRequestId": "PR_kwDOAAABBB"});
+
+        let mutation = MarkReadyForReview::build(MarkReadyForReviewVariables {
+            pull_request_id: &id,
+        });
+        assert!(mutation.query.starts_with("mutation"));
+        assert!(mutation.query.contains("markPullRequestReadyForReview"));
+        assert_eq!(
+            serde_json::to_value(&mutation).unwrap()["variables"],
+            variables
+        );
+
+        let mutation = ConvertToDraft::build(ConvertToDraftVariables {
+            pull_request_id: &id,
+        });
+        assert!(mutation.query.starts_with("mutation"));
+        assert!(mutation.query.contains("convertPullRequestToDraft"));
+        assert_eq!(
+            serde_json::to_value(&mutation).unwrap()["variables"],
+            variables
+        );
+    }
+
+    #[test]
+    fn minimize_comment_mutation() {
+        use cynic::MutationBuilder;
+        use github_graphql::minimize_comment::{MinimizeComment, MinimizeCommentVariables};
+
+        let id = cynic::Id::new("IC_kwDOAAQmKM5gGXcN");
+        let mutation = MinimizeComment::build(MinimizeCommentVariables {
+            subject_id: &id,
+            classifier: ReportedContentClassifiers::Outdated.into(),
+        });
+        assert!(mutation.query.starts_with("mutation"));
+        assert!(mutation.query.contains("minimizeComment"));
+        assert!(mutation.query.contains("isMinimized"));
+        assert_eq!(
+            serde_json::to_value(&mutation).unwrap()["variables"],
+            serde_json::json!({"subjectId": "IC_kwDOAAQmKM5gGXcN", "classifier": "OUTDATED"})
+        );
+    }
+
+    #[test]
+    fn issue_node_id() {
+        // Trimmed down `pull_request` from a `pull_request` webhook payload.
+        let pr: Issue = serde_json::from_value(serde_json::json!({
+            "url": "https://api.github.com/repos/rust-lang/rust/pulls/1234",
+            "id": 1852334422,
+            "node_id": "PR_kwDOAAQmKM5uZ-pW",
+            "html_url": "https://github.com/rust-lang/rust/pull/1234",
+            "number": 1234,
+            "state": "open",
+            "title": "Example PR",
+            "user": {"login": "octocat", "id": 583231},
+            "body": null,
+            "labels": [],
+            "assignees": [],
+            "created_at": "2024-04-20T10:31:31Z",
+            "updated_at": "2024-04-20T10:31:31Z",
+            "draft": true,
+            "comments_url": "https://api.github.com/repos/rust-lang/rust/issues/1234/comments",
+        }))
+        .unwrap();
+        assert_eq!(pr.node_id.as_deref(), Some("PR_kwDOAAQmKM5uZ-pW"));
+        assert!(pr.draft);
+
+        // Older payloads may lack it.
+        let issue: Issue = serde_json::from_value(serde_json::json!({
+            "html_url": "https://github.com/rust-lang/rust/issues/1",
+            "number": 1,
+            "state": "open",
+            "title": "Example issue",
+            "user": {"login": "octocat", "id": 583231},
+            "body": "",
+            "labels": [],
+            "assignees": [],
+            "created_at": "2024-04-20T10:31:31Z",
+            "updated_at": "2024-04-20T10:31:31Z",
+            "comments_url": "https://api.github.com/repos/rust-lang/rust/issues/1/comments",
+        }))
+        .unwrap();
+        assert_eq!(issue.node_id, None);
+    }
+
+    #[test]
+    fn workflow_job_steps() {
+        // Trimmed down response of `GET /repos/{owner}/{repo}/actions/jobs/{job_id}`.
+        let job: WorkflowRunJob = serde_json::from_value(serde_json::json!({
+            "id": 24079883291,
+            "run_id": 8764382154,
+            "head_sha": "2b0ffc2b30c6f8e1e5a5c1f6f3c5c1a79f6c0f2e",
+            "html_url": "https://github.com/rust-lang/rust/actions/runs/8764382154/job/24079883291",
+            "status": "completed",
+            "conclusion": "failure",
+            "name": "PR - x86_64-gnu-llvm-17",
+            "steps": [
+                {
+                    "name": "Set up job",
+                    "status": "completed",
+                    "conclusion": "success",
+                    "number": 1,
+                    "started_at": "2024-04-20T10:31:31Z",
+                    "completed_at": "2024-04-20T10:31:33Z",
+                },
+                {
+                    "name": "run the build",
+                    "status": "completed",
+                    "conclusion": "failure",
+                    "number": 2,
+                    "started_at": "2024-04-20T10:31:33Z",
+                    "completed_at": "2024-04-20T11:02:01Z",
+                },
+                {
+                    "name": "upload artifacts to S3",
+                    "status": "completed",
+                    "conclusion": "skipped",
+                    "number": 3,
+                    "started_at": "2024-04-20T11:02:01Z",
+                    "completed_at": "2024-04-20T11:02:01Z",
+                },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(job.steps.len(), 3);
+        assert_eq!(job.steps[2].conclusion.as_deref(), Some("skipped"));
+        let failed = job.failed_step().unwrap();
+        assert_eq!(failed.name, "run the build");
+        assert_eq!(failed.number, 2);
+    }
+
+    #[tokio::test]
+    async fn large_tree_update_is_chunked() {
+        let tree_json = |sha: &str| serde_json::json!({"sha": sha, "tree": []}).to_string();
+        let server = TestServer::start(vec![
+            ("201 Created", tree_json("tree1")),
+            ("201 Created", tree_json("tree2")),
+            ("201 Created", tree_json("tree3")),
+        ]);
+        let repo = test_repo();
+        let entries: Vec<_> = (0..250)
+            .map(|i| GitTreeEntry {
+                path: format!("src/tools/submodule{i}"),
+                mode: "160000".to_string(),
+                object_type: "commit".to_string(),
+                sha: format!("{i:040x}"),
+            })
+            .collect();
+        let tree = repo
+            .update_tree(&server.github_client(), "base", &entries)
+            .await
+            .unwrap();
+        assert_eq!(tree.sha, "tree3");
+
+        let requests = server.finish();
+        let bodies: Vec<serde_json::Value> = requests
+            .iter()
+            .map(|r| serde_json::from_str(&r.body).unwrap())
+            .collect();
+        let summary: Vec<_> = bodies
+            .iter()
+            .map(|b| {
+                (
+                    b["base_tree"].as_str().unwrap(),
+                    b["tree"].as_array().unwrap().len(),
+                )
+            })
+            .collect();
+        assert_eq!(summary, [("base", 100), ("tree1", 100), ("tree2", 50)]);
+        assert_eq!(bodies[2]["tree"][49]["path"], "src/tools/submodule249");
+    }
+
+    #[tokio::test]
+    async fn create_annotated_tag() {
+        let target = "5e1d3299a290026b85787bc9c7e72bcc53ac283f";
+        let tag_sha = "940bd336248efae0f9ee5bc7b2d5c985887b16ac";
+        let server = TestServer::start(vec![
+            (
+                "201 Created",
+                serde_json::json!({
+                    "node_id": "MDM6VGFnOTQwYmQzMzYyNDhlZmFlMGY5ZWU1YmM3YjJkNWM5ODU4ODdiMTZhYw==",
+                    "tag": "1.80.0",
+                    "sha": tag_sha,
+                    "url": format!("https://api.github.com/repos/rust-lang/rust/git/tags/{tag_sha}"),
+                    "message": "Rust 1.80.0",
+                    "tagger": {
+                        "name": "rustbot",
+                        "email": "rustbot@rust-lang.org",
+                        "date": "2024-07-25T14:00:00Z",
+                    },
+                    "object": {
+                        "type": "commit",
+                        "sha": target,
+                        "url": format!("https://api.github.com/repos/rust-lang/rust/git/commits/{target}"),
+                    },
+                    "verification": {"verified": false, "reason": "unsigned"},
+                })
+                .to_string(),
+            ),
+            (
+                "201 Created",
+                serde_json::json!({
+                    "ref": "refs/tags/1.80.0",
+                    "node_id": "MDM6UmVmcmVmcy90YWdzLzEuODAuMA==",
+                    "url": "https://api.github.com/repos/rust-lang/rust/git/refs/tags/1.80.0",
+                    "object": {
+                        "type": "tag",
+                        "sha": tag_sha,
+                        "url": format!("https://api.github.com/repos/rust-lang/rust/git/tags/{tag_sha}"),
+                    },
+                })
+                .to_string(),
+            ),
+        ]);
+        let client = server.github_client();
+        let repo = test_repo();
+        let tagger = GitTagger {
+            name: "rustbot".to_string(),
+            email: "rustbot@rust-lang.org".to_string(),
+        };
+        let tag = repo
+            .create_tag(&client, "1.80.0", "Rust 1.80.0", target, &tagger)
+            .await
+            .unwrap();
+        assert_eq!(tag.sha, tag_sha);
+        assert_eq!(tag.object.sha, target);
+        let reference = repo
+            .create_reference(&client, "tags/1.80.0", &tag.sha)
+            .await
+            .unwrap();
+        assert_eq!(reference.refname, "refs/tags/1.80.0");
+        assert_eq!(reference.object.object_type, "tag");
+
+        let requests = server.finish();
+        assert_eq!(requests[0].path, "/repos/rust-lang/rust/git/tags");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&requests[0].body).unwrap(),
+            serde_json::json!({
+                "tag": "1.80.0",
+                "message": "Rust 1.80.0",
+                "object": target,
+                "type": "commit",
+                "tagger": {"name": "rustbot", "email": "rustbot@rust-lang.org"},
+            })
+        );
+        assert_eq!(requests[1].path, "/repos/rust-lang/rust/git/refs");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&requests[1].body).unwrap(),
+            serde_json::json!({"ref": "refs/tags/1.80.0", "sha": tag_sha})
+        );
+    }
+
+    #[tokio::test]
+    async fn object_ids_in_one_query() {
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!({
+                "data": {
+                    "u0": {"id": "MDQ6VXNlcjU4MzIzMQ=="},
+                    "u1": null,
+                    "u2": {"id": "MDQ6VXNlcjQ3OTc5MjIz"},
+                    "r0": {"id": "MDEwOlJlcG9zaXRvcnk3MjQ3MTI="},
+                },
+                "errors": [{
+                    "type": "NOT_FOUND",
+                    "path": ["u1"],
+                    "message": "Could not resolve to a User with the login of 'ghost-user'.",
+                }],
+            })
+            .to_string(),
+        )]);
+        let ids = server
+            .github_client()
+            .object_ids(&["octocat", "ghost-user", "rustbot"], &["rust-lang/rust"])
+            .await
+            .unwrap();
+        let id = |id: &str| Some(id.to_string());
+        assert_eq!(
+            ids,
+            ObjectIds {
+                users: HashMap::from([
+                    ("octocat".to_string(), id("MDQ6VXNlcjU4MzIzMQ==")),
+                    ("ghost-user".to_string(), None),
+                    ("rustbot".to_string(), id("MDQ6VXNlcjQ3OTc5MjIz")),
+                ]),
+                repos: HashMap::from([(
+                    "rust-lang/rust".to_string(),
+                    id("MDEwOlJlcG9zaXRvcnk3MjQ3MTI=")
+                )]),
+            }
+        );
+
+        let requests = server.finish();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/graphql");
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(
+            body["query"],
+            "query($u0:String!, $u1:String!, $u2:String!, $ro0:String!, $rn0:String!) { \
+             u0: user(login: $u0) { id } u1: user(login: $u1) { id } \
+             u2: user(login: $u2) { id } r0: repository(owner: $ro0, name: $rn0) { id } }"
+        );
+        assert_eq!(
+            body["variables"],
+            serde_json::json!({
+                "u0": "octocat",
+                "u1": "ghost-user",
+                "u2": "rustbot",
+                "ro0": "rust-lang",
+                "rn0": "rust",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn dismiss_review() {
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!({
+                "id": 2016502424,
+                "node_id": "PRR_kwDOAAQmKM54MiCY",
+                "user": {"login": "oli-obk", "id": 332036},
+                "body": "r=me",
+                "state": "DISMISSED",
+                "html_url": "https://github.com/rust-lang/rust/pull/1234#pullrequestreview-2016502424",
+                "commit_id": "5e1d3299a290026b85787bc9c7e72bcc53ac283f",
+                "submitted_at": "2024-04-20T10:31:31Z",
+            })
+            .to_string(),
+        )]);
+        let review = test_pr()
+            .dismiss_review(
+                &server.github_client(),
+                2016502424,
+                "The approved commits were force-pushed away.",
+            )
+            .await
+            .unwrap();
+        assert_eq!(review.state, "DISMISSED");
+
+        let requests = server.finish();
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(
+            requests[0].path,
+            "/repos/rust-lang/rust/pulls/1234/reviews/2016502424/dismissals"
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&requests[0].body).unwrap(),
+            serde_json::json!({
+                "message": "The approved commits were force-pushed away.",
+                "event": "DISMISS",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn participants() {
+        let comment = |id: u64, login: &str, user_id: u64| {
+            serde_json::json!({
+                "id": id,
+                "node_id": "IC_kwDOAAQmKM5",
+                "body": "",
+                "html_url": format!("https://github.com/rust-lang/rust/pull/1234#issuecomment-{id}"),
+                "user": {"login": login, "id": user_id},
+                "created_at": "2022-06-26T21:35:01Z",
+                "updated_at": "2022-06-26T21:35:01Z",
+            })
+        };
+        let review = |id: u64, login: &str, user_id: u64| {
+            serde_json::json!({
+                "id": id,
+                "user": {"login": login, "id": user_id},
+                "body": "",
+                "state": "COMMENTED",
+                "commit_id": "5e1d3299a290026b85787bc9c7e72bcc53ac283f",
+                "submitted_at": "2022-06-27T10:31:31Z",
+            })
+        };
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!([
+                    comment(1, "rustbot", 47979223),
+                    comment(2, "octocat", 583231),
+                    comment(3, "oli-obk", 332036),
+                    comment(4, "rustbot", 47979223),
+                ])
+                .to_string(),
+            ),
+            (
+                "200 OK",
+                serde_json::json!([
+                    review(5, "oli-obk", 332036),
+                    review(6, "compiler-errors", 3674314),
+                ])
+                .to_string(),
+            ),
+        ]);
+        let mut pr = test_pr_json();
+        pr["pull_request"] = serde_json::json!({});
+        pr["assignees"] = serde_json::json!([
+            {"login": "compiler-errors", "id": 3674314},
+            {"login": "jieyouxu", "id": 39484203},
+        ]);
+        let pr: Issue = serde_json::from_value(pr).unwrap();
+
+        let participants = pr.participants(&server.github_client()).await.unwrap();
+        let logins: Vec<_> = participants.iter().map(|u| u.login.as_str()).collect();
+        assert_eq!(
+            logins,
+            [
+                "rustbot",
+                "octocat",
+                "oli-obk",
+                "compiler-errors",
+                "jieyouxu"
+            ]
+        );
+
+        let paths: Vec<_> = server.finish().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/repos/rust-lang/rust/issues/1234/comments?page=1&per_page=100",
+                "/repos/rust-lang/rust/pulls/1234/reviews?per_page=100",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn blob_roundtrip() {
+        let sha = "3a0f86fb8db8eea7ccbb9a95f325ddbedfb25e15";
+        let server = TestServer::start(vec![
+            (
+                "201 Created",
+                serde_json::json!({
+                    "url": format!("https://api.github.com/repos/rust-lang/rust/git/blobs/{sha}"),
+                    "sha": sha,
+                })
+                .to_string(),
+            ),
+            (
+                "200 OK",
+                serde_json::json!({
+                    "content": "W3JlbGFiZWxdCmFsbG93LXVuYXV0aGVudGljYXRlZCA9IFsiUy0qIl0K\n",
+                    "encoding": "base64",
+                    "url": format!("https://api.github.com/repos/rust-lang/rust/git/blobs/{sha}"),
+                    "sha": sha,
+                    "size": 42,
+                })
+                .to_string(),
+            ),
+        ]);
+        let client = server.github_client();
+        let repo = test_repo();
+        let content = "[relabel]\nallow-unauthenticated = [\"S-*\"]\n";
+        assert_eq!(
+            repo.create_blob(&client, content.as_bytes()).await.unwrap(),
+            sha
+        );
+        assert_eq!(
+            repo.get_blob(&client, sha).await.unwrap(),
+            content.as_bytes()
+        );
+
+        let requests = server.finish();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/repos/rust-lang/rust/git/blobs");
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(
+            body["content"],
+            "W3JlbGFiZWxdCmFsbG93LXVuYXV0aGVudGljYXRlZCA9IFsiUy0qIl0K"
+        );
+        assert_eq!(
+            requests[1].path,
+            format!("/repos/rust-lang/rust/git/blobs/{sha}")
+        );
+    }
+
+    #[tokio::test]
+    async fn default_branch_sha() {
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!({
+                "ref": "refs/heads/master",
+                "node_id": "MDM6UmVmNzI0NzEyOnJlZnMvaGVhZHMvbWFzdGVy",
+                "url": "https://api.github.com/repos/rust-lang/rust/git/refs/heads/master",
+                "object": {
+                    "sha": "5e1d3299a290026b85787bc9c7e72bcc53ac283f",
+                    "type": "commit",
+                    "url": "https://api.github.com/repos/rust-lang/rust/git/commits/5e1d3299a290026b85787bc9c7e72bcc53ac283f",
+                },
+            })
+            .to_string(),
+        )]);
+        let repo = test_repo();
+        let sha = repo
+            .default_branch_sha(&server.github_client())
+            .await
+            .unwrap();
+        assert_eq!(sha, "5e1d3299a290026b85787bc9c7e72bcc53ac283f");
+        let requests = server.finish();
+        assert_eq!(
+            requests[0].path,
+            "/repos/rust-lang/rust/git/ref/heads/master"
+        );
+    }
+
+    #[test]
+    fn latest_workflow_run() {
+        // Trimmed down response of
+        // `GET /repos/{owner}/{repo}/actions/runs?head_sha={sha}&per_page=1`.
+        let runs: WorkflowRuns = serde_json::from_value(serde_json::json!({
+            "total_count": 3,
+            "workflow_runs": [
+                {
+                    "id": 8764382154,
+                    "name": "CI",
+                    "node_id": "WFR_kwLOAAQmKM8AAAACCmYvyg",
+                    "head_branch": "fix-ice",
+                    "head_sha": "2b0ffc2b30c6f8e1e5a5c1f6f3c5c1a79f6c0f2e",
+                    "run_number": 41234,
+                    "event": "pull_request",
+                    "status": "completed",
+                    "conclusion": "failure",
+                    "workflow_id": 3042291,
+                    "html_url": "https://github.com/rust-lang/rust/actions/runs/8764382154",
+                    "pull_requests": [],
+                    "created_at": "2024-04-20T10:31:20Z",
+                    "updated_at": "2024-04-20T11:02:10Z",
+                    "run_attempt": 1,
+                }
+            ],
+        }))
+        .unwrap();
+        let run = runs.workflow_runs.into_iter().next().unwrap();
+        assert_eq!(run.id, 8764382154);
+        assert_eq!(run.name.as_deref(), Some("CI"));
+        assert_eq!(run.status.as_deref(), Some("completed"));
+        assert_eq!(run.conclusion.as_deref(), Some("failure"));
+
+        let runs: WorkflowRuns = serde_json::from_value(serde_json::json!({
+            "total_count": 0,
+            "workflow_runs": [],
+        }))
+        .unwrap();
+        assert!(runs.workflow_runs.is_empty());
+    }
+
+    #[test]
+    fn find_marked_bot_comment() {
+        let comment = |id: u64, body: &str| -> Comment {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "node_id": "IC_kwDOAAQmKM5",
+                "body": body,
+                "html_url": format!("https://github.com/rust-lang/rust/pull/1234#issuecomment-{id}"),
+                "user": {"login": "rustbot", "id": 47979223},
+            }))
+            .unwrap()
+        };
+        let comments = || {
+            vec![
+                comment(1, "Some changes occurred in src/tools/cargo"),
+                comment(2, &with_bot_comment_marker("**Error**: oops", "error")),
+                comment(3, "<!-- triagebot:error-summary -->"),
+            ]
+        };
+        assert_eq!(
+            comments()[1].body,
+            "**Error**: oops\n\n<!-- triagebot:error -->\n"
+        );
+        assert_eq!(
+            find_marked_comment(comments(), "rustbot", "error")
+                .unwrap()
+                .id,
+            2
+        );
+        assert!(find_marked_comment(comments(), "rustbot", "note").is_none());
+    }
+
+    #[test]
+    fn user_authored_marker_is_ignored() {
+        let comment = |id: u64, login: &str, body: &str| -> Comment {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "node_id": "IC_kwDOAAQmKM5",
+                "body": body,
+                "html_url": format!("https://github.com/rust-lang/rust/pull/1234#issuecomment-{id}"),
+                "user": {"login": login, "id": id},
+            }))
+            .unwrap()
+        };
+        let marked = with_bot_comment_marker("**Error**: oops", "error");
+        let comments = || {
+            vec![
+                comment(1, "rustbot", &marked),
+                comment(2, "octocat", &marked),
+            ]
+        };
+        assert_eq!(
+            find_marked_comment(comments(), "rustbot", "error")
+                .unwrap()
+                .id,
+            1
+        );
+        // Only the pasted marker is left.
+        assert!(find_marked_comment(comments().split_off(1), "rustbot", "error").is_none());
+    }
+
+    #[tokio::test]
+    async fn find_bot_comment_ignores_other_users() {
+        let marked = with_bot_comment_marker("Thanks for the report", "issue-template");
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!([
+                {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAQmKM5",
+                    "body": marked,
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": "octocat", "id": 583231},
+                },
+            ])
+            .to_string(),
+        )]);
+        let found = test_pr()
+            .find_bot_comment(&server.github_client(), "issue-template")
+            .await
+            .unwrap();
+        assert!(found.is_none());
+        server.finish();
+    }
+
+    #[tokio::test]
+    async fn find_bot_comment_past_the_first_page() {
+        let comment = |id: u64, body: &str| {
+            serde_json::json!({
+                "id": id,
+                "node_id": format!("IC_{id}"),
+                "body": body,
+                "html_url": format!("https://github.com/rust-lang/rust/pull/1234#issuecomment-{id}"),
+                "user": {"login": "rustbot", "id": 47979223},
+            })
+        };
+        let first_page: Vec<_> = (1..=100).map(|id| comment(id, "Unrelated")).collect();
+        let marked = with_bot_comment_marker("Thanks for the report", "issue-template");
+        let server = TestServer::start(vec![
+            ("200 OK", serde_json::Value::from(first_page).to_string()),
+            (
+                "200 OK",
+                serde_json::json!([comment(101, &marked)]).to_string(),
+            ),
+        ]);
+        let found = test_pr()
+            .find_bot_comment(&server.github_client(), "issue-template")
+            .await
+            .unwrap();
+        assert_eq!(found.unwrap().id, 101);
+        assert_requests(
+            &server.finish(),
+            &[
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/issues/1234/comments?page=1&per_page=100",
+                ),
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/issues/1234/comments?page=2&per_page=100",
+                ),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn post_comment_on_enterprise() {
+        let server = TestServer::start(vec![(
+            "201 Created",
+            crate::test_server::comment_json(1612361421, "Thanks!"),
+        )]);
+        let mut issue = test_pr_json();
+        issue["html_url"] = "https://github.example.com/rust-lang/rust/pull/1234".into();
+        issue["comments_url"] =
+            "https://github.example.com/api/v3/repos/rust-lang/rust/issues/1234/comments".into();
+        let issue: Issue = serde_json::from_value(issue).unwrap();
+        issue
+            .post_comment(&server.github_client(), "Thanks!")
+            .await
+            .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[expect("POST", "/repos/rust-lang/rust/issues/1234/comments")
+                .with_body(serde_json::json!({ "body": "Thanks!" }))],
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_comment() {
+        let server = TestServer::start(vec![("204 No Content", String::new())]);
+        test_pr()
+            .delete_comment(&server.github_client(), 1612361421)
+            .await
+            .unwrap();
+        let requests = server.finish();
+        assert_eq!(requests[0].method, "DELETE");
+        assert_eq!(
+            requests[0].path,
+            "/repos/rust-lang/rust/issues/comments/1612361421"
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_missing_comment() {
+        let server = TestServer::start(vec![("404 Not Found", String::new())]);
+        test_pr()
+            .delete_comment(&server.github_client(), 1612361421)
+            .await
+            .unwrap();
+        server.finish();
+
+        let server = TestServer::start(vec![("403 Forbidden", String::new())]);
+        assert!(test_pr()
+            .delete_comment(&server.github_client(), 1612361421)
+            .await
+            .is_err());
+        server.finish();
+    }
 }
@@ -1,5 +1,7 @@
 use crate::config::{self, Config, ConfigurationError};
-use crate::github::{Event, GithubClient, IssueCommentAction, IssuesAction, IssuesEvent};
+use crate::github::{
+    Event, GithubClient, IssueCommentAction, IssuesAction, IssuesEvent, Repository,
+};
 use octocrab::Octocrab;
 use parser::command::{assign::AssignCommand, Command, Input};
 use std::fmt;
@@ -24,16 +26,27 @@ impl fmt::Display for HandlerError {
 }
 
 mod assign;
+mod author_assignee;
 mod autolabel;
 mod bot_pull_requests;
+mod ci_failure_summary;
 mod close;
+pub mod deferred_nudges;
 pub mod docs_update;
 mod github_releases;
 mod glacier;
+mod help;
+mod issue_template;
+pub mod label_sync;
+mod link_zulip;
 mod major_change;
+mod markdown_links;
+pub mod meeting_reminders;
 mod mentions;
 mod merge_conflicts;
+mod milestone_closed;
 mod milestone_prs;
+mod needs_mcve;
 mod no_merges;
 mod nominate;
 mod note;
@@ -42,20 +55,75 @@ mod notify_zulip;
 mod ping;
 pub mod pr_tracking;
 mod prioritize;
+mod priority;
 pub mod project_goals;
 pub mod pull_requests_assignment_update;
+mod rate_limit;
+pub mod reconcile;
+mod regression_labels;
 mod relabel;
 mod relnotes;
 mod rendered_link;
+mod retry_ci;
+pub mod review_pool;
 mod review_requested;
 mod review_submitted;
+mod rollup;
 pub mod rustc_commits;
 mod shortcut;
+mod stale_reviews;
+mod subtree_update;
+mod time;
 mod transfer;
 pub mod types_planning_updates;
 mod validate_config;
 
+/// The handlers that don't write to the repository, and keep running for the
+/// events of archived repositories.
+const READ_ONLY_HANDLERS: &[&str] = &[
+    "notification",
+    "notify_zulip",
+    "pr_tracking",
+    "project_goals",
+    "rustc_commits",
+];
+
+/// The handlers that ping or message people on their own, and are skipped
+/// while the nudges are paused (see [`crate::jobs::nudges_paused`]).
+///
+/// The commands always run.
+const NUDGE_HANDLERS: &[&str] = &[
+    "ci_failure_summary",
+    "issue_template",
+    "mentions",
+    "merge_conflicts",
+    "needs_mcve",
+    "notify_zulip",
+    "stale_reviews",
+];
+
+/// Returns whether the handler `name` runs for the events of `repo`.
+///
+/// Archived repositories are read-only, so any mutation would just fail with
+/// a 403: only the [`READ_ONLY_HANDLERS`] run for them. The
+/// [`NUDGE_HANDLERS`] are skipped if `nudges_paused` is set.
+fn handler_runs(repo: &Repository, name: &str, nudges_paused: bool) -> bool {
+    (!repo.archived || READ_ONLY_HANDLERS.contains(&name))
+        && !(nudges_paused && NUDGE_HANDLERS.contains(&name))
+}
+
 pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
+    if event.repo().archived {
+        log::info!(
+            "only running the read-only handlers for event on archived repository {}",
+            event.repo().full_name
+        );
+    }
+    let nudges_paused = crate::jobs::nudges_paused();
+    let runs = |name| handler_runs(event.repo(), name, nudges_paused);
+    if let Event::Push(push) = event {
+        config::invalidate_on_push(push);
+    }
     let config = config::get(&ctx.github, event.repo()).await;
     if let Err(e) = &config {
         log::warn!("configuration error {}: {e}", event.repo().full_name);
@@ -63,10 +131,11 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
     let mut errors = Vec::new();
 
     if let (Ok(config), Event::Issue(event)) = (config.as_ref(), event) {
-        handle_issue(ctx, event, config, &mut errors).await;
+        handle_issue(ctx, event, config, nudges_paused, &mut errors).await;
     }
 
-    if let Some(body) = event.comment_body() {
+    // All the commands write to the repository, if only to report errors.
+    if let Some(body) = event.comment_body().filter(|_| runs("commands")) {
         handle_command(ctx, event, &config, body, &mut errors).await;
     }
 
@@ -94,15 +163,36 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         );
     }
 
-    if let Err(e) = milestone_prs::handle(ctx, event).await {
-        log::error!(
-            "failed to process event {:?} with milestone_prs handler: {:?}",
-            event,
-            e
-        );
+    if runs("milestone_prs") {
+        if let Err(e) = milestone_prs::handle(ctx, event).await {
+            log::error!(
+                "failed to process event {:?} with milestone_prs handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(milestone_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.milestone_closed.as_ref())
+        .filter(|_| runs("milestone_closed"))
+    {
+        if let Err(e) = milestone_closed::handle(ctx, event, milestone_config).await {
+            log::error!(
+                "failed to process event {:?} with milestone_closed handler: {:?}",
+                event,
+                e
+            );
+        }
     }
 
-    if let Some(rendered_link_config) = config.as_ref().ok().and_then(|c| c.rendered_link.as_ref())
+    if let Some(rendered_link_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.rendered_link.as_ref())
+        .filter(|_| runs("rendered_link"))
     {
         if let Err(e) = rendered_link::handle(ctx, event, rendered_link_config).await {
             log::error!(
@@ -113,15 +203,17 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         }
     }
 
-    if let Err(e) = relnotes::handle(ctx, event).await {
-        log::error!(
-            "failed to process event {:?} with relnotes handler: {:?}",
-            event,
-            e
-        );
+    if runs("relnotes") {
+        if let Err(e) = relnotes::handle(ctx, event).await {
+            log::error!(
+                "failed to process event {:?} with relnotes handler: {:?}",
+                event,
+                e
+            );
+        }
     }
 
-    if config.as_ref().is_ok_and(|c| c.bot_pull_requests.is_some()) {
+    if config.as_ref().is_ok_and(|c| c.bot_pull_requests.is_some()) && runs("bot_pull_requests") {
         if let Err(e) = bot_pull_requests::handle(ctx, event).await {
             log::error!(
                 "failed to process event {:?} with bot_pull_requests handler: {:?}",
@@ -135,6 +227,7 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         .as_ref()
         .ok()
         .and_then(|c| c.review_submitted.as_ref())
+        .filter(|_| runs("review_submitted"))
     {
         if let Err(e) = review_submitted::handle(ctx, event, config).await {
             log::error!(
@@ -149,6 +242,7 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         .as_ref()
         .ok()
         .and_then(|c| c.github_releases.as_ref())
+        .filter(|_| runs("github_releases"))
     {
         if let Err(e) = github_releases::handle(ctx, event, ghr_config).await {
             log::error!(
@@ -159,12 +253,44 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         }
     }
 
+    if let Some(ci_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.ci_failure_summary.as_ref())
+        .filter(|_| runs("ci_failure_summary"))
+    {
+        if let Err(e) = ci_failure_summary::handle(ctx, event, ci_config).await {
+            log::error!(
+                "failed to process event {:?} with ci_failure_summary handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(stale_reviews_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.stale_reviews.as_ref())
+        .filter(|_| runs("stale_reviews"))
+    {
+        if let Err(e) = stale_reviews::handle(ctx, event, stale_reviews_config).await {
+            log::error!(
+                "failed to process event {:?} with stale_reviews handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
     if let Some(conflict_config) = config
         .as_ref()
         .ok()
         .and_then(|c| c.merge_conflicts.as_ref())
+        .filter(|_| runs("merge_conflicts"))
     {
-        if let Err(e) = merge_conflicts::handle(ctx, event, conflict_config).await {
+        let active_hours = config.as_ref().ok().and_then(|c| c.active_hours.as_ref());
+        if let Err(e) = merge_conflicts::handle(ctx, event, conflict_config, active_hours).await {
             log::error!(
                 "failed to process event {:?} with merge_conflicts handler: {:?}",
                 event,
@@ -173,6 +299,21 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         }
     }
 
+    if let Some(mcve_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.needs_mcve.as_ref())
+        .filter(|_| runs("needs_mcve"))
+    {
+        if let Err(e) = needs_mcve::handle(ctx, event, mcve_config).await {
+            log::error!(
+                "failed to process event {:?} with needs_mcve handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
     errors
 }
 
@@ -182,25 +323,29 @@ macro_rules! issue_handlers {
             ctx: &Context,
             event: &IssuesEvent,
             config: &Arc<Config>,
+            nudges_paused: bool,
             errors: &mut Vec<HandlerError>,
         ) {
             $(
-            match $name::parse_input(ctx, event, config.$name.as_ref()).await {
-                Err(err) => errors.push(HandlerError::Message(err)),
-                Ok(Some(input)) => {
-                    if let Some(config) = &config.$name {
-                        $name::handle_input(ctx, config, event, input).await.unwrap_or_else(|err| errors.push(HandlerError::Other(err)));
-                    } else {
-                        errors.push(HandlerError::Message(format!(
-                            "The feature `{}` is not enabled in this repository.\n\
-                            To enable it add its section in the `triagebot.toml` \
-                            in the root of the repository.",
-                            stringify!($name)
-                        )));
+            if handler_runs(&event.repository, stringify!($name), nudges_paused) {
+                match $name::parse_input(ctx, event, config.$name.as_ref()).await {
+                    Err(err) => errors.push(HandlerError::Message(err)),
+                    Ok(Some(input)) => {
+                        if let Some(config) = &config.$name {
+                            $name::handle_input(ctx, config, event, input).await.unwrap_or_else(|err| errors.push(HandlerError::Other(err)));
+                        } else {
+                            errors.push(HandlerError::Message(format!(
+                                "The feature `{}` is not enabled in this repository.\n\
+                                To enable it add its section in the `triagebot.toml` \
+                                in the root of the repository.",
+                                stringify!($name)
+                            )));
+                        }
                     }
+                    Ok(None) => {}
                 }
-                Ok(None) => {}
-            })*
+            }
+            )*
         }
     }
 }
@@ -211,13 +356,18 @@ macro_rules! issue_handlers {
 // Each module in the list must contain the functions `parse_input` and `handle_input`.
 issue_handlers! {
     assign,
+    author_assignee,
     autolabel,
     major_change,
     mentions,
     no_merges,
+    nominate,
     notify_zulip,
     review_requested,
     pr_tracking,
+    regression_labels,
+    issue_template,
+    markdown_links,
     validate_config,
 }
 
@@ -251,7 +401,7 @@ macro_rules! command_handlers {
                     log::debug!("skipping event, comment was {:?}", e.action);
                     return;
                 }
-                Event::Push(_) | Event::Create(_) => {
+                Event::Push(_) | Event::Create(_) | Event::WorkflowJob(_) => {
                     log::debug!("skipping unsupported event");
                     return;
                 }
@@ -293,14 +443,36 @@ macro_rules! command_handlers {
                 }
             };
 
+            let multiple_commands = commands.len() > 1;
             for command in commands {
+                let kind = command.kind();
                 match command {
                     $(
                     Command::$enum(Ok(command)) => {
+                        if let Some(limits) = &config.command_rate_limit {
+                            if let Err(wait) = rate_limit::check(
+                                limits,
+                                &event.repo().full_name,
+                                &event.user().login,
+                                kind.name(),
+                            ) {
+                                errors.push(HandlerError::Message(
+                                    rate_limit::cooldown_message(kind.name(), wait),
+                                ));
+                                continue;
+                            }
+                        }
                         if let Some(config) = &config.$name {
-                            $name::handle_command(ctx, config, event, command)
-                                .await
-                                .unwrap_or_else(|err| errors.push(HandlerError::Other(err)));
+                            if let Err(err) = $name::handle_command(ctx, config, event, command).await {
+                                // Tell which command failed, since the other ones were applied.
+                                if multiple_commands {
+                                    errors.push(HandlerError::Message(format!(
+                                        "The `{}` command failed, the other commands were still processed.",
+                                        kind.name()
+                                    )));
+                                }
+                                errors.push(HandlerError::Other(err));
+                            }
                         } else {
                             errors.push(HandlerError::Message(format!(
                                 "The feature `{}` is not enabled in this repository.\n\
@@ -341,6 +513,15 @@ command_handlers! {
     close: Close,
     note: Note,
     transfer: Transfer,
+    retry_ci: RetryCi,
+    subtree_update: SubtreeUpdate,
+    rollup: Rollup,
+    time: Time,
+    link_zulip: LinkZulip,
+    review_pool: ReviewPool,
+    milestone_closed: MilestoneClosed,
+    priority: Priority,
+    help: Help,
 }
 
 pub struct Context {
@@ -349,3 +530,144 @@ pub struct Context {
     pub username: String,
     pub octocrab: Octocrab,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, test_pr_json, TestServer};
+
+    fn opened_event(archived: bool) -> Event {
+        Event::Issue(
+            serde_json::from_value(serde_json::json!({
+                "action": "opened",
+                "pull_request": test_pr_json(),
+                "changes": null,
+                "repository": {
+                    "full_name": "rust-lang/rust-www",
+                    "default_branch": "master",
+                    "archived": archived,
+                },
+                "sender": {"login": "octocat", "id": 583231},
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn archived_repository_skips_mutating_handlers() {
+        let event = opened_event(true);
+        for handler in [
+            "assign",
+            "autolabel",
+            "commands",
+            "relnotes",
+            "merge_conflicts",
+        ] {
+            assert!(
+                !handler_runs(event.repo(), handler, false),
+                "{handler} runs"
+            );
+        }
+        for handler in READ_ONLY_HANDLERS {
+            assert!(
+                handler_runs(event.repo(), handler, false),
+                "{handler} is skipped"
+            );
+        }
+
+        let event = opened_event(false);
+        assert!(handler_runs(event.repo(), "assign", false));
+    }
+
+    #[test]
+    fn paused_nudges_keep_the_commands() {
+        let event = opened_event(false);
+        for handler in NUDGE_HANDLERS {
+            assert!(!handler_runs(event.repo(), handler, true), "{handler} runs");
+            assert!(handler_runs(event.repo(), handler, false));
+        }
+        for handler in ["commands", "assign", "autolabel", "pr_tracking"] {
+            assert!(
+                handler_runs(event.repo(), handler, true),
+                "{handler} is skipped"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn one_comment_runs_all_its_commands() {
+        let body = "@rustbot ready and @rustbot retry-ci";
+        let mut pr = test_pr_json();
+        pr["pull_request"] = serde_json::json!({});
+        pr["labels"] = serde_json::json!([{"name": "S-waiting-on-author"}]);
+        let event = Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": pr,
+                "comment": {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAABBB",
+                    "body": body,
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": "octocat", "id": 583231},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        );
+        let config = toml::from_str::<Config>("[shortcut]\n[retry-ci]\n").unwrap();
+
+        let mut head_pr = test_pr_json();
+        head_pr["head"] = serde_json::json!({
+            "sha": "f00dfeed",
+            "ref": "fix",
+            "repo": {"full_name": "octocat/rust", "default_branch": "master"},
+        });
+        let failed_run = serde_json::json!({
+            "id": 2,
+            "head_sha": "f00dfeed",
+            "conclusion": "failure",
+            "html_url": "https://github.com/rust-lang/rust/actions/runs/2",
+        });
+        let server = TestServer::start(vec![
+            // ready
+            ("204 No Content", String::new()),
+            ("200 OK", r#"{"name": "S-waiting-on-review"}"#.to_string()),
+            ("200 OK", r#"[{"name": "S-waiting-on-review"}]"#.to_string()),
+            // retry-ci
+            ("200 OK", head_pr.to_string()),
+            (
+                "200 OK",
+                serde_json::json!({"workflow_runs": [failed_run]}).to_string(),
+            ),
+            ("201 Created", "{}".to_string()),
+        ]);
+        let ctx = server.context();
+        let mut errors = Vec::new();
+        handle_command(&ctx, &event, &Ok(Arc::new(config)), body, &mut errors).await;
+        assert!(errors.is_empty(), "{errors:?}");
+
+        assert_requests(
+            &server.finish(),
+            &[
+                expect(
+                    "DELETE",
+                    "/repos/rust-lang/rust/issues/1234/labels/S-waiting-on-author",
+                ),
+                expect("GET", "/repos/rust-lang/rust/labels/S-waiting-on-review"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({"labels": ["S-waiting-on-review"]})),
+                expect("GET", "/repos/rust-lang/rust/pulls/1234"),
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/actions/runs?head_sha=f00dfeed",
+                ),
+                expect(
+                    "POST",
+                    "/repos/rust-lang/rust/actions/runs/2/rerun-failed-jobs",
+                ),
+            ],
+        );
+    }
+}
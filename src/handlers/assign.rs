@@ -18,11 +18,21 @@
 //!
 //! This also supports auto-assignment of new PRs. Based on rules in the
 //! `assign.owners` config, it will auto-select an assignee based on the files
-//! the PR modifies.
+//! the PR modifies. With `assign.co_assign` enabled, a PR spanning several
+//! `owners` areas gets one reviewer per area. If no reviewer can be picked,
+//! the owners of the most changed files are suggested instead.
+//!
+//! Team members can opt in to the review pool of their team (see
+//! [`review_pool`](super::review_pool)), in which case only the members of
+//! the pool are picked for that team.
 
 use crate::{
     config::{AssignConfig, WarnNonDefaultBranchException},
-    github::{self, Event, FileDiff, Issue, IssuesAction, Selection},
+    db::assignment_history::{record_assignment, AssignmentReason},
+    db::review_pools::review_pools,
+    github::{
+        self, AssigneeSuggestion, Event, FileDiff, Issue, IssuesAction, ReactionContent, Selection,
+    },
     handlers::{pr_tracking::has_user_capacity, Context, GithubClient, IssuesEvent},
     interactions::EditIssueBody,
 };
@@ -31,7 +41,7 @@ use parser::command::assign::AssignCommand;
 use parser::command::{Command, Input};
 use rand::seq::IteratorRandom;
 use rust_team_data::v1::Teams;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt;
 use tokio_postgres::Client as DbClient;
 use tracing as log;
@@ -68,7 +78,22 @@ reassign to another reviewer.
 Use `r?` to explicitly pick a reviewer";
 
 const RETURNING_USER_WELCOME_MESSAGE_NO_REVIEWER: &str =
-    "@{author}: no appropriate reviewer found, use `r?` to override";
+    "@{author}: no appropriate reviewer found, use `r?` to override.
+
+Reviewers can claim this PR by reacting with :eyes: to this comment.";
+
+/// The kind of the bot comment marking PRs that reviewers can claim with a
+/// reaction, see [`first_eligible_reactor`].
+pub(crate) const NEEDS_REVIEWER_COMMENT: &str = "needs-reviewer";
+
+const SUGGESTIONS_MESSAGE: &str =
+    "Based on the changed files, {suggestions} may be able to review this PR.";
+
+/// The number of reviewers suggested when no reviewer could be picked.
+const MAX_SUGGESTIONS: usize = 3;
+
+const CO_ASSIGN_MESSAGE: &str =
+    "This PR touches several areas, so {co_assignees} will also review it.";
 
 const ON_VACATION_WARNING: &str = "{username} is on vacation.
 
@@ -168,6 +193,27 @@ pub(super) async fn handle_input(
             // want any assignments or noise.
             return Ok(());
         }
+        let co_assignees = match &assignee {
+            Some(assignee) if config.co_assign && !from_comment => {
+                determine_co_assignees(ctx, event, config, diff, assignee).await?
+            }
+            _ => Vec::new(),
+        };
+        let suggestions = if assignee.is_none() {
+            event
+                .issue
+                .assignee_suggestions_from_diff(&ctx.github, config)
+                .await
+                .unwrap_or_else(|e| {
+                    log::warn!(
+                        "failed to suggest reviewers for {}: {e:?}",
+                        event.issue.global_id()
+                    );
+                    Vec::new()
+                })
+        } else {
+            Vec::new()
+        };
         let welcome = if ctx
             .github
             .is_new_contributor(&event.repository, &event.issue.user.login)
@@ -200,9 +246,32 @@ pub(super) async fn handle_input(
             // No welcome is posted if they are not new and they used `r?` in the opening body.
             None
         };
+        let welcome = welcome.map(|mut welcome| {
+            if !co_assignees.is_empty() {
+                let names: Vec<_> = co_assignees.iter().map(|name| format!("@{name}")).collect();
+                welcome.push_str("\n\n");
+                welcome.push_str(&CO_ASSIGN_MESSAGE.replace("{co_assignees}", &names.join(", ")));
+            }
+            if assignee.is_none() {
+                if let Some(message) = suggestions_message(&suggestions) {
+                    welcome.push_str("\n\n");
+                    welcome.push_str(&message);
+                }
+                welcome = github::with_bot_comment_marker(&welcome, NEEDS_REVIEWER_COMMENT);
+            }
+            welcome
+        });
         if let Some(assignee) = assignee {
             set_assignee(&event.issue, &ctx.github, &assignee).await;
         }
+        for co_assignee in &co_assignees {
+            if let Err(e) = event.issue.add_assignee(&ctx.github, co_assignee).await {
+                log::warn!(
+                    "failed to add co-assignee {co_assignee} to PR {}: {e}",
+                    event.issue.global_id()
+                );
+            }
+        }
 
         if let Some(welcome) = welcome {
             if let Err(e) = event.issue.post_comment(&ctx.github, &welcome).await {
@@ -231,6 +300,21 @@ pub(super) async fn handle_input(
     Ok(())
 }
 
+/// Returns the message listing the best `suggestions`, if there are any.
+///
+/// The names are quoted so that the suggested people and teams aren't pinged.
+fn suggestions_message(suggestions: &[AssigneeSuggestion]) -> Option<String> {
+    if suggestions.is_empty() {
+        return None;
+    }
+    let names: Vec<_> = suggestions
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|suggestion| format!("`{}`", suggestion.name))
+        .collect();
+    Some(SUGGESTIONS_MESSAGE.replace("{suggestions}", &names.join(", ")))
+}
+
 /// Finds the `r?` command in the PR body.
 ///
 /// Returns the name after the `r?` command, or None if not found.
@@ -281,7 +365,7 @@ fn modifies_submodule(diff: &[FileDiff]) -> Option<String> {
 }
 
 /// Sets the assignee of a PR, alerting any errors.
-async fn set_assignee(issue: &Issue, github: &GithubClient, username: &str) {
+pub(crate) async fn set_assignee(issue: &Issue, github: &GithubClient, username: &str) {
     // Don't re-assign if already assigned, e.g. on comment edit
     if issue.contain_assignee(&username) {
         log::trace!(
@@ -332,14 +416,71 @@ async fn determine_assignee(
     diff: &[FileDiff],
 ) -> anyhow::Result<(Option<String>, bool)> {
     let db_client = ctx.db.get().await;
+    let selection = select_assignee(ctx, &db_client, event, config, diff).await?;
+    if let Err(e) = record_assignment(
+        &db_client,
+        &event.issue,
+        selection.assignee.as_deref(),
+        &selection.candidates,
+        selection.reason,
+    )
+    .await
+    {
+        log::warn!(
+            "failed to record assignment history for PR {}: {e:?}",
+            event.issue.global_id()
+        );
+    }
+    let from_comment = selection.reason == AssignmentReason::ReviewCommand;
+    Ok((selection.assignee, from_comment))
+}
+
+/// The outcome of [`select_assignee`].
+struct AssigneeSelection {
+    assignee: Option<String>,
+    /// The names the assignee was chosen from.
+    candidates: Vec<String>,
+    reason: AssignmentReason,
+}
+
+impl AssigneeSelection {
+    fn new(assignee: Option<String>, candidates: &[String], reason: AssignmentReason) -> Self {
+        AssigneeSelection {
+            assignee,
+            candidates: candidates.to_vec(),
+            reason,
+        }
+    }
+}
+
+/// Does the work of [`determine_assignee`], also keeping track of how the
+/// assignee was chosen.
+async fn select_assignee(
+    ctx: &Context,
+    db_client: &DbClient,
+    event: &IssuesEvent,
+    config: &AssignConfig,
+    diff: &[FileDiff],
+) -> anyhow::Result<AssigneeSelection> {
     let teams = crate::team_data::teams(&ctx.github).await?;
     if let Some(name) = find_assign_command(ctx, event) {
-        if is_self_assign(&name, &event.issue.user.login) {
-            return Ok((Some(name.to_string()), true));
+        let names = [name];
+        if is_self_assign(&names[0], &event.issue.user.login) {
+            return Ok(AssigneeSelection::new(
+                Some(names[0].clone()),
+                &names,
+                AssignmentReason::ReviewCommand,
+            ));
         }
         // User included `r?` in the opening PR body.
-        match find_reviewer_from_names(&db_client, &teams, config, &event.issue, &[name]).await {
-            Ok(assignee) => return Ok((Some(assignee), true)),
+        match find_reviewer_from_names(db_client, &teams, config, &event.issue, &names).await {
+            Ok(assignee) => {
+                return Ok(AssigneeSelection::new(
+                    Some(assignee),
+                    &names,
+                    AssignmentReason::ReviewCommand,
+                ))
+            }
             Err(e) => {
                 event
                     .issue
@@ -352,10 +493,16 @@ async fn determine_assignee(
     // Errors fall-through to try fallback group.
     match find_reviewers_from_diff(config, diff) {
         Ok(candidates) if !candidates.is_empty() => {
-            match find_reviewer_from_names(&db_client, &teams, config, &event.issue, &candidates)
+            match find_reviewer_from_names(db_client, &teams, config, &event.issue, &candidates)
                 .await
             {
-                Ok(assignee) => return Ok((Some(assignee), false)),
+                Ok(assignee) => {
+                    return Ok(AssigneeSelection::new(
+                        Some(assignee),
+                        &candidates,
+                        AssignmentReason::Diff,
+                    ))
+                }
                 Err(FindReviewerError::TeamNotFound(team)) => log::warn!(
                     "team {team} not found via diff from PR {}, \
                     is there maybe a misconfigured group?",
@@ -392,8 +539,14 @@ async fn determine_assignee(
     }
 
     if let Some(fallback) = config.adhoc_groups.get("fallback") {
-        match find_reviewer_from_names(&db_client, &teams, config, &event.issue, fallback).await {
-            Ok(assignee) => return Ok((Some(assignee), false)),
+        match find_reviewer_from_names(db_client, &teams, config, &event.issue, fallback).await {
+            Ok(assignee) => {
+                return Ok(AssigneeSelection::new(
+                    Some(assignee),
+                    fallback,
+                    AssignmentReason::FallbackGroup,
+                ))
+            }
             Err(e) => {
                 log::trace!(
                     "failed to select from fallback group for PR {}: {e}",
@@ -402,7 +555,150 @@ async fn determine_assignee(
             }
         }
     }
-    Ok((None, false))
+
+    if let Some(assignee) = fallback_assignee(&teams, config, &event.issue) {
+        return Ok(AssigneeSelection::new(
+            Some(assignee),
+            config.fallback_assignee.as_slice(),
+            AssignmentReason::FallbackAssignee,
+        ));
+    }
+    Ok(AssigneeSelection::new(
+        None,
+        &[],
+        AssignmentReason::NoneFound,
+    ))
+}
+
+/// Picks a reviewer from the `fallback_assignee` config.
+///
+/// This is the last resort when no reviewer could be determined otherwise,
+/// so review capacity is intentionally not checked.
+fn fallback_assignee(teams: &Teams, config: &AssignConfig, issue: &Issue) -> Option<String> {
+    let fallback = config.fallback_assignee.as_ref()?;
+    match candidate_reviewers_from_names(teams, config, issue, std::slice::from_ref(fallback)) {
+        Ok(candidates) => candidates
+            .into_iter()
+            .choose(&mut rand::thread_rng())
+            .map(|candidate| candidate.to_string()),
+        Err(e) => {
+            log::warn!(
+                "failed to select fallback assignee `{fallback}` for PR {}: {e}",
+                issue.global_id()
+            );
+            None
+        }
+    }
+}
+
+/// Returns the first user who claimed a PR by reacting with :eyes: to the
+/// [`NEEDS_REVIEWER_COMMENT`] of the bot.
+///
+/// Only members of a team who could be assigned with `r?` are eligible.
+pub(crate) fn first_eligible_reactor<'a>(
+    teams: &Teams,
+    config: &AssignConfig,
+    issue: &Issue,
+    reactions: &'a [github::Reaction],
+) -> Option<&'a str> {
+    reactions
+        .iter()
+        .filter(|reaction| reaction.content == ReactionContent::Eyes)
+        .map(|reaction| reaction.user.login.as_str())
+        .find(|login| {
+            let is_team_member = teams
+                .teams
+                .values()
+                .any(|team| team.members.iter().any(|member| member.github == *login));
+            is_team_member
+                && candidate_reviewers_from_names(
+                    teams,
+                    config,
+                    issue,
+                    std::slice::from_ref(&login.to_string()),
+                )
+                .is_ok()
+        })
+}
+
+/// Picks additional reviewers for a new PR when `co_assign` is enabled.
+///
+/// Returns one reviewer for each affected area (see [`affected_areas`]) that
+/// is not already covered by `assignee` or a previously chosen reviewer.
+async fn determine_co_assignees(
+    ctx: &Context,
+    event: &IssuesEvent,
+    config: &AssignConfig,
+    diff: &[FileDiff],
+    assignee: &str,
+) -> anyhow::Result<Vec<String>> {
+    let areas = affected_areas(config, diff)?;
+    if areas.len() < 2 {
+        return Ok(Vec::new());
+    }
+    let db_client = ctx.db.get().await;
+    let teams = crate::team_data::teams(&ctx.github).await?;
+    let mut chosen = vec![assignee.to_string()];
+    for area in &areas {
+        let covered = match candidate_reviewers_from_names(&teams, config, &event.issue, area) {
+            Ok(candidates) => chosen.iter().any(|name| candidates.contains(name.as_str())),
+            Err(_) => false,
+        };
+        if covered {
+            continue;
+        }
+        match find_reviewer_from_names(&db_client, &teams, config, &event.issue, area).await {
+            Ok(reviewer) => {
+                if !chosen.contains(&reviewer) {
+                    if let Err(e) = record_assignment(
+                        &db_client,
+                        &event.issue,
+                        Some(&reviewer),
+                        area,
+                        AssignmentReason::CoAssign,
+                    )
+                    .await
+                    {
+                        log::warn!(
+                            "failed to record assignment history for PR {}: {e:?}",
+                            event.issue.global_id()
+                        );
+                    }
+                    chosen.push(reviewer);
+                }
+            }
+            Err(e) => log::trace!(
+                "no co-assignee could be determined for PR {} from {area:?}: {e}",
+                event.issue.global_id()
+            ),
+        }
+    }
+    chosen.remove(0);
+    Ok(chosen)
+}
+
+/// Returns the `owners` areas touched by a diff.
+///
+/// Each area is the deduplicated list of names from the longest `owners`
+/// patterns matching a changed file. Files that don't match any pattern are
+/// ignored.
+fn affected_areas(config: &AssignConfig, diff: &[FileDiff]) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut areas = BTreeSet::new();
+    for file_diff in diff {
+        let mut names: Vec<String> = config
+            .longest_owner_patterns(&file_diff.path)?
+            .into_iter()
+            .flat_map(|pattern| &config.owners[pattern])
+            .cloned()
+            .collect();
+        if names.is_empty() {
+            continue;
+        }
+        names.sort();
+        names.dedup();
+        areas.insert(names);
+    }
+    Ok(areas.into_iter().collect())
 }
 
 /// Returns a list of candidate reviewers to use based on which files were changed.
@@ -424,37 +720,12 @@ fn find_reviewers_from_diff(
         // prefers choosing reviewers from deeply nested paths over those defined
         // for top-level paths, under the assumption that they are more
         // specialized.
-        //
-        // This is a list to handle the situation if multiple paths of the same
-        // length match.
-        let mut longest_owner_patterns = Vec::new();
-
-        // Find the longest `owners` entries that match this path.
-        let mut longest = HashMap::new();
-        for owner_pattern in config.owners.keys() {
-            let ignore = ignore::gitignore::GitignoreBuilder::new("/")
-                .add_line(None, owner_pattern)
-                .with_context(|| format!("owner file pattern `{owner_pattern}` is not valid"))?
-                .build()?;
-            if ignore
-                .matched_path_or_any_parents(&file_diff.path, false)
-                .is_ignore()
-            {
-                let owner_len = owner_pattern.split('/').count();
-                longest.insert(owner_pattern, owner_len);
-            }
-        }
-        let max_count = longest.values().copied().max().unwrap_or(0);
-        longest_owner_patterns.extend(
-            longest
-                .iter()
-                .filter(|(_, count)| **count == max_count)
-                .map(|x| *x.0),
-        );
+        let longest_owner_patterns = config.longest_owner_patterns(&file_diff.path)?;
+
         // Give some weight to these patterns to start. This helps with
         // files modified without any lines changed.
         for owner_pattern in &longest_owner_patterns {
-            *counts.entry(owner_pattern).or_default() += 1;
+            *counts.entry(*owner_pattern).or_default() += 1;
         }
 
         // Count the modified lines.
@@ -463,7 +734,7 @@ fn find_reviewers_from_diff(
                 || (!line.starts_with("---") && line.starts_with('-'))
             {
                 for owner_path in &longest_owner_patterns {
-                    *counts.entry(owner_path).or_default() += 1;
+                    *counts.entry(*owner_path).or_default() += 1;
                 }
             }
         }
@@ -585,7 +856,7 @@ pub(super) async fn handle_command(
                     if teams.teams.get(team_name).is_some() {
                         let t_label = format!("T-{}", &team_name);
                         if let Err(err) = issue
-                            .add_labels(&ctx.github, vec![github::Label { name: t_label }])
+                            .add_labels(&ctx.github, vec![github::Label::new(t_label)])
                             .await
                         {
                             if let Some(github::UnknownLabels { .. }) = err.downcast_ref() {
@@ -596,16 +867,26 @@ pub(super) async fn handle_command(
                         }
                     }
 
-                    match find_reviewer_from_names(
-                        &db_client,
-                        &teams,
-                        config,
-                        issue,
-                        &[team_name.to_string()],
-                    )
-                    .await
+                    let names = [team_name.to_string()];
+                    match find_reviewer_from_names(&db_client, &teams, config, issue, &names).await
                     {
-                        Ok(assignee) => assignee,
+                        Ok(assignee) => {
+                            if let Err(e) = record_assignment(
+                                &db_client,
+                                issue,
+                                Some(&assignee),
+                                &names,
+                                AssignmentReason::ReviewCommand,
+                            )
+                            .await
+                            {
+                                log::warn!(
+                                    "failed to record assignment history for PR {}: {e:?}",
+                                    issue.global_id()
+                                );
+                            }
+                            assignee
+                        }
                         Err(e) => {
                             issue.post_comment(&ctx.github, &e.to_string()).await?;
                             return Ok(());
@@ -839,6 +1120,8 @@ async fn find_reviewer_from_names(
         return Ok("ghost".to_string());
     }
 
+    let candidates = restrict_to_review_pools(db, teams, names, candidates).await;
+
     // filter out team members without capacity
     let filtered_candidates = filter_by_capacity(db, &candidates)
         .await
@@ -872,27 +1155,107 @@ async fn filter_by_capacity(
     db: &DbClient,
     candidates: &HashSet<&str>,
 ) -> anyhow::Result<HashSet<String>> {
-    let usernames = candidates
-        .iter()
-        .map(|c| *c)
-        .collect::<Vec<&str>>()
-        .join(",");
+    let usernames = candidates.iter().copied().collect::<Vec<&str>>();
 
-    let q = format!(
-        "
+    let q = "
 SELECT username
 FROM review_prefs r
 JOIN users on users.user_id=r.user_id
-AND username = ANY('{{ {} }}')
-AND CARDINALITY(r.assigned_prs) < LEAST(COALESCE(r.max_assigned_prs,1000000))",
-        usernames
-    );
-    let result = db.query(&q, &[]).await.context("Select DB error")?;
+AND username = ANY($1)
+AND CARDINALITY(r.assigned_prs) < LEAST(COALESCE(r.max_assigned_prs,1000000))";
+    let result = db
+        .query(q, &[&usernames])
+        .await
+        .context("Select DB error")?;
     let candidates: HashSet<String> = result.iter().map(|row| row.get("username")).collect();
     log::info!("DB returned these candidates: {:?}", candidates);
     Ok(candidates)
 }
 
+/// Restricts the members of the teams in `names` to the review pools of the
+/// teams, for the teams having one.
+async fn restrict_to_review_pools<'a>(
+    db: &DbClient,
+    teams: &Teams,
+    names: &[String],
+    candidates: HashSet<&'a str>,
+) -> HashSet<&'a str> {
+    let named = named_teams(teams, names);
+    if named.is_empty() {
+        return candidates;
+    }
+    match review_pools(db, &named).await {
+        Ok(pools) => apply_review_pools(teams, names, candidates, &pools),
+        Err(e) => {
+            log::error!("failed to get the review pools of {named:?}: {e:?}");
+            candidates
+        }
+    }
+}
+
+/// The teams in `names`, like `compiler` for `@rust-lang/compiler`.
+fn named_teams<'a>(teams: &Teams, names: &'a [String]) -> Vec<&'a str> {
+    names
+        .iter()
+        .map(|name| {
+            let name = name.strip_prefix('@').unwrap_or(name);
+            name.strip_prefix("rust-lang/").unwrap_or(name)
+        })
+        .filter(|name| teams.teams.contains_key(*name))
+        .collect()
+}
+
+/// Removes the candidates that are members of a team in `names` with a
+/// review pool, but not in the pool, unless they were named directly.
+///
+/// Only the teams named directly are considered, not the teams of the
+/// ad-hoc groups. If no candidate is left, the candidates are returned
+/// unchanged, so that an inactive pool doesn't prevent the assignment.
+fn apply_review_pools<'a>(
+    teams: &Teams,
+    names: &[String],
+    candidates: HashSet<&'a str>,
+    pools: &HashMap<String, HashSet<String>>,
+) -> HashSet<&'a str> {
+    let named_directly = |user: &str| {
+        names.iter().any(|name| {
+            name.strip_prefix('@')
+                .unwrap_or(name)
+                .eq_ignore_ascii_case(user)
+        })
+    };
+    let mut outside_pool = HashSet::new();
+    let mut in_pool = HashSet::new();
+    for team in named_teams(teams, names) {
+        let pool = pools.get(team);
+        for member in &teams.teams[team].members {
+            let login = member.github.to_lowercase();
+            // Teams without a pool draw from all their members.
+            if pool.map_or(true, |pool| {
+                pool.iter().any(|p| p.eq_ignore_ascii_case(&login))
+            }) {
+                in_pool.insert(login);
+            } else if !named_directly(&login) {
+                outside_pool.insert(login);
+            }
+        }
+    }
+    let restricted: HashSet<&str> = candidates
+        .iter()
+        .copied()
+        .filter(|c| {
+            let login = c.to_lowercase();
+            in_pool.contains(&login) || !outside_pool.contains(&login)
+        })
+        .collect();
+    if restricted.is_empty() {
+        log::info!("no candidate is in the review pools, ignoring them");
+        candidates
+    } else {
+        restricted
+    }
+}
+
 /// Returns a list of candidate usernames (from relevant teams) to choose as a reviewer.
 fn candidate_reviewers_from_names<'a>(
     teams: &'a Teams,
@@ -1,4 +1,5 @@
-//! Tests for `candidate_reviewers_from_names`
+//! Tests for `candidate_reviewers_from_names`, `fallback_assignee` and
+//! `first_eligible_reactor`
 
 use super::super::*;
 
@@ -296,3 +297,181 @@ fn vacation() {
         Ok(&["Mark-Simulacrum"]),
     );
 }
+
+#[test]
+fn fallback_assignee_applied() {
+    // Normal selection yields nobody since the author is the only owner, so
+    // the fallback is used instead.
+    let teams = toml::toml!(triage = ["apiraino"]);
+    let config = toml::toml!(
+        fallback_assignee = "triage"
+        [owners]
+        "/compiler" = ["nikomatsakis"]
+    );
+    let issue = generic_issue("nikomatsakis", "rust-lang/rust");
+    test_from_names(
+        Some(teams.clone()),
+        config.clone(),
+        issue.clone(),
+        &["nikomatsakis"],
+        Err(FindReviewerError::AllReviewersFiltered {
+            initial: vec!["nikomatsakis".to_string()],
+            filtered: vec!["nikomatsakis".to_string()],
+        }),
+    );
+    let (teams, config, issue) = convert_simplified(Some(teams), config, issue);
+    assert_eq!(
+        fallback_assignee(&teams, &config, &issue).as_deref(),
+        Some("apiraino")
+    );
+}
+
+#[test]
+fn fallback_assignee_not_configured() {
+    let config = toml::toml!(
+        [owners]
+        "/compiler" = ["nikomatsakis"]
+    );
+    let issue = generic_issue("nikomatsakis", "rust-lang/rust");
+    let (teams, config, issue) = convert_simplified(None, config, issue);
+    assert_eq!(fallback_assignee(&teams, &config, &issue), None);
+}
+
+#[test]
+fn eyes_reaction_claims_pr() {
+    let teams = toml::toml!(compiler = ["nikomatsakis", "oli-obk", "pnkfelix"]);
+    let config = toml::toml!(users_on_vacation = ["oli-obk"]);
+    let issue = generic_issue("nikomatsakis", "rust-lang/rust");
+    let (teams, config, issue) = convert_simplified(Some(teams), config, issue);
+    let reactions: Vec<github::Reaction> = serde_json::from_value(serde_json::json!([
+        // Not a reviewer.
+        {"id": 1, "user": {"login": "octocat", "id": 1}, "content": "eyes", "created_at": "2022-06-27T10:00:00Z"},
+        // The author.
+        {"id": 2, "user": {"login": "nikomatsakis", "id": 2}, "content": "eyes", "created_at": "2022-06-27T10:01:00Z"},
+        // On vacation.
+        {"id": 3, "user": {"login": "oli-obk", "id": 3}, "content": "eyes", "created_at": "2022-06-27T10:02:00Z"},
+        // Not a claim.
+        {"id": 4, "user": {"login": "pnkfelix", "id": 4}, "content": "+1", "created_at": "2022-06-27T10:03:00Z"},
+        {"id": 5, "user": {"login": "pnkfelix", "id": 4}, "content": "eyes", "created_at": "2022-06-27T10:04:00Z"},
+    ]))
+    .unwrap();
+    assert_eq!(
+        first_eligible_reactor(&teams, &config, &issue, &reactions),
+        Some("pnkfelix")
+    );
+    assert_eq!(
+        first_eligible_reactor(&teams, &config, &issue, &reactions[..4]),
+        None
+    );
+}
+
+/// Returns the sorted candidates for `names` once restricted to `pools`.
+fn candidates_in_pools(names: &[&str], pools: &[(&str, &[&str])]) -> Vec<String> {
+    let teams = toml::toml!(
+        compiler = ["nikomatsakis", "oli-obk", "pnkfelix"]
+        types = ["lcnr", "oli-obk"]
+    );
+    let issue = generic_issue("octocat", "rust-lang/rust");
+    let (teams, config, issue) = convert_simplified(Some(teams), toml::Table::new(), issue);
+    let names: Vec<_> = names.iter().map(|n| n.to_string()).collect();
+    let pools: HashMap<String, HashSet<String>> = pools
+        .iter()
+        .map(|(team, members)| {
+            let members = members.iter().map(|m| m.to_string()).collect();
+            (team.to_string(), members)
+        })
+        .collect();
+    let candidates = candidate_reviewers_from_names(&teams, &config, &issue, &names).unwrap();
+    let mut candidates: Vec<_> = apply_review_pools(&teams, &names, candidates, &pools)
+        .into_iter()
+        .map(|c| c.to_string())
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+#[test]
+fn review_pool_opt_in() {
+    // Without a pool, all the members are candidates.
+    assert_eq!(
+        candidates_in_pools(&["compiler"], &[]),
+        ["nikomatsakis", "oli-obk", "pnkfelix"]
+    );
+    // Only the members who opted in are.
+    assert_eq!(
+        candidates_in_pools(&["@rust-lang/compiler"], &[("compiler", &["pnkfelix"])]),
+        ["pnkfelix"]
+    );
+    // The pool of a team doesn't affect the other teams.
+    assert_eq!(
+        candidates_in_pools(&["compiler", "types"], &[("compiler", &["pnkfelix"])]),
+        ["lcnr", "oli-obk", "pnkfelix"]
+    );
+    // Users named directly are kept.
+    assert_eq!(
+        candidates_in_pools(
+            &["compiler", "nikomatsakis"],
+            &[("compiler", &["pnkfelix"])]
+        ),
+        ["nikomatsakis", "pnkfelix"]
+    );
+}
+
+#[test]
+fn review_pool_opt_out() {
+    // Once the last member left the pool, the team has no pool anymore.
+    assert_eq!(
+        candidates_in_pools(&["compiler"], &[("types", &["lcnr"])]),
+        ["nikomatsakis", "oli-obk", "pnkfelix"]
+    );
+    // A pool whose members can't be assigned doesn't prevent the assignment.
+    assert_eq!(
+        candidates_in_pools(&["compiler"], &[("compiler", &["octocat"])]),
+        ["nikomatsakis", "oli-obk", "pnkfelix"]
+    );
+}
+
+#[tokio::test]
+async fn capacity_of_seeded_users() {
+    let Some(db) = crate::db::test_client().await else {
+        return;
+    };
+    // (user, assigned PRs, maximum)
+    let users: [(&str, &[i32], Option<i32>); 5] = [
+        ("full", &[1, 2], Some(2)),
+        ("available", &[1], Some(3)),
+        ("unlimited", &[1, 2, 3], None),
+        ("idle", &[], Some(0)),
+        ("not-candidate", &[], None),
+    ];
+    for (id, (user, assigned, max)) in users.iter().enumerate() {
+        let id = id as i64 + 1;
+        db.execute(
+            "INSERT INTO users (user_id, username) VALUES ($1, $2)",
+            &[&id, user],
+        )
+        .await
+        .unwrap();
+        db.execute(
+            "INSERT INTO review_prefs (user_id, assigned_prs, max_assigned_prs)
+            VALUES ($1, $2, $3)",
+            &[&id, &assigned.to_vec(), max],
+        )
+        .await
+        .unwrap();
+    }
+    // Without review preferences.
+    db.execute(
+        "INSERT INTO users (user_id, username) VALUES (10, 'no-prefs')",
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let candidates = HashSet::from(["full", "available", "unlimited", "idle", "no-prefs"]);
+    let with_capacity = filter_by_capacity(&db, &candidates).await.unwrap();
+    assert_eq!(
+        with_capacity,
+        HashSet::from(["available".to_string(), "unlimited".to_string()])
+    );
+}
@@ -1,7 +1,9 @@
-//! Tests for `find_reviewers_from_diff`
+//! Tests for `find_reviewers_from_diff`, `affected_areas` and
+//! `AssignConfig::assignee_suggestions`
 
 use super::super::*;
 use crate::github::parse_diff;
+use crate::test_server::{test_pr_json, TestServer};
 use std::fmt::Write;
 
 fn test_from_diff(diff: &str, config: toml::Table, expected: &[&str]) {
@@ -13,6 +15,24 @@ fn test_from_diff(diff: &str, config: toml::Table, expected: &[&str]) {
     );
 }
 
+fn test_suggestions(diff: &str, config: toml::Table, expected: &[(&str, u32)]) {
+    let files = parse_diff(diff);
+    let aconfig: AssignConfig = config.try_into().unwrap();
+    let suggestions: Vec<_> = aconfig
+        .assignee_suggestions(&files)
+        .unwrap()
+        .into_iter()
+        .map(|s| (s.name, s.score))
+        .collect();
+    assert_eq!(
+        suggestions,
+        expected
+            .iter()
+            .map(|(name, score)| (name.to_string(), *score))
+            .collect::<Vec<_>>()
+    );
+}
+
 /// Generates a fake diff that touches the given files.
 ///
 /// `paths` should be a slice of `(path, added, removed)` tuples where `added`
@@ -147,3 +167,135 @@ fn basic_gitignore_pattern() {
     let diff = make_fake_diff(&[("src/librustdoc/html/static/js/settings.js", 10, 1)]);
     test_from_diff(&diff, config, &["javascript-reviewers"]);
 }
+
+#[test]
+fn suggestions_ranked_by_owned_files() {
+    let config = toml::toml!(
+        [owners]
+        "/compiler" = ["compiler"]
+        "/compiler/rustc_parse" = ["parser", "user1"]
+        "/library" = ["libs", "user1"]
+    );
+    // Line counts do not matter, only the number of owned files.
+    let diff = make_fake_diff(&[
+        ("compiler/rustc_parse/src/foo.rs", 100, 100),
+        ("compiler/rustc_middle/src/foo.rs", 1, 0),
+        ("compiler/rustc_lint/src/foo.rs", 1, 0),
+        ("library/std/src/lib.rs", 1, 0),
+        ("README.md", 1, 0),
+    ]);
+    test_suggestions(
+        &diff,
+        config,
+        &[("compiler", 2), ("user1", 2), ("libs", 1), ("parser", 1)],
+    );
+}
+
+#[test]
+fn suggestions_count_each_file_once() {
+    // A name listed under several equally long matching patterns only
+    // scores once per file.
+    let config = toml::toml!(
+        [owners]
+        "/src/*doc" = ["user1"]
+        "/src/librustdoc" = ["user1", "rustdoc"]
+    );
+    let diff = make_fake_diff(&[("src/librustdoc/settings.js", 1, 1)]);
+    test_suggestions(&diff, config, &[("rustdoc", 1), ("user1", 1)]);
+}
+
+#[test]
+fn suggestions_empty_without_matches() {
+    let config = toml::toml!(
+        [owners]
+        "/compiler" = ["compiler"]
+    );
+    let diff = make_fake_diff(&[("foo/bar.rs", 5, 0)]);
+    test_suggestions(&diff, config, &[]);
+}
+
+#[tokio::test]
+async fn suggestions_from_pr_diff() {
+    let config = toml::toml!(
+        [owners]
+        "/compiler" = ["compiler"]
+        "/library" = ["libs", "@user1"]
+        "/src/doc" = ["@user1"]
+    );
+    let aconfig: AssignConfig = config.try_into().unwrap();
+    let diff = make_fake_diff(&[
+        ("library/std/src/lib.rs", 1, 0),
+        ("src/doc/index.md", 1, 0),
+        ("compiler/rustc_lint/src/foo.rs", 1, 0),
+    ]);
+    let server = TestServer::start(vec![("200 OK", diff)]);
+    let commit = |sha: &str| {
+        serde_json::json!({
+            "sha": sha,
+            "ref": "master",
+            "repo": {"full_name": "rust-lang/rust", "default_branch": "master", "parent": null},
+        })
+    };
+    let mut pr = test_pr_json();
+    pr["pull_request"] = serde_json::json!({});
+    pr["base"] = commit("5a8d9f6");
+    pr["head"] = commit("c2d1b3e");
+    let pr: Issue = serde_json::from_value(pr).unwrap();
+
+    let suggestions = pr
+        .assignee_suggestions_from_diff(&server.github_client(), &aconfig)
+        .await
+        .unwrap();
+    let requests = server.finish();
+    assert_eq!(
+        requests[0].path,
+        "/repos/rust-lang/rust/compare/5a8d9f6...c2d1b3e"
+    );
+    assert_eq!(
+        suggestions_message(&suggestions).unwrap(),
+        "Based on the changed files, `@user1`, `compiler`, `libs` may be able to review this PR."
+    );
+}
+
+#[test]
+fn suggestions_message_lists_the_best_ones() {
+    let suggestion = |name: &str, score| AssigneeSuggestion {
+        name: name.to_string(),
+        score,
+    };
+    assert_eq!(suggestions_message(&[]), None);
+    assert_eq!(
+        suggestions_message(&[
+            suggestion("compiler", 3),
+            suggestion("@user1", 2),
+            suggestion("libs", 1),
+            suggestion("parser", 1),
+        ])
+        .unwrap(),
+        "Based on the changed files, `compiler`, `@user1`, `libs` may be able to review this PR."
+    );
+}
+
+#[test]
+fn affected_areas_spanning_two_owners() {
+    let config = toml::toml!(
+        [owners]
+        "/compiler" = ["compiler"]
+        "/compiler/rustc_parse" = ["parser"]
+        "/library" = ["libs", "@user1"]
+        "/src/doc" = ["libs", "@user1"]
+    );
+    let diff = make_fake_diff(&[
+        ("compiler/rustc_parse/src/foo.rs", 10, 1),
+        ("compiler/rustc_parse/src/bar.rs", 1, 1),
+        ("library/std/src/lib.rs", 1, 0),
+        ("src/doc/index.md", 1, 0),
+        ("README.md", 1, 0),
+    ]);
+    let aconfig: AssignConfig = config.try_into().unwrap();
+    let files = parse_diff(&diff);
+    assert_eq!(
+        affected_areas(&aconfig, &files).unwrap(),
+        [vec!["@user1", "libs"], vec!["parser"]]
+    );
+}
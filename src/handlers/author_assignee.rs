@@ -0,0 +1,144 @@
+//! Warns when a PR waiting on review is assigned to its own author.
+//!
+//! Reviewers find the PRs to review through the assignee, so a PR assigned
+//! to its author is likely to be overlooked.
+//!
+//! Configuration is done with the `[author-assignee]` table.
+
+use crate::{
+    config::AuthorAssigneeConfig,
+    github::{IssuesAction, IssuesEvent},
+    handlers::Context,
+};
+
+pub(crate) struct AuthorAssigneeInput {}
+
+pub(crate) async fn parse_input(
+    _ctx: &Context,
+    event: &IssuesEvent,
+    config: Option<&AuthorAssigneeConfig>,
+) -> Result<Option<AuthorAssigneeInput>, String> {
+    let Some(config) = config else {
+        return Ok(None);
+    };
+    if is_author_assigned_while_waiting(event, config) {
+        Ok(Some(AuthorAssigneeInput {}))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Whether the event made the PR both waiting on review and assigned to its
+/// author.
+///
+/// Only the event completing the misconfiguration is considered, so that the
+/// note is posted once.
+fn is_author_assigned_while_waiting(event: &IssuesEvent, config: &AuthorAssigneeConfig) -> bool {
+    let issue = &event.issue;
+    if !issue.is_pr() || !issue.is_open() {
+        return false;
+    }
+    let author = &issue.user.login;
+    let waiting_on_review = issue
+        .labels()
+        .iter()
+        .any(|l| l.name == config.waiting_on_review_label);
+    match &event.action {
+        IssuesAction::Labeled { label } => {
+            label.name == config.waiting_on_review_label && issue.contain_assignee(author)
+        }
+        IssuesAction::Assigned { assignee } => {
+            assignee.login.eq_ignore_ascii_case(author) && waiting_on_review
+        }
+        _ => false,
+    }
+}
+
+pub(crate) async fn handle_input(
+    ctx: &Context,
+    _config: &AuthorAssigneeConfig,
+    event: &IssuesEvent,
+    AuthorAssigneeInput {}: AuthorAssigneeInput,
+) -> anyhow::Result<()> {
+    let author = &event.issue.user.login;
+    event
+        .issue
+        .post_comment(
+            &ctx.github,
+            &format!(
+                "@{author} this PR is waiting on review, but it is assigned to you, \
+                so reviewers may not notice it.\n\n\
+                If you didn't mean to assign yourself, you can request a review \
+                with `r? @reviewer` or `r? <team>`."
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::test_pr_json;
+
+    fn config() -> AuthorAssigneeConfig {
+        toml::from_str("").unwrap()
+    }
+
+    fn event(action: serde_json::Value, labels: &[&str], assignees: &[&str]) -> IssuesEvent {
+        let mut pr = test_pr_json();
+        pr["pull_request"] = serde_json::json!({});
+        pr["labels"] = labels
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        pr["assignees"] = assignees
+            .iter()
+            .map(|login| serde_json::json!({ "login": login, "id": 1 }))
+            .collect();
+        let mut event = serde_json::json!({
+            "pull_request": pr,
+            "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            "sender": {"login": "octocat", "id": 583231},
+        });
+        event
+            .as_object_mut()
+            .unwrap()
+            .extend(action.as_object().unwrap().clone());
+        serde_json::from_value(event).unwrap()
+    }
+
+    #[test]
+    fn author_is_assignee() {
+        let labeled = serde_json::json!({
+            "action": "labeled",
+            "label": {"name": "S-waiting-on-review"},
+        });
+        let e = event(labeled, &["S-waiting-on-review"], &["octocat"]);
+        assert!(is_author_assigned_while_waiting(&e, &config()));
+
+        let assigned = serde_json::json!({
+            "action": "assigned",
+            "assignee": {"login": "octocat", "id": 583231},
+        });
+        let e = event(assigned, &["S-waiting-on-review"], &["octocat"]);
+        assert!(is_author_assigned_while_waiting(&e, &config()));
+    }
+
+    #[test]
+    fn other_assignee() {
+        let labeled = serde_json::json!({
+            "action": "labeled",
+            "label": {"name": "S-waiting-on-review"},
+        });
+        let e = event(labeled, &["S-waiting-on-review"], &["oli-obk"]);
+        assert!(!is_author_assigned_while_waiting(&e, &config()));
+
+        let assigned = serde_json::json!({
+            "action": "assigned",
+            "assignee": {"login": "octocat", "id": 583231},
+        });
+        let e = event(assigned, &["S-waiting-on-author"], &["octocat"]);
+        assert!(!is_author_assigned_while_waiting(&e, &config()));
+    }
+}
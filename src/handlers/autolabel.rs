@@ -65,14 +65,10 @@ pub(super) async fn parse_input(
                     .iter()
                     .any(|f| files.iter().any(|file_diff| file_diff.path.starts_with(f)))
                 {
-                    autolabels.push(Label {
-                        name: label.to_owned(),
-                    });
+                    autolabels.push(Label::new(label.to_owned()));
                 }
                 if cfg.new_pr && event.action == IssuesAction::Opened {
-                    autolabels.push(Label {
-                        name: label.to_owned(),
-                    });
+                    autolabels.push(Label::new(label.to_owned()));
                 }
             }
 
@@ -80,9 +76,7 @@ pub(super) async fn parse_input(
                 && cfg.new_issue
                 && event.action == IssuesAction::Opened
             {
-                autolabels.push(Label {
-                    name: label.to_owned(),
-                });
+                autolabels.push(Label::new(label.to_owned()));
             }
         }
 
@@ -121,9 +115,7 @@ pub(super) async fn parse_input(
             }
 
             // If we reach here, no excluded labels were found, so we should apply the autolabel.
-            autolabels.push(Label {
-                name: label.to_owned(),
-            });
+            autolabels.push(Label::new(label.to_owned()));
         }
         if !autolabels.is_empty() {
             return Ok(Some(AutolabelInput {
@@ -141,6 +133,30 @@ pub(super) async fn handle_input(
     event: &IssuesEvent,
     input: AutolabelInput,
 ) -> anyhow::Result<()> {
+    // The renamed labels are set with their new name, the whole config is
+    // loaded again for the aliases. It is cached, so this is cheap.
+    let config = crate::config::get(&ctx.github, &event.repository)
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "failed to load config of {}: {e}",
+                event.repository.full_name
+            )
+        })?;
+    let resolve = |labels: Vec<Label>| -> Vec<Label> {
+        match &config.labels {
+            Some(config) => labels
+                .into_iter()
+                .map(|label| Label::new(config.resolve(&label.name)))
+                .collect(),
+            None => labels,
+        }
+    };
+    let input = AutolabelInput {
+        add: resolve(input.add),
+        remove: resolve(input.remove),
+    };
+
     match event.issue.add_labels(&ctx.github, input.add).await {
         Ok(()) => {}
         Err(e) => {
@@ -172,3 +188,52 @@ pub(super) async fn handle_input(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, test_pr_json, TestServer};
+
+    #[tokio::test]
+    async fn renamed_label_is_applied_with_its_new_name() {
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                "[labels.aliases]\n\"A-diagnostic\" = \"A-diagnostics\"\n".to_string(),
+            ),
+            ("200 OK", r#"{"name": "A-diagnostics"}"#.to_string()),
+            ("200 OK", r#"[{"name": "A-diagnostics"}]"#.to_string()),
+        ]);
+        let ctx = server.context();
+        // The config is cached by repository, so use one of its own.
+        let event: IssuesEvent = serde_json::from_value(serde_json::json!({
+            "action": "opened",
+            "issue": test_pr_json(),
+            "changes": null,
+            "repository": {
+                "full_name": "rust-lang/autolabel-aliases",
+                "default_branch": "master",
+            },
+            "sender": {"login": "octocat", "id": 583231},
+        }))
+        .unwrap();
+        let input = AutolabelInput {
+            add: vec![Label::new("A-diagnostic")],
+            remove: vec![],
+        };
+        let config = AutolabelConfig {
+            labels: Default::default(),
+        };
+        handle_input(&ctx, &config, &event, input).await.unwrap();
+
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/rust-lang/autolabel-aliases/master/triagebot.toml"),
+                expect("GET", "/repos/rust-lang/rust/labels/A-diagnostics"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({"labels": ["A-diagnostics"]})),
+            ],
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Posts a summary of failed CI jobs on PRs.
+//!
+//! When a GitHub Actions job for a PR fails, this posts a comment with a link
+//! to the failing job and the relevant part of its log, so that the author
+//! doesn't have to dig through the CI UI. Summaries are throttled per PR so
+//! that repeated failures don't spam, and the previous summary is minimized
+//! when a new one is posted.
+//!
+//! Configuration is done with the `[ci-failure-summary]` table.
+
+use crate::{
+    ci_logs::extract_snippet,
+    config::CiFailureSummaryConfig,
+    db::issue_data::IssueData,
+    github::{
+        with_bot_comment_marker, Event, ReportedContentClassifiers, WorkflowJobAction,
+        WorkflowRunJob,
+    },
+    handlers::Context,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing as log;
+
+/// Key for the database and the hidden marker of the summary comments.
+const CI_FAILURE_SUMMARY_KEY: &str = "ci-failure-summary";
+
+/// The maximum number of log lines to include in the summary.
+const SNIPPET_LINES: usize = 20;
+
+/// State stored in the database for a PR.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct CiFailureSummaryState {
+    /// When the last summary was posted.
+    last_posted: Option<DateTime<Utc>>,
+    /// The GraphQL node id of the last summary, minimized when superseded.
+    last_comment_node_id: Option<String>,
+}
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &CiFailureSummaryConfig,
+) -> anyhow::Result<()> {
+    let Event::WorkflowJob(event) = event else {
+        return Ok(());
+    };
+    let job = &event.workflow_job;
+    if event.action != WorkflowJobAction::Completed || job.conclusion.as_deref() != Some("failure")
+    {
+        return Ok(());
+    }
+
+    let prs = event
+        .repository
+        .pulls_for_commit(&ctx.github, &job.head_sha)
+        .await?;
+    if !prs.iter().any(|pr| pr.is_open()) {
+        return Ok(());
+    }
+
+    let logs = match event.repository.raw_job_logs(&ctx.github, job.id).await {
+        Ok(logs) => logs,
+        Err(e) => {
+            log::warn!("failed to fetch logs of job {}: {e:?}", job.html_url);
+            String::new()
+        }
+    };
+    let summary = with_bot_comment_marker(&render_summary(job, &logs), CI_FAILURE_SUMMARY_KEY);
+
+    let mut db = ctx.db.get().await;
+    for pr in prs.iter().filter(|pr| pr.is_open()) {
+        let mut state: IssueData<'_, CiFailureSummaryState> =
+            IssueData::load(&mut db, pr, CI_FAILURE_SUMMARY_KEY).await?;
+        let now = Utc::now();
+        if let Some(last_posted) = state.data.last_posted {
+            if now - last_posted < chrono::Duration::minutes(config.throttle_minutes.into()) {
+                log::debug!(
+                    "skipping CI failure summary for {}, last posted at {last_posted}",
+                    pr.global_id()
+                );
+                continue;
+            }
+        }
+        let comment = pr.post_comment(&ctx.github, &summary).await?;
+        if let Some(node_id) = &state.data.last_comment_node_id {
+            if let Err(e) = pr
+                .hide_comment(&ctx.github, node_id, ReportedContentClassifiers::Outdated)
+                .await
+            {
+                log::warn!("failed to minimize outdated CI failure summary: {e:?}");
+            }
+        }
+        state.data.last_posted = Some(now);
+        state.data.last_comment_node_id = Some(comment.node_id);
+        state.save().await?;
+    }
+    Ok(())
+}
+
+/// Renders the comment for a failed job.
+fn render_summary(job: &WorkflowRunJob, logs: &str) -> String {
+    let short_sha = job.head_sha.get(..7).unwrap_or(&job.head_sha);
+    let mut summary = format!(
+        ":broken_heart: CI job [`{}`]({}) failed on {short_sha}",
+        job.name, job.html_url
+    );
+    if let Some(step) = job.failed_step() {
+        summary.push_str(&format!(
+            " at step [`{}`]({}#step:{}:1)",
+            step.name, job.html_url, step.number
+        ));
+    }
+    summary.push('.');
+    let snippet = extract_snippet(logs, SNIPPET_LINES);
+    if !snippet.is_empty() {
+        summary.push_str(&format!(
+            "\n\n<details><summary>Log snippet</summary>\n\n```text\n{snippet}\n```\n\n</details>"
+        ));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_from_failed_job() {
+        let job: WorkflowRunJob = serde_json::from_value(serde_json::json!({
+            "id": 24079883291,
+            "run_id": 8764382154,
+            "workflow_name": "CI",
+            "head_branch": "fix-ice",
+            "run_url": "https://api.github.com/repos/rust-lang/rust/actions/runs/8764382154",
+            "node_id": "CR_kwDOAAQmKM8AAAAFm0PDGw",
+            "head_sha": "2b0ffc2b30c6f8e1e5a5c1f6f3c5c1a79f6c0f2e",
+            "url": "https://api.github.com/repos/rust-lang/rust/actions/jobs/24079883291",
+            "html_url": "https://github.com/rust-lang/rust/actions/runs/8764382154/job/24079883291",
+            "status": "completed",
+            "conclusion": "failure",
+            "started_at": "2024-04-20T10:31:31Z",
+            "completed_at": "2024-04-20T11:02:05Z",
+            "name": "PR - x86_64-gnu-llvm-17",
+        }))
+        .unwrap();
+        let logs = "\
+2024-04-20T11:01:58.1234567Z ##[group]Run src/ci/scripts/run-build-from-ci.sh
+2024-04-20T11:01:59.1234567Z error[E0308]: mismatched types
+2024-04-20T11:01:59.1234567Z
+2024-04-20T11:02:00.1234567Z error: aborting due to 1 previous error
+2024-04-20T11:02:01.1234567Z ##[error]Process completed with exit code 1.
+";
+        assert_eq!(
+            render_summary(&job, logs),
+            ":broken_heart: CI job [`PR - x86_64-gnu-llvm-17`](https://github.com/rust-lang/rust/actions/runs/8764382154/job/24079883291) failed on 2b0ffc2.
+
+<details><summary>Log snippet</summary>
+
+```text
+error[E0308]: mismatched types
+```
+
+</details>"
+        );
+    }
+
+    #[test]
+    fn summary_without_logs() {
+        let job: WorkflowRunJob = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "run_id": 2,
+            "head_sha": "2b0ffc2b30c6f8e1e5a5c1f6f3c5c1a79f6c0f2e",
+            "html_url": "https://github.com/rust-lang/rust/actions/runs/2/job/1",
+            "conclusion": "failure",
+            "name": "tidy",
+            "steps": [
+                {"name": "Set up job", "conclusion": "success", "number": 1},
+                {"name": "run tidy", "conclusion": "failure", "number": 2},
+            ],
+        }))
+        .unwrap();
+        assert_eq!(
+            render_summary(&job, ""),
+            ":broken_heart: CI job [`tidy`](https://github.com/rust-lang/rust/actions/runs/2/job/1) failed on 2b0ffc2 at step [`run tidy`](https://github.com/rust-lang/rust/actions/runs/2/job/1#step:2:1)."
+        );
+    }
+}
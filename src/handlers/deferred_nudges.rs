@@ -0,0 +1,47 @@
+//! A scheduled job posting the nudges that were deferred because they were
+//! due outside of the `[active-hours]` of their repository.
+//!
+//! The job runs every 30 minutes, so a deferred nudge is posted at most 30
+//! minutes after the start of the active hours. A nudge is only removed once
+//! posted, so a failed one is retried by the next run.
+
+use crate::db::deferred_nudges::{due_nudges, remove_nudge};
+use crate::handlers::{merge_conflicts, Context};
+use crate::jobs::Job;
+use async_trait::async_trait;
+use chrono::Utc;
+use tracing as log;
+
+pub struct DeferredNudgesJob;
+
+#[async_trait]
+impl Job for DeferredNudgesJob {
+    fn name(&self) -> &'static str {
+        "deferred_nudges"
+    }
+
+    fn is_nudge(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let db = ctx.db.get().await;
+        let nudges = due_nudges(&db, Utc::now()).await?;
+        for nudge in nudges {
+            let result = match nudge.kind.as_str() {
+                merge_conflicts::MERGE_CONFLICTS_KEY => {
+                    merge_conflicts::post_deferred_warning(ctx, &nudge).await
+                }
+                kind => {
+                    log::warn!("unknown deferred nudge kind `{kind}`");
+                    Ok(())
+                }
+            };
+            match result {
+                Ok(()) => remove_nudge(&db, &nudge).await?,
+                Err(e) => log::error!("failed to post deferred nudge {nudge:?}: {e:?}"),
+            }
+        }
+        Ok(())
+    }
+}
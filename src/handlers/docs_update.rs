@@ -1,11 +1,14 @@
 //! A scheduled job to post a PR to update the documentation on rust-lang/rust.
 
-use crate::github::{self, GitTreeEntry, GithubClient, Issue, Repository};
+use crate::github::{self, GitTreeEntry, GithubClient, Issue, Repository, SubmoduleError};
 use crate::jobs::Job;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::{FutureExt, StreamExt};
+use reqwest::StatusCode;
 use std::fmt::Write;
+use std::future::Future;
 
 /// This is the repository where the commits will be created.
 const WORK_REPO: &str = "rustbot/rust";
@@ -25,6 +28,9 @@ const SUBMODULES: &[&str] = &[
 
 const TITLE: &str = "Update books";
 
+/// The maximum number of submodules checked at the same time.
+const MAX_CONCURRENT_SUBMODULES: usize = 3;
+
 pub struct DocsUpdateJob;
 
 #[async_trait]
@@ -65,17 +71,49 @@ impl Job for DocsUpdateJob {
 
 pub async fn docs_update() -> Result<Option<Issue>> {
     let gh = GithubClient::new_from_env();
+    update_submodules(&gh, SUBMODULES, TITLE, BRANCH_NAME).await
+}
+
+/// Opens a PR updating the given submodules, if any of them changed.
+///
+/// The commit is pushed to `branch` of [`WORK_REPO`], which is created if
+/// needed and overwritten otherwise. If a PR from `branch` is still open, it
+/// is reused, with its description updated.
+pub(crate) async fn update_submodules(
+    gh: &GithubClient,
+    submodules: &[&'static str],
+    title: &str,
+    branch: &str,
+) -> Result<Option<Issue>> {
     let dest_repo = gh.repository(DEST_REPO).await?;
     let work_repo = gh.repository(WORK_REPO).await?;
 
-    let updates = get_submodule_updates(&gh, &dest_repo).await?;
+    let updates = get_submodule_updates(gh, &dest_repo, submodules).await?;
     if updates.is_empty() {
         tracing::trace!("no updates this week?");
         return Ok(None);
     }
 
-    create_commit(&gh, &dest_repo, &work_repo, &updates).await?;
-    Ok(Some(create_pr(&gh, &dest_repo, &updates).await?))
+    create_commit(gh, &dest_repo, &work_repo, &updates, title, branch).await?;
+    Ok(Some(
+        create_pr(gh, &dest_repo, &updates, title, branch).await?,
+    ))
+}
+
+/// Returns the path of a submodule updated by this job, given either its
+/// path, like `src/doc/book`, or its name, like `book`.
+pub(crate) fn find_submodule(name: &str) -> Option<&'static str> {
+    SUBMODULES
+        .iter()
+        .copied()
+        .find(|path| *path == name || path.rsplit('/').next() == Some(name))
+}
+
+/// Returns the names of the submodules updated by this job.
+pub(crate) fn submodule_names() -> impl Iterator<Item = &'static str> {
+    SUBMODULES
+        .iter()
+        .map(|path| path.rsplit('/').next().unwrap())
 }
 
 struct Update {
@@ -87,36 +125,85 @@ struct Update {
 async fn get_submodule_updates(
     gh: &GithubClient,
     repo: &github::Repository,
+    submodules: &[&'static str],
 ) -> Result<Vec<Update>> {
+    let results = for_each_submodule(submodules, |submodule_path| {
+        get_submodule_update(gh, repo, submodule_path)
+    })
+    .await;
     let mut updates = Vec::new();
-    for submodule_path in SUBMODULES {
-        tracing::trace!("checking submodule {submodule_path}");
-        let submodule = repo.submodule(gh, submodule_path, None).await?;
-        let submodule_repo = submodule.repository(gh).await?;
-        let latest_commit = submodule_repo
-            .get_reference(gh, &format!("heads/{}", submodule_repo.default_branch))
-            .await?;
-        if submodule.sha == latest_commit.object.sha {
-            tracing::trace!(
-                "skipping submodule {submodule_path}, no changes sha={}",
-                submodule.sha
-            );
-            continue;
+    let mut failures = 0;
+    for (submodule_path, result) in results {
+        match result {
+            Ok(Some(update)) => updates.push(update),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("failed to check submodule {submodule_path}: {e:?}");
+                failures += 1;
+            }
         }
-        let current_hash = submodule.sha;
-        let new_hash = latest_commit.object.sha;
-        let pr_body = generate_pr_body(gh, &submodule_repo, &current_hash, &new_hash).await?;
-
-        let update = Update {
-            path: submodule.path,
-            new_hash,
-            pr_body,
-        };
-        updates.push(update);
+    }
+    if failures == submodules.len() {
+        anyhow::bail!("failed to check all submodules");
     }
     Ok(updates)
 }
 
+/// Runs `f` for every submodule path, with at most
+/// [`MAX_CONCURRENT_SUBMODULES`] running at the same time.
+///
+/// The results are returned in the order of `paths`.
+async fn for_each_submodule<'a, T, F, Fut>(paths: &[&'a str], f: F) -> Vec<(&'a str, Result<T>)>
+where
+    F: Fn(&'a str) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut results: Vec<_> = futures::stream::iter(paths.iter().copied().enumerate())
+        .map(|(i, path)| f(path).map(move |result| (i, path, result)))
+        .buffer_unordered(MAX_CONCURRENT_SUBMODULES)
+        .collect()
+        .await;
+    results.sort_by_key(|(i, _, _)| *i);
+    results
+        .into_iter()
+        .map(|(_, path, result)| (path, result))
+        .collect()
+}
+
+/// Returns the update of a submodule, if its default branch moved.
+async fn get_submodule_update(
+    gh: &GithubClient,
+    repo: &github::Repository,
+    submodule_path: &str,
+) -> Result<Option<Update>> {
+    tracing::trace!("checking submodule {submodule_path}");
+    let submodule = repo.submodule(gh, submodule_path, None).await?;
+    let submodule_repo = match submodule.repository(gh).await {
+        Ok(repo) => repo,
+        Err(e @ SubmoduleError::Unsupported { .. }) => {
+            tracing::warn!("skipping submodule {submodule_path}: {e}");
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let new_hash = submodule_repo.default_branch_sha(gh).await?;
+    if submodule.sha == new_hash {
+        tracing::trace!(
+            "skipping submodule {submodule_path}, no changes sha={}",
+            submodule.sha
+        );
+        return Ok(None);
+    }
+    let current_hash = submodule.sha;
+    let pr_body = generate_pr_body(gh, &submodule_repo, &current_hash, &new_hash).await?;
+
+    Ok(Some(Update {
+        path: submodule.path,
+        new_hash,
+        pr_body,
+    }))
+}
+
 async fn generate_pr_body(
     gh: &GithubClient,
     repo: &github::Repository,
@@ -160,11 +247,11 @@ async fn create_commit(
     dest_repo: &Repository,
     rust_repo: &Repository,
     updates: &[Update],
+    title: &str,
+    branch: &str,
 ) -> Result<()> {
-    let master_ref = dest_repo
-        .get_reference(gh, &format!("heads/{}", dest_repo.default_branch))
-        .await?;
-    let master_commit = rust_repo.git_commit(gh, &master_ref.object.sha).await?;
+    let master_sha = dest_repo.default_branch_sha(gh).await?;
+    let master_commit = rust_repo.git_commit(gh, &master_sha).await?;
     let tree_entries: Vec<_> = updates
         .iter()
         .map(|update| GitTreeEntry {
@@ -178,25 +265,267 @@ async fn create_commit(
         .update_tree(gh, &master_commit.tree.sha, &tree_entries)
         .await?;
     let commit = rust_repo
-        .create_commit(gh, TITLE, &[&master_ref.object.sha], &new_tree.sha)
-        .await?;
-    rust_repo
-        .update_reference(gh, &format!("heads/{BRANCH_NAME}"), &commit.sha)
+        .create_commit(gh, title, &[&master_sha], &new_tree.sha)
         .await?;
+    set_branch(gh, rust_repo, branch, &commit.sha).await
+}
+
+/// Points `branch` to `sha`, creating it if it doesn't exist.
+async fn set_branch(gh: &GithubClient, repo: &Repository, branch: &str, sha: &str) -> Result<()> {
+    let refname = format!("heads/{branch}");
+    match repo.get_reference(gh, &refname).await {
+        Ok(_) => {
+            repo.update_reference(gh, &refname, sha).await?;
+        }
+        Err(e)
+            if e.downcast_ref::<reqwest::Error>()
+                .map_or(false, |e| e.status() == Some(StatusCode::NOT_FOUND)) =>
+        {
+            repo.create_reference(gh, &refname, sha).await?;
+        }
+        Err(e) => return Err(e),
+    }
     Ok(())
 }
 
-async fn create_pr(gh: &GithubClient, dest_repo: &Repository, updates: &[Update]) -> Result<Issue> {
+async fn create_pr(
+    gh: &GithubClient,
+    dest_repo: &Repository,
+    updates: &[Update],
+    title: &str,
+    branch: &str,
+) -> Result<Issue> {
     let mut body = String::new();
     for update in updates {
         write!(body, "{}\n", update.pr_body).unwrap();
     }
 
     let username = WORK_REPO.split('/').next().unwrap();
-    let head = format!("{username}:{BRANCH_NAME}");
+    let head = format!("{username}:{branch}");
+    // The branch was overwritten, so an open PR from it already has the new
+    // commit.
+    if let Some(pr) = dest_repo.open_pr_for_head(gh, &head).await? {
+        pr.edit_body(gh, &body).await?;
+        tracing::debug!("updated PR {}", pr.html_url);
+        return Ok(pr);
+    }
     let pr = dest_repo
-        .new_pr(gh, TITLE, &head, &dest_repo.default_branch, &body)
+        .new_pr(gh, title, &head, &dest_repo.default_branch, &body)
         .await?;
     tracing::debug!("created PR {}", pr.html_url);
     Ok(pr)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, test_pr_json, test_repo, TestServer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn submodules_are_checked_concurrently() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let results = for_each_submodule(SUBMODULES, |path| {
+            let running = &running;
+            let max_running = &max_running;
+            async move {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now_running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                if path == "src/doc/nomicon" {
+                    anyhow::bail!("not found");
+                }
+                Ok(path.len())
+            }
+        })
+        .await;
+
+        assert_eq!(
+            max_running.load(Ordering::SeqCst),
+            MAX_CONCURRENT_SUBMODULES
+        );
+        // The failure doesn't prevent checking the other submodules.
+        let results: Vec<_> = results
+            .iter()
+            .map(|(path, result)| (*path, result.as_ref().ok().copied()))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("src/doc/book", Some(12)),
+                ("src/doc/edition-guide", Some(21)),
+                ("src/doc/embedded-book", Some(21)),
+                ("src/doc/nomicon", None),
+                ("src/doc/reference", Some(17)),
+                ("src/doc/rust-by-example", Some(23)),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_submodule_by_name() {
+        assert_eq!(find_submodule("book"), Some("src/doc/book"));
+        assert_eq!(find_submodule("src/doc/nomicon"), Some("src/doc/nomicon"));
+        assert_eq!(find_submodule("doc/book"), None);
+        assert_eq!(find_submodule("cargo"), None);
+    }
+
+    #[tokio::test]
+    async fn subtree_update_opens_pr() {
+        let server = TestServer::start(vec![
+            ("200 OK", "[]".to_string()),
+            ("201 Created", test_pr_json().to_string()),
+        ]);
+        let updates = [Update {
+            path: "src/doc/book".to_string(),
+            new_hash: "45c1a6d69edfd1fc91fb7504cb73958dbd09441e".to_string(),
+            pr_body: "## rust-lang/book\n\n1 commits in 5e1d3299..45c1a6d6\n".to_string(),
+        }];
+        let pr = create_pr(
+            &server.github_client(),
+            &test_repo(),
+            &updates,
+            "Update book",
+            "update-book",
+        )
+        .await
+        .unwrap();
+        assert_eq!(pr.number, 1234);
+        assert!(pr.is_pr());
+
+        assert_requests(
+            &server.finish(),
+            &[
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/pulls?head=rustbot:update-book&state=open",
+                ),
+                expect("POST", "/repos/rust-lang/rust/pulls").with_body(serde_json::json!({
+                    "title": "Update book",
+                    "head": "rustbot:update-book",
+                    "base": "master",
+                    "body": "## rust-lang/book\n\n1 commits in 5e1d3299..45c1a6d6\n\n",
+                })),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn subtree_update_reuses_open_pr() {
+        let server = TestServer::start(vec![
+            ("200 OK", serde_json::json!([test_pr_json()]).to_string()),
+            ("200 OK", test_pr_json().to_string()),
+        ]);
+        let updates = [Update {
+            path: "src/doc/book".to_string(),
+            new_hash: "45c1a6d69edfd1fc91fb7504cb73958dbd09441e".to_string(),
+            pr_body: "## rust-lang/book\n\n2 commits in 5e1d3299..45c1a6d6\n".to_string(),
+        }];
+        let pr = create_pr(
+            &server.github_client(),
+            &test_repo(),
+            &updates,
+            "Update book",
+            "update-book",
+        )
+        .await
+        .unwrap();
+        assert_eq!(pr.number, 1234);
+        assert!(pr.is_pr());
+        assert_requests(
+            &server.finish(),
+            &[
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/pulls?head=rustbot:update-book&state=open",
+                ),
+                expect("PATCH", "/repos/rust-lang/rust/issues/1234").with_body(serde_json::json!({
+                    "body": "## rust-lang/book\n\n2 commits in 5e1d3299..45c1a6d6\n\n",
+                })),
+            ],
+        );
+    }
+
+    fn reference_json(sha: &str) -> String {
+        serde_json::json!({
+            "ref": "refs/heads/update-book",
+            "object": {
+                "type": "commit",
+                "sha": sha,
+                "url": format!("https://api.github.com/repos/rustbot/rust/git/commits/{sha}"),
+            },
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn existing_branch_is_overwritten() {
+        let server = TestServer::start(vec![
+            ("200 OK", reference_json("5e1d3299")),
+            ("200 OK", reference_json("45c1a6d6")),
+        ]);
+        set_branch(
+            &server.github_client(),
+            &test_repo(),
+            "update-book",
+            "45c1a6d6",
+        )
+        .await
+        .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust/git/ref/heads/update-book"),
+                expect("PATCH", "/repos/rust-lang/rust/git/refs/heads/update-book")
+                    .with_body(serde_json::json!({"sha": "45c1a6d6", "force": true})),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_branch_is_created() {
+        let server = TestServer::start(vec![
+            ("404 Not Found", r#"{"message": "Not Found"}"#.to_string()),
+            ("201 Created", reference_json("45c1a6d6")),
+        ]);
+        set_branch(
+            &server.github_client(),
+            &test_repo(),
+            "update-book",
+            "45c1a6d6",
+        )
+        .await
+        .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust/git/ref/heads/update-book"),
+                expect("POST", "/repos/rust-lang/rust/git/refs").with_body(serde_json::json!({
+                    "ref": "refs/heads/update-book",
+                    "sha": "45c1a6d6",
+                })),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn branch_lookup_failure_is_an_error() {
+        let server = TestServer::start(vec![(
+            "500 Internal Server Error",
+            r#"{"message": "Server Error"}"#.to_string(),
+        )]);
+        assert!(set_branch(
+            &server.github_client(),
+            &test_repo(),
+            "update-book",
+            "45c1a6d6"
+        )
+        .await
+        .is_err());
+        // The branch isn't created over an existing one.
+        assert_eq!(server.finish().len(), 1);
+    }
+}
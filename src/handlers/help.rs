@@ -0,0 +1,170 @@
+//! Lists the commands enabled in the repository.
+//!
+//! A command is enabled when its section is present in the `triagebot.toml`.
+
+use crate::{
+    config::{self, Config, HelpConfig},
+    github::Event,
+    handlers::Context,
+};
+use parser::command::{help::HelpCommand, CommandKind};
+
+/// Returns the usages of a kind of command with their descriptions.
+///
+/// In the usages, `@bot` is replaced by the bot name.
+fn command_help(kind: CommandKind) -> &'static [(&'static str, &'static str)] {
+    match kind {
+        CommandKind::Relabel => &[("@bot label +A-bug -S-blocked", "add and remove labels")],
+        CommandKind::Assign => &[
+            (
+                "@bot claim / @bot release-assignment",
+                "claim or release the assignment of the issue",
+            ),
+            ("r? @user", "request a review from a user or team"),
+        ],
+        CommandKind::Ping => &[("@bot ping <team>", "ping the members of a team")],
+        CommandKind::Nominate => &[(
+            "@bot nominate <team>",
+            "nominate the issue for discussion by a team",
+        )],
+        CommandKind::Prioritize => &[("@bot prioritize", "request a prioritization of the issue")],
+        CommandKind::Second => &[("@bot second", "second a major change proposal")],
+        CommandKind::Glacier => &[("@bot glacier <link>", "track an ICE in glacier")],
+        CommandKind::Shortcut => &[(
+            "@bot ready / @bot author / @bot blocked",
+            "set the review state of the PR",
+        )],
+        CommandKind::Close => &[("@bot close", "close the issue")],
+        CommandKind::Note => &[(
+            "@bot note <title>",
+            "add a note to the summary of the issue",
+        )],
+        CommandKind::Transfer => &[(
+            "@bot transfer <repo>",
+            "transfer the issue to another repository",
+        )],
+        CommandKind::RetryCi => &[(
+            "@bot retry-ci",
+            "rerun the failed jobs of the latest CI run",
+        )],
+        CommandKind::SubtreeUpdate => &[(
+            "@bot subtree-update <submodule>",
+            "open a PR updating a submodule to its latest commit",
+        )],
+        CommandKind::Rollup => &[(
+            "@bot rollup=always|maybe|iffy|never",
+            "set whether the PR can be included in a rollup",
+        )],
+        CommandKind::Time => &[(
+            "@bot time <team>",
+            "show the next meeting of a team in a few timezones",
+        )],
+        CommandKind::LinkZulip => &[(
+            "@bot link-zulip <code>",
+            "link your GitHub account to the Zulip account that sent `link-github` to the bot",
+        )],
+        CommandKind::ReviewPool => &[(
+            "@bot assign-me-reviewer <team> / @bot unassign-me-reviewer <team>",
+            "join or leave the review pool of a team",
+        )],
+        CommandKind::MilestoneClosed => &[(
+            "@bot milestone-closed",
+            "set the milestone of the PR on the issues it closes",
+        )],
+        CommandKind::Priority => &[(
+            "@bot priority P-critical|P-high|P-medium|P-low",
+            "set the priority of the issue",
+        )],
+        CommandKind::Help => &[("@bot help", "show this help")],
+    }
+}
+
+fn is_enabled(kind: CommandKind, config: &Config) -> bool {
+    match kind {
+        CommandKind::Relabel => config.relabel.is_some(),
+        CommandKind::Assign => config.assign.is_some(),
+        CommandKind::Ping => config.ping.is_some(),
+        CommandKind::Nominate => config.nominate.is_some(),
+        CommandKind::Prioritize => config.prioritize.is_some(),
+        CommandKind::Second => config.major_change.is_some(),
+        CommandKind::Glacier => config.glacier.is_some(),
+        CommandKind::Shortcut => config.shortcut.is_some(),
+        CommandKind::Close => config.close.is_some(),
+        CommandKind::Note => config.note.is_some(),
+        CommandKind::Transfer => config.transfer.is_some(),
+        CommandKind::RetryCi => config.retry_ci.is_some(),
+        CommandKind::SubtreeUpdate => config.subtree_update.is_some(),
+        CommandKind::Rollup => config.rollup.is_some(),
+        CommandKind::Time => config.time.is_some(),
+        CommandKind::LinkZulip => config.link_zulip.is_some(),
+        CommandKind::ReviewPool => config.review_pool.is_some(),
+        CommandKind::MilestoneClosed => config.milestone_closed.is_some(),
+        CommandKind::Priority => config.priority.is_some(),
+        CommandKind::Help => config.help.is_some(),
+    }
+}
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    _config: &HelpConfig,
+    event: &Event,
+    _cmd: HelpCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    // The commands only have access to their own section, so load the whole
+    // config again. It is cached, so this is cheap.
+    let config = config::get(&ctx.github, event.repo())
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to load config of {}: {e}", event.repo().full_name))?;
+    issue
+        .post_comment(&ctx.github, &render_help(&config, &ctx.username))
+        .await?;
+    Ok(())
+}
+
+fn render_help(config: &Config, bot: &str) -> String {
+    let mut help = String::from("Commands enabled in this repository:\n\n");
+    for kind in CommandKind::ALL {
+        if !is_enabled(*kind, config) {
+            continue;
+        }
+        for (usage, description) in command_help(*kind) {
+            help.push_str(&format!(
+                "- `{}`: {description}\n",
+                usage.replace("@bot", &format!("@{bot}"))
+            ));
+        }
+    }
+    help.push_str(
+        "\nSee the [documentation](https://forge.rust-lang.org/triagebot/index.html) for details.",
+    );
+    help
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_commands_are_hidden() {
+        let config: Config = toml::from_str(
+            r#"
+            [relabel]
+            allow_unauthenticated = ["S-*"]
+
+            [shortcut]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            render_help(&config, "rustbot"),
+            "Commands enabled in this repository:
+
+- `@rustbot label +A-bug -S-blocked`: add and remove labels
+- `@rustbot ready / @rustbot author / @rustbot blocked`: set the review state of the PR
+- `@rustbot help`: show this help
+
+See the [documentation](https://forge.rust-lang.org/triagebot/index.html) for details."
+        );
+    }
+}
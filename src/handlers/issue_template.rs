@@ -0,0 +1,338 @@
+//! Asks the authors of new issues to fill in the sections required by the
+//! issue template, like "Steps to reproduce", and labels the issue until they
+//! do.
+//!
+//! Once the body is edited to contain all the sections, the label and the
+//! comment are removed.
+//!
+//! Configuration is done with the `[issue-template]` table.
+
+use crate::{
+    config::IssueTemplateConfig,
+    github::{with_bot_comment_marker, GithubClient, Issue, IssuesAction, IssuesEvent, Label},
+    handlers::Context,
+};
+
+/// The kind of the marker of the comment listing the missing sections.
+const COMMENT_KIND: &str = "issue-template";
+
+pub(crate) enum IssueTemplateInput {
+    /// The new issue lacks these sections.
+    Missing(Vec<String>),
+    /// The labeled issue now contains all the sections.
+    Fixed,
+}
+
+pub(crate) async fn parse_input(
+    _ctx: &Context,
+    event: &IssuesEvent,
+    config: Option<&IssueTemplateConfig>,
+) -> Result<Option<IssueTemplateInput>, String> {
+    let Some(config) = config else {
+        return Ok(None);
+    };
+    if event.issue.is_pr() || config.required_sections.is_empty() {
+        return Ok(None);
+    }
+    let missing = missing_sections(config, &event.issue.body);
+    match event.action {
+        IssuesAction::Opened if !missing.is_empty() => {
+            Ok(Some(IssueTemplateInput::Missing(missing)))
+        }
+        IssuesAction::Edited
+            if missing.is_empty()
+                && event.issue.labels().iter().any(|l| l.name == config.label) =>
+        {
+            Ok(Some(IssueTemplateInput::Fixed))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns the required sections that are absent or left empty in `body`.
+///
+/// A section is a Markdown heading (`### Steps to reproduce`) or a bold line
+/// (`**Steps to reproduce**`) starting with the section name, in any case,
+/// followed by some text. HTML comments, like the hints of the templates, are
+/// not counted as text.
+fn missing_sections(config: &IssueTemplateConfig, body: &str) -> Vec<String> {
+    let body = strip_html_comments(body);
+    // (heading, whether the section has some text)
+    let mut sections: Vec<(String, bool)> = Vec::new();
+    for line in body.lines() {
+        let line = line.trim();
+        if let Some(heading) = heading(line) {
+            sections.push((heading.to_lowercase(), false));
+        } else if !line.is_empty() {
+            if let Some((_, filled)) = sections.last_mut() {
+                *filled = true;
+            }
+        }
+    }
+    config
+        .required_sections
+        .iter()
+        .filter(|required| {
+            let required = required.to_lowercase();
+            !sections
+                .iter()
+                .any(|(heading, filled)| *filled && heading.starts_with(&required))
+        })
+        .cloned()
+        .collect()
+}
+
+/// The text of a heading line, without the Markdown markup and a trailing
+/// colon.
+fn heading(line: &str) -> Option<&str> {
+    let text = if line.starts_with('#') {
+        line.trim_start_matches('#')
+    } else if line.len() > 4 && line.starts_with("**") && line.ends_with("**") {
+        &line[2..line.len() - 2]
+    } else {
+        return None;
+    };
+    Some(text.trim().trim_end_matches(':').trim_end())
+}
+
+fn strip_html_comments(body: &str) -> String {
+    let mut stripped = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find("<!--") {
+        stripped.push_str(&rest[..start]);
+        match rest[start..].find("-->") {
+            Some(end) => rest = &rest[start + end + 3..],
+            None => rest = "",
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+pub(crate) async fn handle_input(
+    ctx: &Context,
+    config: &IssueTemplateConfig,
+    event: &IssuesEvent,
+    input: IssueTemplateInput,
+) -> anyhow::Result<()> {
+    match input {
+        IssueTemplateInput::Missing(missing) => {
+            request_sections(&ctx.github, config, &event.issue, &missing).await
+        }
+        IssueTemplateInput::Fixed => clear_request(&ctx.github, config, &event.issue).await,
+    }
+}
+
+fn missing_sections_message(author: &str, missing: &[String]) -> String {
+    let mut message = format!(
+        "Thanks for the report @{author}! \
+        To help us reproduce it, please edit the issue to fill in these sections:\n\n"
+    );
+    for section in missing {
+        message.push_str(&format!("- {section}\n"));
+    }
+    message
+}
+
+async fn request_sections(
+    client: &GithubClient,
+    config: &IssueTemplateConfig,
+    issue: &Issue,
+    missing: &[String],
+) -> anyhow::Result<()> {
+    let message = missing_sections_message(&issue.user.login, missing);
+    issue
+        .post_comment(client, &with_bot_comment_marker(&message, COMMENT_KIND))
+        .await?;
+    issue
+        .add_labels(client, vec![Label::new(config.label.clone())])
+        .await
+}
+
+async fn clear_request(
+    client: &GithubClient,
+    config: &IssueTemplateConfig,
+    issue: &Issue,
+) -> anyhow::Result<()> {
+    issue.remove_label(client, &config.label).await?;
+    if let Some(comment) = issue.find_bot_comment(client, COMMENT_KIND).await? {
+        issue.delete_comment(client, comment.id).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{comment_json, test_issue_json, TestServer};
+
+    fn config() -> IssueTemplateConfig {
+        toml::from_str(r#"required-sections = ["Steps to reproduce", "Expected"]"#).unwrap()
+    }
+
+    /// An `action` event of the issue `rust-lang/rust#1200`, or of a PR if
+    /// `is_pr` is set.
+    fn event(action: &str, body: &str, labels: &[&str], is_pr: bool) -> IssuesEvent {
+        let mut issue = test_issue_json();
+        issue["body"] = body.into();
+        issue["labels"] = labels
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        if is_pr {
+            issue["pull_request"] = serde_json::json!({});
+        }
+        serde_json::from_value(serde_json::json!({
+            "action": action,
+            "issue": issue,
+            "changes": null,
+            "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            "sender": {"login": "octocat", "id": 583231},
+        }))
+        .unwrap()
+    }
+
+    async fn parse(ctx: &Context, event: IssuesEvent) -> Option<IssueTemplateInput> {
+        parse_input(ctx, &event, Some(&config())).await.unwrap()
+    }
+
+    #[test]
+    fn sections() {
+        let body = "### Steps to reproduce\n\n\
+            1. Run `cargo build`\n\n\
+            ### Expected behavior:\n\n\
+            <!-- What did you expect? -->\n";
+        assert_eq!(missing_sections(&config(), body), ["Expected"]);
+
+        let body = "**steps to reproduce**\n`cargo build`\n**Expected:**\nIt builds.";
+        assert!(missing_sections(&config(), body).is_empty());
+
+        assert_eq!(
+            missing_sections(&config(), "It doesn't build."),
+            ["Steps to reproduce", "Expected"]
+        );
+    }
+
+    #[tokio::test]
+    async fn opened_and_edited_issues() {
+        let server = TestServer::start(vec![]);
+        let ctx = server.context();
+        let partial = "### Steps to reproduce\n`cargo build`";
+        let complete = "### Steps to reproduce\n`cargo build`\n### Expected\nIt builds.";
+
+        assert!(matches!(
+            parse(&ctx, event("opened", partial, &[], false)).await,
+            Some(IssueTemplateInput::Missing(missing)) if missing == ["Expected"]
+        ));
+        assert!(matches!(
+            parse(&ctx, event("opened", complete, &[], false)).await,
+            None
+        ));
+        // The template is only asked of issues.
+        assert!(matches!(
+            parse(&ctx, event("opened", partial, &[], true)).await,
+            None
+        ));
+        assert!(matches!(
+            parse(
+                &ctx,
+                event("edited", complete, &["needs-reproduction"], false)
+            )
+            .await,
+            Some(IssueTemplateInput::Fixed)
+        ));
+        // Nothing to clear on the issues that were never labeled.
+        assert!(matches!(
+            parse(&ctx, event("edited", complete, &[], false)).await,
+            None
+        ));
+        assert!(matches!(
+            parse(
+                &ctx,
+                event("edited", partial, &["needs-reproduction"], false)
+            )
+            .await,
+            None
+        ));
+        assert!(server.finish().is_empty());
+    }
+
+    #[tokio::test]
+    async fn missing_section() {
+        let server = TestServer::start(vec![
+            ("201 Created", comment_json(1612361421, "")),
+            (
+                "200 OK",
+                serde_json::json!({ "name": "needs-reproduction" }).to_string(),
+            ),
+            ("200 OK", "[]".to_string()),
+        ]);
+        let missing = vec!["Expected".to_string()];
+        handle_input(
+            &server.context(),
+            &config(),
+            &event(
+                "opened",
+                "### Steps to reproduce\n`cargo build`",
+                &[],
+                false,
+            ),
+            IssueTemplateInput::Missing(missing),
+        )
+        .await
+        .unwrap();
+
+        let requests = server.finish();
+        assert_eq!(
+            requests
+                .iter()
+                .map(|r| format!("{} {}", r.method, r.path))
+                .collect::<Vec<_>>(),
+            [
+                "POST /repos/rust-lang/rust/issues/1200/comments",
+                "GET /repos/rust-lang/rust/labels/needs-reproduction",
+                "POST /repos/rust-lang/rust/issues/1200/labels",
+            ]
+        );
+        let comment: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(
+            comment["body"],
+            "Thanks for the report @octocat! To help us reproduce it, \
+            please edit the issue to fill in these sections:\n\n\
+            - Expected\n\n\n\
+            <!-- triagebot:issue-template -->\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn fixed_sections() {
+        let marked = with_bot_comment_marker("Thanks for the report", COMMENT_KIND);
+        let server = TestServer::start(vec![
+            ("200 OK", "[]".to_string()),
+            ("200 OK", format!("[{}]", comment_json(1612361421, &marked))),
+            ("204 No Content", String::new()),
+        ]);
+        handle_input(
+            &server.context(),
+            &config(),
+            &event("edited", "", &["needs-reproduction"], false),
+            IssueTemplateInput::Fixed,
+        )
+        .await
+        .unwrap();
+
+        let requests: Vec<_> = server
+            .finish()
+            .into_iter()
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect();
+        assert_eq!(
+            requests,
+            [
+                "DELETE /repos/rust-lang/rust/issues/1200/labels/needs-reproduction",
+                "GET /repos/rust-lang/rust/issues/1200/comments?page=1&per_page=100",
+                "DELETE /repos/rust-lang/rust/issues/comments/1612361421",
+            ]
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! A scheduled job keeping the labels of the repositories in line with the
+//! label set declared in their `[labels]` config:
+//!
+//! ```toml
+//! [labels]
+//! apply = true
+//!
+//! [[labels.set]]
+//! name = "T-compiler"
+//! color = "bfd4f2"
+//! description = "Relevant to the compiler team"
+//! ```
+//!
+//! The missing labels are created, and the labels whose color or description
+//! drifted are updated. The other labels of the repository are left alone.
+//!
+//! The changes are only logged unless `apply` is set, so that a new label set
+//! can be reviewed first.
+//!
+//! The job metadata selects the repositories:
+//!
+//! ```json
+//! { "repos": ["rust-lang/rust"] }
+//! ```
+//!
+//! The scheduled job takes them from the `LABEL_SYNC_REPOS` environment
+//! variable, a comma-separated list like `rust-lang/rust,rust-lang/cargo`.
+
+use crate::config::{LabelDefinition, LabelsConfig};
+use crate::github::{GithubClient, IssueRepository, Label};
+use crate::jobs::Job;
+use anyhow::Context as _;
+use async_trait::async_trait;
+use tracing as log;
+
+pub struct LabelSyncJob;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct LabelSyncMetadata {
+    pub repos: Vec<String>,
+}
+
+/// The environment variable listing the repositories synced by the scheduled
+/// job.
+const REPOS_VAR: &str = "LABEL_SYNC_REPOS";
+
+/// Returns the repositories of the `LABEL_SYNC_REPOS` environment variable,
+/// none if it is unset.
+pub fn repos_from_env() -> Vec<String> {
+    std::env::var(REPOS_VAR)
+        .map(|repos| parse_repos(&repos))
+        .unwrap_or_default()
+}
+
+fn parse_repos(repos: &str) -> Vec<String> {
+    repos
+        .split(',')
+        .map(str::trim)
+        .filter(|repo| !repo.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[async_trait]
+impl Job for LabelSyncJob {
+    fn name(&self) -> &'static str {
+        "label_sync"
+    }
+
+    async fn run(&self, ctx: &super::Context, metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let metadata: LabelSyncMetadata =
+            serde_json::from_value(metadata.clone()).context("invalid label_sync job metadata")?;
+        for repo in &metadata.repos {
+            if let Err(e) = sync_repo_labels(ctx, repo).await {
+                log::error!("failed to sync the labels of {repo}: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn sync_repo_labels(ctx: &super::Context, repo: &str) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo).await?;
+    let config = crate::config::get(&ctx.github, &repo)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to load config of {}: {e}", repo.full_name))?;
+    let Some(labels) = config
+        .labels
+        .as_ref()
+        .filter(|labels| !labels.set.is_empty())
+    else {
+        return Ok(());
+    };
+    let repo = IssueRepository {
+        organization: repo.owner().to_string(),
+        repository: repo.name().to_string(),
+    };
+    sync_labels(&ctx.github, &repo, labels).await?;
+    Ok(())
+}
+
+/// A change bringing a label of the repository in line with the label set.
+#[derive(Debug, PartialEq, Eq)]
+enum LabelChange {
+    Create(Label),
+    /// Sets the color and description that are not `None`.
+    Update(Label),
+}
+
+/// Returns the changes needed for the `existing` labels to match `declared`.
+///
+/// The names are compared case-insensitively, like GitHub does.
+fn label_changes(declared: &[LabelDefinition], existing: &[Label]) -> Vec<LabelChange> {
+    declared
+        .iter()
+        .filter_map(|label| {
+            let Some(current) = existing
+                .iter()
+                .find(|current| current.name.eq_ignore_ascii_case(&label.name))
+            else {
+                return Some(LabelChange::Create(Label {
+                    name: label.name.clone(),
+                    color: Some(label.color.clone()),
+                    description: label.description.clone(),
+                }));
+            };
+            let color = Some(&label.color).filter(|color| {
+                !current
+                    .color
+                    .as_ref()
+                    .is_some_and(|current| current.eq_ignore_ascii_case(color))
+            });
+            // GitHub returns no description and an empty one interchangeably.
+            let description = label.description.as_ref().filter(|description| {
+                *description != current.description.as_deref().unwrap_or_default()
+            });
+            if color.is_none() && description.is_none() {
+                return None;
+            }
+            Some(LabelChange::Update(Label {
+                name: current.name.clone(),
+                color: color.cloned(),
+                description: description.cloned(),
+            }))
+        })
+        .collect()
+}
+
+/// Brings the labels of `repo` in line with the label set of `config`,
+/// returning the changes.
+///
+/// The changes are only logged if `apply` isn't set in `config`. A failed
+/// change is logged and doesn't stop the others, but makes the sync fail once
+/// they are all tried.
+async fn sync_labels(
+    client: &GithubClient,
+    repo: &IssueRepository,
+    config: &LabelsConfig,
+) -> anyhow::Result<Vec<LabelChange>> {
+    let changes = label_changes(&config.set, &repo.labels(client).await?);
+    let mut failed = 0;
+    for change in &changes {
+        if !config.apply {
+            log::info!("label_sync: {repo}: would apply {change:?} (dry run)");
+            continue;
+        }
+        log::info!("label_sync: {repo}: applying {change:?}");
+        let res = match change {
+            LabelChange::Create(label) => repo.create_label(client, label).await,
+            LabelChange::Update(label) => repo.update_label(client, label).await,
+        };
+        if let Err(e) = res {
+            log::error!("label_sync: {repo}: failed to apply {change:?}: {e:?}");
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of the {} label changes failed", changes.len());
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, TestServer};
+
+    fn definition(name: &str, color: &str, description: Option<&str>) -> LabelDefinition {
+        LabelDefinition {
+            name: name.to_string(),
+            color: color.to_string(),
+            description: description.map(str::to_string),
+        }
+    }
+
+    fn label(name: &str, color: Option<&str>, description: Option<&str>) -> Label {
+        Label {
+            name: name.to_string(),
+            color: color.map(str::to_string),
+            description: description.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn created_labels() {
+        let changes = label_changes(
+            &[
+                definition(
+                    "T-compiler",
+                    "bfd4f2",
+                    Some("Relevant to the compiler team"),
+                ),
+                definition("A-diagnostics", "f7e101", None),
+            ],
+            &[label(
+                "t-compiler",
+                Some("BFD4F2"),
+                Some("Relevant to the compiler team"),
+            )],
+        );
+        let [LabelChange::Create(created)] = &changes[..] else {
+            panic!("unexpected changes {changes:?}");
+        };
+        assert_eq!(created.name, "A-diagnostics");
+        assert_eq!(created.color.as_deref(), Some("f7e101"));
+        assert_eq!(created.description, None);
+    }
+
+    #[test]
+    fn updated_labels() {
+        let changes = label_changes(
+            &[
+                definition(
+                    "T-compiler",
+                    "bfd4f2",
+                    Some("Relevant to the compiler team"),
+                ),
+                definition("T-libs", "bfd4f2", Some("Relevant to the library team")),
+                definition("A-diagnostics", "f7e101", None),
+                definition("E-easy", "02e10c", Some("")),
+            ],
+            &[
+                label(
+                    "T-compiler",
+                    Some("ededed"),
+                    Some("Relevant to the compiler team"),
+                ),
+                label("t-libs", Some("bfd4f2"), Some("Relevant to the libs team")),
+                label("A-diagnostics", Some("f7e101"), Some("Diagnostics")),
+                label("E-easy", Some("02e10c"), None),
+            ],
+        );
+        let updates: Vec<_> = changes
+            .iter()
+            .map(|change| match change {
+                LabelChange::Update(label) => (
+                    label.name.as_str(),
+                    label.color.as_deref(),
+                    label.description.as_deref(),
+                ),
+                change => panic!("unexpected change {change:?}"),
+            })
+            .collect();
+        assert_eq!(
+            updates,
+            [
+                ("T-compiler", Some("bfd4f2"), None),
+                ("t-libs", None, Some("Relevant to the library team")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn applied_changes() {
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!([
+                    {"name": "T-compiler", "color": "ededed", "description": null},
+                ])
+                .to_string(),
+            ),
+            ("200 OK", r#"{"name": "T-compiler"}"#.to_string()),
+            ("201 Created", r#"{"name": "A-diagnostics"}"#.to_string()),
+        ]);
+        let repo = IssueRepository {
+            organization: "rust-lang".to_string(),
+            repository: "rust".to_string(),
+        };
+        let mut config: LabelsConfig = toml::from_str(
+            r#"
+            [[set]]
+            name = "T-compiler"
+            color = "bfd4f2"
+            [[set]]
+            name = "A-diagnostics"
+            color = "f7e101"
+            description = "Compiler diagnostics"
+            "#,
+        )
+        .unwrap();
+        config.apply = true;
+        let changes = sync_labels(&server.github_client(), &repo, &config)
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 2);
+
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust/labels?page=1&per_page=100"),
+                expect("PATCH", "/repos/rust-lang/rust/labels/T-compiler")
+                    .with_body(serde_json::json!({"color": "bfd4f2"})),
+                expect("POST", "/repos/rust-lang/rust/labels").with_body(serde_json::json!({
+                    "name": "A-diagnostics",
+                    "color": "f7e101",
+                    "description": "Compiler diagnostics",
+                })),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_change_does_not_stop_the_others() {
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!([
+                    {"name": "T-compiler", "color": "ededed", "description": null},
+                ])
+                .to_string(),
+            ),
+            (
+                "422 Unprocessable Entity",
+                r#"{"message": "Validation Failed"}"#.to_string(),
+            ),
+            ("201 Created", r#"{"name": "A-diagnostics"}"#.to_string()),
+        ]);
+        let repo = IssueRepository {
+            organization: "rust-lang".to_string(),
+            repository: "rust".to_string(),
+        };
+        let mut config: LabelsConfig = toml::from_str(
+            r#"
+            [[set]]
+            name = "T-compiler"
+            color = "bfd4f2"
+            [[set]]
+            name = "A-diagnostics"
+            color = "f7e101"
+            "#,
+        )
+        .unwrap();
+        config.apply = true;
+        let err = sync_labels(&server.github_client(), &repo, &config)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "1 of the 2 label changes failed");
+
+        let requests: Vec<_> = server
+            .finish()
+            .into_iter()
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect();
+        assert_eq!(
+            requests,
+            [
+                "GET /repos/rust-lang/rust/labels?page=1&per_page=100",
+                "PATCH /repos/rust-lang/rust/labels/T-compiler",
+                "POST /repos/rust-lang/rust/labels",
+            ]
+        );
+    }
+
+    #[test]
+    fn repos_list() {
+        assert_eq!(
+            parse_repos(" rust-lang/rust, rust-lang/cargo,"),
+            ["rust-lang/rust", "rust-lang/cargo"]
+        );
+        assert!(parse_repos("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run() {
+        let server = TestServer::start(vec![("200 OK", "[]".to_string())]);
+        let repo = IssueRepository {
+            organization: "rust-lang".to_string(),
+            repository: "rust".to_string(),
+        };
+        let config: LabelsConfig = toml::from_str(
+            r#"
+            [[set]]
+            name = "T-compiler"
+            color = "bfd4f2"
+            "#,
+        )
+        .unwrap();
+        let changes = sync_labels(&server.github_client(), &repo, &config)
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 1);
+        // Only the labels are listed.
+        assert_eq!(server.finish().len(), 1);
+    }
+}
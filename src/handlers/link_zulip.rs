@@ -0,0 +1,115 @@
+//! Allows users to link their GitHub account to their Zulip account, so that
+//! the bot can notify them on Zulip even if they are not in the team
+//! repository.
+//!
+//! The Zulip user first asks for a code with the `link-github` Zulip command,
+//! and then posts it with `@rustbot link-zulip <code>`, so that both accounts
+//! confirm the link.
+//!
+//! Parsing is done in the `parser::command::link_zulip` module, configuration
+//! is done with the `[link-zulip]` table.
+
+use crate::{
+    config::LinkZulipConfig,
+    db::zulip_links::{set_zulip_link, take_link_request},
+    github::Event,
+    handlers::Context,
+    interactions::ErrorComment,
+};
+use chrono::Utc;
+use parser::command::link_zulip::LinkZulipCommand;
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    _config: &LinkZulipConfig,
+    event: &Event,
+    cmd: LinkZulipCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    let db = ctx.db.get().await;
+    let Some(zulip_id) = take_link_request(&db, &cmd.code, Utc::now()).await? else {
+        let cmnt = ErrorComment::new(
+            &issue,
+            "This code is unknown or expired. \
+             Send `link-github` to the bot on Zulip to get a new one.",
+        );
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    };
+
+    let user = event.user();
+    set_zulip_link(&db, user.id, zulip_id).await?;
+    issue
+        .post_comment(&ctx.github, &link_message(&user.login, zulip_id))
+        .await?;
+    Ok(())
+}
+
+fn link_message(login: &str, zulip_id: u64) -> String {
+    format!("@{login} is now linked to the Zulip user @**|{zulip_id}**.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::zulip_links::{create_link_request, get_zulip_link};
+    use crate::test_server::{assert_requests, expect, test_pr_json, TestServer};
+
+    #[test]
+    fn linked_user() {
+        assert_eq!(
+            link_message("octocat", 123456),
+            "@octocat is now linked to the Zulip user @**|123456**."
+        );
+    }
+
+    /// A `@rustbot link-zulip` comment by octocat.
+    fn link_event(code: &str) -> Event {
+        Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": test_pr_json(),
+                "comment": {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAABBB",
+                    "body": format!("@rustbot link-zulip {code}"),
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": "octocat", "id": 583231},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn links_with_the_zulip_code() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let code = create_link_request(&db, 123456).await.unwrap();
+        let server = TestServer::start(vec![(
+            "201 Created",
+            crate::test_server::comment_json(2, ""),
+        )]);
+        let mut ctx = server.context();
+        ctx.db = crate::db::ClientPool::with_client(db);
+        let cmd = LinkZulipCommand { code: code.clone() };
+        handle_command(&ctx, &LinkZulipConfig {}, &link_event(&code), cmd)
+            .await
+            .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments").with_body(
+                    serde_json::json!({
+                        "body": "@octocat is now linked to the Zulip user @**|123456**."
+                    }),
+                ),
+            ],
+        );
+        let db = ctx.db.get().await;
+        assert_eq!(get_zulip_link(&db, 583231).await.unwrap(), Some(123456));
+    }
+}
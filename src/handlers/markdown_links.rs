@@ -0,0 +1,353 @@
+//! Checks that the relative links of the Markdown files changed by a PR point
+//! to files of the PR's branch, like `[guide](../guide/index.md)`.
+//!
+//! The broken links are listed in a comment, which is updated on each push
+//! and removed once all the links are fixed.
+//!
+//! Configuration is done with the `[markdown-links]` table.
+
+use crate::{
+    config::MarkdownLinksConfig,
+    github::{with_bot_comment_marker, GithubClient, Issue, IssuesAction, IssuesEvent, Repository},
+    handlers::Context,
+};
+use anyhow::Context as _;
+use reqwest::StatusCode;
+use std::collections::{HashMap, HashSet};
+
+/// The kind of the marker of the comment listing the broken links.
+const COMMENT_KIND: &str = "markdown-links";
+
+pub(crate) struct MarkdownLinksInput {}
+
+pub(crate) async fn parse_input(
+    _ctx: &Context,
+    event: &IssuesEvent,
+    config: Option<&MarkdownLinksConfig>,
+) -> Result<Option<MarkdownLinksInput>, String> {
+    if config.is_none() || !event.issue.is_pr() || !event.issue.is_open() {
+        return Ok(None);
+    }
+    match event.action {
+        IssuesAction::Opened | IssuesAction::Reopened | IssuesAction::Synchronize => {
+            Ok(Some(MarkdownLinksInput {}))
+        }
+        _ => Ok(None),
+    }
+}
+
+pub(crate) async fn handle_input(
+    ctx: &Context,
+    config: &MarkdownLinksConfig,
+    event: &IssuesEvent,
+    MarkdownLinksInput {}: MarkdownLinksInput,
+) -> anyhow::Result<()> {
+    let broken = broken_links(&ctx.github, config, &event.issue).await?;
+    report_broken_links(&ctx.github, &event.issue, &broken).await
+}
+
+/// Returns the broken links of the changed Markdown files, as the path of the
+/// file and the link.
+async fn broken_links(
+    client: &GithubClient,
+    config: &MarkdownLinksConfig,
+    pr: &Issue,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let head = pr.head.as_ref().context("PR without head")?;
+    let mut listings = HashMap::new();
+    let mut broken = Vec::new();
+    for file in pr.files(client).await? {
+        if file.status == "removed" || !is_checked(config, &file.filename) {
+            continue;
+        }
+        let Some(content) = client
+            .raw_file(&head.repo.full_name, &head.sha, &file.filename)
+            .await?
+        else {
+            continue;
+        };
+        for link in relative_links(&String::from_utf8_lossy(&content)) {
+            let exists = match resolve(&file.filename, &link) {
+                Some(path) => {
+                    path_exists(client, &head.repo, &head.sha, &path, &mut listings).await?
+                }
+                // The link goes above the root of the repository.
+                None => false,
+            };
+            if !exists {
+                broken.push((file.filename.clone(), link));
+            }
+        }
+    }
+    Ok(broken)
+}
+
+fn is_checked(config: &MarkdownLinksConfig, filename: &str) -> bool {
+    filename.ends_with(".md")
+        && (config.paths.is_empty()
+            || config.paths.iter().any(|path| {
+                let path = path.trim_matches('/');
+                path.is_empty() || filename.starts_with(&format!("{path}/"))
+            }))
+}
+
+/// Returns the relative links and images of a Markdown document, without
+/// duplicates.
+///
+/// The links in code blocks and code spans are ignored.
+fn relative_links(content: &str) -> Vec<String> {
+    lazy_static! {
+        static ref INLINE: regex::Regex =
+            regex::Regex::new(r#"\]\(\s*<?([^)\s>]+)>?(?:\s+"[^"]*")?\s*\)"#).unwrap();
+        static ref REFERENCE: regex::Regex =
+            regex::Regex::new(r"^ {0,3}\[[^\]]+\]:\s*<?([^\s>]+)>?").unwrap();
+        static ref SCHEME: regex::Regex = regex::Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:").unwrap();
+    }
+    let mut links = Vec::new();
+    let mut in_code_block = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        // Drops the code spans, which are between odd and even backticks.
+        let text: String = line.split('`').step_by(2).collect();
+        let found = INLINE
+            .captures_iter(&text)
+            .chain(REFERENCE.captures_iter(&text))
+            .map(|caps| caps[1].to_string());
+        for link in found {
+            let is_relative =
+                !link.starts_with('#') && !link.starts_with("//") && !SCHEME.is_match(&link);
+            if is_relative && !links.contains(&link) {
+                links.push(link);
+            }
+        }
+    }
+    links
+}
+
+/// Returns the path from the root of the repository targeted by `link` in
+/// the file at `path`, or `None` if it goes above the root.
+fn resolve(path: &str, link: &str) -> Option<String> {
+    let target = link.split(['#', '?']).next().unwrap_or_default();
+    let target = target.replace("%20", " ");
+    let mut segments: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        // The directory of the file.
+        let mut segments: Vec<_> = path.split('/').collect();
+        segments.pop();
+        segments
+    };
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+/// Whether `path` exists at `sha`, listing its directory once for all the
+/// links.
+async fn path_exists(
+    client: &GithubClient,
+    repo: &Repository,
+    sha: &str,
+    path: &str,
+    listings: &mut HashMap<String, HashSet<String>>,
+) -> anyhow::Result<bool> {
+    if path.is_empty() {
+        // The root of the repository.
+        return Ok(true);
+    }
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    if !listings.contains_key(dir) {
+        let names = match repo.get_contents(client, dir, Some(sha)).await {
+            Ok(entries) => entries.into_iter().map(|entry| entry.name).collect(),
+            Err(e)
+                if e.downcast_ref::<reqwest::Error>()
+                    .map_or(false, |e| e.status() == Some(StatusCode::NOT_FOUND)) =>
+            {
+                HashSet::new()
+            }
+            Err(e) => return Err(e),
+        };
+        listings.insert(dir.to_string(), names);
+    }
+    Ok(listings[dir].contains(name))
+}
+
+fn broken_links_message(broken: &[(String, String)]) -> String {
+    let mut message = "Some relative links of the changed Markdown files don't point to a file \
+        of this branch:\n\n"
+        .to_string();
+    for (file, link) in broken {
+        message.push_str(&format!("- `{file}`: `{link}`\n"));
+    }
+    message
+}
+
+async fn report_broken_links(
+    client: &GithubClient,
+    pr: &Issue,
+    broken: &[(String, String)],
+) -> anyhow::Result<()> {
+    let previous = pr.find_bot_comment(client, COMMENT_KIND).await?;
+    if broken.is_empty() {
+        if let Some(comment) = previous {
+            pr.delete_comment(client, comment.id).await?;
+        }
+        return Ok(());
+    }
+    let body = with_bot_comment_marker(&broken_links_message(broken), COMMENT_KIND);
+    match previous {
+        Some(comment) if comment.body == body => {}
+        Some(comment) => {
+            pr.edit_comment(client, comment.id, &body).await?;
+        }
+        None => {
+            pr.post_comment(client, &body).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, comment_json, expect, test_pr_json, TestServer};
+
+    #[test]
+    fn links() {
+        let content = "# Guide\n\
+            See the [intro](intro.md), [the API](<../api/index.md#items> \"API\"), \
+            [usage](#usage) and [Rust](https://www.rust-lang.org).\n\
+            ![logo](./images/logo.png) `[not a link](code.md)`\n\
+            ```rust\n\
+            let x = [link](block.md);\n\
+            ```\n\
+            [intro]: intro.md\n\
+            [mail]: mailto:someone@example.com\n";
+        assert_eq!(
+            relative_links(content),
+            ["intro.md", "../api/index.md#items", "./images/logo.png"]
+        );
+    }
+
+    #[test]
+    fn resolved_paths() {
+        let resolved = |link| resolve("src/doc/guide.md", link);
+        assert_eq!(resolved("intro.md").unwrap(), "src/doc/intro.md");
+        assert_eq!(
+            resolved("../api/index.md#items").unwrap(),
+            "src/api/index.md"
+        );
+        assert_eq!(
+            resolved("./images/My%20logo.png").unwrap(),
+            "src/doc/images/My logo.png"
+        );
+        assert_eq!(resolved("/README.md?plain=1").unwrap(), "README.md");
+        assert_eq!(resolved("../..").unwrap(), "");
+        assert_eq!(resolved("../../../outside.md"), None);
+    }
+
+    #[test]
+    fn checked_files() {
+        let config: MarkdownLinksConfig = toml::from_str(r#"paths = ["src/doc/"]"#).unwrap();
+        assert!(is_checked(&config, "src/doc/guide.md"));
+        assert!(!is_checked(&config, "src/doc/guide.rs"));
+        assert!(!is_checked(&config, "src/doc-old/guide.md"));
+        assert!(is_checked(&toml::from_str("").unwrap(), "README.md"));
+    }
+
+    #[tokio::test]
+    async fn broken_relative_link() {
+        const SHA: &str = "b7d5ecbe6ed4a4c5b5e9a2d5b8f3b2e8a1f6d9c4";
+        let mut pr = test_pr_json();
+        pr["pull_request"] = serde_json::json!({});
+        pr["head"] = serde_json::json!({
+            "sha": SHA,
+            "ref": "guide",
+            "repo": {"full_name": "octocat/rust", "default_branch": "master"},
+        });
+        let pr: Issue = serde_json::from_value(pr).unwrap();
+        let entries = |names: &[&str]| {
+            serde_json::Value::from(
+                names
+                    .iter()
+                    .map(|name| {
+                        serde_json::json!({
+                            "name": name, "path": name, "sha": SHA, "size": 1, "type": "file",
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .to_string()
+        };
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!([
+                    {"sha": SHA, "filename": "src/doc/guide.md", "blob_url": "", "status": "added"},
+                    {"sha": SHA, "filename": "src/lib.rs", "blob_url": "", "status": "modified"},
+                ])
+                .to_string(),
+            ),
+            (
+                "200 OK",
+                "See the [intro](intro.md) and [the API](../api/index.md#items).\n\n\
+                ![logo](images/logo.png)\n"
+                    .to_string(),
+            ),
+            ("200 OK", entries(&["guide.md", "intro.md", "images"])),
+            ("404 Not Found", r#"{"message": "Not Found"}"#.to_string()),
+            ("200 OK", entries(&["logo.png"])),
+            ("200 OK", "[]".to_string()),
+            ("201 Created", comment_json(1, "")),
+        ]);
+        let client = server.github_client();
+        let config: MarkdownLinksConfig = toml::from_str("").unwrap();
+        let broken = broken_links(&client, &config, &pr).await.unwrap();
+        assert_eq!(
+            broken,
+            [(
+                "src/doc/guide.md".to_string(),
+                "../api/index.md#items".to_string()
+            )]
+        );
+        report_broken_links(&client, &pr, &broken).await.unwrap();
+
+        let requests = server.finish();
+        let contents = |dir: &str| format!("/repos/octocat/rust/contents/{dir}?ref={SHA}");
+        assert_requests(
+            &requests,
+            &[
+                expect("GET", "/repos/rust-lang/rust/pulls/1234/files"),
+                expect("GET", format!("/octocat/rust/{SHA}/src/doc/guide.md")),
+                expect("GET", contents("src/doc")),
+                expect("GET", contents("src/api")),
+                expect("GET", contents("src/doc/images")),
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/issues/1234/comments?page=1&per_page=100",
+                ),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments"),
+            ],
+        );
+        let body: serde_json::Value = serde_json::from_str(&requests[6].body).unwrap();
+        assert!(body["body"].as_str().unwrap().starts_with(
+            "Some relative links of the changed Markdown files don't point to a file \
+            of this branch:\n\n\
+            - `src/doc/guide.md`: `../api/index.md#items`\n"
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+//! A scheduled job that reminds the teams of their upcoming meetings on
+//! Zulip.
+//!
+//! The job metadata lists the Google calendars to watch, the Zulip stream
+//! where the meetings of each calendar are announced, and how long before
+//! the start of a meeting it is reminded:
+//!
+//! ```json
+//! {
+//!     "calendars": [{ "calendar-id": "team@group.calendar.google.com", "zulip-stream": 238009 }],
+//!     "lead-minutes": 60
+//! }
+//! ```
+//!
+//! The job is scheduled every 30 minutes by [`default_jobs`], with the
+//! calendars of the `MEETING_REMINDERS_CALENDARS` environment variable, so
+//! the reminder is posted between `lead-minutes` and `lead-minutes - 30`
+//! minutes before the start. Each meeting is only reminded once, even if its
+//! start time is in the window of several runs, and a failed reminder is
+//! retried by the next run.
+//!
+//! [`default_jobs`]: crate::jobs::default_jobs
+
+use crate::db::meeting_reminders::{record_reminder, was_reminded};
+use crate::handlers::Context;
+use crate::jobs::Job;
+use crate::meetings::{get_meetings, CompilerMeeting};
+use crate::zulip::{self, MessageApiRequest, Recipient};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use tokio_postgres::Client as DbClient;
+use tracing as log;
+
+pub struct MeetingRemindersJob;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MeetingCalendar {
+    pub calendar_id: String,
+    pub zulip_stream: u64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MeetingRemindersMetadata {
+    pub calendars: Vec<MeetingCalendar>,
+    pub lead_minutes: i64,
+}
+
+/// The environment variable listing the calendars reminded by the scheduled
+/// job, as JSON like the `calendars` of the metadata.
+const CALENDARS_VAR: &str = "MEETING_REMINDERS_CALENDARS";
+
+/// Returns the calendars of the `MEETING_REMINDERS_CALENDARS` environment
+/// variable, none if it is unset or invalid.
+pub fn calendars_from_env() -> Vec<MeetingCalendar> {
+    let Ok(calendars) = std::env::var(CALENDARS_VAR) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&calendars).unwrap_or_else(|e| {
+        log::error!("invalid `{CALENDARS_VAR}`: {e}");
+        Vec::new()
+    })
+}
+
+#[async_trait]
+impl Job for MeetingRemindersJob {
+    fn name(&self) -> &'static str {
+        "meeting_reminders"
+    }
+
+    fn is_nudge(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, ctx: &Context, metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let metadata: MeetingRemindersMetadata = serde_json::from_value(metadata.clone())
+            .context("invalid meeting_reminders job metadata")?;
+        let lead = Duration::minutes(metadata.lead_minutes);
+        let now = Utc::now();
+        let db = ctx.db.get().await;
+
+        for calendar in &metadata.calendars {
+            if let Err(e) = remind_meetings(ctx, &db, calendar, now, lead).await {
+                log::error!(
+                    "failed to remind the meetings of {}: {e:?}",
+                    calendar.calendar_id
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reminds the meetings of `calendar` starting in the next `lead`.
+async fn remind_meetings(
+    ctx: &Context,
+    db: &DbClient,
+    calendar: &MeetingCalendar,
+    now: DateTime<Utc>,
+    lead: Duration,
+) -> anyhow::Result<()> {
+    let meetings = get_meetings(ctx.github.raw(), &calendar.calendar_id, now, now + lead).await?;
+    for (meeting, start) in meetings_to_remind(&meetings, now, lead) {
+        if was_reminded(db, &meeting.id, start).await? {
+            continue;
+        }
+        let topic = zulip::truncate_topic(meeting.summary.clone());
+        let content = reminder_message(meeting, start);
+        MessageApiRequest {
+            recipient: Recipient::Stream {
+                id: calendar.zulip_stream,
+                topic: &topic,
+            },
+            content: &content,
+        }
+        .send(ctx.github.raw())
+        .await?
+        .error_for_status()?;
+        // Only recorded once sent, so that a failed reminder is retried.
+        record_reminder(db, &meeting.id, start).await?;
+    }
+    Ok(())
+}
+
+/// Returns the meetings starting in the next `lead`, with their start time.
+fn meetings_to_remind(
+    meetings: &[CompilerMeeting],
+    now: DateTime<Utc>,
+    lead: Duration,
+) -> Vec<(&CompilerMeeting, DateTime<Utc>)> {
+    meetings
+        .iter()
+        .filter_map(|meeting| Some((meeting, meeting.start_time()?)))
+        .filter(|(_, start)| *start > now && *start <= now + lead)
+        .collect()
+}
+
+fn reminder_message(meeting: &CompilerMeeting, start: DateTime<Utc>) -> String {
+    let mut message = format!(
+        "**{}** starts <time:{}>. See the [calendar event]({}).",
+        meeting.summary,
+        start.to_rfc3339(),
+        meeting.html_link
+    );
+    if let Some(agenda) = meeting.agenda_link() {
+        message.push_str(&format!("\n\nAgenda: {agenda}"));
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn meeting(id: &str, summary: &str, start: &str) -> CompilerMeeting {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "summary": summary,
+            "htmlLink": format!("https://www.google.com/calendar/event?eid={id}"),
+            "start": {"dateTime": start, "timeZone": "Europe/Berlin"},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reminds_meetings_within_lead_time() {
+        let meetings = [
+            meeting(
+                "started",
+                "T-compiler steering",
+                "2024-05-16T15:30:00+02:00",
+            ),
+            meeting("triage", "T-compiler triage", "2024-05-16T16:00:00+02:00"),
+            meeting("later", "T-lang design", "2024-05-16T19:00:00+02:00"),
+        ];
+        let now = Utc.with_ymd_and_hms(2024, 5, 16, 13, 30, 0).unwrap();
+        let reminded = meetings_to_remind(&meetings, now, Duration::minutes(60));
+
+        assert_eq!(reminded.len(), 1);
+        let (meeting, start) = reminded[0];
+        assert_eq!(meeting.id, "triage");
+        assert_eq!(
+            reminder_message(meeting, start),
+            "**T-compiler triage** starts <time:2024-05-16T14:00:00+00:00>. \
+            See the [calendar event](https://www.google.com/calendar/event?eid=triage)."
+        );
+    }
+
+    #[test]
+    fn reminder_links_agenda() {
+        let mut triage = meeting("triage", "T-compiler triage", "2024-05-16T16:00:00+02:00");
+        triage.description = Some("Agenda: https://hackmd.io/@rust-compiler/triage".to_string());
+        let start = triage.start_time().unwrap();
+        assert!(reminder_message(&triage, start)
+            .ends_with("\n\nAgenda: https://hackmd.io/@rust-compiler/triage"));
+    }
+}
@@ -12,9 +12,16 @@
 //! In general, multiple pushes happening quickly should be rare. And when it
 //! does happen, hopefully the state in the database will prevent duplicate
 //! messages.
+//!
+//! ## Active hours
+//!
+//! If the repository has `[active-hours]`, the warnings due outside of them
+//! are deferred to the next window, where they are posted by the
+//! [`deferred_nudges`](super::deferred_nudges) job if the PR still conflicts.
 
 use crate::{
-    config::MergeConflictConfig,
+    config::{ActiveHoursConfig, MergeConflictConfig},
+    db::deferred_nudges::{defer_nudge, DeferredNudge},
     db::issue_data::IssueData,
     db::PooledClient,
     github::{
@@ -24,6 +31,7 @@ use crate::{
     handlers::Context,
 };
 use anyhow::Context as _;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::time::Duration;
@@ -31,7 +39,7 @@ use tokio_postgres::Client as DbClient;
 use tracing as log;
 
 /// Key for the database.
-const MERGE_CONFLICTS_KEY: &str = "merge-conflicts";
+pub(super) const MERGE_CONFLICTS_KEY: &str = "merge-conflicts";
 
 /// The amount of time to wait before scanning an unknown mergeable status.
 ///
@@ -57,16 +65,17 @@ pub(super) async fn handle(
     ctx: &Context,
     event: &Event,
     config: &MergeConflictConfig,
+    active_hours: Option<&ActiveHoursConfig>,
 ) -> anyhow::Result<()> {
     match event {
-        Event::Push(push) => handle_branch_push(ctx, config, push).await,
+        Event::Push(push) => handle_branch_push(ctx, config, active_hours, push).await,
         Event::Issue(IssuesEvent {
             action: IssuesAction::Opened | IssuesAction::Reopened | IssuesAction::Synchronize,
             repository,
             issue,
             ..
         }) if issue.pull_request.is_some() => {
-            handle_pr(ctx, config, repository.clone(), issue).await
+            handle_pr(ctx, config, active_hours, repository.clone(), issue).await
         }
         _ => Ok(()),
     }
@@ -78,6 +87,7 @@ pub(super) async fn handle(
 async fn handle_branch_push(
     ctx: &Context,
     config: &MergeConflictConfig,
+    active_hours: Option<&ActiveHoursConfig>,
     push: &PushEvent,
 ) -> anyhow::Result<()> {
     let git_ref = push.git_ref.clone();
@@ -88,13 +98,24 @@ async fn handle_branch_push(
     let branch_name = branch_name.to_string();
     let push_sha = push.after.to_string();
     let config = config.clone();
+    let active_hours = active_hours.cloned();
     let repo = push.repository.clone();
     let db = ctx.db.get().await;
     // Spawn since this can trigger a lot of work.
     let gh = ctx.github.clone();
     tokio::task::spawn(async move {
         // See module note about locking.
-        if let Err(e) = scan_prs(&gh, db, &config, repo, &branch_name, &push_sha).await {
+        if let Err(e) = scan_prs(
+            &gh,
+            db,
+            &config,
+            active_hours.as_ref(),
+            repo,
+            &branch_name,
+            &push_sha,
+        )
+        .await
+        {
             log::error!("failed to scan PRs for merge conflicts: {e:?}");
         }
     });
@@ -106,23 +127,29 @@ async fn handle_branch_push(
 async fn handle_pr(
     ctx: &Context,
     config: &MergeConflictConfig,
+    active_hours: Option<&ActiveHoursConfig>,
     repo: Repository,
     issue: &Issue,
 ) -> anyhow::Result<()> {
     let mut db = ctx.db.get().await;
     match issue.mergeable {
         Some(true) => maybe_hide_comment(&ctx.github, &mut db, issue).await?,
-        Some(false) => maybe_add_comment(&ctx.github, &mut db, config, issue, None).await?,
+        Some(false) => {
+            maybe_add_comment(&ctx.github, &mut db, config, active_hours, issue, None).await?
+        }
         None => {
             // Status is unknown, spawn a task to try again later.
             let pr_number = issue.number;
             let db = ctx.db.get().await;
             let config = config.clone();
+            let active_hours = active_hours.cloned();
             let gh = ctx.github.clone();
             tokio::task::spawn(async move {
                 // See module note about locking.
                 tokio::time::sleep(UNKNOWN_RESCAN_DELAY).await;
-                if let Err(e) = rescan_pr(&gh, db, &config, repo, pr_number).await {
+                if let Err(e) =
+                    rescan_pr(&gh, db, &config, active_hours.as_ref(), repo, pr_number).await
+                {
                     log::error!("failed to rescan PR for merge conflicts: {e:?}");
                 }
             });
@@ -137,6 +164,7 @@ async fn rescan_pr(
     gh: &GithubClient,
     mut db: PooledClient,
     config: &MergeConflictConfig,
+    active_hours: Option<&ActiveHoursConfig>,
     repo: Repository,
     pr_number: u64,
 ) -> anyhow::Result<()> {
@@ -147,7 +175,7 @@ async fn rescan_pr(
     );
     match issue.mergeable {
         Some(true) => maybe_hide_comment(gh, &mut db, &issue).await?,
-        Some(false) => maybe_add_comment(gh, &mut db, config, &issue, None).await?,
+        Some(false) => maybe_add_comment(gh, &mut db, config, active_hours, &issue, None).await?,
         None => log::info!(
             "re-scan of mergeable status still unknown for {}",
             issue.global_id()
@@ -162,6 +190,7 @@ async fn scan_prs(
     gh: &GithubClient,
     mut db: PooledClient,
     config: &MergeConflictConfig,
+    active_hours: Option<&ActiveHoursConfig>,
     repo: Repository,
     branch_name: &str,
     push_sha: &str,
@@ -206,10 +235,19 @@ async fn scan_prs(
 
     for conflict in conflicting {
         let issue = repo.get_issue(gh, conflict.number).await?;
-        maybe_add_comment(gh, &mut db, config, &issue, possibly.as_deref()).await?;
+        maybe_add_comment(
+            gh,
+            &mut db,
+            config,
+            active_hours,
+            &issue,
+            possibly.as_deref(),
+        )
+        .await?;
     }
     if !unknowns.is_empty() {
         let config = config.clone();
+        let active_hours = active_hours.cloned();
         let gh = gh.clone();
         tokio::task::spawn(async move {
             // See module note about locking.
@@ -218,7 +256,17 @@ async fn scan_prs(
             // to the risk that another push happened while we were waiting.
             // May want to consider changing it to `None` if it regularly
             // points to the wrong thing.
-            if let Err(e) = scan_unknowns(&gh, db, &config, &repo, &unknowns, possibly).await {
+            if let Err(e) = scan_unknowns(
+                &gh,
+                db,
+                &config,
+                active_hours.as_ref(),
+                &repo,
+                &unknowns,
+                possibly,
+            )
+            .await
+            {
                 log::error!("failed to scan unknown PRs for merge conflicts: {e:?}");
             }
         });
@@ -233,6 +281,7 @@ async fn scan_unknowns(
     gh: &GithubClient,
     mut db: PooledClient,
     config: &MergeConflictConfig,
+    active_hours: Option<&ActiveHoursConfig>,
     repo: &Repository,
     unknowns: &[MergeConflictInfo],
     possibly: Option<String>,
@@ -246,7 +295,15 @@ async fn scan_unknowns(
         let issue = repo.get_issue(&gh, unknown.number).await?;
         // Ignore None, we don't want to repeatedly hammer GitHub asking for the answer.
         if issue.mergeable == Some(false) {
-            maybe_add_comment(gh, &mut db, config, &issue, possibly.as_deref()).await?;
+            maybe_add_comment(
+                gh,
+                &mut db,
+                config,
+                active_hours,
+                &issue,
+                possibly.as_deref(),
+            )
+            .await?;
         } else if issue.mergeable == None {
             log::info!("unable to determine mergeable after delay for {unknown:?}");
         }
@@ -258,6 +315,7 @@ async fn maybe_add_comment(
     gh: &GithubClient,
     db: &mut DbClient,
     config: &MergeConflictConfig,
+    active_hours: Option<&ActiveHoursConfig>,
     issue: &Issue,
     possibly: Option<&str>,
 ) -> anyhow::Result<()> {
@@ -267,6 +325,30 @@ async fn maybe_add_comment(
         // There was already an unresolved notification, don't warn again.
         return Ok(());
     }
+    if crate::jobs::nudges_paused() {
+        log::info!(
+            "not warning {} of merge conflicts: the nudges are paused",
+            issue.global_id()
+        );
+        return Ok(());
+    }
+    if let Some(run_at) = active_hours.and_then(|hours| hours.next_active(Utc::now())) {
+        log::info!(
+            "deferring the merge conflict warning of {} to {run_at}",
+            issue.global_id()
+        );
+        let nudge = DeferredNudge {
+            kind: MERGE_CONFLICTS_KEY.to_string(),
+            repo: issue.repository().full_repo_name(),
+            issue_number: issue.number,
+            metadata: serde_json::json!({ "possibly": possibly }),
+            run_at,
+        };
+        // The state is only loaded to check it, release its lock.
+        drop(state);
+        defer_nudge(db, &nudge).await?;
+        return Ok(());
+    }
 
     let possibly = possibly
         .as_ref()
@@ -291,11 +373,7 @@ async fn maybe_add_comment(
         for label in &config.remove {
             issue.remove_label(gh, label).await?;
         }
-        let to_add = config
-            .add
-            .iter()
-            .map(|l| Label { name: l.clone() })
-            .collect();
+        let to_add = config.add.iter().map(|l| Label::new(l.clone())).collect();
         issue.add_labels(gh, to_add).await?;
     }
 
@@ -322,3 +400,87 @@ async fn maybe_hide_comment(
 
     Ok(())
 }
+
+/// Posts a merge conflict warning deferred by [`maybe_add_comment`], if the
+/// PR still conflicts.
+pub(super) async fn post_deferred_warning(
+    ctx: &Context,
+    nudge: &DeferredNudge,
+) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(&nudge.repo).await?;
+    let config = crate::config::get(&ctx.github, &repo)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to load config of {}: {e}", repo.full_name))?;
+    let Some(conflict_config) = &config.merge_conflicts else {
+        return Ok(());
+    };
+    let issue = repo.get_issue(&ctx.github, nudge.issue_number).await?;
+    if !issue.is_open() || issue.mergeable != Some(false) {
+        log::debug!("{} no longer conflicts", issue.global_id());
+        return Ok(());
+    }
+    let mut db = ctx.db.get().await;
+    maybe_add_comment(
+        &ctx.github,
+        &mut db,
+        conflict_config,
+        config.active_hours.as_ref(),
+        &issue,
+        nudge.metadata["possibly"].as_str(),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::deferred_nudges::due_nudges;
+    use crate::test_server::{assert_requests, test_pr, TestServer};
+    use chrono::Timelike;
+
+    #[tokio::test]
+    async fn warning_outside_active_hours_is_deferred() {
+        let Some(mut db) = crate::db::test_client().await else {
+            return;
+        };
+        // A one-hour window two hours from now, so that the warning is
+        // deferred even if the hour changes during the test.
+        let hour = Utc::now().hour();
+        let active_hours = ActiveHoursConfig {
+            timezone: chrono_tz::UTC,
+            start: (hour + 2) % 24,
+            end: (hour + 3) % 24,
+            weekdays_only: false,
+        };
+        let server = TestServer::start(vec![]);
+        let pr = test_pr();
+        maybe_add_comment(
+            &server.github_client(),
+            &mut db,
+            &MergeConflictConfig {
+                remove: HashSet::new(),
+                add: HashSet::new(),
+                unless: HashSet::new(),
+            },
+            Some(&active_hours),
+            &pr,
+            Some("#1233"),
+        )
+        .await
+        .unwrap();
+        assert_requests(&server.finish(), &[]);
+
+        let next_active = active_hours.next_active(Utc::now()).unwrap();
+        let nudges = due_nudges(&db, next_active).await.unwrap();
+        assert_eq!(
+            nudges,
+            [DeferredNudge {
+                kind: MERGE_CONFLICTS_KEY.to_string(),
+                repo: "rust-lang/rust".to_string(),
+                issue_number: 1234,
+                metadata: serde_json::json!({ "possibly": "#1233" }),
+                run_at: next_active,
+            }]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Sets the milestone of a PR on the issues it closes, so that the issues are
+//! tracked in the release shipping their fix.
+//!
+//! This is done with the `milestone-closed` command, or automatically when a
+//! PR is merged if `on-merge` is set.
+//!
+//! Parsing is done in the `parser::command::milestone_closed` module,
+//! configuration is done with the `[milestone-closed]` table.
+
+use crate::{
+    config::MilestoneClosedConfig,
+    github::{Event, GithubClient, Issue, IssuesAction, Milestone},
+    handlers::Context,
+    interactions::ErrorComment,
+};
+use parser::command::milestone_closed::MilestoneClosedCommand;
+use tracing as log;
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    _config: &MilestoneClosedConfig,
+    event: &Event,
+    _cmd: MilestoneClosedCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    if !issue.is_pr() {
+        let cmnt = ErrorComment::new(
+            &issue,
+            "The milestone-closed command only works on pull requests.",
+        );
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+    let is_team_member = event
+        .user()
+        .is_team_member(&ctx.github)
+        .await
+        .unwrap_or(false);
+    if !is_team_member {
+        let cmnt = ErrorComment::new(&issue, "Only team members can set milestones.");
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+    let Some(milestone) = &issue.milestone else {
+        let cmnt = ErrorComment::new(&issue, "This pull request has no milestone.");
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    };
+
+    let closed = propagate_milestone(&ctx.github, issue, milestone).await?;
+    issue
+        .post_comment(&ctx.github, &propagated_message(milestone, &closed))
+        .await?;
+    Ok(())
+}
+
+/// Propagates the milestone of the PRs merged while `on-merge` is set.
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &MilestoneClosedConfig,
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if !config.on_merge || e.action != IssuesAction::Closed || !e.issue.merged {
+        return Ok(());
+    }
+    // The milestone is fetched again, as it may have just been set by the
+    // `milestone_prs` handler.
+    let pr = ctx
+        .github
+        .get_issue(&e.repository.full_name, e.issue.number)
+        .await?;
+    let Some(milestone) = &pr.milestone else {
+        log::trace!("{} was merged without a milestone", pr.global_id());
+        return Ok(());
+    };
+    propagate_milestone(&ctx.github, &e.issue, milestone).await?;
+    Ok(())
+}
+
+/// Sets `milestone` on the issues closed by `pr`, and returns their numbers.
+async fn propagate_milestone(
+    client: &GithubClient,
+    pr: &Issue,
+    milestone: &Milestone,
+) -> anyhow::Result<Vec<u64>> {
+    let closed = pr.closing_issues(client).await?;
+    let full_repo_name = pr.repository().full_repo_name();
+    for &number in &closed {
+        log::info!("setting milestone {milestone:?} on {full_repo_name}#{number}");
+        client
+            .set_milestone(&full_repo_name, milestone, number)
+            .await?;
+    }
+    Ok(closed)
+}
+
+fn propagated_message(milestone: &Milestone, closed: &[u64]) -> String {
+    if closed.is_empty() {
+        return "This pull request doesn't close any issue.".to_string();
+    }
+    let issues: Vec<_> = closed.iter().map(|n| format!("#{n}")).collect();
+    format!(
+        "Set the `{}` milestone on {}.",
+        milestone.title,
+        issues.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{test_pr, TestServer};
+
+    #[tokio::test]
+    async fn propagates_to_closed_issues() {
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!({
+                    "data": {"repository": {"pullRequest": {"closingIssuesReferences": {"nodes": [
+                        {"number": 1200, "repository": {"nameWithOwner": "rust-lang/rust"}},
+                        {"number": 1201, "repository": {"nameWithOwner": "rust-lang/rust"}},
+                        {"number": 42, "repository": {"nameWithOwner": "rust-lang/cargo"}},
+                    ]}}}}
+                })
+                .to_string(),
+            ),
+            ("200 OK", "{}".to_string()),
+            ("200 OK", "{}".to_string()),
+        ]);
+        let milestone: Milestone =
+            serde_json::from_value(serde_json::json!({"number": 101, "title": "1.80.0"})).unwrap();
+        let closed = propagate_milestone(&server.github_client(), &test_pr(), &milestone)
+            .await
+            .unwrap();
+        assert_eq!(closed, [1200, 1201]);
+        assert_eq!(
+            propagated_message(&milestone, &closed),
+            "Set the `1.80.0` milestone on #1200, #1201."
+        );
+
+        let requests = server.finish();
+        assert_eq!(requests[0].path, "/graphql");
+        for (request, number) in requests[1..].iter().zip([1200, 1201]) {
+            assert_eq!(request.method, "PATCH");
+            assert_eq!(
+                request.path,
+                format!("/repos/rust-lang/rust/issues/{number}")
+            );
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&request.body).unwrap(),
+                serde_json::json!({ "milestone": 101 })
+            );
+        }
+    }
+}
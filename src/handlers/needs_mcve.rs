@@ -0,0 +1,298 @@
+//! Notices when the author of an issue waiting for a minimal reproduction,
+//! an MCVE, posts a comment that looks like one.
+//!
+//! A comment is considered a reproduction when it contains a fenced code
+//! block with enough lines, in one of the configured languages (so that
+//! pasted compiler output in a `console` block doesn't count). The bot then
+//! pings the triagers so they can check it, and optionally removes the label
+//! until then.
+//!
+//! A reproduction is only reported once while the issue has the label, so
+//! that the author posting several code blocks doesn't ping the triagers
+//! each time. Adding the label again reports the next one.
+//!
+//! Configuration is done with the `[needs-mcve]` table.
+
+use crate::{
+    config::NeedsMcveConfig,
+    db::issue_data::IssueData,
+    github::{
+        Comment, Event, GithubClient, Issue, IssueCommentAction, IssueCommentEvent, IssuesAction,
+        IssuesEvent,
+    },
+    handlers::Context,
+};
+use serde::{Deserialize, Serialize};
+use tracing as log;
+
+const NEEDS_MCVE_KEY: &str = "needs-mcve";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct NeedsMcveState {
+    /// Whether a reproduction was reported since the label was added.
+    reported: bool,
+}
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &NeedsMcveConfig,
+) -> anyhow::Result<()> {
+    match event {
+        Event::Issue(IssuesEvent {
+            action: IssuesAction::Labeled { label },
+            issue,
+            ..
+        }) if label.name == config.label => {
+            // A new reproduction is asked for, so report the next one.
+            let mut db = ctx.db.get().await;
+            let mut state: IssueData<'_, NeedsMcveState> =
+                IssueData::load(&mut db, issue, NEEDS_MCVE_KEY).await?;
+            if state.data.reported {
+                state.data.reported = false;
+                state.save().await?;
+            }
+            Ok(())
+        }
+        Event::IssueComment(IssueCommentEvent {
+            action: IssueCommentAction::Created,
+            issue,
+            comment,
+            ..
+        }) => {
+            if issue.is_pr()
+                || comment.user != issue.user
+                || !issue.labels().iter().any(|l| l.name == config.label)
+                || !has_reproduction(config, &comment.body)
+            {
+                return Ok(());
+            }
+            let mut db = ctx.db.get().await;
+            let mut state: IssueData<'_, NeedsMcveState> =
+                IssueData::load(&mut db, issue, NEEDS_MCVE_KEY).await?;
+            if state.data.reported {
+                log::debug!(
+                    "a reproduction was already reported on {}",
+                    issue.global_id()
+                );
+                return Ok(());
+            }
+            report_reproduction(&ctx.github, config, issue, comment).await?;
+            state.data.reported = true;
+            state.save().await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether `body` contains a fenced code block in one of the configured
+/// languages, with at least `min-lines` non-blank lines.
+fn has_reproduction(config: &NeedsMcveConfig, body: &str) -> bool {
+    let mut lines = body.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim_start();
+        let fence_len = line.chars().take_while(|&c| c == '`' || c == '~').count();
+        if fence_len < 3 {
+            continue;
+        }
+        let fence = &line[..fence_len];
+        let language = line[fence_len..]
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .next()
+            .unwrap_or("");
+        let code_lines = lines
+            .by_ref()
+            .take_while(|l| !l.trim_start().starts_with(fence))
+            .filter(|l| !l.trim().is_empty())
+            .count();
+        if code_lines >= config.min_lines
+            && config
+                .languages
+                .iter()
+                .any(|l| l.eq_ignore_ascii_case(language))
+        {
+            return true;
+        }
+    }
+    false
+}
+
+fn reproduction_message(config: &NeedsMcveConfig, comment: &Comment) -> String {
+    let mut message = format!(
+        "@{} posted [a possible reproduction]({}).",
+        comment.user.login, comment.html_url
+    );
+    if let Some(ping) = &config.ping {
+        message.push_str(&format!(
+            " {ping} could you check whether it reproduces the issue?"
+        ));
+    }
+    if config.remove_label {
+        message.push_str(&format!(
+            "\n\nThe `{}` label was removed, add it back if the reproduction is not enough.",
+            config.label
+        ));
+    }
+    message
+}
+
+async fn report_reproduction(
+    client: &GithubClient,
+    config: &NeedsMcveConfig,
+    issue: &Issue,
+    comment: &Comment,
+) -> anyhow::Result<()> {
+    if config.remove_label {
+        issue.remove_label(client, &config.label).await?;
+    }
+    issue
+        .post_comment(client, &reproduction_message(config, comment))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, comment_json, expect, test_pr_json, TestServer};
+
+    fn config(toml: &str) -> NeedsMcveConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn code_blocks() {
+        let config = config("");
+        let mcve = "Here it is:\n\n```rust\nfn main() {\n    let x: u8 = 256;\n}\n```\n";
+        assert!(has_reproduction(&config, mcve));
+        assert!(has_reproduction(&config, &mcve.replace("```rust", "```")));
+        assert!(!has_reproduction(
+            &config,
+            &mcve.replace("```rust", "```console")
+        ));
+        assert!(!has_reproduction(&config, "```rust\nfoo();\n```"));
+        assert!(!has_reproduction(&config, "I can't reproduce it anymore."));
+    }
+
+    #[test]
+    fn configured_heuristic() {
+        let config = config(
+            r#"min-lines = 1
+languages = ["toml"]"#,
+        );
+        assert!(has_reproduction(&config, "```toml\n[dependencies]\n```"));
+        assert!(!has_reproduction(&config, "```rust\nfoo();\n```"));
+    }
+
+    #[tokio::test]
+    async fn comment_with_code_block() {
+        let config = config(
+            r#"ping = "@rust-lang/wg-triage"
+remove-label = true"#,
+        );
+        let mut issue = test_pr_json();
+        issue["labels"] = serde_json::json!([{ "name": "E-needs-mcve" }]);
+        let issue: Issue = serde_json::from_value(issue).unwrap();
+        let comment: Comment = serde_json::from_str(&comment_json(
+            1612361421,
+            "Reduced it to:\n\n```\nfn main() {\n    let x: u8 = 256;\n}\n```",
+        ))
+        .unwrap();
+        assert!(has_reproduction(&config, &comment.body));
+
+        let server = TestServer::start(vec![
+            ("200 OK", "[]".to_string()),
+            ("201 Created", comment_json(1612361422, "")),
+        ]);
+        report_reproduction(&server.github_client(), &config, &issue, &comment)
+            .await
+            .unwrap();
+        let requests = server.finish();
+        assert_eq!(requests[0].method, "DELETE");
+        assert_eq!(
+            requests[0].path,
+            "/repos/rust-lang/rust/issues/1234/labels/E-needs-mcve"
+        );
+        assert_eq!(
+            requests[1].path,
+            "/repos/rust-lang/rust/issues/1234/comments"
+        );
+        let posted: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(
+            posted["body"],
+            "@rustbot posted [a possible reproduction]\
+            (https://github.com/rust-lang/rust/pull/1234#issuecomment-1612361421). \
+            @rust-lang/wg-triage could you check whether it reproduces the issue?\n\n\
+            The `E-needs-mcve` label was removed, add it back if the reproduction is not enough."
+        );
+    }
+
+    /// The issue waiting for a reproduction from octocat.
+    fn waiting_issue() -> serde_json::Value {
+        let mut issue = test_pr_json();
+        issue["labels"] = serde_json::json!([{ "name": "E-needs-mcve" }]);
+        issue
+    }
+
+    fn reproduction_event(id: u64) -> Event {
+        Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": waiting_issue(),
+                "comment": {
+                    "id": id,
+                    "node_id": format!("IC_{id}"),
+                    "body": "```rust\nfn main() {\n    let x: u8 = 256;\n}\n```",
+                    "html_url": format!("https://github.com/rust-lang/rust/issues/1234#issuecomment-{id}"),
+                    "user": {"login": "octocat", "id": 583231},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn reproduction_reported_once_per_label() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        let config = config("");
+        let server = TestServer::start(vec![
+            ("201 Created", comment_json(10, "")),
+            ("201 Created", comment_json(11, "")),
+        ]);
+        let mut ctx = server.context();
+        ctx.db = crate::db::ClientPool::with_client(db);
+
+        handle(&ctx, &reproduction_event(1), &config).await.unwrap();
+        // Already reported.
+        handle(&ctx, &reproduction_event(2), &config).await.unwrap();
+        // The label is added again, so the next one is reported.
+        let labeled = Event::Issue(
+            serde_json::from_value(serde_json::json!({
+                "action": "labeled",
+                "label": {"name": "E-needs-mcve"},
+                "issue": waiting_issue(),
+                "changes": null,
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+                "sender": {"login": "octocat", "id": 583231},
+            }))
+            .unwrap(),
+        );
+        handle(&ctx, &labeled, &config).await.unwrap();
+        handle(&ctx, &reproduction_event(3), &config).await.unwrap();
+
+        let requests = server.finish();
+        assert_requests(
+            &requests,
+            &[
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments"),
+            ],
+        );
+        assert!(requests[0].body.contains("issuecomment-1"));
+        assert!(requests[1].body.contains("issuecomment-3"));
+    }
+}
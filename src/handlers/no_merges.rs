@@ -205,7 +205,7 @@ pub(super) async fn handle_input(
             // Only add labels not already on the issue.
             if existing_labels.iter().all(|l| &l.name != name) {
                 state.data.added_labels.push(name.clone());
-                labels.push(Label { name: name.clone() });
+                labels.push(Label::new(name.clone()));
             }
         }
 
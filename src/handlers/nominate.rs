@@ -1,12 +1,17 @@
 //! Purpose: Allow team members to nominate issues or PRs.
+//!
+//! Issues can also be nominated automatically when one of the labels of the
+//! `[nominate.auto]` table is added.
 
 use crate::{
     config::NominateConfig,
-    github::{self, Event},
+    github::{self, Event, GithubClient, Issue, IssuesAction, IssuesEvent, Label},
     handlers::Context,
     interactions::ErrorComment,
+    zulip::{self, MessageApiRequest, Recipient},
 };
 use parser::command::nominate::{NominateCommand, Style};
+use tracing as log;
 
 pub(super) async fn handle_command(
     ctx: &Context,
@@ -51,9 +56,7 @@ pub(super) async fn handle_command(
 
         // Add the beta-accepted label, but don't attempt to remove beta-nominated or the team
         // label.
-        labels_to_add.push(github::Label {
-            name: "beta-accepted".into(),
-        });
+        labels_to_add.push(github::Label::new("beta-accepted"));
     } else {
         if !config.teams.contains_key(&cmd.team) {
             let cmnt = ErrorComment::new(
@@ -68,17 +71,30 @@ pub(super) async fn handle_command(
             return Ok(());
         }
 
-        let label = config.teams[&cmd.team].clone();
-        labels_to_add.push(github::Label { name: label });
+        if github::get_team(&ctx.github, &cmd.team).await?.is_none() {
+            let cmnt = ErrorComment::new(
+                &event.issue().unwrap(),
+                format!("`{}` is not a team of the Rust project.", cmd.team),
+            );
+            cmnt.post(&ctx.github).await?;
+            return Ok(());
+        }
 
-        let style_label = match cmd.style {
-            Style::Decision => "I-nominated",
-            Style::Beta => "beta-nominated",
-            Style::BetaApprove => unreachable!(),
-        };
-        labels_to_add.push(github::Label {
-            name: style_label.into(),
-        });
+        let rationale = format!(
+            "{} wrote in [the nomination comment]({}):\n\n{}",
+            event.user().login,
+            event.html_url().unwrap(),
+            quote_block(event.comment_body().unwrap_or_default().trim()),
+        );
+        return nominate(
+            &ctx.github,
+            config,
+            event.issue().unwrap(),
+            &cmd.team,
+            cmd.style,
+            &rationale,
+        )
+        .await;
     }
 
     event
@@ -89,3 +105,301 @@ pub(super) async fn handle_command(
 
     Ok(())
 }
+
+pub(crate) struct AutoNominateInput {
+    label: String,
+    team: String,
+}
+
+pub(crate) async fn parse_input(
+    _ctx: &Context,
+    event: &IssuesEvent,
+    config: Option<&NominateConfig>,
+) -> Result<Option<AutoNominateInput>, String> {
+    let Some(config) = config else {
+        return Ok(None);
+    };
+    Ok(auto_nomination(config, event))
+}
+
+/// Returns the team to nominate the issue for, if the event added one of the
+/// labels of the `[nominate.auto]` table.
+fn auto_nomination(config: &NominateConfig, event: &IssuesEvent) -> Option<AutoNominateInput> {
+    let IssuesAction::Labeled { label } = &event.action else {
+        return None;
+    };
+    let team = config.auto.get(&label.name)?;
+    if !config.teams.contains_key(team) {
+        log::warn!(
+            "cannot auto-nominate {} for `{team}`, the team is missing from `[nominate.teams]`",
+            event.issue.global_id()
+        );
+        return None;
+    }
+    // Adding the nomination labels triggers `labeled` events too, so the
+    // issue must not be nominated again, even if a nomination label is
+    // itself configured to auto-nominate.
+    let nomination_labels = nomination_labels(config, team, Style::Decision);
+    if nomination_labels.contains(label)
+        || nomination_labels
+            .iter()
+            .all(|l| event.issue.labels().contains(l))
+    {
+        return None;
+    }
+    Some(AutoNominateInput {
+        label: label.name.clone(),
+        team: team.clone(),
+    })
+}
+
+pub(crate) async fn handle_input(
+    ctx: &Context,
+    config: &NominateConfig,
+    event: &IssuesEvent,
+    input: AutoNominateInput,
+) -> anyhow::Result<()> {
+    let rationale = format!(
+        "Nominated automatically because the `{}` label was added.",
+        input.label
+    );
+    nominate(
+        &ctx.github,
+        config,
+        &event.issue,
+        &input.team,
+        Style::Decision,
+        &rationale,
+    )
+    .await
+}
+
+/// Nominates `issue` for `team`: adds the nomination labels and announces
+/// the nomination on the team's Zulip stream, if one is configured.
+///
+/// `team` must be one of the teams of the config.
+pub(super) async fn nominate(
+    client: &GithubClient,
+    config: &NominateConfig,
+    issue: &Issue,
+    team: &str,
+    style: Style,
+    rationale: &str,
+) -> anyhow::Result<()> {
+    issue
+        .add_labels(client, nomination_labels(config, team, style))
+        .await?;
+
+    if let Some(&stream) = config.zulip_streams.get(team) {
+        let topic = zulip::truncate_topic(format!("#{} {}", issue.number, issue.title));
+        let content = nomination_message(issue, team, style, rationale);
+        MessageApiRequest {
+            recipient: Recipient::Stream {
+                id: stream,
+                topic: &topic,
+            },
+            content: &content,
+        }
+        .send(client.raw())
+        .await?;
+    }
+    Ok(())
+}
+
+/// Quotes `text` in a Zulip quote block, with a fence longer than the
+/// backtick runs of `text`, so that its code blocks don't end the quote.
+fn quote_block(text: &str) -> String {
+    let longest_run = text
+        .split(|c: char| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{fence}quote\n{text}\n{fence}")
+}
+
+fn nomination_labels(config: &NominateConfig, team: &str, style: Style) -> Vec<Label> {
+    let style_label = match style {
+        Style::Decision => format!("I-{team}-nominated"),
+        Style::Beta => "beta-nominated".to_string(),
+        Style::BetaApprove => unreachable!(),
+    };
+    vec![
+        Label::new(config.teams[team].clone()),
+        Label::new(style_label),
+    ]
+}
+
+fn nomination_message(issue: &Issue, team: &str, style: Style, rationale: &str) -> String {
+    let purpose = match style {
+        Style::Decision => "for discussion",
+        Style::Beta => "for a beta backport",
+        Style::BetaApprove => unreachable!(),
+    };
+    format!(
+        "[{}]({}) \"{}\" was nominated {purpose} by T-{team}.\n\n{rationale}",
+        issue.global_id(),
+        issue.html_url,
+        issue.title,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, test_pr, test_pr_json, TestServer};
+    use std::collections::HashMap;
+
+    fn config() -> NominateConfig {
+        toml::from_str(
+            r#"
+            [teams]
+            lang = "T-lang"
+
+            [zulip_streams]
+            lang = 213817
+            "#,
+        )
+        .unwrap()
+    }
+
+    /// A nomination comment by a member of the lang team.
+    fn nominate_event(body: &str) -> Event {
+        Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": test_pr_json(),
+                "comment": {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAABBB",
+                    "body": body,
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": "team-member", "id": 1},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn nominating_adds_team_labels() {
+        crate::team_data::use_test_teams();
+        let server = TestServer::start(vec![
+            ("200 OK", r#"{"name": "T-lang"}"#.to_string()),
+            ("200 OK", r#"{"name": "I-lang-nominated"}"#.to_string()),
+            ("200 OK", "[]".to_string()),
+            ("200 OK", r#"{"result": "success", "id": 42}"#.to_string()),
+        ]);
+        crate::zulip::use_test_zulip(server.url());
+        let body = "@rustbot nominate lang\n\nIs this ok?\n\n```rust\nfoo();\n```";
+        let cmd = NominateCommand {
+            team: "lang".to_string(),
+            style: Style::Decision,
+        };
+        handle_command(&server.context(), &config(), &nominate_event(body), cmd)
+            .await
+            .unwrap();
+        let requests = server.finish();
+        assert_requests(
+            &requests,
+            &[
+                expect("GET", "/repos/rust-lang/rust/labels/T-lang"),
+                expect("GET", "/repos/rust-lang/rust/labels/I-lang-nominated"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({"labels": ["T-lang", "I-lang-nominated"]})),
+                expect("POST", "/api/v1/messages"),
+            ],
+        );
+        let message: HashMap<String, String> =
+            url::form_urlencoded::parse(requests[3].body.as_bytes())
+                .into_owned()
+                .collect();
+        assert_eq!(message["type"], "stream");
+        assert_eq!(message["to"], "213817");
+        assert_eq!(message["topic"], "#1234 Example PR");
+        assert_eq!(
+            message["content"],
+            "[rust-lang/rust#1234](https://github.com/rust-lang/rust/pull/1234) \"Example PR\" \
+            was nominated for discussion by T-lang.\n\n\
+            team-member wrote in [the nomination comment]\
+            (https://github.com/rust-lang/rust/pull/1234#issuecomment-1):\n\n\
+            ````quote\n@rustbot nominate lang\n\nIs this ok?\n\n```rust\nfoo();\n```\n````"
+        );
+    }
+
+    #[test]
+    fn quoted_code_blocks() {
+        assert_eq!(quote_block("Is this ok?"), "```quote\nIs this ok?\n```");
+        assert_eq!(
+            quote_block("`a` and\n`````\nb\n`````"),
+            "``````quote\n`a` and\n`````\nb\n`````\n``````"
+        );
+    }
+
+    fn labeled_event(label: &str, labels: &[&str]) -> IssuesEvent {
+        let mut issue = test_pr_json();
+        issue["labels"] = labels
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "action": "labeled",
+            "label": {"name": label},
+            "issue": issue,
+            "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            "sender": {"login": "octocat", "id": 583231},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn auto_nominate_on_label() {
+        let config: NominateConfig = toml::from_str(
+            r#"
+            [teams]
+            lang = "T-lang"
+
+            [auto]
+            I-unsound = "lang"
+            I-lang-nominated = "lang"
+            "#,
+        )
+        .unwrap();
+
+        let input = auto_nomination(&config, &labeled_event("I-unsound", &["I-unsound"])).unwrap();
+        assert_eq!(input.team, "lang");
+        assert_eq!(input.label, "I-unsound");
+
+        // Other labels don't nominate.
+        assert!(auto_nomination(&config, &labeled_event("A-lints", &["A-lints"])).is_none());
+        // Neither do the nomination labels, even when configured.
+        assert!(auto_nomination(
+            &config,
+            &labeled_event("I-lang-nominated", &["I-lang-nominated"])
+        )
+        .is_none());
+        // Already nominated.
+        assert!(auto_nomination(
+            &config,
+            &labeled_event("I-unsound", &["I-unsound", "T-lang", "I-lang-nominated"])
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn nomination_announcement() {
+        assert_eq!(
+            nomination_message(
+                &test_pr(),
+                "lang",
+                Style::Decision,
+                "Should this lint be warn-by-default?"
+            ),
+            "[rust-lang/rust#1234](https://github.com/rust-lang/rust/pull/1234) \"Example PR\" \
+            was nominated for discussion by T-lang.\n\n\
+            Should this lint be warn-by-default?"
+        );
+    }
+}
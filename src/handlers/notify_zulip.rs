@@ -144,13 +144,8 @@ pub(super) async fn handle_input<'a>(
 
         let topic = &config.topic;
         let topic = topic.replace("{number}", &event.issue.number.to_string());
-        let mut topic = topic.replace("{title}", &event.issue.title);
-        // Truncate to 60 chars (a Zulip limitation)
-        let mut chars = topic.char_indices().skip(59);
-        if let (Some((len, _)), Some(_)) = (chars.next(), chars.next()) {
-            topic.truncate(len);
-            topic.push('…');
-        }
+        let topic = topic.replace("{title}", &event.issue.title);
+        let topic = crate::zulip::truncate_topic(topic);
 
         let msgs = match input.notification_type {
             NotificationType::Labeled => &config.messages_on_add,
@@ -207,38 +202,23 @@ fn test_notification() {
     assert!(msg.contains("Needs `I-{team}-nominated`?"), "{}", msg);
 
     msg = replace_team_to_be_nominated(
-        &[Label {
-            name: "T-cooks".to_string(),
-        }],
+        &[Label::new("T-cooks")],
         "Needs `I-{team}-nominated`?".to_string(),
     );
     assert!(msg.contains("I-cooks-nominated"), "{}", msg);
 
     msg = replace_team_to_be_nominated(
         &[
-            Label {
-                name: "T-compiler".to_string(),
-            },
-            Label {
-                name: "T-libs".to_string(),
-            },
-            Label {
-                name: "T-cooks".to_string(),
-            },
+            Label::new("T-compiler"),
+            Label::new("T-libs"),
+            Label::new("T-cooks"),
         ],
         "Needs `I-{team}-nominated`?".to_string(),
     );
     assert!(msg.contains("I-compiler-nominated"), "{}", msg);
 
     msg = replace_team_to_be_nominated(
-        &[
-            Label {
-                name: "T-libs".to_string(),
-            },
-            Label {
-                name: "T-cooks".to_string(),
-            },
-        ],
+        &[Label::new("T-libs"), Label::new("T-cooks")],
         "Needs `I-{team}-nominated`?".to_string(),
     );
     assert!(msg.contains("Needs `I-{team}-nominated`?"), "{}", msg);
@@ -69,7 +69,7 @@ pub(super) async fn handle_command(
         event
             .issue()
             .unwrap()
-            .add_labels(&ctx.github, vec![github::Label { name: label }])
+            .add_labels(&ctx.github, vec![github::Label::new(label)])
             .await?;
     }
 
@@ -5,12 +5,14 @@
 //!
 //! - Adds the PR to the workqueue of one team member (after the PR has been assigned)
 //! - Removes the PR from the workqueue of one team member (after the PR has been unassigned or closed)
+//! - Sends a Zulip direct message to the new assignee, if they opted in with `work notify on`
 
 use crate::{
     config::ReviewPrefsConfig,
-    db::notifications::record_username,
-    github::{IssuesAction, IssuesEvent},
+    db::{assign_notifications::wants_assign_notifications, notifications::record_username},
+    github::{Issue, IssuesAction, IssuesEvent},
     handlers::Context,
+    zulip::{get_members, lookup_zulip_id, MessageApiRequest, Recipient},
     ReviewPrefs,
 };
 use anyhow::Context as _;
@@ -107,11 +109,61 @@ pub(super) async fn handle_input<'a>(
         upsert_pr_into_workqueue(&db_client, assignee.id, event.issue.number)
             .await
             .context("Failed to add PR to work queue")?;
+
+        if wants_assign_notifications(&db_client, assignee.id).await? {
+            if let Err(e) = notify_assignee(ctx, &event.issue, assignee.id).await {
+                log::warn!(
+                    "[#{}] failed to notify {} on Zulip: {e:?}",
+                    event.issue.number,
+                    &assignee.login
+                );
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Sends a direct message on Zulip to the new assignee of the PR.
+async fn notify_assignee(ctx: &Context, issue: &Issue, assignee_id: u64) -> anyhow::Result<()> {
+    if crate::jobs::nudges_paused() {
+        log::info!(
+            "not notifying the assignee of {}: the nudges are paused",
+            issue.global_id()
+        );
+        return Ok(());
+    }
+    let Some(zulip_id) = lookup_zulip_id(ctx, assignee_id).await? else {
+        return Ok(());
+    };
+    let members = get_members(ctx.github.raw()).await?;
+    let Some(member) = members.iter().find(|m| m.user_id == zulip_id) else {
+        return Ok(());
+    };
+    MessageApiRequest {
+        recipient: Recipient::Private {
+            id: member.user_id,
+            email: &member.email,
+        },
+        content: &assignment_message(issue),
+    }
+    .send(ctx.github.raw())
+    .await?
+    .error_for_status()?;
+    Ok(())
+}
+
+fn assignment_message(issue: &Issue) -> String {
+    format!(
+        "You have been assigned to [{}#{}]({}): {}\n\n\
+        You can stop these messages with `work notify off`.",
+        issue.repository(),
+        issue.number,
+        issue.html_url,
+        issue.title
+    )
+}
+
 // Check user review capacity.
 // Returns error if SQL query fails or user has no capacity
 pub async fn has_user_capacity(
@@ -164,3 +216,19 @@ WHERE r.user_id = $1;";
         .await
         .context("Update DB error")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::test_pr;
+
+    #[test]
+    fn assignment_notification() {
+        assert_eq!(
+            assignment_message(&test_pr()),
+            "You have been assigned to \
+            [rust-lang/rust#1234](https://github.com/rust-lang/rust/pull/1234): Example PR\n\n\
+            You can stop these messages with `work notify off`."
+        );
+    }
+}
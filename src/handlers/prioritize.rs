@@ -12,9 +12,7 @@ pub(super) async fn handle_command(
     _: PrioritizeCommand,
 ) -> anyhow::Result<()> {
     let mut labels = vec![];
-    labels.push(github::Label {
-        name: config.label.to_owned(),
-    });
+    labels.push(github::Label::new(config.label.to_owned()));
     event
         .issue()
         .unwrap()
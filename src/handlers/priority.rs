@@ -0,0 +1,279 @@
+//! Allows team members to set the priority of an issue.
+//!
+//! Each priority level maps to a label, and setting a level removes the labels
+//! of the other levels from the issue.
+//!
+//! Parsing is done in the `parser::command::priority` module, configuration is
+//! done with the `[priority]` table.
+
+use crate::{
+    config::PriorityConfig,
+    github::{Event, GithubClient, Issue, Label},
+    handlers::Context,
+    interactions::ErrorComment,
+};
+use parser::command::priority::{PriorityCommand, PriorityLevel};
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &PriorityConfig,
+    event: &Event,
+    cmd: PriorityCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    let is_team_member = event
+        .user()
+        .is_team_member(&ctx.github)
+        .await
+        .unwrap_or(false);
+    if !is_team_member {
+        let cmnt = ErrorComment::new(&issue, "Only team members can set the priority.");
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+
+    set_priority_labels(&ctx.github, issue, config, cmd.0).await
+}
+
+fn priority_label(config: &PriorityConfig, level: PriorityLevel) -> &str {
+    match level {
+        PriorityLevel::Critical => &config.critical,
+        PriorityLevel::High => &config.high,
+        PriorityLevel::Medium => &config.medium,
+        PriorityLevel::Low => &config.low,
+    }
+}
+
+/// Adds the label of `level`, removing the labels of the other levels that
+/// are on the issue.
+async fn set_priority_labels(
+    client: &GithubClient,
+    issue: &Issue,
+    config: &PriorityConfig,
+    level: PriorityLevel,
+) -> anyhow::Result<()> {
+    let add = priority_label(config, level);
+    for other in PriorityLevel::ALL {
+        let remove = priority_label(config, other);
+        // The labels missing from the issue are skipped by `remove_label`.
+        if remove != add {
+            issue.remove_label(client, remove).await?;
+        }
+    }
+    if issue.labels().iter().any(|l| l.name == add) {
+        return Ok(());
+    }
+    issue.add_labels(client, vec![Label::new(add)]).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, comment_json, expect, test_pr_json, TestServer};
+
+    fn config() -> PriorityConfig {
+        toml::from_str("").unwrap()
+    }
+
+    fn issue_with_labels(labels: &[&str]) -> Issue {
+        let mut issue = test_pr_json();
+        issue["labels"] = labels
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        serde_json::from_value(issue).unwrap()
+    }
+
+    #[tokio::test]
+    async fn set_priority() {
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!({ "name": "P-high" }).to_string(),
+            ),
+            ("200 OK", "[]".to_string()),
+        ]);
+        set_priority_labels(
+            &server.github_client(),
+            &issue_with_labels(&["T-compiler"]),
+            &config(),
+            PriorityLevel::High,
+        )
+        .await
+        .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust/labels/P-high"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({ "labels": ["P-high"] })),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn change_priority() {
+        let server = TestServer::start(vec![
+            ("204 No Content", String::new()),
+            ("204 No Content", String::new()),
+            (
+                "200 OK",
+                serde_json::json!({ "name": "P-critical" }).to_string(),
+            ),
+            ("200 OK", "[]".to_string()),
+        ]);
+        set_priority_labels(
+            &server.github_client(),
+            &issue_with_labels(&["P-medium", "T-compiler", "P-low"]),
+            &config(),
+            PriorityLevel::Critical,
+        )
+        .await
+        .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect(
+                    "DELETE",
+                    "/repos/rust-lang/rust/issues/1234/labels/P-medium",
+                ),
+                expect("DELETE", "/repos/rust-lang/rust/issues/1234/labels/P-low"),
+                expect("GET", "/repos/rust-lang/rust/labels/P-critical"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({ "labels": ["P-critical"] })),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn priority_already_set() {
+        let server = TestServer::start(vec![]);
+        set_priority_labels(
+            &server.github_client(),
+            &issue_with_labels(&["P-low"]),
+            &config(),
+            PriorityLevel::Low,
+        )
+        .await
+        .unwrap();
+        assert!(server.finish().is_empty());
+    }
+
+    #[tokio::test]
+    async fn configured_labels() {
+        let server = TestServer::start(vec![
+            ("204 No Content", String::new()),
+            (
+                "200 OK",
+                serde_json::json!({ "name": "I-prioritize-high" }).to_string(),
+            ),
+            ("200 OK", "[]".to_string()),
+        ]);
+        let config: PriorityConfig = toml::from_str(
+            r#"
+            high = "I-prioritize-high"
+            low = "I-prioritize-low"
+            "#,
+        )
+        .unwrap();
+        set_priority_labels(
+            &server.github_client(),
+            &issue_with_labels(&["I-prioritize-low", "P-low"]),
+            &config,
+            PriorityLevel::High,
+        )
+        .await
+        .unwrap();
+        let paths: Vec<_> = server.finish().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/repos/rust-lang/rust/issues/1234/labels/I-prioritize-low",
+                "/repos/rust-lang/rust/labels/I-prioritize-high",
+                "/repos/rust-lang/rust/issues/1234/labels",
+            ]
+        );
+    }
+
+    /// A `@rustbot priority P-high` comment by `login`.
+    fn priority_event(login: &str) -> Event {
+        Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": test_pr_json(),
+                "comment": {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAABBB",
+                    "body": "@rustbot priority P-high",
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": login, "id": 1},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn team_member_sets_the_priority() {
+        crate::team_data::use_test_teams();
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!({ "name": "P-high" }).to_string(),
+            ),
+            ("200 OK", "[]".to_string()),
+        ]);
+        let cmd = PriorityCommand(PriorityLevel::High);
+        handle_command(
+            &server.context(),
+            &config(),
+            &priority_event("team-member"),
+            cmd,
+        )
+        .await
+        .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust/labels/P-high"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({ "labels": ["P-high"] })),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn non_member_gets_an_error() {
+        crate::team_data::use_test_teams();
+        let server = TestServer::start(vec![
+            ("200 OK", "[]".to_string()),
+            ("201 Created", comment_json(2, "")),
+        ]);
+        let cmd = PriorityCommand(PriorityLevel::High);
+        handle_command(
+            &server.context(),
+            &config(),
+            &priority_event("octocat"),
+            cmd,
+        )
+        .await
+        .unwrap();
+        let requests = server.finish();
+        // Only the error comment, no label change.
+        assert_requests(
+            &requests,
+            &[
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/issues/1234/comments?page=1&per_page=100",
+                ),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments"),
+            ],
+        );
+        assert!(requests[1]
+            .body
+            .contains("Only team members can set the priority."));
+    }
+}
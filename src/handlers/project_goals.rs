@@ -38,6 +38,10 @@ impl Job for ProjectGoalsUpdateJob {
         "project_goals_update_job"
     }
 
+    fn is_nudge(&self) -> bool {
+        true
+    }
+
     async fn run(&self, ctx: &super::Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
         ping_project_goals_owners_automatically(&ctx.github).await
     }
@@ -17,41 +17,86 @@ impl Job for PullRequestAssignmentUpdate {
     }
 
     async fn run(&self, ctx: &super::Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
-        let db = ctx.db.get().await;
-        let gh = &ctx.github;
-
         tracing::trace!("starting pull_request_assignment_update");
+        let changed = sync_assignments(ctx).await?;
+        tracing::info!("pull_request_assignment_update: {changed} work queue(s) reconciled");
+        Ok(())
+    }
+}
 
-        let rust_repo = gh.repository("rust-lang/rust").await?;
-        let prs = retrieve_pull_requests(&rust_repo, &gh).await?;
+/// Reconciles the PR work queues in `review_prefs` with the current
+/// assignees of the open PRs on GitHub, correcting any drift (e.g. from
+/// missed webhooks).
+///
+/// This only touches rows that differ, so running it repeatedly is harmless.
+/// Returns the number of work queues that were changed.
+pub async fn sync_assignments(ctx: &super::Context) -> anyhow::Result<usize> {
+    let db = ctx.db.get().await;
+    let gh = &ctx.github;
 
-        // delete all PR assignments before populating
-        init_table(&db).await?;
+    let rust_repo = gh.repository("rust-lang/rust").await?;
+    let prs = retrieve_pull_requests(&rust_repo, &gh).await?;
 
-        // aggregate by user first
-        let aggregated = prs.into_iter().fold(HashMap::new(), |mut acc, (user, pr)| {
-            let (_, prs) = acc.entry(user.id).or_insert_with(|| (user, Vec::new()));
-            prs.push(pr);
-            acc
-        });
-
-        // populate the table
-        for (_user_id, (assignee, prs)) in &aggregated {
-            let assignee_id = assignee.id;
-            let _ = record_username(&db, assignee_id, &assignee.login).await;
-            create_team_member_workqueue(&db, assignee_id, &prs).await?;
-        }
+    // aggregate by user first
+    let mut desired: HashMap<u64, Vec<i32>> = HashMap::new();
+    let mut usernames = HashMap::new();
+    for (user, pr) in prs {
+        desired.entry(user.id).or_default().push(pr);
+        usernames.insert(user.id, user.login);
+    }
+    for (user_id, username) in &usernames {
+        let _ = record_username(&db, *user_id, username).await;
+    }
 
-        Ok(())
+    let current = current_work_queues(&db).await?;
+    let changes = work_queue_changes(&current, desired);
+    for (user_id, prs) in &changes {
+        create_team_member_workqueue(&db, *user_id, prs).await?;
     }
+    Ok(changes.len())
 }
 
-/// Truncate the review prefs table
-async fn init_table(db: &DbClient) -> anyhow::Result<u64> {
-    let res = db
-        .execute("UPDATE review_prefs SET assigned_prs='{}';", &[])
-        .await?;
-    Ok(res)
+/// Returns the assigned PRs of every user in the `review_prefs` table.
+async fn current_work_queues(db: &DbClient) -> anyhow::Result<HashMap<u64, Vec<i32>>> {
+    let rows = db
+        .query("SELECT user_id, assigned_prs FROM review_prefs", &[])
+        .await
+        .context("Error retrieving work queues")?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let user_id: i64 = row.get(0);
+            (user_id as u64, row.get(1))
+        })
+        .collect())
+}
+
+/// Computes the work queues that need to be written for the DB to match
+/// `desired`.
+///
+/// Users missing from `desired` get their work queue emptied. The order of
+/// PRs within a work queue is not significant.
+fn work_queue_changes(
+    current: &HashMap<u64, Vec<i32>>,
+    mut desired: HashMap<u64, Vec<i32>>,
+) -> Vec<(u64, Vec<i32>)> {
+    for (user_id, prs) in current {
+        if !prs.is_empty() {
+            desired.entry(*user_id).or_default();
+        }
+    }
+    let mut changes: Vec<_> = desired
+        .into_iter()
+        .filter_map(|(user_id, mut prs)| {
+            prs.sort();
+            prs.dedup();
+            let mut existing = current.get(&user_id).cloned().unwrap_or_default();
+            existing.sort();
+            (existing != prs).then_some((user_id, prs))
+        })
+        .collect();
+    changes.sort();
+    changes
 }
 
 /// Create a team member work queue
@@ -84,3 +129,182 @@ WHERE r.user_id = $1;";
         .unwrap();
     Ok(row.into())
 }
+
+/// Get the pull request assignments of every team member, by username.
+pub async fn get_all_review_prefs(db: &DbClient) -> anyhow::Result<Vec<ReviewPrefs>> {
+    let q = "
+SELECT username,r.*
+FROM review_prefs r
+JOIN users on r.user_id=users.user_id
+ORDER BY username;";
+    let rows = db
+        .query(q, &[])
+        .await
+        .context("Error retrieving review preferences")?;
+    Ok(rows.into_iter().map(|row| row.into()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{expect, TestServer};
+
+    #[test]
+    fn work_queue_reconciliation() {
+        let current = HashMap::from([
+            // In sync, but in a different order.
+            (1, vec![20, 10]),
+            // Missed an assignment.
+            (2, vec![30]),
+            // Missed an unassignment of every PR.
+            (3, vec![40]),
+            // Already empty and not assigned anything.
+            (4, vec![]),
+        ]);
+        let desired = HashMap::from([
+            (1, vec![10, 20]),
+            (2, vec![50, 30]),
+            // Not in the DB yet.
+            (5, vec![60]),
+        ]);
+        let changes = work_queue_changes(&current, desired.clone());
+        assert_eq!(changes, [(2, vec![30, 50]), (3, vec![]), (5, vec![60])]);
+
+        // Applying the changes brings the DB in sync.
+        let mut current = current;
+        current.extend(changes);
+        assert!(work_queue_changes(&current, desired).is_empty());
+    }
+
+    #[tokio::test]
+    async fn all_review_prefs_as_json() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        record_username(&db, 2, "bob").await.unwrap();
+        record_username(&db, 1, "alice").await.unwrap();
+        // Without a work queue, so not listed.
+        record_username(&db, 3, "carol").await.unwrap();
+        create_team_member_workqueue(&db, 2, &vec![10, 20])
+            .await
+            .unwrap();
+        create_team_member_workqueue(&db, 1, &vec![]).await.unwrap();
+        db.execute(
+            "UPDATE review_prefs SET max_assigned_prs = 5 WHERE user_id = 2",
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let prefs = get_all_review_prefs(&db).await.unwrap();
+        let mut json = serde_json::to_value(&prefs).unwrap();
+        // The ids are random.
+        for prefs in json.as_array_mut().unwrap() {
+            prefs.as_object_mut().unwrap().remove("id");
+        }
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"username": "alice", "user_id": 1, "assigned_prs": [], "max_assigned_prs": null},
+                {"username": "bob", "user_id": 2, "assigned_prs": [10, 20], "max_assigned_prs": 5},
+            ])
+        );
+    }
+
+    /// An open PR of the `pullRequests` GraphQL connection.
+    fn open_pr(
+        number: i32,
+        assignees: &[(&str, i32)],
+        labels: &[&str],
+        draft: bool,
+    ) -> serde_json::Value {
+        let assignees: Vec<_> = assignees
+            .iter()
+            .map(|(login, id)| serde_json::json!({"login": login, "databaseId": id}))
+            .collect();
+        let labels: Vec<_> = labels
+            .iter()
+            .map(|name| serde_json::json!({"name": name}))
+            .collect();
+        serde_json::json!({
+            "number": number,
+            "author": {"login": "octocat"},
+            "createdAt": "2024-05-16T14:00:00Z",
+            "url": format!("https://github.com/rust-lang/rust/pull/{number}"),
+            "title": "Example PR",
+            "labels": {"nodes": labels},
+            "isDraft": draft,
+            "assignees": {"nodes": assignees},
+            "comments": {"totalCount": 0, "nodes": []},
+            "latestReviews": {"totalCount": 0, "nodes": []},
+        })
+    }
+
+    #[tokio::test]
+    async fn sync_with_the_open_prs() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        record_username(&db, 1, "alice").await.unwrap();
+        record_username(&db, 3, "carol").await.unwrap();
+        // A missed assignment, and a missed unassignment.
+        create_team_member_workqueue(&db, 1, &vec![10])
+            .await
+            .unwrap();
+        create_team_member_workqueue(&db, 3, &vec![40])
+            .await
+            .unwrap();
+
+        let prs = serde_json::json!({
+            "data": {
+                "repository": {
+                    "pullRequests": {
+                        "totalCount": 4,
+                        "pageInfo": {"hasNextPage": false, "endCursor": null},
+                        "nodes": [
+                            open_pr(10, &[("alice", 1)], &[], false),
+                            open_pr(20, &[("alice", 1), ("bob", 2)], &[], false),
+                            // Neither drafts nor rollups are counted.
+                            open_pr(30, &[("bob", 2)], &[], true),
+                            open_pr(50, &[("bob", 2)], &["rollup"], false),
+                        ],
+                    },
+                },
+            },
+        });
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!({"full_name": "rust-lang/rust", "default_branch": "master"})
+                    .to_string(),
+            ),
+            ("200 OK", prs.to_string()),
+        ]);
+        let mut ctx = server.context();
+        ctx.db = crate::db::ClientPool::with_client(db);
+        assert_eq!(sync_assignments(&ctx).await.unwrap(), 3);
+        crate::test_server::assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust"),
+                expect("POST", "/graphql"),
+            ],
+        );
+
+        let db = ctx.db.get().await;
+        let queues: HashMap<_, _> = get_all_review_prefs(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|prefs| (prefs.username, prefs.assigned_prs))
+            .collect();
+        assert_eq!(
+            queues,
+            HashMap::from([
+                ("alice".to_string(), vec![10, 20]),
+                ("bob".to_string(), vec![20]),
+                ("carol".to_string(), vec![]),
+            ])
+        );
+    }
+}
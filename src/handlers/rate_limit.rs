@@ -0,0 +1,124 @@
+//! Per-user rate limiting of commands.
+//!
+//! Every user gets a token bucket per repository and command kind, so that a
+//! single user spamming a command can't cause excessive API calls. The
+//! buckets are kept in memory, so they are reset when triagebot restarts.
+//!
+//! Configuration is done with the `[command-rate-limit]` table.
+
+use crate::config::CommandRateLimitConfig;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Above this number of buckets, the full ones are dropped.
+const MAX_BUCKETS: usize = 1000;
+
+lazy_static::lazy_static! {
+    static ref RATE_LIMITER: Mutex<RateLimiter> = Mutex::new(RateLimiter::default());
+}
+
+/// Takes a token for a command run by `user` in `repo`.
+///
+/// Returns how long the user has to wait if the command is throttled.
+pub(super) fn check(
+    config: &CommandRateLimitConfig,
+    repo: &str,
+    user: &str,
+    command: &str,
+) -> Result<(), Duration> {
+    RATE_LIMITER
+        .lock()
+        .unwrap()
+        .take(config, repo, user, command, Instant::now())
+}
+
+/// Returns the message explaining that a command was throttled.
+pub(super) fn cooldown_message(command: &str, wait: Duration) -> String {
+    format!(
+        "You are running `{command}` commands too quickly, \
+        please wait {} seconds before trying again.",
+        wait.as_secs().max(1)
+    )
+}
+
+/// A token bucket implemented as a generic cell rate algorithm: instead of
+/// counting tokens, each bucket stores when it will be full again.
+#[derive(Default)]
+struct RateLimiter {
+    /// (repository, user, command) -> when the bucket is full again
+    buckets: HashMap<(String, String, String), Instant>,
+}
+
+impl RateLimiter {
+    fn take(
+        &mut self,
+        config: &CommandRateLimitConfig,
+        repo: &str,
+        user: &str,
+        command: &str,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let refill = Duration::from_secs(config.refill_seconds);
+        // How far in the future the bucket may be full again for a token
+        // to still be available.
+        let tolerance = refill * config.burst.saturating_sub(1);
+
+        if self.buckets.len() > MAX_BUCKETS {
+            self.buckets.retain(|_, full_at| *full_at > now);
+        }
+
+        let full_at = self
+            .buckets
+            .entry((repo.to_string(), user.to_string(), command.to_string()))
+            .or_insert(now);
+        let pending = full_at.saturating_duration_since(now);
+        if pending > tolerance {
+            return Err(pending.saturating_sub(tolerance));
+        }
+        *full_at = now.max(*full_at) + refill;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nth_rapid_command_is_throttled() {
+        let config = CommandRateLimitConfig {
+            burst: 3,
+            refill_seconds: 60,
+        };
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+        let take = |limiter: &mut RateLimiter, user, command, secs| {
+            limiter.take(
+                &config,
+                "rust-lang/rust",
+                user,
+                command,
+                start + Duration::from_secs(secs),
+            )
+        };
+
+        for secs in 0..3 {
+            assert_eq!(take(&mut limiter, "spammer", "ping", secs), Ok(()));
+        }
+        // The bucket is empty, and 3 seconds of the refill already passed.
+        assert_eq!(
+            take(&mut limiter, "spammer", "ping", 3),
+            Err(Duration::from_secs(57))
+        );
+        // Other users and other commands are not affected.
+        assert_eq!(take(&mut limiter, "octocat", "ping", 3), Ok(()));
+        assert_eq!(take(&mut limiter, "spammer", "relabel", 3), Ok(()));
+        // A token is available again after the refill period.
+        assert_eq!(take(&mut limiter, "spammer", "ping", 60), Ok(()));
+        assert_eq!(
+            take(&mut limiter, "spammer", "ping", 61),
+            Err(Duration::from_secs(59))
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! A scheduled job that re-derives state the bot maintains from GitHub and
+//! fixes inconsistencies, covering windows where webhooks were dropped.
+//!
+//! The job metadata selects which repositories are watched and what is
+//! reconciled:
+//!
+//! ```json
+//! { "repos": ["rust-lang/rust"], "scope": ["state-labels", "work-queues"] }
+//! ```
+//!
+//! The repositories must also opt in to each per-repository scope with their
+//! `[reconcile]` config:
+//!
+//! ```toml
+//! [reconcile]
+//! state-labels = true
+//! reaction-claims = true
+//! ```
+//!
+//! * `state-labels`: open PRs that carry none of the state labels (the `S-*`
+//!   labels, the `new_pr` labels of `[autolabel]` and the labels of
+//!   `[review-submitted]`) get the `new_pr` labels they missed when opened.
+//! * `work-queues`: the review work queues are synced with the current PR
+//!   assignees (see [`sync_assignments`]).
+//! * `reaction-claims`: unassigned PRs are assigned to the first eligible
+//!   reviewer who reacted with :eyes: to the bot comment saying that no
+//!   reviewer was found (see [`first_eligible_reactor`]).
+
+use crate::config::{AutolabelLabelConfig, Config};
+use crate::db::assignment_history::{record_assignment, AssignmentReason};
+use crate::github::{Issue, Label, Query, Repository};
+use crate::handlers::assign::{first_eligible_reactor, set_assignee, NEEDS_REVIEWER_COMMENT};
+use crate::handlers::pull_requests_assignment_update::sync_assignments;
+use crate::jobs::Job;
+use anyhow::Context as _;
+use async_trait::async_trait;
+use tracing as log;
+
+pub struct ReconcileJob;
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReconcileScope {
+    StateLabels,
+    WorkQueues,
+    ReactionClaims,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ReconcileMetadata {
+    pub repos: Vec<String>,
+    pub scope: Vec<ReconcileScope>,
+}
+
+#[async_trait]
+impl Job for ReconcileJob {
+    fn name(&self) -> &'static str {
+        "reconcile"
+    }
+
+    async fn run(&self, ctx: &super::Context, metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let metadata: ReconcileMetadata =
+            serde_json::from_value(metadata.clone()).context("invalid reconcile job metadata")?;
+
+        for repo in &metadata.repos {
+            if let Err(e) = reconcile_repo(ctx, repo, &metadata.scope).await {
+                log::error!("failed to reconcile {repo}: {e:?}");
+            }
+        }
+        if metadata.scope.contains(&ReconcileScope::WorkQueues) {
+            let changed = sync_assignments(ctx).await?;
+            log::info!("reconcile: {changed} work queue(s) reconciled");
+        }
+        Ok(())
+    }
+}
+
+/// Runs the per-repository scopes the repository opted in to.
+async fn reconcile_repo(
+    ctx: &super::Context,
+    repo: &str,
+    scope: &[ReconcileScope],
+) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo).await?;
+    let config = crate::config::get(&ctx.github, &repo)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to load config of {}: {e}", repo.full_name))?;
+    let Some(reconcile) = &config.reconcile else {
+        return Ok(());
+    };
+
+    if reconcile.state_labels && scope.contains(&ReconcileScope::StateLabels) {
+        if let Err(e) = reconcile_state_labels(ctx, &repo, &config).await {
+            log::error!(
+                "failed to reconcile state labels of {}: {e:?}",
+                repo.full_name
+            );
+        }
+    }
+    if reconcile.reaction_claims && scope.contains(&ReconcileScope::ReactionClaims) {
+        if let Err(e) = assign_reaction_claims(ctx, &repo, &config).await {
+            log::error!(
+                "failed to assign the reaction claims of {}: {e:?}",
+                repo.full_name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Adds the missing review state labels to the open PRs of `repo`.
+async fn reconcile_state_labels(
+    ctx: &super::Context,
+    repo: &Repository,
+    config: &Config,
+) -> anyhow::Result<()> {
+    if new_pr_labels(config).next().is_none() {
+        return Ok(());
+    }
+
+    // The `S-*` labels can't be excluded from the search, they are filtered
+    // by `missing_state_labels` instead.
+    let configured = configured_state_labels(config);
+    let prs = repo
+        .get_issues(
+            &ctx.github,
+            &Query {
+                filters: vec![("state", "open"), ("is", "pull-request")],
+                include_labels: vec![],
+                exclude_labels: configured.iter().map(|l| l.as_str()).collect(),
+            },
+        )
+        .await?;
+    for pr in prs {
+        let missing = missing_state_labels(config, &pr);
+        if missing.is_empty() {
+            continue;
+        }
+        log::info!(
+            "reconcile: adding {:?} to {}",
+            missing.iter().map(|l| &l.name).collect::<Vec<_>>(),
+            pr.global_id()
+        );
+        pr.add_labels(&ctx.github, missing).await?;
+    }
+    Ok(())
+}
+
+/// Assigns the unassigned PRs of `repo` that a reviewer claimed with a
+/// reaction.
+async fn assign_reaction_claims(
+    ctx: &super::Context,
+    repo: &Repository,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let Some(assign_config) = &config.assign else {
+        return Ok(());
+    };
+    let teams = crate::team_data::teams(&ctx.github).await?;
+
+    let prs = repo
+        .get_issues(
+            &ctx.github,
+            &Query {
+                filters: vec![
+                    ("state", "open"),
+                    ("is", "pull-request"),
+                    ("no", "assignee"),
+                ],
+                include_labels: vec![],
+                exclude_labels: vec![],
+            },
+        )
+        .await?;
+    for pr in prs {
+        let Some(comment) = pr
+            .find_bot_comment(&ctx.github, NEEDS_REVIEWER_COMMENT)
+            .await?
+        else {
+            continue;
+        };
+        let reactions = pr.list_reactions(&ctx.github, Some(comment.id)).await?;
+        let Some(reviewer) = first_eligible_reactor(&teams, assign_config, &pr, &reactions) else {
+            continue;
+        };
+        log::info!(
+            "reconcile: assigning {} to {reviewer}, who claimed it with a reaction",
+            pr.global_id()
+        );
+        set_assignee(&pr, &ctx.github, reviewer).await;
+        let db_client = ctx.db.get().await;
+        if let Err(e) = record_assignment(
+            &db_client,
+            &pr,
+            Some(reviewer),
+            &[reviewer.to_string()],
+            AssignmentReason::ReactionClaim,
+        )
+        .await
+        {
+            log::warn!(
+                "failed to record the assignment of {}: {e:?}",
+                pr.global_id()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns the `new_pr` labels of `[autolabel]`.
+fn new_pr_labels(config: &Config) -> impl Iterator<Item = (&String, &AutolabelLabelConfig)> {
+    config
+        .autolabel
+        .iter()
+        .flat_map(|autolabel| autolabel.labels.iter())
+        .filter(|(_, cfg)| cfg.new_pr)
+}
+
+/// Returns the configured labels that mark the state of a PR, on top of the
+/// `S-*` labels.
+fn configured_state_labels(config: &Config) -> Vec<String> {
+    let mut labels: Vec<_> = new_pr_labels(config)
+        .map(|(label, _)| label.clone())
+        .collect();
+    if let Some(review_submitted) = &config.review_submitted {
+        labels.extend(review_submitted.review_labels.iter().cloned());
+        labels.push(review_submitted.reviewed_label.clone());
+    }
+    labels.sort();
+    labels.dedup();
+    labels
+}
+
+/// Returns whether `label` marks the state of a PR, like
+/// `S-waiting-on-review` or `S-blocked`.
+fn is_state_label(configured: &[String], label: &str) -> bool {
+    label.starts_with("S-") || configured.iter().any(|l| l == label)
+}
+
+/// Returns the `new_pr` labels the PR should have been given when opened,
+/// if it carries none of the state labels.
+fn missing_state_labels(config: &Config, pr: &Issue) -> Vec<Label> {
+    let configured = configured_state_labels(config);
+    if pr
+        .labels()
+        .iter()
+        .any(|l| is_state_label(&configured, &l.name))
+    {
+        return Vec::new();
+    }
+    let mut missing: Vec<_> = new_pr_labels(config)
+        .filter(|(_, cfg)| {
+            // Respect the exclusions, like the autolabel handler does.
+            !cfg.exclude_labels.iter().any(|exclude| {
+                glob::Pattern::new(exclude)
+                    .map(|pat| pr.labels().iter().any(|l| pat.matches(&l.name)))
+                    .unwrap_or(false)
+            })
+        })
+        .map(|(label, _)| Label::new(label.clone()))
+        .collect();
+    missing.sort_by(|a, b| a.name.cmp(&b.name));
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [autolabel."S-waiting-on-review"]
+            new_pr = true
+            exclude_labels = ["rollup"]
+
+            [autolabel."T-compiler"]
+            trigger_files = ["compiler"]
+
+            [review-submitted]
+            review_labels = ["S-waiting-on-review"]
+            reviewed_label = "S-waiting-on-author"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn pr(labels: &[&str]) -> Issue {
+        serde_json::from_value(serde_json::json!({
+            "number": 1234,
+            "created_at": "2022-06-26T21:31:31Z",
+            "updated_at": "2022-06-26T21:31:31Z",
+            "title": "Example PR",
+            "body": "PR body",
+            "html_url": "https://github.com/rust-lang/rust/pull/1234",
+            "user": {"login": "octocat", "id": 583231},
+            "labels": labels.iter().map(|name| serde_json::json!({"name": name})).collect::<Vec<_>>(),
+            "assignees": [],
+            "comments_url": "https://api.github.com/repos/rust-lang/rust/pull/1234/comments",
+            "state": "open",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn missing_review_state_label_is_restored() {
+        let config = config();
+        assert_eq!(
+            configured_state_labels(&config),
+            ["S-waiting-on-author", "S-waiting-on-review"]
+        );
+        // The `opened` webhook was dropped, so the PR never got its state label.
+        assert_eq!(
+            missing_state_labels(&config, &pr(&["T-compiler"])),
+            [Label::new("S-waiting-on-review")]
+        );
+        // Already in a review state.
+        assert!(missing_state_labels(&config, &pr(&["S-waiting-on-author"])).is_empty());
+        // Excluded from the autolabel.
+        assert!(missing_state_labels(&config, &pr(&["rollup"])).is_empty());
+    }
+
+    #[test]
+    fn other_state_labels_are_kept() {
+        let config = config();
+        for state in ["S-waiting-on-bors", "S-blocked", "S-waiting-on-team"] {
+            assert!(
+                missing_state_labels(&config, &pr(&["T-compiler", state])).is_empty(),
+                "{state} is a state label"
+            );
+        }
+    }
+}
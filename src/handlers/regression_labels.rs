@@ -0,0 +1,165 @@
+//! Labels new issues reporting a regression, like "this used to work in
+//! 1.70, but fails in 1.75", with the matching `regression-from-stable-to-*`
+//! label.
+//!
+//! Configuration is done with the `[regression-labels]` table, whose
+//! `patterns` are regexes with a `good` and a `bad` capture group for the
+//! last working and the first broken versions.
+
+use crate::{
+    config::RegressionLabelsConfig,
+    github::{IssuesAction, IssuesEvent, Label},
+    handlers::Context,
+};
+
+pub(crate) struct RegressionLabelsInput {
+    label: &'static str,
+}
+
+pub(crate) async fn parse_input(
+    _ctx: &Context,
+    event: &IssuesEvent,
+    config: Option<&RegressionLabelsConfig>,
+) -> Result<Option<RegressionLabelsInput>, String> {
+    let Some(config) = config else {
+        return Ok(None);
+    };
+    if event.action != IssuesAction::Opened || event.issue.is_pr() {
+        return Ok(None);
+    }
+    if event
+        .issue
+        .labels()
+        .iter()
+        .any(|l| l.name.starts_with("regression-"))
+    {
+        return Ok(None);
+    }
+    let text = format!("{}\n{}", event.issue.title, event.issue.body);
+    Ok(regression_label(config, &text).map(|label| RegressionLabelsInput { label }))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Channel {
+    Stable,
+    Beta,
+    Nightly,
+}
+
+/// The release channel of a version mentioned in an issue, like `1.75.0`,
+/// `beta` or `nightly-2024-05-16`.
+fn channel(version: &str) -> Option<Channel> {
+    let version = version.to_ascii_lowercase();
+    if version.contains("nightly") {
+        Some(Channel::Nightly)
+    } else if version.contains("beta") {
+        Some(Channel::Beta)
+    } else if version == "stable"
+        || version
+            .strip_prefix("1.")
+            .is_some_and(|minor| minor.starts_with(|c: char| c.is_ascii_digit()))
+    {
+        Some(Channel::Stable)
+    } else {
+        None
+    }
+}
+
+/// Returns the regression label for the first pattern matching `text`.
+fn regression_label(config: &RegressionLabelsConfig, text: &str) -> Option<&'static str> {
+    for pattern in &config.patterns {
+        let Some(captures) = pattern.captures(text) else {
+            continue;
+        };
+        let (Some(good), Some(bad)) = (captures.name("good"), captures.name("bad")) else {
+            continue;
+        };
+        if channel(good.as_str()) != Some(Channel::Stable) {
+            continue;
+        }
+        match channel(bad.as_str()) {
+            Some(Channel::Stable) => return Some("regression-from-stable-to-stable"),
+            Some(Channel::Beta) => return Some("regression-from-stable-to-beta"),
+            Some(Channel::Nightly) => return Some("regression-from-stable-to-nightly"),
+            None => {}
+        }
+    }
+    None
+}
+
+pub(crate) async fn handle_input(
+    ctx: &Context,
+    _config: &RegressionLabelsConfig,
+    event: &IssuesEvent,
+    input: RegressionLabelsInput,
+) -> anyhow::Result<()> {
+    event
+        .issue
+        .add_labels(&ctx.github, vec![Label::new(input.label.to_string())])
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(text: &str) -> Option<&'static str> {
+        regression_label(&toml::from_str("").unwrap(), text)
+    }
+
+    #[test]
+    fn stable_to_stable() {
+        assert_eq!(
+            label("This used to work in 1.70, but it is broken in 1.75.0."),
+            Some("regression-from-stable-to-stable")
+        );
+        assert_eq!(
+            label("Compiles fine with Rust 1.78.0 but fails to compile with 1.79.0"),
+            Some("regression-from-stable-to-stable")
+        );
+    }
+
+    #[test]
+    fn stable_to_nightly() {
+        assert_eq!(
+            label("ICE on nightly\n\nThe code worked on stable, but it crashes on nightly-2024-05-16."),
+            Some("regression-from-stable-to-nightly")
+        );
+        assert_eq!(
+            label("Works with 1.78 but errors with 1.80.0-nightly (2024-05-16)"),
+            Some("regression-from-stable-to-nightly")
+        );
+    }
+
+    #[test]
+    fn stable_to_beta() {
+        assert_eq!(
+            label("It used to compile on 1.77. It doesn't compile on beta anymore."),
+            Some("regression-from-stable-to-beta")
+        );
+    }
+
+    #[test]
+    fn not_a_regression() {
+        assert_eq!(label("This fails to compile in 1.75."), None);
+        assert_eq!(label("Works with nightly, but fails on 1.75"), None);
+    }
+
+    #[test]
+    fn configured_patterns() {
+        let config: RegressionLabelsConfig =
+            toml::from_str(r#"patterns = ['(?i)ok:\s*(?P<good>\S+)\s+ko:\s*(?P<bad>\S+)']"#)
+                .unwrap();
+        assert_eq!(
+            regression_label(&config, "ok: 1.70 ko: 1.71"),
+            Some("regression-from-stable-to-stable")
+        );
+        assert_eq!(
+            regression_label(&config, "This used to work in 1.70, but is broken in 1.75"),
+            None
+        );
+        // Invalid patterns are rejected with the configuration, which
+        // `validate_config` reports on the PR changing it.
+        assert!(toml::from_str::<RegressionLabelsConfig>(r#"patterns = ['(?P<good>']"#).is_err());
+    }
+}
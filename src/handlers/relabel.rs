@@ -1,7 +1,11 @@
 //! Purpose: Allow any user to modify issue labels on GitHub via comments.
 //!
-//! Labels are checked against the labels in the project; the bot does not support creating new
-//! labels.
+//! Labels are checked against the labels in the project. The unknown labels are rejected, unless
+//! `auto-create` is enabled in the `[labels]` config, in which case the team members can create
+//! them.
+//!
+//! The renamed labels listed in the `aliases` of the `[labels]` config are set and removed with
+//! their new name.
 //!
 //! Parsing is done in the `parser::command::relabel` module.
 //!
@@ -9,7 +13,7 @@
 //! notification noise.
 
 use crate::{
-    config::RelabelConfig,
+    config::{self, RelabelConfig},
     github::{self, Event, GithubClient},
     handlers::Context,
     interactions::ErrorComment,
@@ -22,11 +26,21 @@ pub(super) async fn handle_command(
     event: &Event,
     input: RelabelCommand,
 ) -> anyhow::Result<()> {
+    // The commands only have access to their own section, so load the whole
+    // config again. It is cached, so this is cheap.
+    let repo_config = config::get(&ctx.github, event.repo())
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to load config of {}: {e}", event.repo().full_name))?;
+    let labels_config = repo_config.labels.as_ref();
+
     let mut results = vec![];
     let mut to_add = vec![];
+    let membership = is_member(&event.user(), &ctx.github).await;
     for delta in &input.0 {
+        // The renamed labels are set with their new name.
         let name = delta.label().as_str();
-        let err = match check_filter(name, config, is_member(&event.user(), &ctx.github).await) {
+        let name = labels_config.map_or(name, |labels| labels.resolve(name));
+        let err = match check_filter(name, config, membership) {
             Ok(CheckFilterResult::Allow) => None,
             Ok(CheckFilterResult::Deny) => Some(format!(
                 "Label {} can only be set by Rust team members",
@@ -45,24 +59,22 @@ pub(super) async fn handle_command(
             return Ok(());
         }
         match delta {
-            LabelDelta::Add(label) => {
-                to_add.push(github::Label {
-                    name: label.to_string(),
-                });
+            LabelDelta::Add(_) => {
+                to_add.push(github::Label::new(name));
             }
-            LabelDelta::Remove(label) => {
-                results.push((
-                    label,
-                    event.issue().unwrap().remove_label(&ctx.github, &label),
-                ));
+            LabelDelta::Remove(_) => {
+                results.push((name, event.issue().unwrap().remove_label(&ctx.github, name)));
             }
         }
     }
 
+    let auto_create = labels_config.map_or(false, |labels| labels.auto_create);
+    // Only the team members can create labels.
+    let create_unknown = auto_create && membership == TeamMembership::Member;
     if let Err(e) = event
         .issue()
         .unwrap()
-        .add_labels(&ctx.github, to_add.clone())
+        .add_labels_with(&ctx.github, to_add.clone(), create_unknown)
         .await
     {
         tracing::error!(
@@ -89,7 +101,7 @@ pub(super) async fn handle_command(
     Ok(())
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum TeamMembership {
     Member,
     Outsider,
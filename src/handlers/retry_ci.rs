@@ -0,0 +1,146 @@
+//! Allows to rerun the failed jobs of the latest CI run of a PR.
+//!
+//! Only the author of the PR and team members can use the command.
+//!
+//! Configuration is done with the `[retry-ci]` table.
+
+use crate::{config::RetryCiConfig, github::Event, handlers::Context, interactions::ErrorComment};
+use parser::command::retry_ci::RetryCiCommand;
+use tracing as log;
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    _config: &RetryCiConfig,
+    event: &Event,
+    _cmd: RetryCiCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    if !issue.is_pr() {
+        let cmnt = ErrorComment::new(&issue, "CI can only be retried on pull requests.");
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+
+    // Only look the team up when needed.
+    let is_author = event.user().login == issue.user.login;
+    if !is_author
+        && !event
+            .user()
+            .is_team_member(&ctx.github)
+            .await
+            .unwrap_or(false)
+    {
+        let cmnt = ErrorComment::new(
+            &issue,
+            "Only the author of the PR and team members can retry CI.",
+        );
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+
+    // Comment events don't carry the PR head.
+    let repo = event.repo();
+    let pr = repo.get_issue(&ctx.github, issue.number).await?;
+    let Some(head) = &pr.head else {
+        anyhow::bail!("{} has no head commit", pr.global_id());
+    };
+    let runs = repo.workflow_runs_for_sha(&ctx.github, &head.sha).await?;
+    let Some(run) = runs
+        .iter()
+        .find(|run| run.conclusion.as_deref() == Some("failure"))
+    else {
+        let cmnt = ErrorComment::new(
+            &issue,
+            format!(
+                "There is no failed CI run for the head commit {}.",
+                head.sha
+            ),
+        );
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    };
+
+    log::info!(
+        "rerunning failed jobs of {} for {}",
+        run.html_url,
+        pr.global_id()
+    );
+    repo.rerun_failed_jobs(&ctx.github, run.id).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, test_pr_json, TestServer};
+
+    /// A `@rustbot retry-ci` comment by the author of the PR.
+    fn retry_event() -> Event {
+        let mut pr = test_pr_json();
+        pr["pull_request"] = serde_json::json!({});
+        Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": pr,
+                "comment": {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAABBB",
+                    "body": "@rustbot retry-ci",
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": "octocat", "id": 583231},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn reruns_the_failed_run() {
+        let mut pr = test_pr_json();
+        pr["head"] = serde_json::json!({
+            "sha": "f00dfeed",
+            "ref": "fix",
+            "repo": {"full_name": "octocat/rust", "default_branch": "master"},
+        });
+        let run = |id: u64, conclusion: &str| {
+            serde_json::json!({
+                "id": id,
+                "name": "CI",
+                "head_sha": "f00dfeed",
+                "status": "completed",
+                "conclusion": conclusion,
+                "html_url": format!("https://github.com/rust-lang/rust/actions/runs/{id}"),
+            })
+        };
+        let server = TestServer::start(vec![
+            ("200 OK", pr.to_string()),
+            (
+                "200 OK",
+                serde_json::json!({"workflow_runs": [run(1, "success"), run(2, "failure")]})
+                    .to_string(),
+            ),
+            ("201 Created", "{}".to_string()),
+        ]);
+        let ctx = server.context();
+        handle_command(&ctx, &RetryCiConfig {}, &retry_event(), RetryCiCommand)
+            .await
+            .unwrap();
+
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", "/repos/rust-lang/rust/pulls/1234"),
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/actions/runs?head_sha=f00dfeed",
+                ),
+                expect(
+                    "POST",
+                    "/repos/rust-lang/rust/actions/runs/2/rerun-failed-jobs",
+                ),
+            ],
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Allows team members to opt in to the review pool of their team, so that
+//! the PRs for the team are only assigned to the members of its pool (see
+//! [`crate::handlers::assign`]).
+//!
+//! The pools can be joined and left on GitHub with
+//! `@rustbot assign-me-reviewer <team>` and
+//! `@rustbot unassign-me-reviewer <team>`, or on Zulip with
+//! `work pool join|leave <team>`.
+//!
+//! Parsing is done in the `parser::command::review_pool` module,
+//! configuration is done with the `[review-pool]` table.
+
+use crate::{
+    config::ReviewPoolConfig,
+    db::notifications::record_username,
+    db::review_pools::set_review_pool_member,
+    github::{Event, GithubClient},
+    handlers::Context,
+    interactions::ErrorComment,
+};
+use parser::command::review_pool::ReviewPoolCommand;
+use rust_team_data::v1::Teams;
+use tokio_postgres::Client as DbClient;
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    _config: &ReviewPoolConfig,
+    event: &Event,
+    cmd: ReviewPoolCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    let user = event.user();
+    let db = ctx.db.get().await;
+    let message = match cmd {
+        ReviewPoolCommand::Join { team } => {
+            match join_review_pool(&ctx.github, &db, &team, user.id).await? {
+                Ok(message) => message,
+                Err(error) => {
+                    ErrorComment::new(&issue, error).post(&ctx.github).await?;
+                    return Ok(());
+                }
+            }
+        }
+        ReviewPoolCommand::Leave { team } => leave_review_pool(&db, &team, user.id).await?,
+    };
+    issue
+        .post_comment(&ctx.github, &format!("@{}: {message}", user.login))
+        .await?;
+    Ok(())
+}
+
+/// Adds the GitHub user to the review pool of `team`, returning the reply to
+/// the user, or why they can't join it.
+pub(crate) async fn join_review_pool(
+    gh: &GithubClient,
+    db: &DbClient,
+    team: &str,
+    user_id: u64,
+) -> anyhow::Result<Result<String, String>> {
+    let teams = crate::team_data::teams(gh).await?;
+    let login = match team_member_login(&teams, team, user_id) {
+        Ok(login) => login,
+        Err(error) => return Ok(Err(error)),
+    };
+    // The pools are matched with the candidates by username.
+    record_username(db, user_id, login).await?;
+    set_review_pool_member(db, team, user_id, true).await?;
+    Ok(Ok(format!(
+        "You joined the review pool of `{team}`: the PRs for `{team}` are only \
+        assigned to the members of the pool."
+    )))
+}
+
+/// Removes the GitHub user from the review pool of `team`, returning the
+/// reply to the user.
+pub(crate) async fn leave_review_pool(
+    db: &DbClient,
+    team: &str,
+    user_id: u64,
+) -> anyhow::Result<String> {
+    set_review_pool_member(db, team, user_id, false).await?;
+    Ok(format!("You left the review pool of `{team}`."))
+}
+
+/// Returns the username of the user in `team`, or why they can't join its
+/// review pool.
+fn team_member_login<'a>(teams: &'a Teams, team: &str, user_id: u64) -> Result<&'a str, String> {
+    let Some(team_data) = teams.teams.get(team) else {
+        return Err(format!("Unknown team `{team}`."));
+    };
+    team_data
+        .members
+        .iter()
+        .find(|m| m.github_id == user_id)
+        .map(|m| m.github.as_str())
+        .ok_or_else(|| format!("You are not a member of `{team}`."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::review_pools::user_review_pools;
+    use crate::test_server::{assert_requests, comment_json, expect, test_pr_json, TestServer};
+
+    /// A review pool comment by `login`, whose id is `user_id`.
+    fn pool_event(body: &str, login: &str, user_id: u64) -> Event {
+        Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": test_pr_json(),
+                "comment": {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAABBB",
+                    "body": body,
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": login, "id": user_id},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn assign_me_reviewer_joins_the_pool() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        crate::team_data::use_test_teams();
+        let server = TestServer::start(vec![("201 Created", comment_json(2, ""))]);
+        let mut ctx = server.context();
+        ctx.db = crate::db::ClientPool::with_client(db);
+        let cmd = ReviewPoolCommand::Join {
+            team: "all".to_string(),
+        };
+        let event = pool_event("@rustbot assign-me-reviewer all", "team-member", 1);
+        handle_command(&ctx, &ReviewPoolConfig {}, &event, cmd)
+            .await
+            .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments").with_body(
+                    serde_json::json!({
+                        "body": "@team-member: You joined the review pool of `all`: the PRs \
+                            for `all` are only assigned to the members of the pool."
+                    }),
+                ),
+            ],
+        );
+        let db = ctx.db.get().await;
+        assert_eq!(user_review_pools(&db, 1).await.unwrap(), ["all"]);
+    }
+
+    #[tokio::test]
+    async fn only_team_members_join_the_pool() {
+        let Some(db) = crate::db::test_client().await else {
+            return;
+        };
+        crate::team_data::use_test_teams();
+        let server = TestServer::start(vec![
+            ("200 OK", "[]".to_string()),
+            ("201 Created", comment_json(2, "")),
+        ]);
+        let mut ctx = server.context();
+        ctx.db = crate::db::ClientPool::with_client(db);
+        let cmd = ReviewPoolCommand::Join {
+            team: "all".to_string(),
+        };
+        let event = pool_event("@rustbot assign-me-reviewer all", "octocat", 583231);
+        handle_command(&ctx, &ReviewPoolConfig {}, &event, cmd)
+            .await
+            .unwrap();
+        let requests = server.finish();
+        assert_requests(
+            &requests,
+            &[
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/issues/1234/comments?page=1&per_page=100",
+                ),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments"),
+            ],
+        );
+        assert!(requests[1].body.contains("You are not a member of `all`."));
+        let db = ctx.db.get().await;
+        assert!(user_review_pools(&db, 583231).await.unwrap().is_empty());
+    }
+}
@@ -43,12 +43,7 @@ pub(crate) async fn handle_input(
         .issue
         .add_labels(
             &ctx.github,
-            config
-                .add_labels
-                .iter()
-                .cloned()
-                .map(|name| Label { name })
-                .collect(),
+            config.add_labels.iter().cloned().map(Label::new).collect(),
         )
         .await?;
 
@@ -29,12 +29,7 @@ pub(crate) async fn handle(
             // Add waiting on author
             event
                 .issue
-                .add_labels(
-                    &ctx.github,
-                    vec![Label {
-                        name: config.reviewed_label.clone(),
-                    }],
-                )
+                .add_labels(&ctx.github, vec![Label::new(config.reviewed_label.clone())])
                 .await?;
         }
     }
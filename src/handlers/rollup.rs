@@ -0,0 +1,178 @@
+//! Allows team members to set whether a PR can be included in a rollup.
+//!
+//! Each rollup level maps to a label, and setting a level removes the labels
+//! of the other levels.
+//!
+//! Parsing is done in the `parser::command::rollup` module, configuration is
+//! done with the `[rollup]` table.
+
+use crate::{
+    config::RollupConfig,
+    github::{Event, GithubClient, Issue, Label},
+    handlers::Context,
+    interactions::ErrorComment,
+};
+use parser::command::rollup::{RollupCommand, RollupLevel};
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &RollupConfig,
+    event: &Event,
+    cmd: RollupCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    if !issue.is_pr() {
+        let cmnt = ErrorComment::new(&issue, "The rollup command only works on pull requests.");
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+    let is_team_member = event
+        .user()
+        .is_team_member(&ctx.github)
+        .await
+        .unwrap_or(false);
+    if !is_team_member {
+        let cmnt = ErrorComment::new(&issue, "Only team members can set the rollup status.");
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+
+    set_rollup_labels(&ctx.github, issue, config, cmd.0).await
+}
+
+fn rollup_label(config: &RollupConfig, level: RollupLevel) -> &str {
+    match level {
+        RollupLevel::Always => &config.always,
+        RollupLevel::Maybe => &config.maybe,
+        RollupLevel::Iffy => &config.iffy,
+        RollupLevel::Never => &config.never,
+    }
+}
+
+/// Adds the label of `level`, removing the labels of the other levels.
+async fn set_rollup_labels(
+    client: &GithubClient,
+    issue: &Issue,
+    config: &RollupConfig,
+    level: RollupLevel,
+) -> anyhow::Result<()> {
+    let add = rollup_label(config, level);
+    if issue.labels().iter().any(|l| l.name == add) {
+        return Ok(());
+    }
+    for other in RollupLevel::ALL {
+        let remove = rollup_label(config, other);
+        if remove != add {
+            issue.remove_label(client, remove).await?;
+        }
+    }
+    issue
+        .add_labels(client, vec![Label::new(add.to_owned())])
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, test_pr_json, TestServer};
+
+    fn config() -> RollupConfig {
+        toml::from_str("").unwrap()
+    }
+
+    fn pr_with_labels(labels: &[&str]) -> Issue {
+        let mut pr = test_pr_json();
+        pr["labels"] = labels
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        serde_json::from_value(pr).unwrap()
+    }
+
+    /// Sets `level` on a PR with the `rollup=never` label and checks that
+    /// `rollup=never` is replaced by `label`.
+    async fn set_level(level: RollupLevel, label: &str) {
+        let server = TestServer::start(vec![
+            ("204 No Content", String::new()),
+            ("200 OK", serde_json::json!({ "name": label }).to_string()),
+            ("200 OK", "[]".to_string()),
+        ]);
+        set_rollup_labels(
+            &server.github_client(),
+            &pr_with_labels(&["T-compiler", "rollup=never"]),
+            &config(),
+            level,
+        )
+        .await
+        .unwrap();
+        assert_requests(
+            &server.finish(),
+            &[
+                expect(
+                    "DELETE",
+                    "/repos/rust-lang/rust/issues/1234/labels/rollup=never",
+                ),
+                expect("GET", format!("/repos/rust-lang/rust/labels/{label}")),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({ "labels": [label] })),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn rollup_always() {
+        set_level(RollupLevel::Always, "rollup").await;
+    }
+
+    #[tokio::test]
+    async fn rollup_maybe() {
+        set_level(RollupLevel::Maybe, "rollup-").await;
+    }
+
+    #[tokio::test]
+    async fn rollup_iffy() {
+        set_level(RollupLevel::Iffy, "rollup=iffy").await;
+    }
+
+    #[tokio::test]
+    async fn rollup_never_already_set() {
+        let server = TestServer::start(vec![]);
+        set_rollup_labels(
+            &server.github_client(),
+            &pr_with_labels(&["rollup=never"]),
+            &config(),
+            RollupLevel::Never,
+        )
+        .await
+        .unwrap();
+        assert!(server.finish().is_empty());
+    }
+
+    #[tokio::test]
+    async fn configured_labels() {
+        let server = TestServer::start(vec![
+            (
+                "200 OK",
+                serde_json::json!({ "name": "rollup-never" }).to_string(),
+            ),
+            ("200 OK", "[]".to_string()),
+        ]);
+        let config: RollupConfig = toml::from_str(r#"never = "rollup-never""#).unwrap();
+        set_rollup_labels(
+            &server.github_client(),
+            &pr_with_labels(&["rollup=never"]),
+            &config,
+            RollupLevel::Never,
+        )
+        .await
+        .unwrap();
+        let paths: Vec<_> = server.finish().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/repos/rust-lang/rust/labels/rollup-never",
+                "/repos/rust-lang/rust/issues/1234/labels",
+            ]
+        );
+    }
+}
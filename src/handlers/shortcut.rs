@@ -4,15 +4,16 @@
 
 use crate::{
     config::ShortcutConfig,
-    github::{Event, Label},
+    github::{Event, Issue, Label},
     handlers::Context,
     interactions::ErrorComment,
 };
 use parser::command::shortcut::ShortcutCommand;
+use tracing as log;
 
 pub(super) async fn handle_command(
     ctx: &Context,
-    _config: &ShortcutConfig,
+    config: &ShortcutConfig,
     event: &Event,
     input: ShortcutCommand,
 ) -> anyhow::Result<()> {
@@ -44,14 +45,232 @@ pub(super) async fn handle_command(
             }
         }
         issue
-            .add_labels(
-                &ctx.github,
-                vec![Label {
-                    name: add.to_owned(),
-                }],
-            )
+            .add_labels(&ctx.github, vec![Label::new(add.to_owned())])
             .await?;
     }
 
+    if config.update_draft {
+        update_draft(ctx, event, issue, input).await;
+    }
+
     Ok(())
 }
+
+/// Keeps the GitHub draft state in line with the shortcut: `ready` run by the
+/// PR author marks a draft ready for review, and `author` run by the PR
+/// author or a team member turns the PR back into a draft.
+async fn update_draft(ctx: &Context, event: &Event, issue: &Issue, input: ShortcutCommand) {
+    let user = event.user();
+    let is_author = user.login == issue.user.login;
+    let result = match input {
+        ShortcutCommand::Ready if issue.draft && is_author => {
+            match issue.pr_node_id(&ctx.github).await {
+                Ok(node_id) => issue.mark_ready(&ctx.github, &node_id).await,
+                Err(e) => Err(e),
+            }
+        }
+        ShortcutCommand::Author if !issue.draft => {
+            let allowed = is_author
+                || match user.is_team_member(&ctx.github).await {
+                    Ok(is_member) => is_member,
+                    Err(e) => {
+                        log::warn!(
+                            "failed to check the team membership of {}: {e:?}",
+                            user.login
+                        );
+                        false
+                    }
+                };
+            if !allowed {
+                return;
+            }
+            match issue.pr_node_id(&ctx.github).await {
+                Ok(node_id) => issue.convert_to_draft(&ctx.github, &node_id).await,
+                Err(e) => Err(e),
+            }
+        }
+        _ => return,
+    };
+    if let Err(e) = result {
+        log::warn!(
+            "failed to update the draft state of {}: {e:?}",
+            issue.global_id()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, expect, test_pr_json, ExpectedRequest, TestServer};
+
+    /// A `@rustbot ready` comment by the author of a draft PR waiting on them.
+    fn ready_event() -> Event {
+        let mut pr = test_pr_json();
+        pr["draft"] = serde_json::json!(true);
+        pr["labels"] = serde_json::json!([{"name": "S-waiting-on-author"}]);
+        comment_event(pr, "octocat", "@rustbot ready")
+    }
+
+    /// A `@rustbot author` comment by `login` on a PR waiting on review.
+    fn author_event(login: &str) -> Event {
+        let mut pr = test_pr_json();
+        pr["labels"] = serde_json::json!([{"name": "S-waiting-on-review"}]);
+        comment_event(pr, login, "@rustbot author")
+    }
+
+    fn comment_event(mut pr: serde_json::Value, login: &str, body: &str) -> Event {
+        pr["pull_request"] = serde_json::json!({});
+        pr["node_id"] = serde_json::json!("I_kwDOAAABBB");
+        Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": pr,
+                "comment": {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAABBB",
+                    "body": body,
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": login, "id": 583231},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn ready_marks_the_draft_ready_for_review() {
+        let server = TestServer::start(vec![
+            ("204 No Content", String::new()),
+            ("200 OK", r#"{"name": "S-waiting-on-review"}"#.to_string()),
+            ("200 OK", r#"[{"name": "S-waiting-on-review"}]"#.to_string()),
+            (
+                "200 OK",
+                r#"{"data": {"repository": {"pullRequest": {"id": "PR_kwDOAAABBB"}}}}"#
+                    .to_string(),
+            ),
+            (
+                "200 OK",
+                r#"{"data": {"markPullRequestReadyForReview": {"pullRequest": {"isDraft": false}}}}"#
+                    .to_string(),
+            ),
+        ]);
+        let ctx = server.context();
+        let config = ShortcutConfig { update_draft: true };
+        handle_command(&ctx, &config, &ready_event(), ShortcutCommand::Ready)
+            .await
+            .unwrap();
+
+        let requests = server.finish();
+        assert_requests(
+            &requests,
+            &[
+                expect(
+                    "DELETE",
+                    "/repos/rust-lang/rust/issues/1234/labels/S-waiting-on-author",
+                ),
+                expect("GET", "/repos/rust-lang/rust/labels/S-waiting-on-review"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({"labels": ["S-waiting-on-review"]})),
+                expect("POST", "/graphql"),
+                expect("POST", "/graphql"),
+            ],
+        );
+        let variables = |i: usize| {
+            serde_json::from_str::<serde_json::Value>(&requests[i].body).unwrap()["variables"]
+                .clone()
+        };
+        assert_eq!(variables(3)["prNum"], 1234);
+        let mutation: serde_json::Value = serde_json::from_str(&requests[4].body).unwrap();
+        assert!(mutation["query"]
+            .as_str()
+            .unwrap()
+            .contains("markPullRequestReadyForReview"));
+        assert_eq!(
+            variables(4),
+            serde_json::json!({"pullRequestId": "PR_kwDOAAABBB"})
+        );
+    }
+
+    /// The requests changing the labels of [`author_event`].
+    fn author_label_requests() -> Vec<ExpectedRequest> {
+        vec![
+            expect(
+                "DELETE",
+                "/repos/rust-lang/rust/issues/1234/labels/S-waiting-on-review",
+            ),
+            expect("GET", "/repos/rust-lang/rust/labels/S-waiting-on-author"),
+            expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                .with_body(serde_json::json!({"labels": ["S-waiting-on-author"]})),
+        ]
+    }
+
+    fn author_label_responses() -> Vec<(&'static str, String)> {
+        vec![
+            ("204 No Content", String::new()),
+            ("200 OK", r#"{"name": "S-waiting-on-author"}"#.to_string()),
+            ("200 OK", r#"[{"name": "S-waiting-on-author"}]"#.to_string()),
+        ]
+    }
+
+    #[tokio::test]
+    async fn author_converts_the_pr_to_draft() {
+        let mut responses = author_label_responses();
+        responses.extend([
+            (
+                "200 OK",
+                r#"{"data": {"repository": {"pullRequest": {"id": "PR_kwDOAAABBB"}}}}"#.to_string(),
+            ),
+            (
+                "200 OK",
+                r#"{"data": {"convertPullRequestToDraft": {"pullRequest": {"isDraft": true}}}}"#
+                    .to_string(),
+            ),
+        ]);
+        let server = TestServer::start(responses);
+        let ctx = server.context();
+        let config = ShortcutConfig { update_draft: true };
+        handle_command(
+            &ctx,
+            &config,
+            &author_event("octocat"),
+            ShortcutCommand::Author,
+        )
+        .await
+        .unwrap();
+
+        let requests = server.finish();
+        let mut expected = author_label_requests();
+        expected.extend([expect("POST", "/graphql"), expect("POST", "/graphql")]);
+        assert_requests(&requests, &expected);
+        let mutation: serde_json::Value = serde_json::from_str(&requests[4].body).unwrap();
+        assert!(mutation["query"]
+            .as_str()
+            .unwrap()
+            .contains("convertPullRequestToDraft"));
+        assert_eq!(
+            mutation["variables"],
+            serde_json::json!({"pullRequestId": "PR_kwDOAAABBB"})
+        );
+    }
+
+    #[tokio::test]
+    async fn author_by_others_keeps_the_draft_state() {
+        crate::team_data::use_test_teams();
+        let server = TestServer::start(author_label_responses());
+        let ctx = server.context();
+        let config = ShortcutConfig { update_draft: true };
+        handle_command(
+            &ctx,
+            &config,
+            &author_event("someone"),
+            ShortcutCommand::Author,
+        )
+        .await
+        .unwrap();
+        // Only the labels are changed.
+        assert_requests(&server.finish(), &author_label_requests());
+    }
+}
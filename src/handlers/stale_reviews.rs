@@ -0,0 +1,212 @@
+//! Dismisses approvals and re-requests reviews when a force-push drops the
+//! commits a reviewer approved.
+//!
+//! A regular push only adds commits on top of the previous head, so the
+//! approvals stay meaningful. When the history is rewritten, an approval is
+//! stale if the commit it was given on is no longer part of the PR.
+//!
+//! Configuration is done with the `[stale-reviews]` table.
+
+use crate::{
+    config::StaleReviewsConfig,
+    github::{CompareStatus, Event, GithubClient, Issue, IssuesAction},
+    handlers::Context,
+};
+use std::collections::HashMap;
+use tracing as log;
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    _config: &StaleReviewsConfig,
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if e.action != IssuesAction::Synchronize || !e.issue.is_pr() {
+        return Ok(());
+    }
+    let (Some(before), Some(after)) = (&e.before, &e.after) else {
+        return Ok(());
+    };
+    rerequest_stale_reviews(&ctx.github, &e.issue, before, after).await?;
+    Ok(())
+}
+
+/// Dismisses the approvals given on a commit dropped by the push from
+/// `before` to `after`, and requests a review from their authors again.
+///
+/// Returns the reviewers who were requested again.
+async fn rerequest_stale_reviews(
+    client: &GithubClient,
+    pr: &Issue,
+    before: &str,
+    after: &str,
+) -> anyhow::Result<Vec<String>> {
+    let repo = pr.repository();
+    if repo.compare_status(client, before, after).await? == CompareStatus::Ahead {
+        return Ok(Vec::new());
+    }
+
+    // Only the latest review of each reviewer matters, the comments don't
+    // change an approval.
+    let mut latest_reviews = HashMap::new();
+    for review in pr.reviews(client).await? {
+        if review.user.login != pr.user.login
+            && review.state != "COMMENTED"
+            && review.state != "PENDING"
+        {
+            latest_reviews.insert(review.user.login.clone(), review);
+        }
+    }
+    let mut approvals: Vec<_> = latest_reviews
+        .into_values()
+        .filter(|review| review.state == "APPROVED")
+        .collect();
+    approvals.sort_by_key(|review| review.id);
+
+    let mut stale = Vec::new();
+    for review in approvals {
+        let status = repo
+            .compare_status(client, &review.commit_id, after)
+            .await?;
+        if !matches!(status, CompareStatus::Ahead | CompareStatus::Identical) {
+            pr.dismiss_review(client, review.id, &dismissal_message(&review.commit_id))
+                .await?;
+            stale.push(review.user.login);
+        }
+    }
+    if stale.is_empty() {
+        return Ok(stale);
+    }
+
+    log::info!(
+        "{} was force-pushed past the approvals of {stale:?}, requesting their review again",
+        pr.global_id()
+    );
+    let reviewers: Vec<_> = stale.iter().map(|s| s.as_str()).collect();
+    pr.request_reviewers(client, &reviewers).await?;
+    Ok(stale)
+}
+
+fn dismissal_message(commit_id: &str) -> String {
+    format!("The approved commit {commit_id} is no longer part of this PR after a force-push.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{test_pr, TestServer};
+
+    fn review(id: u64, login: &str, state: &str, commit_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "user": {"login": login, "id": id},
+            "body": "",
+            "state": state,
+            "commit_id": commit_id,
+            "submitted_at": "2024-04-20T10:31:31Z",
+        })
+    }
+
+    #[tokio::test]
+    async fn rewriting_force_push_rerequests_review() {
+        let comparison = |status: &str| serde_json::json!({"status": status}).to_string();
+        let server = TestServer::start(vec![
+            // before...after
+            ("200 OK", comparison("diverged")),
+            (
+                "200 OK",
+                serde_json::json!([
+                    review(1, "oli-obk", "APPROVED", "aaa"),
+                    review(2, "compiler-errors", "APPROVED", "bbb"),
+                    review(3, "lcnr", "COMMENTED", "aaa"),
+                    review(4, "oli-obk", "CHANGES_REQUESTED", "bbb"),
+                    review(5, "jieyouxu", "APPROVED", "aaa"),
+                ])
+                .to_string(),
+            ),
+            // bbb...after, bbb was kept by the rebase
+            ("200 OK", comparison("ahead")),
+            // aaa...after, aaa was dropped
+            ("200 OK", comparison("diverged")),
+            (
+                "200 OK",
+                review(5, "jieyouxu", "DISMISSED", "aaa").to_string(),
+            ),
+            ("201 Created", "{}".to_string()),
+        ]);
+        let stale = rerequest_stale_reviews(&server.github_client(), &test_pr(), "aaa", "ccc")
+            .await
+            .unwrap();
+        assert_eq!(stale, ["jieyouxu"]);
+
+        let requests = server.finish();
+        let paths: Vec<_> = requests.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/repos/rust-lang/rust/compare/aaa...ccc?per_page=1",
+                "/repos/rust-lang/rust/pulls/1234/reviews?per_page=100",
+                "/repos/rust-lang/rust/compare/bbb...ccc?per_page=1",
+                "/repos/rust-lang/rust/compare/aaa...ccc?per_page=1",
+                "/repos/rust-lang/rust/pulls/1234/reviews/5/dismissals",
+                "/repos/rust-lang/rust/pulls/1234/requested_reviewers",
+            ]
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&requests[4].body).unwrap(),
+            serde_json::json!({
+                "message": "The approved commit aaa is no longer part of this PR after a force-push.",
+                "event": "DISMISS",
+            })
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&requests[5].body).unwrap(),
+            serde_json::json!({"reviewers": ["jieyouxu"]})
+        );
+    }
+
+    #[tokio::test]
+    async fn approval_followed_by_a_comment_is_dismissed() {
+        let comparison = |status: &str| serde_json::json!({"status": status}).to_string();
+        let server = TestServer::start(vec![
+            ("200 OK", comparison("diverged")),
+            (
+                "200 OK",
+                serde_json::json!([
+                    review(1, "oli-obk", "APPROVED", "aaa"),
+                    review(2, "oli-obk", "COMMENTED", "aaa"),
+                ])
+                .to_string(),
+            ),
+            ("200 OK", comparison("diverged")),
+            (
+                "200 OK",
+                review(1, "oli-obk", "DISMISSED", "aaa").to_string(),
+            ),
+            ("201 Created", "{}".to_string()),
+        ]);
+        let stale = rerequest_stale_reviews(&server.github_client(), &test_pr(), "aaa", "ccc")
+            .await
+            .unwrap();
+        assert_eq!(stale, ["oli-obk"]);
+        assert_eq!(
+            server.finish()[3].path,
+            "/repos/rust-lang/rust/pulls/1234/reviews/1/dismissals"
+        );
+    }
+
+    #[tokio::test]
+    async fn regular_push_keeps_reviews() {
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!({"status": "ahead"}).to_string(),
+        )]);
+        let stale = rerequest_stale_reviews(&server.github_client(), &test_pr(), "aaa", "ccc")
+            .await
+            .unwrap();
+        assert!(stale.is_empty());
+        assert_eq!(server.finish().len(), 1);
+    }
+}
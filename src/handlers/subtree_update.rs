@@ -0,0 +1,214 @@
+//! Allows to open a PR updating one of the documentation submodules on
+//! demand, rather than waiting for the scheduled docs-update job.
+//!
+//! Only team members can use the command. A placeholder comment is posted
+//! right away, and edited with the link to the PR once it is opened.
+//!
+//! Configuration is done with the `[subtree-update]` table.
+
+use crate::{
+    config::SubtreeUpdateConfig,
+    github::Event,
+    handlers::{docs_update, Context},
+    interactions::{ErrorComment, PlaceholderComment},
+};
+use parser::command::subtree_update::SubtreeUpdateCommand;
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    _config: &SubtreeUpdateConfig,
+    event: &Event,
+    cmd: SubtreeUpdateCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    let is_team_member = event
+        .user()
+        .is_team_member(&ctx.github)
+        .await
+        .unwrap_or(false);
+    if !is_team_member {
+        let cmnt = ErrorComment::new(&issue, "Only team members can update submodules.");
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+
+    let Some(path) = docs_update::find_submodule(&cmd.submodule) else {
+        let names: Vec<_> = docs_update::submodule_names()
+            .map(|name| format!("`{name}`"))
+            .collect();
+        let cmnt = ErrorComment::new(
+            &issue,
+            format!(
+                "Unknown submodule `{}`, expected one of {}.",
+                cmd.submodule,
+                names.join(", ")
+            ),
+        );
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    };
+
+    let name = path.rsplit('/').next().unwrap();
+    let title = format!("Update {name}");
+    let branch = format!("update-{name}");
+    // Creating the commit and the PR takes a while.
+    let placeholder = PlaceholderComment::post(&ctx.github, issue).await?;
+    let message = match docs_update::update_submodules(&ctx.github, &[path], &title, &branch).await
+    {
+        Ok(Some(pr)) => format!("{} updates `{path}`.", pr.html_url),
+        Ok(None) => format!("`{path}` is already up to date."),
+        Err(e) => {
+            placeholder.remove(&ctx.github).await?;
+            return Err(e);
+        }
+    };
+    placeholder.finish(&ctx.github, &message).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{assert_requests, comment_json, expect, test_pr_json, TestServer};
+
+    /// A `@rustbot subtree-update` comment by `login`.
+    fn subtree_update_event(login: &str) -> Event {
+        Event::IssueComment(
+            serde_json::from_value(serde_json::json!({
+                "action": "created",
+                "changes": null,
+                "issue": test_pr_json(),
+                "comment": {
+                    "id": 1,
+                    "node_id": "IC_kwDOAAABBB",
+                    "body": "@rustbot subtree-update book",
+                    "html_url": "https://github.com/rust-lang/rust/pull/1234#issuecomment-1",
+                    "user": {"login": login, "id": 1},
+                },
+                "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn repo_json(full_name: &str, default_branch: &str) -> String {
+        serde_json::json!({
+            "full_name": full_name,
+            "default_branch": default_branch,
+            "parent": null,
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn submodule_already_up_to_date() {
+        crate::team_data::use_test_teams();
+        let sha = "5e1d3299a290026b85787bc9c7e72bcc53ac283f";
+        let server = TestServer::start_keyed(vec![
+            (
+                "POST /repos/rust-lang/rust/issues/1234/comments",
+                "201 Created",
+                comment_json(2, ":hourglass_flowing_sand: Working on it..."),
+            ),
+            (
+                "GET /repos/rust-lang/rust",
+                "200 OK",
+                repo_json("rust-lang/rust", "master"),
+            ),
+            (
+                "GET /repos/rustbot/rust",
+                "200 OK",
+                repo_json("rustbot/rust", "master"),
+            ),
+            (
+                "GET /repos/rust-lang/rust/contents/src/doc/book",
+                "200 OK",
+                serde_json::json!({
+                    "name": "book",
+                    "path": "src/doc/book",
+                    "sha": sha,
+                    "submodule_git_url": "https://github.com/rust-lang/book.git",
+                })
+                .to_string(),
+            ),
+            (
+                "GET /repos/rust-lang/book",
+                "200 OK",
+                repo_json("rust-lang/book", "main"),
+            ),
+            (
+                "GET /repos/rust-lang/book/git/ref/heads/main",
+                "200 OK",
+                serde_json::json!({
+                    "ref": "refs/heads/main",
+                    "object": {
+                        "type": "commit",
+                        "sha": sha,
+                        "url": format!("https://api.github.com/repos/rust-lang/book/git/commits/{sha}"),
+                    },
+                })
+                .to_string(),
+            ),
+            (
+                "PATCH /repos/rust-lang/rust/issues/comments/2",
+                "200 OK",
+                comment_json(2, "`src/doc/book` is already up to date."),
+            ),
+        ]);
+        let cmd = SubtreeUpdateCommand {
+            submodule: "book".to_string(),
+        };
+        handle_command(
+            &server.context(),
+            &SubtreeUpdateConfig {},
+            &subtree_update_event("team-member"),
+            cmd,
+        )
+        .await
+        .unwrap();
+        let requests = server.finish();
+        assert_eq!(requests.len(), 7);
+        assert_eq!(
+            requests[0].path,
+            "/repos/rust-lang/rust/issues/1234/comments"
+        );
+        assert_requests(
+            &requests[6..],
+            &[expect("PATCH", "/repos/rust-lang/rust/issues/comments/2")
+                .with_body(serde_json::json!({"body": "`src/doc/book` is already up to date."}))],
+        );
+    }
+
+    #[tokio::test]
+    async fn only_team_members_update_submodules() {
+        crate::team_data::use_test_teams();
+        let server = TestServer::start(vec![
+            ("200 OK", "[]".to_string()),
+            ("201 Created", comment_json(2, "")),
+        ]);
+        let cmd = SubtreeUpdateCommand {
+            submodule: "book".to_string(),
+        };
+        handle_command(
+            &server.context(),
+            &SubtreeUpdateConfig {},
+            &subtree_update_event("octocat"),
+            cmd,
+        )
+        .await
+        .unwrap();
+        let requests = server.finish();
+        assert_requests(
+            &requests,
+            &[
+                expect(
+                    "GET",
+                    "/repos/rust-lang/rust/issues/1234/comments?page=1&per_page=100",
+                ),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments"),
+            ],
+        );
+        assert!(requests[1]
+            .body
+            .contains("Only team members can update submodules."));
+    }
+}
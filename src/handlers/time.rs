@@ -0,0 +1,120 @@
+//! Replies with the next meeting of a team, converted to a few common
+//! timezones, to help contributors across the world join it.
+//!
+//! Parsing is done in the `parser::command::time` module, configuration is
+//! done with the `[time]` table, which maps team names to their calendar.
+
+use crate::{
+    config::TimeConfig,
+    github::Event,
+    handlers::Context,
+    interactions::ErrorComment,
+    meetings::{get_meetings, CompilerMeeting},
+};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use parser::command::time::TimeCommand;
+
+/// The timezones the meeting time is converted to.
+const TIMEZONES: &[Tz] = &[
+    Tz::UTC,
+    Tz::America__Los_Angeles,
+    Tz::America__New_York,
+    Tz::Europe__Berlin,
+    Tz::Asia__Kolkata,
+    Tz::Asia__Shanghai,
+    Tz::Australia__Sydney,
+];
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &TimeConfig,
+    event: &Event,
+    cmd: TimeCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    let Some(calendar_id) = config.calendars.get(&cmd.team) else {
+        let mut teams: Vec<_> = config.calendars.keys().map(|t| format!("`{t}`")).collect();
+        teams.sort();
+        let cmnt = ErrorComment::new(
+            &issue,
+            format!(
+                "Unknown team `{}`, expected one of {}.",
+                cmd.team,
+                teams.join(", ")
+            ),
+        );
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let meetings =
+        get_meetings(ctx.github.raw(), calendar_id, now, now + Duration::days(31)).await?;
+    let message = match meetings
+        .iter()
+        .find_map(|meeting| Some((meeting, meeting.start_time()?)))
+    {
+        Some((meeting, start)) => time_reply(&cmd.team, meeting, start),
+        None => format!("There is no `{}` meeting in the next month.", cmd.team),
+    };
+    issue.post_comment(&ctx.github, &message).await?;
+    Ok(())
+}
+
+fn time_reply(team: &str, meeting: &CompilerMeeting, start: DateTime<Utc>) -> String {
+    let mut reply = format!(
+        "The next `{team}` meeting, [{}]({}), starts on:\n\n\
+        | Timezone | Time |\n\
+        | --- | --- |\n",
+        meeting.summary, meeting.html_link
+    );
+    for tz in TIMEZONES {
+        reply.push_str(&format!(
+            "| {} | {} |\n",
+            tz.name(),
+            start.with_timezone(tz).format("%a %b %-d, %H:%M %Z")
+        ));
+    }
+    reply.push_str(&format!(
+        "\nSee it in [other timezones](https://www.timeanddate.com/worldclock/fixedtime.html?iso={}).",
+        start.format("%Y%m%dT%H%M")
+    ));
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn reply_in_timezones() {
+        let meeting: CompilerMeeting = serde_json::from_value(serde_json::json!({
+            "id": "6onjdvf1a3ji2mq20bp0v8n6e0_20240516T140000Z",
+            "summary": "T-compiler triage meeting",
+            "htmlLink": "https://www.google.com/calendar/event?eid=Nm9uamR2ZjFhM2pp",
+            "start": {"dateTime": "2024-05-16T16:00:00+02:00", "timeZone": "Europe/Berlin"},
+        }))
+        .unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 5, 16, 14, 0, 0).unwrap();
+        assert_eq!(
+            time_reply("compiler", &meeting, start),
+            "The next `compiler` meeting, \
+            [T-compiler triage meeting](https://www.google.com/calendar/event?eid=Nm9uamR2ZjFhM2pp), \
+            starts on:
+
+| Timezone | Time |
+| --- | --- |
+| UTC | Thu May 16, 14:00 UTC |
+| America/Los_Angeles | Thu May 16, 07:00 PDT |
+| America/New_York | Thu May 16, 10:00 EDT |
+| Europe/Berlin | Thu May 16, 16:00 CEST |
+| Asia/Kolkata | Thu May 16, 19:30 IST |
+| Asia/Shanghai | Thu May 16, 22:00 CST |
+| Australia/Sydney | Fri May 17, 00:00 AEST |
+
+See it in [other timezones](https://www.timeanddate.com/worldclock/fixedtime.html?iso=20240516T1400)."
+        );
+    }
+}
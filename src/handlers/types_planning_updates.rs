@@ -18,6 +18,10 @@ impl Job for TypesPlanningMeetingThreadOpenJob {
         "types_planning_meeting_thread_open"
     }
 
+    fn is_nudge(&self) -> bool {
+        true
+    }
+
     async fn run(&self, ctx: &super::Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
         // On the last week of the month, we open a thread on zulip for the next Monday
         let today = chrono::Utc::now().date_naive();
@@ -76,6 +80,10 @@ impl Job for TypesPlanningMeetingUpdatesPing {
         "types_planning_meeting_updates_ping"
     }
 
+    fn is_nudge(&self) -> bool {
+        true
+    }
+
     async fn run(&self, ctx: &super::Context, metadata: &serde_json::Value) -> anyhow::Result<()> {
         let metadata = serde_json::from_value(metadata.clone())?;
         // On the thursday before the first monday, we want to ping for updates
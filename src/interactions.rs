@@ -1,4 +1,4 @@
-use crate::github::{GithubClient, Issue};
+use crate::github::{with_bot_comment_marker, Comment, GithubClient, Issue};
 use std::fmt::Write;
 
 pub struct ErrorComment<'a> {
@@ -6,6 +6,9 @@ pub struct ErrorComment<'a> {
     message: String,
 }
 
+/// The kind of the hidden marker of error comments.
+const ERROR_COMMENT_KIND: &str = "error";
+
 impl<'a> ErrorComment<'a> {
     pub fn new<T>(issue: &'a Issue, message: T) -> ErrorComment<'a>
     where
@@ -17,7 +20,26 @@ impl<'a> ErrorComment<'a> {
         }
     }
 
+    /// Posts the error, editing the previous error comment of the bot on the
+    /// issue if there is one, so that repeated errors don't flood the thread.
     pub async fn post(&self, client: &GithubClient) -> anyhow::Result<()> {
+        let body = self.body()?;
+        match self
+            .issue
+            .find_bot_comment(client, ERROR_COMMENT_KIND)
+            .await?
+        {
+            Some(comment) => {
+                self.issue.edit_comment(client, comment.id, &body).await?;
+            }
+            None => {
+                self.issue.post_comment(client, &body).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn body(&self) -> anyhow::Result<String> {
         let mut body = String::new();
         writeln!(body, "**Error**: {}", self.message)?;
         writeln!(body)?;
@@ -26,8 +48,7 @@ impl<'a> ErrorComment<'a> {
             "Please file an issue on GitHub at [triagebot](https://github.com/rust-lang/triagebot) if there's \
             a problem with this bot, or reach out on [#t-infra](https://rust-lang.zulipchat.com/#narrow/stream/242791-t-infra) on Zulip."
         )?;
-        self.issue.post_comment(client, &body).await?;
-        Ok(())
+        Ok(with_bot_comment_marker(&body, ERROR_COMMENT_KIND))
     }
 }
 
@@ -51,6 +72,38 @@ impl<'a> PingComment<'a> {
     }
 }
 
+/// A "working on it" comment for commands that take a while.
+///
+/// The comment is posted right away, and later replaced with the result of
+/// the command with [`PlaceholderComment::finish`], or removed with
+/// [`PlaceholderComment::remove`].
+pub struct PlaceholderComment<'a> {
+    issue: &'a Issue,
+    comment: Comment,
+}
+
+impl<'a> PlaceholderComment<'a> {
+    pub async fn post(client: &GithubClient, issue: &'a Issue) -> anyhow::Result<Self> {
+        let comment = issue
+            .post_comment(client, ":hourglass_flowing_sand: Working on it...")
+            .await?;
+        Ok(PlaceholderComment { issue, comment })
+    }
+
+    /// Replaces the placeholder with the final result.
+    pub async fn finish(self, client: &GithubClient, body: &str) -> anyhow::Result<()> {
+        self.issue
+            .edit_comment(client, self.comment.id, body)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes the placeholder, for commands without any output.
+    pub async fn remove(self, client: &GithubClient) -> anyhow::Result<()> {
+        self.issue.delete_comment(client, self.comment.id).await
+    }
+}
+
 pub struct EditIssueBody<'a> {
     issue: &'a Issue,
     id: &'static str,
@@ -169,3 +222,94 @@ impl<'a> EditIssueBody<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::bot_comment_marker;
+    use crate::test_server::{assert_requests, comment_json, expect, test_pr, TestServer};
+
+    #[test]
+    fn error_comment_is_marked() {
+        let issue = test_pr();
+        let body = ErrorComment::new(&issue, "second failure").body().unwrap();
+        assert!(body.starts_with("**Error**: second failure\n"));
+        assert!(body.ends_with(&format!("{}\n", bot_comment_marker("error"))));
+    }
+
+    #[tokio::test]
+    async fn second_error_edits_the_first() {
+        let issue = test_pr();
+        let first = ErrorComment::new(&issue, "first failure").body().unwrap();
+        let second = ErrorComment::new(&issue, "second failure").body().unwrap();
+        let comments = |comments: &[String]| format!("[{}]", comments.join(","));
+        let server = TestServer::start(vec![
+            ("200 OK", comments(&[comment_json(1, "r? @octocat")])),
+            ("201 Created", comment_json(2, &first)),
+            (
+                "200 OK",
+                comments(&[
+                    comment_json(1, "r? @octocat"),
+                    comment_json(2, &first),
+                    comment_json(3, "Thanks!"),
+                ]),
+            ),
+            ("200 OK", comment_json(2, &second)),
+        ]);
+        let client = server.github_client();
+
+        // The first error is posted as a new comment, the second one edits it.
+        ErrorComment::new(&issue, "first failure")
+            .post(&client)
+            .await
+            .unwrap();
+        ErrorComment::new(&issue, "second failure")
+            .post(&client)
+            .await
+            .unwrap();
+        let comments_path = "/repos/rust-lang/rust/issues/1234/comments?page=1&per_page=100";
+        assert_requests(
+            &server.finish(),
+            &[
+                expect("GET", comments_path),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/comments")
+                    .with_body(serde_json::json!({ "body": first })),
+                expect("GET", comments_path),
+                expect("PATCH", "/repos/rust-lang/rust/issues/comments/2")
+                    .with_body(serde_json::json!({ "body": second })),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn placeholder_is_edited_to_result() {
+        let server = TestServer::start(vec![
+            (
+                "201 Created",
+                comment_json(1612361421, ":hourglass_flowing_sand: Working on it..."),
+            ),
+            ("200 OK", comment_json(1612361421, "r? @octocat")),
+        ]);
+        let client = server.github_client();
+        let issue = test_pr();
+        let placeholder = PlaceholderComment::post(&client, &issue).await.unwrap();
+        placeholder.finish(&client, "r? @octocat").await.unwrap();
+
+        let requests = server.finish();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(
+            requests[0].path,
+            "/repos/rust-lang/rust/issues/1234/comments"
+        );
+        assert_eq!(requests[1].method, "PATCH");
+        assert_eq!(
+            requests[1].path,
+            "/repos/rust-lang/rust/issues/comments/1612361421"
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&requests[1].body).unwrap(),
+            serde_json::json!({"body": "r? @octocat"})
+        );
+    }
+}
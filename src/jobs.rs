@@ -11,7 +11,10 @@
 //! Please refer to https://docs.rs/serde_json/latest/serde_json/value/fn.from_value.html
 //! on how to interpret it as an instance of type T, implementing Serialize/Deserialize.
 //!
-//! The schedule is a cron::Schedule
+//! The schedule is a cron::Schedule, which can be created with `parse_schedule`
+//! from either a standard cron expression (`min hour day month weekday`, like
+//! `0 9 * * Mon-Fri` for every weekday at 9am UTC) or the extended format of
+//! the cron crate, which adds the seconds and the year.
 //! Please refer to https://docs.rs/cron/latest/cron/struct.Schedule.html for further info
 //!
 //! ## Example, sending a zulip message once a week
@@ -35,20 +38,43 @@
 //!     JobSchedule {
 //!         name: ZulipMessageJob.name(),
 //!         schedule: Schedule::from_str("0 30 11 * * FRI *").unwrap(),
+//!         timezone: chrono_tz::America::New_York,
 //!         metadata: serde_json::value::to_value(ZulipMetadata {
 //!             message: "@T-release meeting!".to_string()
 //!             channel: "T-release".to_string(),
 //!         }).unwrap(),
 //!     }
+//!
+//! ## Pausing the nudges
+//!
+//! Jobs that ping people or post reminders return `true` from
+//! [`Job::is_nudge`]. They are skipped while the nudges are paused, either
+//! with the `TRIAGEBOT_DISABLE_NUDGES` environment variable (unless it is
+//! empty, `0`, `false`, `no` or `off`) or with the
+//! `/pause-nudges` admin endpoint, e.g. during an incident. The other jobs
+//! and the commands keep working.
 
+use std::env;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+use anyhow::Context as _;
 use async_trait::async_trait;
+use chrono_tz::Tz;
 use cron::Schedule;
 
 use crate::{
     db::jobs::JobSchedule,
-    handlers::{docs_update::DocsUpdateJob, rustc_commits::RustcCommitsJob, Context},
+    handlers::{
+        deferred_nudges::DeferredNudgesJob,
+        docs_update::DocsUpdateJob,
+        label_sync::{self, LabelSyncJob, LabelSyncMetadata},
+        meeting_reminders::{self, MeetingRemindersJob, MeetingRemindersMetadata},
+        pull_requests_assignment_update::PullRequestAssignmentUpdate,
+        reconcile::{ReconcileJob, ReconcileMetadata, ReconcileScope},
+        rustc_commits::RustcCommitsJob,
+        Context,
+    },
 };
 
 /// How often new cron-based jobs will be placed in the queue.
@@ -61,7 +87,15 @@ pub const JOB_PROCESSING_CADENCE_IN_SECS: u64 = 60;
 
 // The default jobs list that are currently scheduled to run
 pub fn jobs() -> Vec<Box<dyn Job + Send + Sync>> {
-    vec![Box::new(DocsUpdateJob), Box::new(RustcCommitsJob)]
+    vec![
+        Box::new(DocsUpdateJob),
+        Box::new(RustcCommitsJob),
+        Box::new(PullRequestAssignmentUpdate),
+        Box::new(ReconcileJob),
+        Box::new(MeetingRemindersJob),
+        Box::new(DeferredNudgesJob),
+        Box::new(LabelSyncJob),
+    ]
 }
 
 // Definition of the schedule repetition for the jobs we want to run.
@@ -71,21 +105,166 @@ pub fn default_jobs() -> Vec<JobSchedule> {
             name: DocsUpdateJob.name(),
             // Around 9am Pacific time on every Monday.
             schedule: Schedule::from_str("0 00 17 * * Mon *").unwrap(),
+            timezone: Tz::UTC,
             metadata: serde_json::Value::Null,
         },
         JobSchedule {
             name: RustcCommitsJob.name(),
             // Every 30 minutes...
             schedule: Schedule::from_str("* 0,30 * * * * *").unwrap(),
+            timezone: Tz::UTC,
+            metadata: serde_json::Value::Null,
+        },
+        JobSchedule {
+            name: PullRequestAssignmentUpdate.name(),
+            // Every 6 hours. The work queues already follow the assignments
+            // through the webhooks, this only corrects the drift from the
+            // missed ones, and each run pages through all the open PRs of
+            // rust-lang/rust, a few dozen GraphQL queries.
+            schedule: Schedule::from_str("0 15 */6 * * * *").unwrap(),
+            timezone: Tz::UTC,
+            metadata: serde_json::Value::Null,
+        },
+        JobSchedule {
+            name: ReconcileJob.name(),
+            // Every hour, to recover from dropped webhooks. Only the scopes
+            // enabled by the `[reconcile]` config of the repositories are run.
+            schedule: Schedule::from_str("0 45 * * * * *").unwrap(),
+            timezone: Tz::UTC,
+            metadata: serde_json::to_value(ReconcileMetadata {
+                repos: vec!["rust-lang/rust".to_string()],
+                scope: vec![ReconcileScope::StateLabels, ReconcileScope::ReactionClaims],
+            })
+            .unwrap(),
+        },
+        JobSchedule {
+            name: MeetingRemindersJob.name(),
+            // Every 30 minutes, reminding the meetings of the next hour.
+            schedule: Schedule::from_str("0 5,35 * * * * *").unwrap(),
+            timezone: Tz::UTC,
+            metadata: serde_json::to_value(MeetingRemindersMetadata {
+                calendars: meeting_reminders::calendars_from_env(),
+                lead_minutes: 60,
+            })
+            .unwrap(),
+        },
+        JobSchedule {
+            name: DeferredNudgesJob.name(),
+            // Every 30 minutes, to post the nudges deferred to the active hours.
+            schedule: Schedule::from_str("0 10,40 * * * * *").unwrap(),
+            timezone: Tz::UTC,
             metadata: serde_json::Value::Null,
         },
+        JobSchedule {
+            name: LabelSyncJob.name(),
+            // Once a day, the label sets rarely change.
+            schedule: Schedule::from_str("0 20 4 * * * *").unwrap(),
+            timezone: Tz::UTC,
+            metadata: serde_json::to_value(LabelSyncMetadata {
+                repos: label_sync::repos_from_env(),
+            })
+            .unwrap(),
+        },
     ]
 }
 
+/// Whether the nudges were paused with [`set_nudges_paused`].
+static NUDGES_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Returns whether the nudges are currently disabled: the nudge jobs, and the
+/// handlers pinging or messaging people on their own. The commands still run.
+pub fn nudges_paused() -> bool {
+    NUDGES_PAUSED.load(Ordering::Relaxed)
+        || env::var("TRIAGEBOT_DISABLE_NUDGES").map_or(false, |value| is_set_flag(&value))
+}
+
+/// Whether the value of a flag environment variable sets it: anything but an
+/// empty value, `0`, `false`, `no` or `off`.
+fn is_set_flag(value: &str) -> bool {
+    let value = value.trim();
+    !(value.is_empty()
+        || ["0", "false", "no", "off"]
+            .iter()
+            .any(|unset| value.eq_ignore_ascii_case(unset)))
+}
+
+/// Pauses or resumes the nudge jobs, until the next restart.
+///
+/// This has no effect on the `TRIAGEBOT_DISABLE_NUDGES` environment variable.
+pub fn set_nudges_paused(paused: bool) {
+    NUDGES_PAUSED.store(paused, Ordering::Relaxed);
+}
+
+/// Returns whether `job` must be skipped instead of run, given whether the
+/// nudges are paused (see [`nudges_paused`]).
+pub fn is_skipped(job: &dyn Job, nudges_paused: bool) -> bool {
+    job.is_nudge() && nudges_paused
+}
+
+/// Parses a cron expression, in UTC.
+///
+/// Standard expressions with 5 fields are run at the start of the minute,
+/// every year, and number the days of the week from 0 (or 7) for Sunday to 6
+/// for Saturday. Expressions with 6 or 7 fields are passed as is to the cron
+/// crate, which numbers them from 1 for Sunday to 7 for Saturday.
+pub fn parse_schedule(expr: &str) -> anyhow::Result<Schedule> {
+    let fields: Vec<_> = expr.split_whitespace().collect();
+    let expr = match fields.len() {
+        5 => {
+            let day_of_week = standard_day_of_week(fields[4])
+                .with_context(|| format!("invalid cron expression `{expr}`"))?;
+            format!("0 {} {day_of_week} *", fields[..4].join(" "))
+        }
+        6 | 7 => fields.join(" "),
+        n => anyhow::bail!("invalid cron expression `{expr}`: expected 5 to 7 fields, got {n}"),
+    };
+    Schedule::from_str(&expr).with_context(|| format!("invalid cron expression `{expr}`"))
+}
+
+/// Converts the numbered days of the week of a standard cron expression to
+/// their name, which means the same day for the cron crate.
+fn standard_day_of_week(field: &str) -> anyhow::Result<String> {
+    const DAYS: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+    let day = |day: &str| -> anyhow::Result<String> {
+        match day.parse::<usize>() {
+            Ok(n) => DAYS
+                .get(n)
+                .map(|day| day.to_string())
+                .with_context(|| format!("day of the week `{n}` is not between 0 and 7")),
+            Err(_) => Ok(day.to_string()),
+        }
+    };
+    let items = field.split(',').map(|item| {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (item, None),
+        };
+        let range = match range.split_once('-') {
+            // Sunday is the first day for the cron crate, so a range ending
+            // with it is split in two.
+            Some((start, "7")) if step.is_none() => format!("{}-SAT,SUN", day(start)?),
+            Some((_, "7")) => anyhow::bail!("use `0` rather than `7` for Sunday in `{item}`"),
+            Some((start, end)) => format!("{}-{}", day(start)?, day(end)?),
+            None => day(range)?,
+        };
+        Ok(match step {
+            Some(step) => format!("{range}/{step}"),
+            None => range,
+        })
+    });
+    Ok(items.collect::<anyhow::Result<Vec<_>>>()?.join(","))
+}
+
 #[async_trait]
 pub trait Job {
     fn name(&self) -> &str;
 
+    /// Whether the job pings people or posts reminders, and must be skipped
+    /// while the nudges are paused.
+    fn is_nudge(&self) -> bool {
+        false
+    }
+
     async fn run(&self, ctx: &Context, metadata: &serde_json::Value) -> anyhow::Result<()>;
 }
 
@@ -107,3 +286,149 @@ fn jobs_defined() {
         .iter()
         .for_each(|j| assert!(all_job_names.contains(&j.name.to_string())));
 }
+
+#[test]
+fn paused_nudges_are_skipped() {
+    let skipped: Vec<_> = jobs()
+        .into_iter()
+        .filter(|job| is_skipped(job.as_ref(), true))
+        .map(|job| job.name().to_string())
+        .collect();
+    assert_eq!(skipped, ["meeting_reminders", "deferred_nudges"]);
+    assert!(!jobs().iter().any(|job| is_skipped(job.as_ref(), false)));
+}
+
+#[test]
+fn disable_nudges_values() {
+    for value in ["1", "true", "yes", "on"] {
+        assert!(is_set_flag(value), "{value} is not set");
+    }
+    for value in ["", " ", "0", "false", "FALSE", "no", "off"] {
+        assert!(!is_set_flag(value), "{value} is set");
+    }
+}
+
+#[test]
+fn parse_cron_expressions() {
+    use chrono::{TimeZone, Utc};
+
+    let next_runs = |expr: &str, after| {
+        parse_schedule(expr)
+            .unwrap()
+            .after(&after)
+            .take(3)
+            .map(|t| t.to_rfc3339())
+            .collect::<Vec<_>>()
+    };
+    // A Friday.
+    let friday = Utc.with_ymd_and_hms(2024, 5, 17, 10, 0, 0).unwrap();
+
+    // Every weekday at 9am UTC.
+    assert_eq!(
+        next_runs("0 9 * * Mon-Fri", friday),
+        [
+            "2024-05-20T09:00:00+00:00",
+            "2024-05-21T09:00:00+00:00",
+            "2024-05-22T09:00:00+00:00",
+        ]
+    );
+    // Every 6 hours, at 15 past the hour.
+    assert_eq!(
+        next_runs("0 15 */6 * * * *", friday),
+        [
+            "2024-05-17T12:15:00+00:00",
+            "2024-05-17T18:15:00+00:00",
+            "2024-05-18T00:15:00+00:00",
+        ]
+    );
+    // The first day of each month at midnight.
+    assert_eq!(
+        next_runs("0 0 1 * *", friday),
+        [
+            "2024-06-01T00:00:00+00:00",
+            "2024-07-01T00:00:00+00:00",
+            "2024-08-01T00:00:00+00:00",
+        ]
+    );
+
+    // Every weekday at 9am UTC, with the standard numbering.
+    assert_eq!(
+        next_runs("0 9 * * 1-5", friday),
+        [
+            "2024-05-20T09:00:00+00:00",
+            "2024-05-21T09:00:00+00:00",
+            "2024-05-22T09:00:00+00:00",
+        ]
+    );
+    // Every Sunday, numbered 0 or 7.
+    let sundays = [
+        "2024-05-19T09:00:00+00:00",
+        "2024-05-26T09:00:00+00:00",
+        "2024-06-02T09:00:00+00:00",
+    ];
+    assert_eq!(next_runs("0 9 * * 0", friday), sundays);
+    assert_eq!(next_runs("0 9 * * 7", friday), sundays);
+    // From Friday to Sunday.
+    assert_eq!(
+        next_runs("0 9 * * 5-7", friday),
+        [
+            "2024-05-18T09:00:00+00:00",
+            "2024-05-19T09:00:00+00:00",
+            "2024-05-24T09:00:00+00:00",
+        ]
+    );
+    assert_eq!(
+        standard_day_of_week("1,3-5,*/2,Sat").unwrap(),
+        "MON,WED-FRI,*/2,Sat"
+    );
+
+    assert!(parse_schedule("0 9 * *").is_err());
+    assert!(parse_schedule("0 25 * * *").is_err());
+    assert!(parse_schedule("0 9 * * 8").is_err());
+}
+
+#[test]
+fn next_run_is_after() {
+    use chrono::{TimeZone, Utc};
+
+    let job = JobSchedule {
+        name: DocsUpdateJob.name(),
+        schedule: parse_schedule("0 17 * * Mon").unwrap(),
+        timezone: Tz::UTC,
+        metadata: serde_json::Value::Null,
+    };
+    let monday = Utc.with_ymd_and_hms(2024, 5, 20, 17, 0, 0).unwrap();
+    // The next run is strictly after, even when the job would run now.
+    assert_eq!(
+        job.next_run(monday),
+        Some(Utc.with_ymd_and_hms(2024, 5, 27, 17, 0, 0).unwrap())
+    );
+}
+
+#[test]
+fn next_run_across_dst() {
+    use chrono::{TimeZone, Utc};
+
+    let job = JobSchedule {
+        name: DocsUpdateJob.name(),
+        // Every day at 9am in Berlin.
+        schedule: parse_schedule("0 9 * * *").unwrap(),
+        timezone: chrono_tz::Europe::Berlin,
+        metadata: serde_json::Value::Null,
+    };
+    let mut runs = Vec::new();
+    // Berlin switches from UTC+1 to UTC+2 on 2024-03-31.
+    let mut after = Utc.with_ymd_and_hms(2024, 3, 29, 12, 0, 0).unwrap();
+    for _ in 0..3 {
+        after = job.next_run(after).unwrap();
+        runs.push(after.to_rfc3339());
+    }
+    assert_eq!(
+        runs,
+        [
+            "2024-03-30T08:00:00+00:00",
+            "2024-03-31T07:00:00+00:00",
+            "2024-04-01T07:00:00+00:00",
+        ]
+    );
+}
@@ -15,17 +15,24 @@ use tracing as log;
 pub mod actions;
 pub mod agenda;
 mod changelogs;
+mod ci_logs;
+pub mod compression;
 pub mod config;
 pub mod db;
 pub mod github;
 pub mod handlers;
 pub mod interactions;
 pub mod jobs;
+pub mod logging;
+pub mod meetings;
+pub mod metrics;
 pub mod notification_listing;
 pub mod payload;
 pub mod rfcbot;
 pub mod team;
-mod team_data;
+pub mod team_data;
+#[cfg(test)]
+mod test_server;
 pub mod triage;
 pub mod zulip;
 
@@ -74,6 +81,12 @@ pub enum EventName {
     ///
     /// <https://docs.github.com/en/developers/webhooks-and-events/webhooks/webhook-events-and-payloads#create>
     Create,
+    /// Activity on a GitHub Actions job.
+    ///
+    /// This gets translated to [`github::Event::WorkflowJob`] when sent to a handler.
+    ///
+    /// <https://docs.github.com/en/webhooks/webhook-events-and-payloads#workflow_job>
+    WorkflowJob,
     /// All other unhandled webhooks.
     Other,
 }
@@ -89,6 +102,7 @@ impl std::str::FromStr for EventName {
             "issues" => EventName::Issue,
             "push" => EventName::Push,
             "create" => EventName::Create,
+            "workflow_job" => EventName::WorkflowJob,
             _ => EventName::Other,
         })
     }
@@ -107,6 +121,7 @@ impl fmt::Display for EventName {
                 EventName::PullRequest => "pull_request",
                 EventName::Push => "push",
                 EventName::Create => "create",
+                EventName::WorkflowJob => "workflow_job",
                 EventName::Other => "other",
             }
         )
@@ -183,6 +198,7 @@ pub async fn webhook(
     payload: String,
     ctx: &handlers::Context,
 ) -> Result<bool, WebhookError> {
+    let event_name = event.to_string();
     let event = match event {
         EventName::PullRequestReview => {
             let mut payload = deserialize_payload::<github::PullRequestReviewEvent>(&payload)
@@ -269,12 +285,22 @@ pub async fn webhook(
 
             github::Event::Create(payload)
         }
+        EventName::WorkflowJob => {
+            let payload = deserialize_payload::<github::WorkflowJobEvent>(&payload)
+                .with_context(|| format!("{:?} failed to deserialize", event))
+                .map_err(anyhow::Error::from)?;
+
+            log::info!("handling workflow job event {:?}", payload);
+
+            github::Event::WorkflowJob(payload)
+        }
         // Other events need not be handled
         EventName::Other => {
             return Ok(false);
         }
     };
     let errors = handlers::handle(&ctx, &event).await;
+    metrics::metrics().count_event(&event_name);
     let mut other_error = false;
     let mut message = String::new();
     for err in errors {
@@ -0,0 +1,93 @@
+//! Sets up the logging of the server.
+//!
+//! Logs are human-readable by default. Setting `TRIAGEBOT_LOG_FORMAT=json`
+//! emits one JSON object per line instead, with the fields of the current
+//! span (like the `uuid` of the webhook request) for log aggregation.
+//!
+//! In both cases, the level is filtered with `RUST_LOG`.
+
+use std::env;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Installs the global subscriber.
+///
+/// Panics if the format is unknown or a subscriber was already installed.
+pub fn init() {
+    let filter = EnvFilter::from_default_env();
+    let format = env::var("TRIAGEBOT_LOG_FORMAT").unwrap_or_default();
+    match format.as_str() {
+        "" | "human" => tracing_subscriber::fmt::Subscriber::builder()
+            .with_env_filter(filter)
+            .with_ansi(env::var_os("DISABLE_COLOR").is_none())
+            .finish()
+            .try_init(),
+        "json" => json_subscriber(filter, std::io::stdout).try_init(),
+        _ => panic!("unknown `TRIAGEBOT_LOG_FORMAT` {format:?}, expected `human` or `json`"),
+    }
+    .unwrap();
+}
+
+fn json_subscriber<W>(filter: EnvFilter, make_writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::Subscriber::builder()
+        .with_env_filter(filter)
+        .with_writer(make_writer)
+        .json()
+        .with_current_span(true)
+        .with_span_list(false)
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = json_subscriber(EnvFilter::new("info"), move || writer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("starting");
+            let span = tracing::span!(tracing::Level::INFO, "request", uuid = "4f0b6e6a");
+            let _entered = span.enter();
+            tracing::warn!(issue = 1234, "handling \"event\"");
+            tracing::debug!("filtered out");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["fields"]["message"], "starting");
+        assert!(lines[0].get("span").is_none());
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["fields"]["message"], "handling \"event\"");
+        assert_eq!(lines[1]["fields"]["issue"], 1234);
+        assert_eq!(lines[1]["span"]["name"], "request");
+        assert_eq!(lines[1]["span"]["uuid"], "4f0b6e6a");
+    }
+}
@@ -5,7 +5,7 @@ use futures::future::FutureExt;
 use futures::StreamExt;
 use hyper::{header, Body, Request, Response, Server, StatusCode};
 use route_recognizer::Router;
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{env, net::SocketAddr, sync::Arc, time::Instant};
 use tokio::{task, time};
 use tower::{Service, ServiceExt};
 use tracing as log;
@@ -14,20 +14,44 @@ use triagebot::handlers::pull_requests_assignment_update::PullRequestAssignmentU
 use triagebot::jobs::{
     default_jobs, Job, JOB_PROCESSING_CADENCE_IN_SECS, JOB_SCHEDULING_CADENCE_IN_SECS,
 };
-use triagebot::{db, github, handlers::Context, notification_listing, payload, EventName};
+use triagebot::{
+    compression, db, github, handlers::Context, notification_listing, payload, EventName,
+};
 
-async fn handle_agenda_request(req: String) -> anyhow::Result<String> {
-    if req == "/agenda/lang/triage" {
-        return triagebot::agenda::lang().call().await;
+/// Returns the agenda served at `path`, like `/agenda/lang/triage`.
+fn agenda_at(path: &str) -> Option<Box<dyn triagebot::actions::Action + Send + Sync>> {
+    match path {
+        "/agenda/lang/triage" => Some(triagebot::agenda::lang()),
+        "/agenda/lang/planning" => Some(triagebot::agenda::lang_planning()),
+        "/agenda/types/planning" => Some(triagebot::agenda::types_planning()),
+        _ => None,
     }
-    if req == "/agenda/lang/planning" {
-        return triagebot::agenda::lang_planning().call().await;
-    }
-    if req == "/agenda/types/planning" {
-        return triagebot::agenda::types_planning().call().await;
+}
+
+async fn handle_agenda_request(req: String) -> anyhow::Result<String> {
+    match agenda_at(&req) {
+        Some(agenda) => agenda.call().await,
+        None => anyhow::bail!("Unknown agenda; see /agenda for index."),
     }
+}
+
+/// The maximum number of results in a page of an agenda query.
+const MAX_AGENDA_PAGE_LIMIT: usize = 100;
 
-    anyhow::bail!("Unknown agenda; see /agenda for index.")
+/// Returns whether `authorization` is the `Bearer` token matching the
+/// `TRIAGEBOT_ADMIN_SECRET` environment variable.
+///
+/// Without the variable, the admin endpoints are disabled.
+fn is_admin_authorized(authorization: Option<&str>) -> bool {
+    let Ok(secret) = env::var("TRIAGEBOT_ADMIN_SECRET") else {
+        return false;
+    };
+    let Some(token) = authorization.and_then(|auth| auth.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    !secret.is_empty()
+        && token.len() == secret.len()
+        && openssl::memcmp::eq(token.as_bytes(), secret.as_bytes())
 }
 
 async fn serve_req(
@@ -39,6 +63,8 @@ async fn serve_req(
     let mut router = Router::new();
     router.add("/triage", "index".to_string());
     router.add("/triage/:owner/:repo", "pulls".to_string());
+    router.add("/triage/:owner/:repo/stats", "stats".to_string());
+    router.add("/triage/:owner/:repo/burndown", "burndown".to_string());
     let (req, body_stream) = req.into_parts();
 
     if let Ok(matcher) = router.recognize(req.uri.path()) {
@@ -47,6 +73,33 @@ async fn serve_req(
             let owner = params.find("owner");
             let repo = params.find("repo");
             return triagebot::triage::pulls(ctx, owner.unwrap(), repo.unwrap()).await;
+        } else if matcher.handler().as_str() == "stats" {
+            let params = matcher.params();
+            let owner = params.find("owner");
+            let repo = params.find("repo");
+            return triagebot::triage::stats(ctx, owner.unwrap(), repo.unwrap(), req.uri.query())
+                .await;
+        } else if matcher.handler().as_str() == "burndown" {
+            let authorization = req
+                .headers
+                .get(header::AUTHORIZATION)
+                .and_then(|auth| auth.to_str().ok());
+            if req.method != hyper::Method::GET || !is_admin_authorized(authorization) {
+                return Ok(Response::builder()
+                    .status(StatusCode::FORBIDDEN)
+                    .body(Body::empty())
+                    .unwrap());
+            }
+            let params = matcher.params();
+            let owner = params.find("owner");
+            let repo = params.find("repo");
+            return triagebot::triage::burndown(
+                ctx,
+                owner.unwrap(),
+                repo.unwrap(),
+                req.uri.query(),
+            )
+            .await;
         } else {
             return triagebot::triage::index();
         }
@@ -84,12 +137,95 @@ async fn serve_req(
         }
     }
 
+    // A page of the results of a query of an agenda, like
+    // `/agenda/lang/triage/page?query=nominated&repo=rust-lang/rust&cursor=0&limit=20`,
+    // for the UIs loading the agenda incrementally.
+    if let Some(step) = req.uri.path().strip_suffix("/page").and_then(agenda_at) {
+        let mut name = None;
+        let mut repo = None;
+        let mut page = github::Page {
+            cursor: 0,
+            limit: MAX_AGENDA_PAGE_LIMIT,
+        };
+        for (k, v) in url::form_urlencoded::parse(req.uri.query().unwrap_or("").as_bytes()) {
+            let number = match &*k {
+                "query" => {
+                    name = Some(v.into_owned());
+                    continue;
+                }
+                "repo" => {
+                    repo = Some(v.into_owned());
+                    continue;
+                }
+                "cursor" => &mut page.cursor,
+                "limit" => &mut page.limit,
+                _ => continue,
+            };
+            match v.parse() {
+                Ok(n) => *number = n,
+                Err(_) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from(format!("invalid `{k}` `{v}`")))
+                        .unwrap());
+                }
+            }
+        }
+        page.limit = page.limit.min(MAX_AGENDA_PAGE_LIMIT);
+        let (Some(name), Some(repo)) = (name, repo) else {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(
+                    "Please provide the `?query=<name>&repo=<owner>/<name>` query params on URL.",
+                ))
+                .unwrap());
+        };
+        return match step.query_page(&ctx.github, &name, &repo, page).await {
+            Ok(Some(page)) => Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&page).unwrap()))
+                .unwrap()),
+            Ok(None) => Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from(format!("Unknown query `{name}` on `{repo}`.")))
+                .unwrap()),
+            Err(e) => Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from(format!("{:?}", e)))
+                .unwrap()),
+        };
+    }
+
     if req.uri.path() == "/" {
         return Ok(Response::builder()
             .status(StatusCode::OK)
             .body(Body::from("Triagebot is awaiting triage."))
             .unwrap());
     }
+    if req.uri.path() == "/metrics" {
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(triagebot::metrics::metrics().render()))
+            .unwrap());
+    }
+    if req.uri.path() == "/rate-limit" {
+        let status = match ctx.github.rate_limit().await {
+            Ok(status) => status,
+            Err(e) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::from(format!("{:?}", e)))
+                    .unwrap());
+            }
+        };
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&status).unwrap()))
+            .unwrap());
+    }
     if req.uri.path() == "/bors-commit-list" {
         let res = db::rustc_commits::get_commits_with_artifacts(&*ctx.db.get().await).await;
         let res = match res {
@@ -127,6 +263,134 @@ async fn serve_req(
             )))
             .unwrap());
     }
+    if req.uri.path() == "/assignment-history" {
+        let authorization = req
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|auth| auth.to_str().ok());
+        if req.method != hyper::Method::GET || !is_admin_authorized(authorization) {
+            return Ok(Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::empty())
+                .unwrap());
+        }
+        let mut repo = None;
+        let mut pr = None;
+        if let Some(query) = req.uri.query() {
+            for (k, v) in url::form_urlencoded::parse(query.as_bytes()) {
+                match &*k {
+                    "repo" => repo = Some(v.into_owned()),
+                    "pr" => match v.parse() {
+                        Ok(n) => pr = Some(n),
+                        Err(_) => {
+                            return Ok(Response::builder()
+                                .status(StatusCode::BAD_REQUEST)
+                                .body(Body::from(format!("invalid PR number `{v}`")))
+                                .unwrap());
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+        let res = db::assignment_history::get_assignment_history(
+            &*ctx.db.get().await,
+            repo.as_deref(),
+            pr,
+            100,
+        )
+        .await;
+        let res = match res {
+            Ok(r) => r,
+            Err(e) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::from(format!("{:?}", e)))
+                    .unwrap());
+            }
+        };
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&res).unwrap()))
+            .unwrap());
+    }
+    if req.uri.path() == "/review-prefs" {
+        let authorization = req
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|auth| auth.to_str().ok());
+        if req.method != hyper::Method::GET || !is_admin_authorized(authorization) {
+            return Ok(Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::empty())
+                .unwrap());
+        }
+        let res = triagebot::handlers::pull_requests_assignment_update::get_all_review_prefs(
+            &*ctx.db.get().await,
+        )
+        .await;
+        let res = match res {
+            Ok(r) => r,
+            Err(e) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::from(format!("{:?}", e)))
+                    .unwrap());
+            }
+        };
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&res).unwrap()))
+            .unwrap());
+    }
+    if req.uri.path() == "/refresh-teams" {
+        let authorization = req
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|auth| auth.to_str().ok());
+        if req.method != hyper::Method::POST || !is_admin_authorized(authorization) {
+            return Ok(Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::empty())
+                .unwrap());
+        }
+        triagebot::team_data::clear_cache();
+        log::info!("team data cache cleared");
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::from("Team data will be fetched again on next use."))
+            .unwrap());
+    }
+    if req.uri.path() == "/pause-nudges" || req.uri.path() == "/resume-nudges" {
+        let authorization = req
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|auth| auth.to_str().ok());
+        if req.method != hyper::Method::POST || !is_admin_authorized(authorization) {
+            return Ok(Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::empty())
+                .unwrap());
+        }
+        let paused = req.uri.path() == "/pause-nudges";
+        triagebot::jobs::set_nudges_paused(paused);
+        log::warn!("nudges {}", if paused { "paused" } else { "resumed" });
+        let message = if triagebot::jobs::nudges_paused() == paused {
+            if paused {
+                "Nudges are paused, commands keep working."
+            } else {
+                "Nudges are resumed."
+            }
+        } else {
+            "Nudges stay paused by the `TRIAGEBOT_DISABLE_NUDGES` environment variable."
+        };
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::from(message))
+            .unwrap());
+    }
     if req.uri.path() == "/zulip-hook" {
         let mut c = body_stream;
         let mut payload = Vec::new();
@@ -242,16 +506,24 @@ async fn serve_req(
 }
 
 async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
+    // Check the configuration before touching the database.
+    let gh = github::GithubClient::try_new_from_env()?;
+    gh.validate_urls()?;
+    if env::var_os("TRIAGEBOT_PING_GITHUB").is_some() {
+        gh.ping().await?;
+    }
+    let oc = octocrab::OctocrabBuilder::new()
+        .personal_token(github::token_from_env()?)
+        .base_uri(gh.api_url())
+        .context("invalid GITHUB_API_URL")?
+        .build()
+        .expect("Failed to build octograb.");
+
     let pool = db::ClientPool::new();
     db::run_migrations(&mut *pool.get().await)
         .await
         .context("database migrations")?;
 
-    let gh = github::GithubClient::new_from_env();
-    let oc = octocrab::OctocrabBuilder::new()
-        .personal_token(github::default_token_from_env())
-        .build()
-        .expect("Failed to build octograb.");
     let ctx = Arc::new(Context {
         username: std::env::var("TRIAGEBOT_USERNAME").or_else(|err| match err {
             std::env::VarError::NotPresent => Ok("rustbot".to_owned()),
@@ -301,8 +573,15 @@ async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
                 // Only log the webhook responses at INFO level to avoid flooding the
                 // logs with huge responses. Other responses are at DEBUG.
                 let log_info_response = matches!(req.uri().path(), "/github-hook" | "/zulip-hook");
+                let accepts_gzip = compression::accepts_gzip(req.headers());
+                let route = triagebot::metrics::route(req.uri().path());
+                let start = Instant::now();
                 serve_req(req, ctx.clone(), agenda.clone())
+                    .then(move |resp| async move {
+                        compression::compress_response(accepts_gzip, resp?).await
+                    })
                     .map(move |mut resp| {
+                        triagebot::metrics::metrics().record_latency(route, start.elapsed());
                         if let Ok(resp) = &mut resp {
                             resp.headers_mut()
                                 .insert("X-Request-Id", uuid.to_string().parse().unwrap());
@@ -437,11 +716,7 @@ fn is_scheduled_jobs_disabled() -> bool {
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     dotenv::dotenv().ok();
-    tracing_subscriber::fmt::Subscriber::builder()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_ansi(std::env::var_os("DISABLE_COLOR").is_none())
-        .try_init()
-        .unwrap();
+    triagebot::logging::init();
 
     let port = env::var("PORT")
         .ok()
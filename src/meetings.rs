@@ -0,0 +1,214 @@
+//! Fetches the meetings of the Rust teams from their public Google calendars.
+//!
+//! Requests are authenticated with the `GOOGLE_API_KEY` environment variable.
+
+use anyhow::Context as _;
+use chrono::{DateTime, FixedOffset, Utc};
+use std::env;
+
+const GOOGLE_CALENDAR_API_URL: &str = "https://www.googleapis.com/calendar/v3";
+
+#[derive(Debug, serde::Deserialize)]
+struct CompilerMeetings {
+    #[serde(default)]
+    items: Vec<CompilerMeeting>,
+}
+
+/// An event of a team calendar.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompilerMeeting {
+    pub id: String,
+    #[serde(default)]
+    pub summary: String,
+    pub html_link: String,
+    pub start: Start,
+    /// The description of the event, which may contain HTML.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub attendees: Vec<Attendee>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attendee {
+    pub email: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Whether the attendee is the calendar the event was fetched from.
+    #[serde(rename = "self", default)]
+    pub is_self: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Start {
+    /// `None` for all-day events, which only have a date.
+    pub date_time: Option<DateTime<FixedOffset>>,
+}
+
+impl CompilerMeeting {
+    /// The start time of the meeting, `None` for all-day events.
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        self.start.date_time.map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Returns the first link of the description, which is usually the
+    /// agenda of the meeting.
+    pub fn agenda_link(&self) -> Option<&str> {
+        let description = self.description.as_deref()?;
+        let start = description.find("https://")?;
+        let link = &description[start..];
+        let end = link
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '<' | '>' | ')'))
+            .unwrap_or(link.len());
+        Some(&link[..end])
+    }
+}
+
+/// Returns the meetings of a calendar starting between `from` and `to`.
+pub async fn get_meetings(
+    client: &reqwest::Client,
+    calendar_id: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<CompilerMeeting>> {
+    let api_key = env::var("GOOGLE_API_KEY").context("`GOOGLE_API_KEY` is not set")?;
+    get_meetings_from(
+        client,
+        GOOGLE_CALENDAR_API_URL,
+        &api_key,
+        calendar_id,
+        from,
+        to,
+    )
+    .await
+}
+
+async fn get_meetings_from(
+    client: &reqwest::Client,
+    api_url: &str,
+    api_key: &str,
+    calendar_id: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<CompilerMeeting>> {
+    let calendar: String = url::form_urlencoded::byte_serialize(calendar_id.as_bytes()).collect();
+    let meetings: CompilerMeetings = client
+        .get(format!("{api_url}/calendars/{calendar}/events"))
+        .query(&[
+            ("key", api_key),
+            ("timeMin", &from.to_rfc3339()),
+            ("timeMax", &to.to_rfc3339()),
+            // Expands the recurring events into their occurrences.
+            ("singleEvents", "true"),
+            ("orderBy", "startTime"),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("failed to get the meetings of calendar {calendar_id}"))?;
+    Ok(meetings.items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServer;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn meetings_of_calendar() {
+        let server = TestServer::start(vec![(
+            "200 OK",
+            serde_json::json!({
+                "kind": "calendar#events",
+                "summary": "T-compiler",
+                "items": [
+                    {
+                        "id": "6onjdvf1a3ji2mq20bp0v8n6e0_20240516T140000Z",
+                        "summary": "T-compiler triage meeting",
+                        "htmlLink": "https://www.google.com/calendar/event?eid=Nm9uamR2ZjFhM2pp",
+                        "start": {"dateTime": "2024-05-16T16:00:00+02:00", "timeZone": "Europe/Berlin"},
+                    },
+                    {
+                        "id": "1k9kb6pusldfg7vn4r1fsbc3fs",
+                        "summary": "RustConf",
+                        "htmlLink": "https://www.google.com/calendar/event?eid=MWs5a2I2cHVzbGRm",
+                        "start": {"date": "2024-05-17"},
+                    },
+                ],
+            })
+            .to_string(),
+        )]);
+        let from = Utc.with_ymd_and_hms(2024, 5, 16, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 5, 18, 0, 0, 0).unwrap();
+        let meetings = get_meetings_from(
+            &reqwest::Client::new(),
+            server.url(),
+            "key",
+            "team@group.calendar.google.com",
+            from,
+            to,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(meetings.len(), 2);
+        assert_eq!(meetings[0].summary, "T-compiler triage meeting");
+        assert_eq!(
+            meetings[0].start_time(),
+            Some(Utc.with_ymd_and_hms(2024, 5, 16, 14, 0, 0).unwrap())
+        );
+        assert_eq!(meetings[1].start_time(), None);
+        assert_eq!(meetings[1].description, None);
+        assert!(meetings[1].attendees.is_empty());
+
+        let requests = server.finish();
+        assert!(requests[0]
+            .path
+            .starts_with("/calendars/team%40group.calendar.google.com/events?key=key&"));
+    }
+
+    #[test]
+    fn meeting_with_description() {
+        let meeting: CompilerMeeting = serde_json::from_value(serde_json::json!({
+            "kind": "calendar#event",
+            "id": "6onjdvf1a3ji2mq20bp0v8n6e0_20240516T140000Z",
+            "status": "confirmed",
+            "htmlLink": "https://www.google.com/calendar/event?eid=Nm9uamR2ZjFhM2pp",
+            "summary": "T-compiler triage meeting",
+            "description": "Weekly triage meeting.<br><br>Agenda: \
+                <a href=\"https://hackmd.io/@rust-compiler/triage\">https://hackmd.io/@rust-compiler/triage</a>",
+            "start": {"dateTime": "2024-05-16T16:00:00+02:00", "timeZone": "Europe/Berlin"},
+            "end": {"dateTime": "2024-05-16T17:00:00+02:00", "timeZone": "Europe/Berlin"},
+            "attendees": [
+                {
+                    "email": "team@group.calendar.google.com",
+                    "displayName": "T-compiler",
+                    "self": true,
+                    "responseStatus": "accepted",
+                },
+                {"email": "someone@example.com", "responseStatus": "needsAction"},
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            meeting.agenda_link(),
+            Some("https://hackmd.io/@rust-compiler/triage")
+        );
+        assert_eq!(meeting.attendees.len(), 2);
+        assert_eq!(
+            meeting.attendees[0].display_name.as_deref(),
+            Some("T-compiler")
+        );
+        assert!(meeting.attendees[0].is_self);
+        assert_eq!(meeting.attendees[1].email, "someone@example.com");
+        assert_eq!(meeting.attendees[1].display_name, None);
+        assert!(!meeting.attendees[1].is_self);
+    }
+}
@@ -0,0 +1,374 @@
+//! Collects the metrics of the server, exposed on `/metrics` in the
+//! Prometheus text format so that it can be scraped directly:
+//!
+//! - `triagebot_events_processed_total`: the GitHub webhook events processed
+//!   by the handlers, per event.
+//! - `triagebot_github_rate_limit_remaining`: the requests left in the GitHub
+//!   rate limit, as of the last response, per resource (like `core` or
+//!   `search`, which have separate limits).
+//! - `triagebot_request_duration_seconds`: the time to respond to the HTTP
+//!   requests, per route.
+//!
+//! The metrics are kept in memory, so they are reset when the server
+//! restarts.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The upper bounds of the latency buckets, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+lazy_static! {
+    static ref METRICS: Metrics = Metrics::default();
+}
+
+pub struct Metrics {
+    events: Mutex<BTreeMap<String, u64>>,
+    /// The resources are only known once a response with their rate limit is
+    /// received.
+    rate_limit_remaining: Mutex<BTreeMap<String, u64>>,
+    latencies: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            events: Mutex::default(),
+            rate_limit_remaining: Mutex::default(),
+            latencies: Mutex::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// The number of samples of each bucket, not cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|bound| value <= *bound) {
+            self.buckets[i] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+impl Metrics {
+    /// Counts a webhook event processed by the handlers.
+    pub fn count_event(&self, event: &str) {
+        *self
+            .events
+            .lock()
+            .unwrap()
+            .entry(event.to_string())
+            .or_default() += 1;
+    }
+
+    /// Records the remaining requests of the GitHub rate limit of `resource`,
+    /// as given by the `X-RateLimit-Resource` header.
+    pub fn set_rate_limit_remaining(&self, resource: &str, remaining: u64) {
+        let mut rate_limits = self.rate_limit_remaining.lock().unwrap();
+        match rate_limits.get_mut(resource) {
+            Some(value) => *value = remaining,
+            None => {
+                rate_limits.insert(resource.to_string(), remaining);
+            }
+        }
+    }
+
+    /// Records the time it took to respond to a request to `route`.
+    pub fn record_latency(&self, route: &'static str, latency: Duration) {
+        self.latencies
+            .lock()
+            .unwrap()
+            .entry(route)
+            .or_default()
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str(
+            "# HELP triagebot_events_processed_total GitHub webhook events processed by the handlers.\n\
+            # TYPE triagebot_events_processed_total counter\n",
+        );
+        for (event, count) in self.events.lock().unwrap().iter() {
+            writeln!(
+                out,
+                "triagebot_events_processed_total{{event=\"{event}\"}} {count}"
+            )
+            .unwrap();
+        }
+        let rate_limits = self.rate_limit_remaining.lock().unwrap().clone();
+        if !rate_limits.is_empty() {
+            out.push_str(
+                "# HELP triagebot_github_rate_limit_remaining Requests left in the GitHub rate limit.\n\
+                # TYPE triagebot_github_rate_limit_remaining gauge\n",
+            );
+        }
+        for (resource, remaining) in &rate_limits {
+            writeln!(
+                out,
+                "triagebot_github_rate_limit_remaining{{resource=\"{resource}\"}} {remaining}"
+            )
+            .unwrap();
+        }
+        let latencies = self.latencies.lock().unwrap().clone();
+        out.push_str(
+            "# HELP triagebot_request_duration_seconds Time to respond to an HTTP request.\n\
+            # TYPE triagebot_request_duration_seconds histogram\n",
+        );
+        for (route, histogram) in &latencies {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                writeln!(
+                    out,
+                    "triagebot_request_duration_seconds_bucket{{route=\"{route}\",le=\"{bound}\"}} {cumulative}"
+                )
+                .unwrap();
+            }
+            writeln!(
+                out,
+                "triagebot_request_duration_seconds_bucket{{route=\"{route}\",le=\"+Inf\"}} {}\n\
+                triagebot_request_duration_seconds_sum{{route=\"{route}\"}} {}\n\
+                triagebot_request_duration_seconds_count{{route=\"{route}\"}} {}",
+                histogram.count, histogram.sum, histogram.count
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+/// The metrics of the server.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Returns the route of `path`, used to label its metrics.
+///
+/// The paths with parameters are collapsed into their route, and unknown
+/// paths are grouped together, so that the number of labels stays bounded.
+pub fn route(path: &str) -> &'static str {
+    match path {
+        "/" => "/",
+        "/agenda" => "/agenda",
+        "/agenda/lang/triage" => "/agenda/lang/triage",
+        "/agenda/lang/planning" => "/agenda/lang/planning",
+        "/agenda/types/planning" => "/agenda/types/planning",
+        "/agenda/lang/triage/page" => "/agenda/lang/triage/page",
+        "/agenda/lang/planning/page" => "/agenda/lang/planning/page",
+        "/agenda/types/planning/page" => "/agenda/types/planning/page",
+        "/bors-commit-list" => "/bors-commit-list",
+        "/notifications" => "/notifications",
+        "/assignment-history" => "/assignment-history",
+        "/review-prefs" => "/review-prefs",
+        "/refresh-teams" => "/refresh-teams",
+        "/pause-nudges" => "/pause-nudges",
+        "/resume-nudges" => "/resume-nudges",
+        "/metrics" => "/metrics",
+        "/rate-limit" => "/rate-limit",
+        "/zulip-hook" => "/zulip-hook",
+        "/github-hook" => "/github-hook",
+        "/triage" => "/triage",
+        _ => {
+            let segments: Vec<_> = path.trim_matches('/').split('/').collect();
+            match segments.as_slice() {
+                ["triage", _, _] => "/triage/:owner/:repo",
+                ["triage", _, _, "stats"] => "/triage/:owner/:repo/stats",
+                ["triage", _, _, "burndown"] => "/triage/:owner/:repo/burndown",
+                _ => "other",
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes() {
+        assert_eq!(route("/github-hook"), "/github-hook");
+        assert_eq!(route("/triage/rust-lang/rust"), "/triage/:owner/:repo");
+        assert_eq!(
+            route("/triage/rust-lang/rust/stats"),
+            "/triage/:owner/:repo/stats"
+        );
+        assert_eq!(route("/wp-login.php"), "other");
+        assert_eq!(route("/triage/rust-lang/rust/secret"), "other");
+    }
+
+    #[test]
+    fn latency_histogram() {
+        let metrics = Metrics::default();
+        metrics.record_latency("/github-hook", Duration::from_millis(30));
+        metrics.record_latency("/github-hook", Duration::from_millis(200));
+        metrics.record_latency("/github-hook", Duration::from_secs(60));
+        metrics.record_latency("/agenda", Duration::from_millis(1));
+
+        let output = metrics.render();
+        let lines: Vec<_> = output.lines().collect();
+        assert!(lines.contains(
+            &"triagebot_request_duration_seconds_bucket{route=\"/agenda\",le=\"0.005\"} 1"
+        ));
+        for line in [
+            "triagebot_request_duration_seconds_bucket{route=\"/github-hook\",le=\"0.025\"} 0",
+            "triagebot_request_duration_seconds_bucket{route=\"/github-hook\",le=\"0.05\"} 1",
+            "triagebot_request_duration_seconds_bucket{route=\"/github-hook\",le=\"0.25\"} 2",
+            "triagebot_request_duration_seconds_bucket{route=\"/github-hook\",le=\"30\"} 2",
+            "triagebot_request_duration_seconds_bucket{route=\"/github-hook\",le=\"+Inf\"} 3",
+            "triagebot_request_duration_seconds_sum{route=\"/github-hook\"} 60.23",
+            "triagebot_request_duration_seconds_count{route=\"/github-hook\"} 3",
+        ] {
+            assert!(lines.contains(&line), "missing {line} in:\n{output}");
+        }
+    }
+
+    /// A sample of the Prometheus text format.
+    #[derive(Debug, PartialEq)]
+    struct Sample {
+        name: String,
+        labels: Vec<(String, String)>,
+        value: f64,
+    }
+
+    /// Parses the Prometheus text format, checking that each sample belongs to
+    /// a metric declared with `# TYPE` beforehand.
+    fn parse(text: &str) -> Vec<Sample> {
+        let mut types: BTreeMap<String, String> = BTreeMap::new();
+        let mut samples = Vec::new();
+        for line in text.lines() {
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut words = comment.splitn(3, ' ');
+                match (words.next(), words.next(), words.next()) {
+                    (Some("TYPE"), Some(name), Some(kind)) => {
+                        assert!(
+                            ["counter", "gauge", "histogram"].contains(&kind),
+                            "unknown type in {line}"
+                        );
+                        assert!(types.insert(name.to_string(), kind.to_string()).is_none());
+                    }
+                    (Some("HELP"), Some(_), Some(_)) => {}
+                    _ => panic!("invalid comment {line}"),
+                }
+                continue;
+            }
+            let (series, value) = line.rsplit_once(' ').expect(line);
+            let (name, labels) = match series.split_once('{') {
+                Some((name, labels)) => {
+                    let labels = labels.strip_suffix('}').expect(line);
+                    let labels = labels
+                        .split(',')
+                        .map(|label| {
+                            let (key, value) = label.split_once('=').expect(line);
+                            let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"'));
+                            (key.to_string(), value.expect(line).to_string())
+                        })
+                        .collect();
+                    (name, labels)
+                }
+                None => (series, Vec::new()),
+            };
+            assert!(
+                name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                "invalid name in {line}"
+            );
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|suffix| name.strip_suffix(suffix))
+                .filter(|family| types.get(*family).map(String::as_str) == Some("histogram"))
+                .unwrap_or(name);
+            assert!(types.contains_key(family), "undeclared metric in {line}");
+            let value = match value {
+                "+Inf" => f64::INFINITY,
+                value => value.parse().expect(line),
+            };
+            samples.push(Sample {
+                name: name.to_string(),
+                labels,
+                value,
+            });
+        }
+        samples
+    }
+
+    #[test]
+    fn prometheus_format() {
+        let metrics = Metrics::default();
+        // The rate limit is unknown until the first response.
+        assert!(!metrics.render().contains("rate_limit"));
+        metrics.count_event("issue_comment");
+        metrics.count_event("issue_comment");
+        metrics.count_event("push");
+        metrics.set_rate_limit_remaining("core", 4321);
+        metrics.set_rate_limit_remaining("search", 29);
+        metrics.set_rate_limit_remaining("core", 4320);
+        metrics.record_latency("/github-hook", Duration::from_millis(30));
+
+        let samples = parse(&metrics.render());
+        let sample = |name: &str, labels: &[(&str, &str)]| {
+            samples
+                .iter()
+                .find(|s| {
+                    s.name == name
+                        && s.labels
+                            .iter()
+                            .map(|(k, v)| (k.as_str(), v.as_str()))
+                            .eq(labels.iter().copied())
+                })
+                .unwrap_or_else(|| panic!("missing {name} {labels:?}"))
+                .value
+        };
+        assert_eq!(
+            sample(
+                "triagebot_events_processed_total",
+                &[("event", "issue_comment")]
+            ),
+            2.0
+        );
+        assert_eq!(
+            sample("triagebot_events_processed_total", &[("event", "push")]),
+            1.0
+        );
+        assert_eq!(
+            sample(
+                "triagebot_github_rate_limit_remaining",
+                &[("resource", "core")]
+            ),
+            4320.0
+        );
+        assert_eq!(
+            sample(
+                "triagebot_github_rate_limit_remaining",
+                &[("resource", "search")]
+            ),
+            29.0
+        );
+        assert_eq!(
+            sample(
+                "triagebot_request_duration_seconds_bucket",
+                &[("route", "/github-hook"), ("le", "+Inf")]
+            ),
+            1.0
+        );
+        assert_eq!(
+            sample(
+                "triagebot_request_duration_seconds_count",
+                &[("route", "/github-hook")]
+            ),
+            1.0
+        );
+    }
+}
@@ -10,15 +10,9 @@ pub enum Team {
 impl Team {
     pub fn label(&self) -> crate::github::Label {
         match self {
-            Team::Libs => crate::github::Label {
-                name: String::from("T-libs"),
-            },
-            Team::Compiler => crate::github::Label {
-                name: String::from("T-compiler"),
-            },
-            Team::Lang => crate::github::Label {
-                name: String::from("T-lang"),
-            },
+            Team::Libs => crate::github::Label::new("T-libs"),
+            Team::Compiler => crate::github::Label::new("T-compiler"),
+            Team::Lang => crate::github::Label::new("T-lang"),
         }
     }
 }
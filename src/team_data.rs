@@ -2,37 +2,170 @@ use crate::github::GithubClient;
 use anyhow::Context as _;
 use rust_team_data::v1::{Teams, ZulipMapping, BASE_URL};
 use serde::de::DeserializeOwned;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
-async fn by_url<T: DeserializeOwned>(client: &GithubClient, path: &str) -> anyhow::Result<T> {
-    let base = std::env::var("TEAMS_API_URL").unwrap_or(BASE_URL.to_string());
-    let url = format!("{}{}", base, path);
-    for _ in 0i32..3 {
-        let map: Result<T, _> = client.json(client.raw().get(&url)).await;
-        match map {
-            Ok(v) => return Ok(v),
-            Err(e) => {
-                if e.downcast_ref::<reqwest::Error>()
-                    .map_or(false, |e| e.is_timeout())
-                {
-                    continue;
-                } else {
-                    return Err(e);
+/// How long the team data is cached before being fetched again.
+const REFRESH_EVERY: Duration = Duration::from_secs(5 * 60);
+
+lazy_static::lazy_static! {
+    static ref TEAM_CLIENT: TeamClient = TeamClient::new(
+        std::env::var("TEAMS_API_URL").unwrap_or(BASE_URL.to_string())
+    );
+}
+
+/// Fetches the data of the team API, caching it for [`REFRESH_EVERY`].
+pub struct TeamClient {
+    base_url: String,
+    teams: RwLock<Option<(Teams, Instant)>>,
+    zulip_map: RwLock<Option<(ZulipMapping, Instant)>>,
+}
+
+impl TeamClient {
+    pub fn new(base_url: String) -> TeamClient {
+        TeamClient {
+            base_url,
+            teams: RwLock::new(None),
+            zulip_map: RwLock::new(None),
+        }
+    }
+
+    pub async fn teams(&self, client: &GithubClient) -> anyhow::Result<Teams> {
+        cached(&self.teams, || async {
+            self.by_url(client, "/teams.json")
+                .await
+                .context("team-api: teams.json")
+        })
+        .await
+    }
+
+    pub async fn zulip_map(&self, client: &GithubClient) -> anyhow::Result<ZulipMapping> {
+        cached(&self.zulip_map, || async {
+            self.by_url(client, "/zulip-map.json")
+                .await
+                .context("team-api: zulip-map.json")
+        })
+        .await
+    }
+
+    /// Drops the cached data, so that it is fetched again on the next use.
+    pub fn clear_cache(&self) {
+        *self.teams.write().unwrap() = None;
+        *self.zulip_map.write().unwrap() = None;
+    }
+
+    async fn by_url<T: DeserializeOwned>(
+        &self,
+        client: &GithubClient,
+        path: &str,
+    ) -> anyhow::Result<T> {
+        let url = format!("{}{}", self.base_url, path);
+        for _ in 0i32..3 {
+            let map: Result<T, _> = client.json(client.raw().get(&url)).await;
+            match map {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if e.downcast_ref::<reqwest::Error>()
+                        .map_or(false, |e| e.is_timeout())
+                    {
+                        continue;
+                    } else {
+                        return Err(e);
+                    }
                 }
             }
         }
+
+        Err(anyhow::anyhow!("Failed to retrieve {} in 3 requests", url))
     }
+}
 
-    Err(anyhow::anyhow!("Failed to retrieve {} in 3 requests", url))
+async fn cached<T, F, Fut>(cache: &RwLock<Option<(T, Instant)>>, fetch: F) -> anyhow::Result<T>
+where
+    T: Clone,
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<T>>,
+{
+    let fresh = cache
+        .read()
+        .unwrap()
+        .as_ref()
+        .filter(|(_, fetch_time)| fetch_time.elapsed() < REFRESH_EVERY)
+        .map(|(value, _)| value.clone());
+    if let Some(value) = fresh {
+        return Ok(value);
+    }
+    let value = fetch().await?;
+    *cache.write().unwrap() = Some((value.clone(), Instant::now()));
+    Ok(value)
 }
 
 pub async fn zulip_map(client: &GithubClient) -> anyhow::Result<ZulipMapping> {
-    by_url(client, "/zulip-map.json")
-        .await
-        .context("team-api: zulip-map.json")
+    TEAM_CLIENT.zulip_map(client).await
 }
 
 pub async fn teams(client: &GithubClient) -> anyhow::Result<Teams> {
-    by_url(client, "/teams.json")
-        .await
-        .context("team-api: teams.json")
+    TEAM_CLIENT.teams(client).await
+}
+
+/// Drops the cached team data, see [`TeamClient::clear_cache`].
+pub fn clear_cache() {
+    TEAM_CLIENT.clear_cache();
+}
+
+/// Makes [`teams`] return the test team data, in which only `team-member`
+/// is a member of the `all` team, and of the `lang` team.
+///
+/// Every test uses the same data, so tests running in parallel can share the
+/// cache.
+#[cfg(test)]
+pub(crate) fn use_test_teams() {
+    let teams = serde_json::from_value(serde_json::json!({
+        "all": {
+            "name": "all",
+            "kind": "team",
+            "members": [
+                {"name": "team-member", "github": "team-member", "github_id": 1, "is_lead": false},
+            ],
+            "alumni": [],
+            "discord": [],
+            "roles": [],
+        },
+        "lang": {
+            "name": "lang",
+            "kind": "team",
+            "members": [
+                {"name": "team-member", "github": "team-member", "github_id": 1, "is_lead": false},
+            ],
+            "alumni": [],
+            "discord": [],
+            "roles": [],
+        },
+    }))
+    .unwrap();
+    *TEAM_CLIENT.teams.write().unwrap() = Some((teams, Instant::now()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServer;
+
+    #[tokio::test]
+    async fn clear_cache_refetches_teams() {
+        let teams = "{}".to_string();
+        let server = TestServer::start(vec![("200 OK", teams.clone()), ("200 OK", teams)]);
+        let client = server.github_client();
+        let team_client = TeamClient::new(server.url().to_string());
+
+        team_client.teams(&client).await.unwrap();
+        // Served from the cache.
+        team_client.teams(&client).await.unwrap();
+        team_client.clear_cache();
+        team_client.teams(&client).await.unwrap();
+
+        let requests = server.finish();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.path == "/teams.json"));
+    }
 }
@@ -0,0 +1,479 @@
+//! A minimal HTTP server for testing the GitHub client.
+//!
+//! The server answers a fixed sequence of requests with canned responses, and
+//! records the requests it received so that tests can assert on them.
+//!
+//! The responses are either served in order with [`TestServer::start`], or
+//! matched against each request with [`TestServer::start_keyed`]. The latter
+//! tells apart the GraphQL requests, which are all sent to `/graphql`, by
+//! their query.
+//!
+//! The requests can be checked with [`assert_requests`], which prints the
+//! difference with the expected requests on mismatch.
+
+use crate::github::{GithubClient, Issue, Repository};
+use crate::handlers::Context;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::JoinHandle;
+
+/// A request received by the [`TestServer`].
+#[derive(Debug)]
+pub(crate) struct RecordedRequest {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    /// The headers, with their name in lowercase.
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: String,
+}
+
+pub(crate) struct TestServer {
+    url: String,
+    handle: JoinHandle<Vec<RecordedRequest>>,
+}
+
+impl TestServer {
+    /// Starts a server answering one request per response, in order.
+    ///
+    /// Each response is a status line like `200 OK` and a body.
+    pub(crate) fn start(responses: Vec<(&'static str, String)>) -> TestServer {
+        TestServer::start_with_headers(
+            responses
+                .into_iter()
+                .map(|(status, body)| (status, Vec::new(), body))
+                .collect(),
+        )
+    }
+
+    /// Starts a server answering one request per response, in order, like
+    /// [`TestServer::start`], with additional headers like `("ETag", "\"1\"")`.
+    pub(crate) fn start_with_headers(
+        responses: Vec<(&'static str, Vec<(&'static str, &'static str)>, String)>,
+    ) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|(status, headers, body)| {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream);
+                    let request = read_request(&mut reader);
+                    write_response(reader.get_mut(), status, &headers, &body);
+                    request
+                })
+                .collect()
+        });
+        TestServer { url, handle }
+    }
+
+    /// Starts a server answering each request with the first unused response
+    /// whose key matches it, whatever the order of the requests.
+    ///
+    /// The key of a GraphQL request is a part of its query, like
+    /// `"closingIssuesReferences"`. The key of the other requests is their
+    /// method and path, like `"GET /repos/rust-lang/rust/issues/1234"`.
+    ///
+    /// A request matching no response is answered with `404 Not Found`, and
+    /// stops the server.
+    pub(crate) fn start_keyed(responses: Vec<(&'static str, &'static str, String)>) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut responses: Vec<_> = responses.into_iter().map(Some).collect();
+            let mut requests = Vec::new();
+            while responses.iter().any(Option::is_some) {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let request = read_request(&mut reader);
+                let response = responses.iter_mut().find(|response| {
+                    response
+                        .as_ref()
+                        .is_some_and(|(key, _, _)| request.matches(key))
+                });
+                match response.and_then(Option::take) {
+                    Some((_, status, body)) => write_response(reader.get_mut(), status, &[], &body),
+                    None => {
+                        write_response(reader.get_mut(), "404 Not Found", &[], "{}");
+                        requests.push(request);
+                        break;
+                    }
+                }
+                requests.push(request);
+            }
+            requests
+        });
+        TestServer { url, handle }
+    }
+
+    /// Returns the base URL of this server, like `http://127.0.0.1:1234`.
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns a client sending its REST API requests to this server.
+    pub(crate) fn github_client(&self) -> GithubClient {
+        GithubClient::new(
+            "token".to_string(),
+            self.url.clone(),
+            format!("{}/graphql", self.url),
+            self.url.clone(),
+        )
+    }
+
+    /// Returns a handler context whose clients talk to this server.
+    ///
+    /// The database is never connected to, so handlers using it can't be
+    /// tested this way.
+    pub(crate) fn context(&self) -> Context {
+        Context {
+            github: self.github_client(),
+            db: crate::db::ClientPool::new(),
+            username: "rustbot".to_string(),
+            octocrab: octocrab::Octocrab::builder()
+                .base_uri(self.url.as_str())
+                .unwrap()
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// Waits for all the responses to be served, and returns the requests.
+    pub(crate) fn finish(self) -> Vec<RecordedRequest> {
+        self.handle.join().unwrap()
+    }
+}
+
+impl RecordedRequest {
+    /// Returns the value of the header `name`, given in lowercase like
+    /// `if-none-match`.
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether this request matches `key`, see [`TestServer::start_keyed`].
+    fn matches(&self, key: &str) -> bool {
+        if self.method == "POST" && self.path == "/graphql" {
+            serde_json::from_str::<serde_json::Value>(&self.body)
+                .ok()
+                .and_then(|body| Some(body["query"].as_str()?.contains(key)))
+                .unwrap_or(false)
+        } else {
+            key == format!("{} {}", self.method, self.path)
+        }
+    }
+}
+
+/// A request expected by [`assert_requests`].
+pub(crate) struct ExpectedRequest {
+    method: &'static str,
+    path: String,
+    body: Option<serde_json::Value>,
+}
+
+/// Expects a request with `method` to `path`, with any body.
+pub(crate) fn expect(method: &'static str, path: impl Into<String>) -> ExpectedRequest {
+    ExpectedRequest {
+        method,
+        path: path.into(),
+        body: None,
+    }
+}
+
+impl ExpectedRequest {
+    /// Also expects the JSON body of the request to be `body`.
+    pub(crate) fn with_body(mut self, body: serde_json::Value) -> ExpectedRequest {
+        self.body = Some(body);
+        self
+    }
+
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        self.method == request.method
+            && self.path == request.path
+            && self.body.as_ref().map_or(true, |body| {
+                serde_json::from_str::<serde_json::Value>(&request.body)
+                    .ok()
+                    .as_ref()
+                    == Some(body)
+            })
+    }
+
+    fn describe(&self) -> String {
+        match &self.body {
+            Some(body) => format!("{} {} {body}", self.method, self.path),
+            None => format!("{} {}", self.method, self.path),
+        }
+    }
+}
+
+impl RecordedRequest {
+    fn describe(&self) -> String {
+        if self.body.is_empty() {
+            format!("{} {}", self.method, self.path)
+        } else {
+            format!("{} {} {}", self.method, self.path, self.body)
+        }
+    }
+}
+
+/// Asserts that `requests` are the `expected` ones, in order.
+///
+/// On mismatch, panics with the expected requests prefixed by `-` and the
+/// actual requests prefixed by `+`.
+#[track_caller]
+pub(crate) fn assert_requests(requests: &[RecordedRequest], expected: &[ExpectedRequest]) {
+    let mut diff = String::new();
+    let mut matched = requests.len() == expected.len();
+    for i in 0..requests.len().max(expected.len()) {
+        match (expected.get(i), requests.get(i)) {
+            (Some(expected), Some(request)) if expected.matches(request) => {
+                diff.push_str(&format!("  {}\n", request.describe()));
+            }
+            (expected, request) => {
+                matched = false;
+                if let Some(expected) = expected {
+                    diff.push_str(&format!("- {}\n", expected.describe()));
+                }
+                if let Some(request) = request {
+                    diff.push_str(&format!("+ {}\n", request.describe()));
+                }
+            }
+        }
+    }
+    assert!(matched, "unexpected requests:\n{diff}");
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, headers: &[(&str, &str)], body: &str) {
+    let headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}\r\n"))
+        .collect();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: application/json\r\n\
+         {headers}\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> RecordedRequest {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap().to_string();
+    let path = parts.next().unwrap().to_string();
+
+    let mut content_length = 0;
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap();
+            }
+            headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    RecordedRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8(body).unwrap(),
+    }
+}
+
+/// Returns an open PR, `rust-lang/rust#1234`.
+pub(crate) fn test_pr() -> Issue {
+    serde_json::from_value(test_pr_json()).unwrap()
+}
+
+/// Returns the JSON of [`test_pr`].
+pub(crate) fn test_pr_json() -> serde_json::Value {
+    serde_json::json!({
+        "number": 1234,
+        "created_at": "2022-06-26T21:31:31Z",
+        "updated_at": "2022-06-26T21:31:31Z",
+        "title": "Example PR",
+        "body": "PR body",
+        "html_url": "https://github.com/rust-lang/rust/pull/1234",
+        "user": {"login": "octocat", "id": 583231},
+        "labels": [],
+        "assignees": [],
+        "comments_url": "https://api.github.com/repos/rust-lang/rust/issues/1234/comments",
+        "state": "open",
+    })
+}
+
+/// Returns the JSON of an open issue, `rust-lang/rust#1200`.
+pub(crate) fn test_issue_json() -> serde_json::Value {
+    serde_json::json!({
+        "number": 1200,
+        "created_at": "2022-06-26T21:31:31Z",
+        "updated_at": "2022-06-26T21:31:31Z",
+        "title": "Example issue",
+        "body": "Issue body",
+        "html_url": "https://github.com/rust-lang/rust/issues/1200",
+        "user": {"login": "octocat", "id": 583231},
+        "labels": [],
+        "assignees": [],
+        "comments_url": "https://api.github.com/repos/rust-lang/rust/issues/1200/comments",
+        "state": "open",
+    })
+}
+
+/// Returns the `rust-lang/rust` repository.
+pub(crate) fn test_repo() -> Repository {
+    serde_json::from_value(serde_json::json!({
+        "full_name": "rust-lang/rust",
+        "default_branch": "master",
+        "parent": null,
+    }))
+    .unwrap()
+}
+
+/// Returns the JSON of a comment by the bot on `rust-lang/rust#1234`.
+pub(crate) fn comment_json(id: u64, body: &str) -> String {
+    serde_json::json!({
+        "id": id,
+        "node_id": "IC_kwDOAAQmKM5",
+        "body": body,
+        "html_url": format!("https://github.com/rust-lang/rust/pull/1234#issuecomment-{id}"),
+        "user": {"login": "rustbot", "id": 47979223},
+        "created_at": "2022-06-26T21:35:01Z",
+        "updated_at": "2022-06-26T21:35:01Z",
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn keyed_graphql_responses() {
+        // The responses are declared in another order than the requests.
+        let server = TestServer::start_keyed(vec![
+            (
+                "milestone",
+                "200 OK",
+                serde_json::json!({"data": {"milestone": "1.80.0"}}).to_string(),
+            ),
+            (
+                "GET /repos/rust-lang/rust/labels/T-compiler",
+                "200 OK",
+                serde_json::json!({"name": "T-compiler"}).to_string(),
+            ),
+            (
+                "viewer",
+                "200 OK",
+                serde_json::json!({"data": {"viewer": "rustbot"}}).to_string(),
+            ),
+        ]);
+        let client = server.github_client();
+        let viewer = client
+            .graphql_query("query { viewer { login } }", serde_json::json!({}))
+            .await
+            .unwrap();
+        let milestone = client
+            .graphql_query(
+                "query($number: Int!) { milestone(number: $number) { title } }",
+                serde_json::json!({"number": 1}),
+            )
+            .await
+            .unwrap();
+        let label: serde_json::Value = client
+            .json(client.raw().get(&format!(
+                "{}/repos/rust-lang/rust/labels/T-compiler",
+                server.url()
+            )))
+            .await
+            .unwrap();
+
+        assert_eq!(viewer["data"]["viewer"], "rustbot");
+        assert_eq!(milestone["data"]["milestone"], "1.80.0");
+        assert_eq!(label["name"], "T-compiler");
+        let requests = server.finish();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].body.contains(r#""variables":{"number":1}"#));
+    }
+
+    #[tokio::test]
+    async fn keyed_unmatched_request() {
+        let server = TestServer::start_keyed(vec![("viewer", "200 OK", "{}".to_string())]);
+        let client = server.github_client();
+        assert!(client
+            .graphql_query("query { rateLimit { remaining } }", serde_json::json!({}))
+            .await
+            .is_err());
+        let requests = server.finish();
+        assert_eq!(requests.len(), 1);
+        assert!(!requests[0].matches("viewer"));
+    }
+
+    fn request(method: &str, path: &str, body: &str) -> RecordedRequest {
+        RecordedRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn requests_diff() {
+        let requests = [
+            request("GET", "/repos/rust-lang/rust/labels/A-diagnostics", ""),
+            request(
+                "POST",
+                "/repos/rust-lang/rust/issues/1234/labels",
+                r#"{"labels":["A-diagnostics"]}"#,
+            ),
+        ];
+        assert_requests(
+            &requests,
+            &[
+                expect("GET", "/repos/rust-lang/rust/labels/A-diagnostics"),
+                expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                    .with_body(serde_json::json!({"labels": ["A-diagnostics"]})),
+            ],
+        );
+
+        let panic = std::panic::catch_unwind(|| {
+            assert_requests(
+                &requests,
+                &[
+                    expect("GET", "/repos/rust-lang/rust/labels/A-diagnostics"),
+                    expect("POST", "/repos/rust-lang/rust/issues/1234/labels")
+                        .with_body(serde_json::json!({"labels": ["T-compiler"]})),
+                    expect(
+                        "DELETE",
+                        "/repos/rust-lang/rust/issues/1234/labels/S-blocked",
+                    ),
+                ],
+            )
+        })
+        .unwrap_err();
+        assert_eq!(
+            panic.downcast_ref::<String>().unwrap(),
+            r#"unexpected requests:
+  GET /repos/rust-lang/rust/labels/A-diagnostics
+- POST /repos/rust-lang/rust/issues/1234/labels {"labels":["T-compiler"]}
++ POST /repos/rust-lang/rust/issues/1234/labels {"labels":["A-diagnostics"]}
+- DELETE /repos/rust-lang/rust/issues/1234/labels/S-blocked
+"#
+        );
+    }
+}
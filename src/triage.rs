@@ -1,13 +1,38 @@
+use crate::github::GithubClient;
 use crate::handlers::Context;
-use chrono::{Duration, Utc};
+use anyhow::Context as _;
+use chrono::{DateTime, Duration, Utc};
 use hyper::{Body, Response, StatusCode};
 use serde::Serialize;
 use serde_json::value::{to_value, Value};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 use url::Url;
 
 const YELLOW_DAYS: i64 = 7;
 const RED_DAYS: i64 = 14;
+/// The default and maximum windows of the throughput stats, in days.
+const STATS_DEFAULT_DAYS: i64 = 7;
+const STATS_MAX_DAYS: i64 = 365;
+/// The default window of the milestone burndowns, in days.
+const BURNDOWN_DEFAULT_DAYS: i64 = 30;
+/// The maximum window of the milestone burndowns, in days.
+const BURNDOWN_MAX_DAYS: i64 = 90;
+/// The maximum number of points of a burndown. Each point costs two
+/// searches, so the longer windows are sampled every few days.
+const BURNDOWN_MAX_POINTS: i64 = 31;
+/// How long the stats and burndowns are cached before being computed again.
+const CACHE_REFRESH_EVERY: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+/// The maximum number of cached stats, and of cached burndowns.
+const CACHE_CAPACITY: usize = 64;
+
+lazy_static::lazy_static! {
+    static ref STATS_CACHE: RwLock<HashMap<String, (Throughput, Instant)>> =
+        RwLock::new(HashMap::new());
+    static ref BURNDOWN_CACHE: RwLock<HashMap<String, (Burndown, Instant)>> =
+        RwLock::new(HashMap::new());
+}
 
 pub fn index() -> Result<Response<Body>, hyper::Error> {
     Ok(Response::builder()
@@ -130,3 +155,523 @@ struct PullRequest {
     pub wait_for_review: bool,
     pub days_from_last_updated_at: i64,
 }
+
+/// Returns the triage throughput of a repository as JSON, over the last
+/// `?days=N` days (7 by default).
+///
+/// Only the repositories configured for the bot are served, and the stats
+/// are cached for [`CACHE_REFRESH_EVERY`].
+pub async fn stats(
+    ctx: Arc<Context>,
+    owner: &str,
+    repo: &str,
+    query: Option<&str>,
+) -> Result<Response<Body>, hyper::Error> {
+    let mut days = STATS_DEFAULT_DAYS;
+    for (k, v) in url::form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        if k == "days" {
+            match v.parse() {
+                Ok(n) if (1..=STATS_MAX_DAYS).contains(&n) => days = n,
+                _ => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from(format!(
+                            "`days` must be a number between 1 and {STATS_MAX_DAYS}"
+                        )))
+                        .unwrap());
+                }
+            }
+        }
+    }
+
+    let full_name = format!("{owner}/{repo}");
+    let key = format!("{full_name}:{days}");
+    let cached = STATS_CACHE
+        .read()
+        .unwrap()
+        .get(&key)
+        .filter(|(_, computed)| computed.elapsed() < CACHE_REFRESH_EVERY)
+        .map(|(stats, _)| stats.clone());
+    let stats = match cached {
+        Some(stats) => stats,
+        None => {
+            if !crate::config::is_configured(&ctx.github, &full_name).await {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::from(
+                        "The repository is not configured for triagebot.",
+                    ))
+                    .unwrap());
+            }
+            let since = Utc::now() - Duration::days(days);
+            match throughput(&ctx.github, &full_name, since).await {
+                Ok(stats) => {
+                    cache_insert(&mut STATS_CACHE.write().unwrap(), key, stats.clone());
+                    stats
+                }
+                Err(e) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .body(Body::from(format!("{:?}", e)))
+                        .unwrap());
+                }
+            }
+        }
+    };
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(Body::from(serde_json::to_string(&stats).unwrap()))
+        .unwrap())
+}
+
+/// The activity of a repository since a given time.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Throughput {
+    pub repo: String,
+    pub since: DateTime<Utc>,
+    pub issues_opened: u64,
+    pub issues_closed: u64,
+    pub prs_merged: u64,
+    /// The average time between the opening of the merged PRs and their
+    /// first review, `None` if none of them was reviewed.
+    pub average_hours_to_first_review: Option<f64>,
+}
+
+async fn throughput(
+    client: &GithubClient,
+    repo: &str,
+    since: DateTime<Utc>,
+) -> anyhow::Result<Throughput> {
+    let date = since.format("%Y-%m-%dT%H:%M:%SZ");
+    let opened = format!("repo:{repo} is:issue created:>={date}");
+    let closed = format!("repo:{repo} is:issue closed:>={date}");
+    let merged = format!("repo:{repo} is:pr is:merged merged:>={date}");
+    let counts = client
+        .issue_search_counts(&[opened.as_str(), closed.as_str(), merged.as_str()])
+        .await?;
+    let &[issues_opened, issues_closed, prs_merged] = &counts[..] else {
+        unreachable!("one count per query");
+    };
+
+    // Only the last 100 merged PRs are considered for the review time.
+    let data = client
+        .graphql_query(
+            "query($query: String!) {
+                search(query: $query, type: ISSUE, first: 100) {
+                    nodes {
+                        ... on PullRequest {
+                            createdAt
+                            reviews(first: 1) { nodes { createdAt } }
+                        }
+                    }
+                }
+            }",
+            serde_json::json!({ "query": format!("{merged} sort:updated-desc") }),
+        )
+        .await
+        .context("failed to get the reviews of the merged PRs")?;
+    let prs: Vec<MergedPullRequest> =
+        serde_json::from_value(data["data"]["search"]["nodes"].clone())
+            .context("failed to parse the merged PRs")?;
+
+    Ok(Throughput {
+        repo: repo.to_string(),
+        since,
+        issues_opened,
+        issues_closed,
+        prs_merged,
+        average_hours_to_first_review: average_hours_to_first_review(&prs),
+    })
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MergedPullRequest {
+    created_at: DateTime<Utc>,
+    reviews: Reviews,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Reviews {
+    nodes: Vec<Review>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Review {
+    created_at: DateTime<Utc>,
+}
+
+fn average_hours_to_first_review(prs: &[MergedPullRequest]) -> Option<f64> {
+    let hours: Vec<f64> = prs
+        .iter()
+        .filter_map(|pr| {
+            let first_review = pr.reviews.nodes.first()?;
+            Some((first_review.created_at - pr.created_at).num_minutes() as f64 / 60.0)
+        })
+        .collect();
+    if hours.is_empty() {
+        None
+    } else {
+        Some(hours.iter().sum::<f64>() / hours.len() as f64)
+    }
+}
+
+/// Returns the burndown of a milestone as JSON, given as `?milestone=X`,
+/// over the last `?days=N` days (30 by default).
+///
+/// The burndowns are cached for [`CACHE_REFRESH_EVERY`]. The caller must
+/// check the admin token, as each burndown runs many searches.
+pub async fn burndown(
+    ctx: Arc<Context>,
+    owner: &str,
+    repo: &str,
+    query: Option<&str>,
+) -> Result<Response<Body>, hyper::Error> {
+    let mut milestone = None;
+    let mut days = BURNDOWN_DEFAULT_DAYS;
+    for (k, v) in url::form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        match &*k {
+            "milestone" => milestone = Some(v.into_owned()),
+            "days" => match v.parse() {
+                Ok(n) if (1..=BURNDOWN_MAX_DAYS).contains(&n) => days = n,
+                _ => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from(format!(
+                            "`days` must be a number between 1 and {BURNDOWN_MAX_DAYS}"
+                        )))
+                        .unwrap());
+                }
+            },
+            _ => {}
+        }
+    }
+    let Some(milestone) = milestone else {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from("missing `milestone`"))
+            .unwrap());
+    };
+    // The milestone is quoted in the search query, so a quote would let the
+    // caller add other qualifiers.
+    if milestone.contains('"') {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from("`milestone` must not contain `\"`"))
+            .unwrap());
+    }
+
+    let full_name = format!("{owner}/{repo}");
+    let key = format!("{full_name}:{milestone}:{days}");
+    let cached = BURNDOWN_CACHE
+        .read()
+        .unwrap()
+        .get(&key)
+        .filter(|(_, computed)| computed.elapsed() < CACHE_REFRESH_EVERY)
+        .map(|(burndown, _)| burndown.clone());
+    let burndown = match cached {
+        Some(burndown) => burndown,
+        None => match milestone_burndown(&ctx.github, &full_name, &milestone, days).await {
+            Ok(burndown) => {
+                cache_insert(&mut BURNDOWN_CACHE.write().unwrap(), key, burndown.clone());
+                burndown
+            }
+            Err(e) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::from(format!("{:?}", e)))
+                    .unwrap());
+            }
+        },
+    };
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(Body::from(serde_json::to_string(&burndown).unwrap()))
+        .unwrap())
+}
+
+/// The open and closed issues and PRs of a milestone over time.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Burndown {
+    pub repo: String,
+    pub milestone: String,
+    /// At most [`BURNDOWN_MAX_POINTS`] points evenly spaced by whole days,
+    /// oldest first, the last one being now.
+    pub points: Vec<BurndownPoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BurndownPoint {
+    pub date: DateTime<Utc>,
+    pub open: u64,
+    pub closed: u64,
+}
+
+/// Caches `value`, dropping the expired entries and, if the cache is still
+/// full, the oldest one.
+fn cache_insert<T>(cache: &mut HashMap<String, (T, Instant)>, key: String, value: T) {
+    cache.retain(|_, (_, computed)| computed.elapsed() < CACHE_REFRESH_EVERY);
+    if cache.len() >= CACHE_CAPACITY && !cache.contains_key(&key) {
+        let oldest = cache
+            .iter()
+            .min_by_key(|(_, (_, computed))| *computed)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(key, (value, Instant::now()));
+}
+
+/// Computes the burndown of a milestone from the number of its issues
+/// created and closed by each day, so that it isn't limited to the first
+/// 1000 search results.
+///
+/// The issues are counted from their creation, as the time they were added
+/// to the milestone is not known.
+async fn milestone_burndown(
+    client: &GithubClient,
+    full_name: &str,
+    milestone: &str,
+    days: i64,
+) -> anyhow::Result<Burndown> {
+    let dates = burndown_dates(Utc::now(), days);
+    let queries: Vec<String> = dates
+        .iter()
+        .flat_map(|date| {
+            let date = date.format("%Y-%m-%dT%H:%M:%SZ");
+            let milestone = format!("repo:{full_name} milestone:\"{milestone}\"");
+            [
+                format!("{milestone} created:<={date}"),
+                format!("{milestone} closed:<={date}"),
+            ]
+        })
+        .collect();
+    let queries: Vec<&str> = queries.iter().map(|q| q.as_str()).collect();
+    let counts = client
+        .issue_search_counts(&queries)
+        .await
+        .with_context(|| format!("failed to count the issues of milestone {milestone}"))?;
+    Ok(Burndown {
+        repo: full_name.to_string(),
+        milestone: milestone.to_string(),
+        points: burndown_points(&dates, &counts),
+    })
+}
+
+/// Returns the dates of the points of a burndown over the `days` before
+/// `now`: every day, or every few days for the longer windows so that there
+/// are at most [`BURNDOWN_MAX_POINTS`].
+fn burndown_dates(now: DateTime<Utc>, days: i64) -> Vec<DateTime<Utc>> {
+    let step = (days + BURNDOWN_MAX_POINTS - 2) / (BURNDOWN_MAX_POINTS - 1);
+    (0..=days / step)
+        .rev()
+        .map(|i| now - Duration::days(i * step))
+        .collect()
+}
+
+/// Pairs each date with its counts of created and closed issues, given in
+/// that order.
+fn burndown_points(dates: &[DateTime<Utc>], counts: &[u64]) -> Vec<BurndownPoint> {
+    dates
+        .iter()
+        .zip(counts.chunks(2))
+        .map(|(&date, counts)| {
+            let &[created, closed] = counts else {
+                unreachable!("two counts per date");
+            };
+            BurndownPoint {
+                date,
+                open: created.saturating_sub(closed),
+                closed,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServer;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn throughput_stats() {
+        let prs = serde_json::json!({
+            "data": {
+                "search": {
+                    "nodes": [
+                        {
+                            "createdAt": "2024-05-10T10:00:00Z",
+                            "reviews": { "nodes": [{ "createdAt": "2024-05-10T16:00:00Z" }] },
+                        },
+                        {
+                            "createdAt": "2024-05-11T10:00:00Z",
+                            "reviews": { "nodes": [{ "createdAt": "2024-05-12T04:00:00Z" }] },
+                        },
+                        // Merged without review.
+                        { "createdAt": "2024-05-12T10:00:00Z", "reviews": { "nodes": [] } },
+                    ],
+                },
+            },
+        });
+        let counts = serde_json::json!({
+            "data": {
+                "q0": { "issueCount": 42 },
+                "q1": { "issueCount": 30 },
+                "q2": { "issueCount": 3 },
+            },
+        });
+        let server = TestServer::start(vec![
+            ("200 OK", counts.to_string()),
+            ("200 OK", prs.to_string()),
+        ]);
+        let since = Utc.with_ymd_and_hms(2024, 5, 9, 0, 0, 0).unwrap();
+        let stats = throughput(&server.github_client(), "rust-lang/rust", since)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stats,
+            Throughput {
+                repo: "rust-lang/rust".to_string(),
+                since,
+                issues_opened: 42,
+                issues_closed: 30,
+                prs_merged: 3,
+                average_hours_to_first_review: Some(12.0),
+            }
+        );
+        let requests = server.finish();
+        let variables = |i: usize| {
+            let body: serde_json::Value = serde_json::from_str(&requests[i].body).unwrap();
+            body["variables"].clone()
+        };
+        assert_eq!(
+            variables(0)["q0"],
+            "repo:rust-lang/rust is:issue created:>=2024-05-09T00:00:00Z"
+        );
+        assert_eq!(
+            variables(1)["query"],
+            "repo:rust-lang/rust is:pr is:merged merged:>=2024-05-09T00:00:00Z sort:updated-desc"
+        );
+    }
+
+    #[tokio::test]
+    async fn stats_of_unconfigured_repo() {
+        let server = TestServer::start(vec![(
+            "404 Not Found",
+            r#"{"message": "Not Found"}"#.to_string(),
+        )]);
+        let res = stats(Arc::new(server.context()), "octocat", "unknown", None)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let requests = server.finish();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/repos/octocat/unknown");
+    }
+
+    #[test]
+    fn burndown_series() {
+        let from = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let dates: Vec<_> = (0..=4).map(|day| from + Duration::days(day)).collect();
+        let counts = [0, 0, 2, 0, 3, 1, 3, 1, 3, 2];
+        let series: Vec<_> = burndown_points(&dates, &counts)
+            .into_iter()
+            .map(|p| (p.date.format("%m-%d").to_string(), p.open, p.closed))
+            .collect();
+        assert_eq!(
+            series,
+            [
+                ("05-01".to_string(), 0, 0),
+                ("05-02".to_string(), 2, 0),
+                ("05-03".to_string(), 2, 1),
+                ("05-04".to_string(), 2, 1),
+                ("05-05".to_string(), 1, 2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn burndown_from_search_counts() {
+        let counts = serde_json::json!({
+            "data": {
+                "q0": { "issueCount": 1500 },
+                "q1": { "issueCount": 1200 },
+                "q2": { "issueCount": 1510 },
+                "q3": { "issueCount": 1250 },
+            },
+        });
+        let server = TestServer::start(vec![("200 OK", counts.to_string())]);
+        let burndown = milestone_burndown(&server.github_client(), "rust-lang/rust", "1.80.0", 1)
+            .await
+            .unwrap();
+        let points: Vec<_> = burndown.points.iter().map(|p| (p.open, p.closed)).collect();
+        assert_eq!(points, [(300, 1200), (260, 1250)]);
+
+        let requests = server.finish();
+        assert_eq!(requests.len(), 1);
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        let date = burndown.points[1].date.format("%Y-%m-%dT%H:%M:%SZ");
+        assert_eq!(
+            body["variables"]["q2"],
+            format!("repo:rust-lang/rust milestone:\"1.80.0\" created:<={date}")
+        );
+        assert_eq!(
+            body["variables"]["q3"],
+            format!("repo:rust-lang/rust milestone:\"1.80.0\" closed:<={date}")
+        );
+    }
+
+    #[test]
+    fn burndown_sampling() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 31, 12, 0, 0).unwrap();
+        let days = |dates: Vec<DateTime<Utc>>| -> Vec<_> {
+            dates.iter().map(|d| (now - *d).num_days()).collect()
+        };
+        assert_eq!(days(burndown_dates(now, 2)), [2, 1, 0]);
+        let daily = burndown_dates(now, 30);
+        assert_eq!(daily.len(), 31);
+        assert_eq!(daily[0], now - Duration::days(30));
+        let sampled = days(burndown_dates(now, BURNDOWN_MAX_DAYS));
+        assert_eq!(sampled.len() as i64, BURNDOWN_MAX_POINTS);
+        assert_eq!(sampled[..3], [90, 87, 84]);
+        assert_eq!(*sampled.last().unwrap(), 0);
+        for days in 1..=BURNDOWN_MAX_DAYS {
+            assert!(burndown_dates(now, days).len() as i64 <= BURNDOWN_MAX_POINTS);
+        }
+    }
+
+    #[test]
+    fn burndown_cache_is_bounded() {
+        let burndown = |milestone: &str| Burndown {
+            repo: "rust-lang/rust".to_string(),
+            milestone: milestone.to_string(),
+            points: vec![],
+        };
+        let mut cache = HashMap::new();
+        for i in 0..CACHE_CAPACITY {
+            let age = std::time::Duration::from_secs((CACHE_CAPACITY - i) as u64);
+            cache.insert(
+                format!("key{i}"),
+                (burndown(&i.to_string()), Instant::now() - age),
+            );
+        }
+        cache.insert(
+            "expired".to_string(),
+            (burndown("expired"), Instant::now() - CACHE_REFRESH_EVERY),
+        );
+        cache_insert(&mut cache, "new".to_string(), burndown("new"));
+        assert_eq!(cache.len(), CACHE_CAPACITY);
+        assert!(!cache.contains_key("expired"));
+        // The oldest burndown made room for the last one.
+        assert!(!cache.contains_key("key0"));
+        assert!(cache.contains_key("key1"));
+        assert!(cache.contains_key("new"));
+    }
+}
@@ -1,11 +1,21 @@
+use crate::agenda;
+use crate::db::assign_notifications::set_assign_notifications;
 use crate::db::notifications::add_metadata;
 use crate::db::notifications::{self, delete_ping, move_indices, record_ping, Identifier};
-use crate::github::{get_id_for_username, GithubClient};
+use crate::db::review_pools::user_review_pools;
+use crate::db::zulip_links::{
+    create_link_request, get_github_link, get_zulip_link, LINK_REQUEST_VALIDITY,
+};
+use crate::github::{
+    get_id_for_username, GithubClient, Issue, IssueState, PullRequestReview, WorkflowRun,
+};
 use crate::handlers::docs_update::docs_update;
 use crate::handlers::project_goals::{self, ping_project_goals_owners};
 use crate::handlers::pull_requests_assignment_update::get_review_prefs;
+use crate::handlers::review_pool::{join_review_pool, leave_review_pool};
 use crate::handlers::Context;
 use anyhow::{format_err, Context as _};
+use std::collections::HashMap;
 use std::env;
 use std::fmt::Write as _;
 use std::str::FromStr;
@@ -18,6 +28,41 @@ static ZULIP_BOT_EMAIL: LazyLock<String> = LazyLock::new(|| {
     env::var("ZULIP_BOT_EMAIL").unwrap_or("triage-rust-lang-bot@zulipchat.com".into())
 });
 
+#[cfg(test)]
+thread_local! {
+    /// The Zulip server of the test running on this thread, see
+    /// [`use_test_zulip`].
+    static TEST_ZULIP_URL: std::cell::RefCell<Option<String>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Sends the Zulip messages of the test running on this thread to `url`,
+/// like a [`crate::test_server::TestServer`], with a dummy token.
+///
+/// The `#[tokio::test]` runtime runs the test on a single thread, so tests
+/// running in parallel don't see each other's server.
+#[cfg(test)]
+pub(crate) fn use_test_zulip(url: &str) {
+    TEST_ZULIP_URL.with(|test_url| *test_url.borrow_mut() = Some(url.to_string()));
+}
+
+/// The Zulip server the messages are sent to.
+fn zulip_url() -> String {
+    #[cfg(test)]
+    if let Some(url) = TEST_ZULIP_URL.with(|url| url.borrow().clone()) {
+        return url;
+    }
+    ZULIP_URL.clone()
+}
+
+fn zulip_api_token() -> anyhow::Result<String> {
+    #[cfg(test)]
+    if TEST_ZULIP_URL.with(|url| url.borrow().is_some()) {
+        return Ok("token".to_string());
+    }
+    env::var("ZULIP_API_TOKEN").context("`ZULIP_API_TOKEN` is not set")
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct Request {
     /// Markdown body of the sent message.
@@ -92,6 +137,24 @@ pub async fn to_zulip_id(client: &GithubClient, github_id: u64) -> anyhow::Resul
         .map(|v| *v.0))
 }
 
+/// Returns the Zulip user of the GitHub user, from the team data or else from
+/// the accounts linked with the `link-zulip` command.
+pub async fn lookup_zulip_id(ctx: &Context, github_id: u64) -> anyhow::Result<Option<u64>> {
+    if let Some(zulip_id) = to_zulip_id(&ctx.github, github_id).await? {
+        return Ok(Some(zulip_id));
+    }
+    get_zulip_link(&*ctx.db.get().await, github_id).await
+}
+
+/// Returns the GitHub user of the Zulip user, from the team data or else from
+/// the accounts linked with the `link-zulip` command.
+async fn lookup_github_id(ctx: &Context, zulip_id: u64) -> anyhow::Result<Option<u64>> {
+    if let Some(github_id) = to_github_id(&ctx.github, zulip_id).await? {
+        return Ok(Some(github_id));
+    }
+    get_github_link(&*ctx.db.get().await, zulip_id).await
+}
+
 /// Top-level handler for Zulip webhooks.
 ///
 /// Returns a JSON response.
@@ -120,11 +183,12 @@ async fn process_zulip_request(ctx: &Context, req: Request) -> anyhow::Result<Op
     }
 
     log::trace!("zulip hook: {:?}", req);
-    let gh_id = match to_github_id(&ctx.github, req.message.sender_id).await {
+    let gh_id = match lookup_github_id(ctx, req.message.sender_id).await {
         Ok(Some(gh_id)) => Ok(gh_id),
         Ok(None) => Err(format_err!(
             "Unknown Zulip user. Please add `zulip-id = {}` to your file in \
-                [rust-lang/team](https://github.com/rust-lang/team).",
+                [rust-lang/team](https://github.com/rust-lang/team), \
+                or link your GitHub account with `link-github`.",
             req.message.sender_id
         )),
         Err(e) => anyhow::bail!("Failed to query team API: {e:?}"),
@@ -152,6 +216,14 @@ fn handle_command<'a>(
                     format_err!("Failed to parse; expected `as <username> <command...>`: {e:?}.")
                 });
         }
+        if let Some("help") = next {
+            return Ok(Some(HELP_MESSAGE.to_string()));
+        }
+        // Available to the users unknown to the team data, to link their
+        // GitHub account.
+        if let Some("link-github") = next {
+            return request_github_link(&ctx, message_data).await;
+        }
         let gh_id = gh_id?;
 
         match next {
@@ -163,9 +235,12 @@ fn handle_command<'a>(
                 .map_err(|e| format_err!("Failed to parse movement, expected `move <from> <to>`: {e:?}.")),
             Some("meta") => add_meta_notification(&ctx, gh_id, words).await
                 .map_err(|e| format_err!("Failed to parse `meta` command. Synopsis: meta <num> <text>: Add <text> to your notification identified by <num> (>0)\n\nError: {e:?}")),
+            Some("status") => issue_status(&ctx, words).await
+                .map_err(|e| format_err!("Failed to parse `status` command. Synopsis: status <issue-url>: shows the labels, assignees, milestone, review and CI state of an issue or PR\n\nError: {e:?}")),
             Some("work") => query_pr_assignments(&ctx, gh_id, words).await
-                                                                    .map_err(|e| format_err!("Failed to parse `work` command. Synopsis: work <show>: shows your current PRs assignment\n\nError: {e:?}")),
+                                                                    .map_err(|e| format_err!("Failed to parse `work` command. Synopsis: work <show>: shows your current PRs assignment; work notify <on|off>: toggles the direct messages when a PR is assigned to you; work pool [join|leave <team>]: shows or changes the review pools you are in\n\nError: {e:?}")),
             _ => {
+                let mut unknown = next;
                 while let Some(word) = next {
                     if word == "@**triagebot**" {
                         let next = words.next();
@@ -229,18 +304,308 @@ fn handle_command<'a>(
                                 }
                             }
                             Some("docs-update") => return trigger_docs_update(message_data),
-                            _ => {}
+                            Some("post-agenda") => {
+                                return post_agenda(&ctx, gh_id, words.next(), message_data)
+                                    .await
+                                    .map_err(|e| format_err!("Failed to post the agenda: {e:?}"))
+                            }
+                            _ => unknown = next,
                         }
                     }
                     next = words.next();
                 }
 
-                Ok(Some(String::from("Unknown command")))
+                Ok(Some(unknown_command_message(unknown)))
             }
         }
     })
 }
 
+const HELP_MESSAGE: &str = "Available commands:
+
+- `acknowledge <identifier>` (or `ack`): acknowledge a notification
+- `add <url> <description>`: add a notification
+- `move <from> <to>`: move a notification
+- `meta <num> <text>`: add a description to a notification
+- `status <issue-url>`: show the triage state of an issue or PR
+- `work show`: show your assigned PRs
+- `work notify <on|off>`: toggle the direct messages when a PR is assigned to you
+- `work pool [join|leave <team>]`: show the review pools you are in, or join or leave the one of a team
+- `link-github`: get a code to link your GitHub account with `@rustbot link-zulip <code>`
+- `as <github-user> <command>`: run a command as another user
+
+In a stream, after mentioning @**triagebot**:
+
+- `end-topic` (or `await`), `end-meeting`, `read`: ask the participants to react when done
+- `docs-update`: open a PR updating the book submodules
+- `ping-goals <days> <next-update>`: ping the owners of stale project goals
+- `post-agenda lang-triage`: post the T-lang triage agenda";
+
+/// The commands suggested for a mistyped command.
+const COMMANDS: &[&str] = &[
+    "acknowledge",
+    "ack",
+    "add",
+    "move",
+    "meta",
+    "status",
+    "work",
+    "link-github",
+    "as",
+    "help",
+    "end-topic",
+    "await",
+    "end-meeting",
+    "read",
+    "docs-update",
+    "ping-goals",
+    "post-agenda",
+];
+
+/// Replies to an unknown command, suggesting the closest known command.
+fn unknown_command_message(command: Option<&str>) -> String {
+    let suggestion = command.and_then(|command| {
+        COMMANDS
+            .iter()
+            .map(|known| (edit_distance(command, known), known))
+            .filter(|(distance, _)| *distance <= 2 && *distance < command.len())
+            .min_by_key(|(distance, _)| *distance)
+    });
+    match suggestion {
+        Some((_, known)) => {
+            format!("Unknown command, did you mean `{known}`? Use `help` to list the commands.")
+        }
+        None => String::from("Unknown command. Use `help` to list the commands."),
+    }
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[test]
+fn test_unknown_command() {
+    assert_eq!(edit_distance("wrok", "work"), 2);
+    assert_eq!(edit_distance("acknowledge", "acknowledge"), 0);
+    assert_eq!(
+        unknown_command_message(Some("acknowlege")),
+        "Unknown command, did you mean `acknowledge`? Use `help` to list the commands."
+    );
+    assert_eq!(
+        unknown_command_message(Some("end-meting")),
+        "Unknown command, did you mean `end-meeting`? Use `help` to list the commands."
+    );
+    assert_eq!(
+        unknown_command_message(Some("frobnicate")),
+        "Unknown command. Use `help` to list the commands."
+    );
+    assert_eq!(
+        unknown_command_message(None),
+        "Unknown command. Use `help` to list the commands."
+    );
+}
+
+/// Replies with an overview of the triage state of the issue or PR at the
+/// given URL.
+async fn issue_status(
+    ctx: &Context,
+    mut words: impl Iterator<Item = &str>,
+) -> anyhow::Result<Option<String>> {
+    let Some(url) = words.next() else {
+        anyhow::bail!("no URL provided");
+    };
+    let Some((repo, number)) = parse_issue_url(ctx.github.html_url(), url) else {
+        anyhow::bail!("`{url}` is not the URL of a GitHub issue or PR");
+    };
+    let issue = ctx.github.get_issue(&repo, number).await?;
+    if !issue.is_pr() {
+        return Ok(Some(status_message(&issue, None)));
+    }
+
+    // The issues endpoint doesn't return the head commit of PRs.
+    let repository = ctx.github.repository(&repo).await?;
+    let pr = repository.get_issue(&ctx.github, number).await?;
+    let reviews = pr.reviews(&ctx.github).await?;
+    let ci = match &pr.head {
+        Some(head) => {
+            repository
+                .latest_workflow_run_for_sha(&ctx.github, &head.sha)
+                .await?
+        }
+        None => None,
+    };
+    Ok(Some(status_message(
+        &pr,
+        Some((review_decision(&reviews), ci.as_ref())),
+    )))
+}
+
+/// Returns the `owner/repo` and number of a GitHub issue or PR URL, like
+/// `https://github.com/rust-lang/rust/pull/1234` for the `html_url` of
+/// github.com.
+fn parse_issue_url(html_url: &str, url: &str) -> Option<(String, u64)> {
+    let path = url
+        .strip_prefix(html_url.trim_end_matches('/'))?
+        .strip_prefix('/')?;
+    let mut parts = path.split(['/', '#', '?']);
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    let repo = parts.next().filter(|s| !s.is_empty())?;
+    if !matches!(parts.next()?, "issues" | "pull") {
+        return None;
+    }
+    let number = parts.next()?.parse().ok()?;
+    Some((format!("{owner}/{repo}"), number))
+}
+
+/// The review decision of a PR, from the latest review of each reviewer.
+fn review_decision(reviews: &[PullRequestReview]) -> &'static str {
+    let mut latest = HashMap::new();
+    for review in reviews {
+        if review.state != "COMMENTED" && review.state != "PENDING" {
+            latest.insert(review.user.id, review.state.as_str());
+        }
+    }
+    if latest.values().any(|state| *state == "CHANGES_REQUESTED") {
+        "changes requested"
+    } else if latest.values().any(|state| *state == "APPROVED") {
+        "approved"
+    } else {
+        "review required"
+    }
+}
+
+/// Renders the triage state of an issue, with the review decision and the
+/// latest CI run if it is a PR.
+fn status_message(issue: &Issue, pr: Option<(&str, Option<&WorkflowRun>)>) -> String {
+    let state = match issue.state {
+        IssueState::Open if issue.draft => "open (draft)",
+        IssueState::Open => "open",
+        IssueState::Closed if issue.merged => "merged",
+        IssueState::Closed => "closed",
+    };
+    let list = |items: Vec<String>| {
+        if items.is_empty() {
+            "none".to_string()
+        } else {
+            items.join(", ")
+        }
+    };
+    let labels = issue.labels.iter().map(|l| format!("`{}`", l.name));
+    let assignees = issue.assignees.iter().map(|u| u.login.clone());
+    let milestone = issue.milestone.iter().map(|m| m.title.clone());
+    let mut message = format!(
+        "**[{}#{}]({})** {}\n\
+        - State: {state}\n\
+        - Labels: {}\n\
+        - Assignees: {}\n\
+        - Milestone: {}",
+        issue.repository(),
+        issue.number,
+        issue.html_url,
+        issue.title,
+        list(labels.collect()),
+        list(assignees.collect()),
+        list(milestone.collect()),
+    );
+    if let Some((review, ci)) = pr {
+        let ci = match ci {
+            Some(run) => format!(
+                "[{}]({})",
+                run.conclusion
+                    .as_deref()
+                    .or(run.status.as_deref())
+                    .unwrap_or("unknown"),
+                run.html_url
+            ),
+            None => "none".to_string(),
+        };
+        write!(message, "\n- Review: {review}\n- CI: {ci}").unwrap();
+    }
+    message
+}
+
+#[test]
+fn test_issue_status() {
+    let github = "https://github.com";
+    assert_eq!(
+        parse_issue_url(
+            github,
+            "https://github.com/rust-lang/rust/pull/1234#issuecomment-1"
+        ),
+        Some(("rust-lang/rust".to_string(), 1234))
+    );
+    assert_eq!(
+        parse_issue_url(github, "https://github.com/rust-lang/triagebot/issues/42"),
+        Some(("rust-lang/triagebot".to_string(), 42))
+    );
+    assert_eq!(
+        parse_issue_url(github, "https://github.com/rust-lang/rust"),
+        None
+    );
+    assert_eq!(
+        parse_issue_url(github, "https://github.com/rust-lang/rust/commit/abc"),
+        None
+    );
+    assert_eq!(parse_issue_url(github, "rust-lang/rust#1234"), None);
+    // GitHub Enterprise.
+    assert_eq!(
+        parse_issue_url(
+            "https://github.example.com/",
+            "https://github.example.com/infra/bot/issues/7"
+        ),
+        Some(("infra/bot".to_string(), 7))
+    );
+    assert_eq!(
+        parse_issue_url(
+            "https://github.example.com",
+            "https://github.com/rust-lang/rust/pull/1234"
+        ),
+        None
+    );
+
+    let mut pr = crate::test_server::test_pr_json();
+    pr["labels"] = serde_json::json!([{"name": "T-compiler"}, {"name": "S-waiting-on-review"}]);
+    pr["assignees"] = serde_json::json!([{"login": "oli-obk", "id": 332036}]);
+    pr["milestone"] = serde_json::json!({"number": 3, "title": "1.80.0"});
+    let pr: Issue = serde_json::from_value(pr).unwrap();
+    let reviews: Vec<PullRequestReview> = serde_json::from_value(serde_json::json!([
+        {"id": 1, "user": {"login": "oli-obk", "id": 332036}, "state": "CHANGES_REQUESTED", "commit_id": "a"},
+        {"id": 2, "user": {"login": "oli-obk", "id": 332036}, "state": "APPROVED", "commit_id": "b"},
+        {"id": 3, "user": {"login": "jieyouxu", "id": 39484203}, "state": "COMMENTED", "commit_id": "b"},
+    ]))
+    .unwrap();
+    let run: WorkflowRun = serde_json::from_value(serde_json::json!({
+        "id": 8764382154u64,
+        "name": "CI",
+        "head_sha": "b",
+        "status": "completed",
+        "conclusion": "success",
+        "html_url": "https://github.com/rust-lang/rust/actions/runs/8764382154",
+    }))
+    .unwrap();
+    assert_eq!(
+        status_message(&pr, Some((review_decision(&reviews), Some(&run)))),
+        "**[rust-lang/rust#1234](https://github.com/rust-lang/rust/pull/1234)** Example PR\n\
+        - State: open\n\
+        - Labels: `T-compiler`, `S-waiting-on-review`\n\
+        - Assignees: oli-obk\n\
+        - Milestone: 1.80.0\n\
+        - Review: approved\n\
+        - CI: [success](https://github.com/rust-lang/rust/actions/runs/8764382154)"
+    );
+}
+
 async fn query_pr_assignments(
     ctx: &&Context,
     gh_id: u64,
@@ -253,18 +618,49 @@ async fn query_pr_assignments(
 
     let db_client = ctx.db.get().await;
 
-    let record = match subcommand {
+    match subcommand {
         "show" => {
             let rec = get_review_prefs(&db_client, gh_id).await;
             if rec.is_err() {
                 anyhow::bail!("No preferences set.")
             }
-            rec?
+            Ok(Some(rec?.to_string()))
+        }
+        "notify" => {
+            let enabled = match words.next() {
+                Some("on") => true,
+                Some("off") => false,
+                _ => anyhow::bail!("expected `on` or `off`"),
+            };
+            set_assign_notifications(&db_client, gh_id, enabled).await?;
+            Ok(Some(if enabled {
+                "You will get a direct message when a PR is assigned to you.".to_string()
+            } else {
+                "You will no longer get a direct message when a PR is assigned to you.".to_string()
+            }))
         }
+        "pool" => match (words.next(), words.next()) {
+            (None, _) => {
+                let pools = user_review_pools(&db_client, gh_id).await?;
+                Ok(Some(if pools.is_empty() {
+                    "You are not in any review pool.".to_string()
+                } else {
+                    format!("You are in the review pools of: {}.", pools.join(", "))
+                }))
+            }
+            (Some("join"), Some(team)) => {
+                match join_review_pool(&ctx.github, &db_client, team, gh_id).await? {
+                    Ok(message) => Ok(Some(message)),
+                    Err(error) => anyhow::bail!(error),
+                }
+            }
+            (Some("leave"), Some(team)) => {
+                Ok(Some(leave_review_pool(&db_client, team, gh_id).await?))
+            }
+            _ => anyhow::bail!("expected `join <team>` or `leave <team>`"),
+        },
         _ => anyhow::bail!("Invalid subcommand."),
-    };
-
-    Ok(Some(record.to_string()))
+    }
 }
 
 // This does two things:
@@ -299,20 +695,7 @@ async fn execute_for_other_user(
         assert_eq!(command.pop(), Some(' ')); // pop trailing space
         command
     };
-    let bot_api_token = env::var("ZULIP_API_TOKEN").expect("ZULIP_API_TOKEN");
-
-    let members = ctx
-        .github
-        .raw()
-        .get(format!("{}/api/v1/users", *ZULIP_URL))
-        .basic_auth(&*ZULIP_BOT_EMAIL, Some(&bot_api_token))
-        .send()
-        .await
-        .map_err(|e| format_err!("Failed to get list of zulip users: {e:?}."))?;
-    let members = members
-        .json::<MembersApiResponse>()
-        .await
-        .map_err(|e| format_err!("Failed to get list of zulip users: {e:?}."))?;
+    let members = get_members(ctx.github.raw()).await?;
 
     // Map GitHub `user_id` to `zulip_user_id`.
     let zulip_user_id = match to_zulip_id(&ctx.github, user_id).await {
@@ -322,7 +705,6 @@ async fn execute_for_other_user(
     };
 
     let user = members
-        .members
         .iter()
         .find(|m| m.user_id == zulip_user_id)
         .ok_or_else(|| format_err!("Could not find Zulip user email."))?;
@@ -362,6 +744,52 @@ async fn execute_for_other_user(
     Ok(Some(output))
 }
 
+/// Replies with a code to post on GitHub with `@rustbot link-zulip`, which
+/// links the GitHub account of the commenter to the sender.
+async fn request_github_link(ctx: &Context, message: &Message) -> anyhow::Result<Option<String>> {
+    if message.stream_id.is_some() {
+        anyhow::bail!("`link-github` must be sent in a direct message to the bot");
+    }
+    let code = create_link_request(&*ctx.db.get().await, message.sender_id).await?;
+    Ok(Some(link_github_message(&code)))
+}
+
+fn link_github_message(code: &str) -> String {
+    format!(
+        "To link your GitHub account, comment `@rustbot link-zulip {code}` on an issue \
+        of a repository using the bot, within {} minutes.",
+        LINK_REQUEST_VALIDITY.num_minutes()
+    )
+}
+
+#[test]
+fn test_link_github_message() {
+    assert_eq!(
+        link_github_message("3f2a9c1e"),
+        "To link your GitHub account, comment `@rustbot link-zulip 3f2a9c1e` on an issue \
+        of a repository using the bot, within 60 minutes."
+    );
+}
+
+/// Returns the members of the Zulip organization.
+pub async fn get_members(client: &reqwest::Client) -> anyhow::Result<Vec<Member>> {
+    // Called when handling the webhooks, so a missing token fails the request
+    // rather than the whole process.
+    let bot_api_token = env::var("ZULIP_API_TOKEN").context("`ZULIP_API_TOKEN` is not set")?;
+
+    let members = client
+        .get(format!("{}/api/v1/users", *ZULIP_URL))
+        .basic_auth(&*ZULIP_BOT_EMAIL, Some(&bot_api_token))
+        .send()
+        .await
+        .map_err(|e| format_err!("Failed to get list of zulip users: {e:?}."))?;
+    let members = members
+        .json::<MembersApiResponse>()
+        .await
+        .map_err(|e| format_err!("Failed to get list of zulip users: {e:?}."))?;
+    Ok(members.members)
+}
+
 #[derive(serde::Deserialize)]
 pub struct MembersApiResponse {
     pub members: Vec<Member>,
@@ -450,6 +878,16 @@ fn test_encode() {
     check_encode("áé…", ".C3.A1.C3.A9.E2.80.A6");
 }
 
+/// Truncates a topic to 60 chars, the maximum length allowed by Zulip.
+pub fn truncate_topic(mut topic: String) -> String {
+    let mut chars = topic.char_indices().skip(59);
+    if let (Some((len, _)), Some(_)) = (chars.next(), chars.next()) {
+        topic.truncate(len);
+        topic.push('…');
+    }
+    topic
+}
+
 #[derive(serde::Serialize)]
 pub struct MessageApiRequest<'a> {
     pub recipient: Recipient<'a>,
@@ -462,7 +900,7 @@ impl<'a> MessageApiRequest<'a> {
     }
 
     pub async fn send(&self, client: &reqwest::Client) -> anyhow::Result<reqwest::Response> {
-        let bot_api_token = env::var("ZULIP_API_TOKEN").expect("ZULIP_API_TOKEN");
+        let bot_api_token = zulip_api_token()?;
 
         #[derive(serde::Serialize)]
         struct SerializedApi<'a> {
@@ -475,7 +913,7 @@ impl<'a> MessageApiRequest<'a> {
         }
 
         Ok(client
-            .post(format!("{}/api/v1/messages", *ZULIP_URL))
+            .post(format!("{}/api/v1/messages", zulip_url()))
             .basic_auth(&*ZULIP_BOT_EMAIL, Some(&bot_api_token))
             .form(&SerializedApi {
                 type_: match self.recipient {
@@ -513,7 +951,7 @@ pub struct UpdateMessageApiRequest<'a> {
 
 impl<'a> UpdateMessageApiRequest<'a> {
     pub async fn send(&self, client: &reqwest::Client) -> anyhow::Result<reqwest::Response> {
-        let bot_api_token = env::var("ZULIP_API_TOKEN").expect("ZULIP_API_TOKEN");
+        let bot_api_token = env::var("ZULIP_API_TOKEN").context("`ZULIP_API_TOKEN` is not set")?;
 
         #[derive(serde::Serialize)]
         struct SerializedApi<'a> {
@@ -826,3 +1264,164 @@ fn trigger_docs_update(message: &Message) -> anyhow::Result<Option<String>> {
         "Docs update in progress, I'll let you know when I'm finished.".to_string(),
     ))
 }
+
+/// Stream where `post-agenda lang-triage` posts the T-lang triage agenda.
+const LANG_TRIAGE_AGENDA_STREAM_VAR: &str = "LANG_TRIAGE_AGENDA_ZULIP_STREAM";
+/// Topic where `post-agenda lang-triage` posts the T-lang triage agenda.
+const LANG_TRIAGE_AGENDA_TOPIC_VAR: &str = "LANG_TRIAGE_AGENDA_ZULIP_TOPIC";
+
+async fn post_agenda(
+    ctx: &Context,
+    gh_id: u64,
+    name: Option<&str>,
+    message: &Message,
+) -> anyhow::Result<Option<String>> {
+    let agenda = match name {
+        // The agenda served at `/agenda/lang/triage`.
+        Some("lang-triage") => agenda::lang(),
+        _ => anyhow::bail!("expected `post-agenda lang-triage`"),
+    };
+    let teams = crate::team_data::teams(&ctx.github).await?;
+    let is_team_member = teams.teams.get("all").map_or(false, |all| {
+        all.members.iter().any(|m| m.github_id == gh_id)
+    });
+    if !is_team_member {
+        anyhow::bail!("only team members can post the agenda");
+    }
+    let stream: u64 = env::var(LANG_TRIAGE_AGENDA_STREAM_VAR)
+        .with_context(|| format!("`{LANG_TRIAGE_AGENDA_STREAM_VAR}` is not set"))?
+        .parse()
+        .with_context(|| format!("`{LANG_TRIAGE_AGENDA_STREAM_VAR}` is not a stream id"))?;
+    let topic = env::var(LANG_TRIAGE_AGENDA_TOPIC_VAR).unwrap_or_else(|_| {
+        format!(
+            "lang triage meeting {}",
+            chrono::Utc::now().format("%Y-%m-%d")
+        )
+    });
+
+    let message = message.clone();
+    // Rendering the agenda runs many GitHub queries, which can take longer
+    // than the Zulip timeout, so process in the background.
+    tokio::task::spawn(async move {
+        let result = match agenda.call().await {
+            Ok(agenda) => send_agenda(stream, &topic, &agenda).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            log::error!("failed to post the T-lang triage agenda: {e:?}");
+            let response = "Posting the agenda failed, please check the logs for more details.";
+            let reply = MessageApiRequest {
+                recipient: message.sender_to_recipient(),
+                content: response,
+            };
+            if let Err(e) = reply.send(&reqwest::Client::new()).await {
+                log::error!("failed to send Zulip response: {e:?}");
+            }
+        }
+    });
+    Ok(Some("Posting the T-lang triage agenda...".to_string()))
+}
+
+/// The maximum length of a Zulip message, in characters.
+const MAX_MESSAGE_CHARS: usize = 10000;
+
+/// Posts the agenda to the topic, in several messages if it is longer than
+/// Zulip allows.
+async fn send_agenda(stream: u64, topic: &str, agenda: &str) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    for part in split_message(agenda, MAX_MESSAGE_CHARS) {
+        agenda_message(stream, topic, part)
+            .send(&client)
+            .await?
+            .error_for_status()
+            .context("failed to post a part of the agenda")?;
+    }
+    Ok(())
+}
+
+/// Splits `content` into parts of at most `max` characters, after a line
+/// when possible.
+fn split_message(content: &str, max: usize) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut rest = content;
+    while rest.chars().count() > max {
+        // The end of the first `max` characters.
+        let limit = rest.char_indices().nth(max).map_or(rest.len(), |(i, _)| i);
+        let end = match rest[..limit].rfind('\n') {
+            Some(newline) if newline > 0 => newline + 1,
+            _ => limit,
+        };
+        parts.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    parts.push(rest);
+    parts
+}
+
+fn agenda_message<'a>(stream: u64, topic: &'a str, agenda: &'a str) -> MessageApiRequest<'a> {
+    MessageApiRequest {
+        recipient: Recipient::Stream { id: stream, topic },
+        content: agenda,
+    }
+}
+
+#[test]
+fn test_agenda_message() {
+    let agenda = "# T-lang meeting agenda\n\n- Meeting date: 2026-10-17";
+    let message = agenda_message(213817, "lang triage meeting 2026-10-17", agenda);
+    assert_eq!(
+        serde_json::to_value(&message).unwrap(),
+        serde_json::json!({
+            "recipient": {
+                "type": "stream",
+                "to": 213817,
+                "topic": "lang triage meeting 2026-10-17",
+            },
+            "content": agenda,
+        })
+    );
+}
+
+#[test]
+fn test_split_message() {
+    assert_eq!(split_message("short", 10), ["short"]);
+    assert_eq!(
+        split_message("line one\nline two\nthree", 12),
+        ["line one\n", "line two\n", "three"]
+    );
+    // A line too long is cut, on a character boundary.
+    assert_eq!(split_message("ééééé\nab", 3), ["ééé", "éé\n", "ab"]);
+}
+
+#[tokio::test]
+async fn test_send_long_agenda() {
+    let server = crate::test_server::TestServer::start(vec![
+        ("200 OK", r#"{"result": "success", "id": 1}"#.to_string()),
+        ("200 OK", r#"{"result": "success", "id": 2}"#.to_string()),
+    ]);
+    use_test_zulip(server.url());
+    let item = format!("- {}\n", "x".repeat(98));
+    let agenda = format!("# T-lang meeting agenda\n\n{}", item.repeat(150));
+    send_agenda(213817, "lang triage meeting", &agenda)
+        .await
+        .unwrap();
+
+    let requests = server.finish();
+    let contents: Vec<String> = requests
+        .iter()
+        .map(|request| {
+            assert_eq!(request.path, "/api/v1/messages");
+            let mut message: HashMap<String, String> =
+                url::form_urlencoded::parse(request.body.as_bytes())
+                    .into_owned()
+                    .collect();
+            assert_eq!(message["topic"], "lang triage meeting");
+            message.remove("content").unwrap()
+        })
+        .collect();
+    assert_eq!(contents.len(), 2);
+    assert!(contents[0].chars().count() <= MAX_MESSAGE_CHARS);
+    assert!(contents[0].ends_with('\n'));
+    assert!(contents[1].starts_with("- x"));
+    assert_eq!(contents.concat(), agenda);
+}